@@ -1,9 +1,45 @@
-use crate::config::{AppConfig, LocalConfig, SyncRules};
+use crate::cli::style::ok;
+use crate::config::{check_globs, home_dir, AppConfig, LocalConfig, SyncRules, DEFAULT_IGNORE};
 use crate::error::{DriftersError, Result};
-use crate::git::{commit_and_push, EphemeralRepoGuard};
+use crate::git::{commit_and_push, confirm_operation, EphemeralRepoGuard};
+use std::fs;
 use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 
-pub fn add_app(app_name: String) -> Result<()> {
+/// How many directory levels `--from-dir` looks into by default.
+pub const DEFAULT_SCAN_DEPTH: usize = 4;
+
+/// Directories `--from-dir` never looks into: version control data,
+/// dependency trees and caches, none of which is configuration.
+const SKIPPED_DIRS: [&str; 12] = [
+    ".git",
+    "node_modules",
+    "__pycache__",
+    ".venv",
+    "target",
+    ".cache",
+    "cache",
+    "Cache",
+    "CachedData",
+    "GPUCache",
+    "Code Cache",
+    "logs",
+];
+
+/// A subdirectory with at least this many files is offered as a single
+/// `dir/**` pattern rather than file by file.
+const COLLAPSE_AT: usize = 3;
+
+/// Add an app to the sync rules. Patterns come from `--include` /
+/// `--exclude` when given, from the files found in `from_dir` when given,
+/// otherwise they are asked for interactively.
+pub fn add_app(
+    app_name: String,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    from_dir: Option<PathBuf>,
+    max_depth: usize,
+) -> Result<()> {
     log::info!("Adding app: {}", app_name);
 
     // ── Validate app_name ─────────────────────────────────────────────────────
@@ -15,9 +51,18 @@ pub fn add_app(app_name: String) -> Result<()> {
             "App name cannot contain '/' or '\\'.".to_string(),
         ));
     }
+    // Resolving would only warn about a bad pattern and skip it on every push
+    check_globs(&format!("{}.include", app_name), &include)?;
+    check_globs(&format!("{}.exclude", app_name), &exclude)?;
+
+    // With --from-dir, --exclude flags stand in for the exclude prompt
+    let (from_flags, scanned) = match &from_dir {
+        Some(dir) => (None, Some((scan_from_dir(dir, max_depth)?, exclude))),
+        None => (patterns_from_flags(include, exclude)?, None),
+    };
 
     // Load local config
-    let config = LocalConfig::load()?;
+    let mut config = LocalConfig::load()?;
 
     // Set up ephemeral repo
     println!("Setting up repository...");
@@ -25,7 +70,7 @@ pub fn add_app(app_name: String) -> Result<()> {
     let repo_path = repo_guard.path();
 
     // Guard: detect stale machine IDs
-    crate::cli::common::verify_machine_registration(&config, repo_path)?;
+    crate::cli::common::verify_machine_registration(&mut config, repo_path)?;
 
     // Load sync rules
     let mut rules = SyncRules::load(repo_path)?;
@@ -37,6 +82,104 @@ pub fn add_app(app_name: String) -> Result<()> {
     }
 
     println!("Adding app '{}'", app_name);
+    let (include_patterns, exclude_patterns) = match (from_flags, scanned) {
+        (Some(patterns), _) => patterns,
+        (None, Some(((prefix, tree), exclude))) => {
+            println!(
+                "\nFound {} file(s) under {}. Choose what to include:",
+                tree.file_count(),
+                prefix
+            );
+            let include = propose_patterns(&tree, &prefix, &mut confirm_operation)?;
+            if include.is_empty() {
+                println!("No patterns specified, cancelling");
+                return Ok(());
+            }
+            let exclude = if exclude.is_empty() { prompt_excludes()? } else { exclude };
+            (include, exclude)
+        }
+        (None, None) => match prompt_patterns()? {
+            Some(patterns) => patterns,
+            None => {
+                println!("No patterns specified, cancelling");
+                return Ok(());
+            }
+        },
+    };
+
+    println!("\nNote: Files will be scanned for section tags automatically.");
+    println!("Use '# drifters::exclude::start' and '# drifters::exclude::stop' to exclude sections.");
+
+    // Create app config
+    let app_config = AppConfig {
+        include: include_patterns,
+        exclude: exclude_patterns,
+        include_macos: vec![],
+        include_linux: vec![],
+        include_windows: vec![],
+        exclude_macos: vec![],
+        exclude_linux: vec![],
+        exclude_windows: vec![],
+        no_merge: false,
+        redact: vec![],
+        comment_syntax: None,
+        match_hidden: None,
+        follow_symlinks: false,
+        merge_strategy: Default::default(),
+        line_endings: Default::default(),
+        sync_mode: Default::default(),
+        file_modes: Default::default(),
+        priority: vec![],
+        required: false,
+        machines: Default::default(),
+    };
+
+    // Add to rules
+    rules.add_app(app_name.clone(), app_config);
+
+    // Save rules
+    rules.save(repo_path)?;
+    println!("\n{} Added '{}' to sync rules", ok(), app_name);
+
+    // Commit and push
+    println!("\nCommitting changes...");
+    commit_and_push(
+        repo_path,
+        &format!("Add {} app from {}", app_name, config.machine_id),
+    )?;
+
+    println!("{} Changes committed and pushed", ok());
+    println!("\nYou can now use:");
+    println!("  drifters push-app {}              - push your configs for this app", app_name);
+    println!("  drifters pull-app {}              - pull configs from other machines", app_name);
+    println!("  drifters remove-app {}            - remove this machine's configs", app_name);
+    println!("  drifters remove-app {} --all      - remove the app from every machine", app_name);
+    println!("  drifters rename-app {} <new-name> - rename this app everywhere", app_name);
+
+    Ok(())
+}
+
+/// Patterns given as `--include` / `--exclude` flags, or `None` to ask for
+/// them interactively. Excludes alone are an error: they would only narrow
+/// the patterns still to be asked for.
+fn patterns_from_flags(
+    include: Vec<String>,
+    exclude: Vec<String>,
+) -> Result<Option<(Vec<String>, Vec<String>)>> {
+    if include.is_empty() {
+        if !exclude.is_empty() {
+            return Err(DriftersError::Config(
+                "--exclude needs at least one --include pattern".to_string(),
+            ));
+        }
+        return Ok(None);
+    }
+    Ok(Some((include, exclude)))
+}
+
+/// Ask for include, then exclude patterns, one per line. `None` if no
+/// include pattern was entered.
+fn prompt_patterns() -> Result<Option<(Vec<String>, Vec<String>)>> {
     println!("\nEnter file patterns to include (one per line, empty line to finish):");
     println!("Examples:");
     println!("  ~/.config/zed/settings.json");
@@ -57,17 +200,24 @@ pub fn add_app(app_name: String) -> Result<()> {
         if trimmed.is_empty() {
             break;
         }
+        if let Err(e) = glob::Pattern::new(trimmed) {
+            println!("  Invalid glob, not added: {}", e.msg);
+            continue;
+        }
 
         include_patterns.push(trimmed.to_string());
         println!("  Added: {}", trimmed);
     }
 
     if include_patterns.is_empty() {
-        println!("No patterns specified, cancelling");
-        return Ok(());
+        return Ok(None);
     }
 
-    // Ask for optional exclude patterns
+    Ok(Some((include_patterns, prompt_excludes()?)))
+}
+
+/// Ask for optional exclude patterns, one per line.
+fn prompt_excludes() -> Result<Vec<String>> {
     println!("\nEnter file patterns to exclude (optional, empty line to skip):");
     println!("Examples:");
     println!("  ~/.config/zed/workspace-*.json");
@@ -87,49 +237,175 @@ pub fn add_app(app_name: String) -> Result<()> {
         if trimmed.is_empty() {
             break;
         }
+        if let Err(e) = glob::Pattern::new(trimmed) {
+            println!("  Invalid glob, not added: {}", e.msg);
+            continue;
+        }
 
         exclude_patterns.push(trimmed.to_string());
         println!("  Added exclusion: {}", trimmed);
     }
 
-    println!("\nNote: Files will be scanned for section tags automatically.");
-    println!("Use '# drifters::exclude::start' and '# drifters::exclude::stop' to exclude sections.");
+    Ok(exclude_patterns)
+}
 
-    // Create app config
-    let app_config = AppConfig {
-        include: include_patterns,
-        exclude: exclude_patterns,
-        include_macos: vec![],
-        include_linux: vec![],
-        include_windows: vec![],
-        exclude_macos: vec![],
-        exclude_linux: vec![],
-        exclude_windows: vec![],
-        no_merge: false,
-        machines: Default::default(),
+/// The files under a `--from-dir` directory, with the pattern prefix that
+/// names the directory: `~/...` when it is under the home directory.
+fn scan_from_dir(dir: &Path, max_depth: usize) -> Result<(String, DirTree)> {
+    let dir = match dir.strip_prefix("~") {
+        Ok(rest) => home_dir().unwrap_or_default().join(rest),
+        Err(_) => std::path::absolute(dir)?,
     };
+    if !dir.is_dir() {
+        return Err(DriftersError::Config(format!("Not a directory: {}", dir.display())));
+    }
+    let tree = DirTree::scan(&dir, max_depth)?;
+    if tree.file_count() == 0 {
+        return Err(DriftersError::Config(format!(
+            "No files found under {} (within {} levels)",
+            dir.display(),
+            max_depth
+        )));
+    }
+    let prefix = match home_dir().and_then(|home| dir.strip_prefix(home).ok().map(Path::to_owned)) {
+        Some(rel) if rel.as_os_str().is_empty() => "~".to_string(),
+        Some(rel) => format!("~/{}", glob::Pattern::escape(&rel.to_string_lossy())),
+        None => glob::Pattern::escape(&dir.to_string_lossy()),
+    };
+    Ok((prefix, tree))
+}
 
-    // Add to rules
-    rules.add_app(app_name.clone(), app_config);
+/// Files and subdirectories of a directory, by name, as `--from-dir` found
+/// them. Symlinks, [`SKIPPED_DIRS`] and [`DEFAULT_IGNORE`] files are left
+/// out.
+#[derive(Debug, Default, PartialEq)]
+struct DirTree {
+    files: Vec<String>,
+    dirs: Vec<(String, DirTree)>,
+}
 
-    // Save rules
-    rules.save(repo_path)?;
-    println!("\n✓ Added '{}' to sync rules", app_name);
+impl DirTree {
+    fn scan(dir: &Path, depth_left: usize) -> Result<Self> {
+        let mut tree = DirTree::default();
+        let mut entries: Vec<_> = fs::read_dir(dir)?.flatten().collect();
+        entries.sort_by_key(|e| e.file_name());
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                if depth_left > 1 && !SKIPPED_DIRS.contains(&name.as_str()) {
+                    let sub = DirTree::scan(&entry.path(), depth_left - 1)?;
+                    if sub.file_count() > 0 {
+                        tree.dirs.push((name, sub));
+                    }
+                }
+            } else if file_type.is_file() && !is_default_ignored(&name) {
+                tree.files.push(name);
+            }
+        }
+        Ok(tree)
+    }
 
-    // Commit and push
-    println!("\nCommitting changes...");
-    commit_and_push(
-        repo_path,
-        &format!("Add {} app from {}", app_name, config.machine_id),
-    )?;
+    fn file_count(&self) -> usize {
+        self.files.len() + self.dirs.iter().map(|(_, d)| d.file_count()).sum::<usize>()
+    }
 
-    println!("✓ Changes committed and pushed");
-    println!("\nYou can now use:");
-    println!("  drifters push-app {}              - push your configs for this app", app_name);
-    println!("  drifters pull-app {}              - pull configs from other machines", app_name);
-    println!("  drifters remove-app {}            - remove this machine's configs", app_name);
-    println!("  drifters remove-app {} --all      - remove the app from every machine", app_name);
-    println!("  drifters rename-app {} <new-name> - rename this app everywhere", app_name);
+    /// Whether any file or directory below is hidden, which a `dir/**`
+    /// glob wouldn't match without `match_hidden`.
+    fn has_hidden(&self) -> bool {
+        self.files.iter().any(|f| f.starts_with('.'))
+            || self.dirs.iter().any(|(name, d)| name.starts_with('.') || d.has_hidden())
+    }
+}
 
-    Ok(())
+fn is_default_ignored(name: &str) -> bool {
+    DEFAULT_IGNORE
+        .iter()
+        .any(|p| glob::Pattern::new(p).is_ok_and(|p| p.matches(name)))
+}
+
+/// Walk `tree`, asking through `ask(question, default)` which files to
+/// include. A subdirectory with many files is first offered as one
+/// `dir/**` pattern; declining that goes through it file by file if wanted.
+fn propose_patterns(
+    tree: &DirTree,
+    prefix: &str,
+    ask: &mut impl FnMut(&str, bool) -> Result<bool>,
+) -> Result<Vec<String>> {
+    let mut patterns = Vec::new();
+    for name in &tree.files {
+        let pattern = format!("{}/{}", prefix, glob::Pattern::escape(name));
+        if ask(&format!("Include {}?", pattern), true)? {
+            patterns.push(pattern);
+        }
+    }
+    for (name, sub) in &tree.dirs {
+        let sub_prefix = format!("{}/{}", prefix, glob::Pattern::escape(name));
+        let count = sub.file_count();
+        if count >= COLLAPSE_AT && !sub.has_hidden() {
+            let question = format!("Include {}/** ({} files)?", sub_prefix, count);
+            if ask(&question, true)? {
+                patterns.push(format!("{}/**", sub_prefix));
+                continue;
+            }
+            if !ask(&format!("Go through the files under {}/ one by one?", name), false)? {
+                continue;
+            }
+        }
+        patterns.extend(propose_patterns(sub, &sub_prefix, ask)?);
+    }
+    Ok(patterns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patterns_from_flags_skip_prompts_only_with_includes() {
+        let include = vec!["~/.zshrc".to_string()];
+        let exclude = vec!["~/.zsh_history".to_string()];
+        assert_eq!(
+            patterns_from_flags(include.clone(), exclude.clone()).unwrap(),
+            Some((include, exclude.clone()))
+        );
+        assert_eq!(patterns_from_flags(vec![], vec![]).unwrap(), None);
+        assert!(patterns_from_flags(vec![], exclude).is_err());
+    }
+
+    #[test]
+    fn test_from_dir_proposes_files_and_collapses_big_subdirectories() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        for file in ["init.lua", "lua/a.lua", "lua/b.lua", "lua/c/d.lua", "after/x.vim"] {
+            fs::create_dir_all(root.join(file).parent().unwrap()).unwrap();
+            fs::write(root.join(file), "").unwrap();
+        }
+        for skipped in [".git/HEAD", "node_modules/m/index.js", "server.key"] {
+            fs::create_dir_all(root.join(skipped).parent().unwrap()).unwrap();
+            fs::write(root.join(skipped), "").unwrap();
+        }
+        let tree = DirTree::scan(root, DEFAULT_SCAN_DEPTH).unwrap();
+        assert_eq!(tree.file_count(), 5);
+        // Depth counts the directory itself
+        assert_eq!(DirTree::scan(root, 2).unwrap().file_count(), 4);
+
+        let mut asked = Vec::new();
+        let mut accept_all = |q: &str, _: bool| {
+            asked.push(q.to_string());
+            Ok(true)
+        };
+        let patterns = propose_patterns(&tree, "~/.config/nvim", &mut accept_all).unwrap();
+        assert_eq!(
+            patterns,
+            ["~/.config/nvim/init.lua", "~/.config/nvim/after/x.vim", "~/.config/nvim/lua/**"]
+        );
+        assert!(asked.contains(&"Include ~/.config/nvim/lua/** (3 files)?".to_string()));
+
+        // Declining the glob can still go file by file
+        let mut one_by_one = |q: &str, _: bool| Ok(!q.ends_with("/** (3 files)?"));
+        let patterns = propose_patterns(&tree, "~/n", &mut one_by_one).unwrap();
+        assert!(patterns.contains(&"~/n/lua/c/d.lua".to_string()));
+        assert!(!patterns.iter().any(|p| p.ends_with("**")));
+    }
 }
@@ -0,0 +1,228 @@
+use crate::cli::common::TempDirGuard;
+use crate::cli::style::ok;
+use crate::config::LocalConfig;
+use crate::error::{DriftersError, Result};
+use crate::git::{
+    checkout_branch, commit_and_push, confirm_operation, create_branch, init_repo,
+    list_all_branch_files, main_branch, remote_has_branch, set_remote_origin, EphemeralRepoGuard,
+};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Top-level directories of a branch that make up drifters' data.
+const ARCHIVED_DIRS: [&str; 2] = [".drifters", "apps"];
+
+/// Package the whole repository — `.drifters/` and `apps/` of main and of
+/// every machine branch — into a `.tar.gz`. Each branch's tree is stored
+/// under its branch name (`main/…`, `machines/<id>/…`).
+pub fn archive(output: PathBuf) -> Result<()> {
+    let config = LocalConfig::load()?;
+
+    println!("Fetching latest from repository...");
+    let repo_guard = EphemeralRepoGuard::new(&config)?;
+    let repo_path = repo_guard.path();
+
+    let branches: Vec<String> = list_all_branch_files(repo_path)?
+        .into_iter()
+        .map(|(branch, _)| branch)
+        .collect();
+
+    let file = fs::File::create(&output)?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    for branch in &branches {
+        checkout_branch(repo_path, branch)?;
+        append_tree(&mut builder, repo_path, branch)?;
+        println!("  {} {}", ok(), branch);
+    }
+    builder.into_inner()?.finish()?;
+    checkout_branch(repo_path, main_branch())?;
+
+    println!(
+        "\n{} Archived {} branch(es) to {}",
+        ok(),
+        branches.len(),
+        output.display()
+    );
+    println!("Restore into an empty repository with: drifters restore-archive {}", output.display());
+    Ok(())
+}
+
+/// Recreate a repository from an archive written by [`archive`] and push it
+/// to the configured remote, which must not have a `main` branch yet.
+pub fn restore_archive(file: PathBuf) -> Result<()> {
+    let config = LocalConfig::load()?;
+
+    let work_dir = LocalConfig::get_temp_repo_path()?.with_extension("restore");
+    if work_dir.exists() {
+        fs::remove_dir_all(&work_dir)?;
+    }
+    let _cleanup = TempDirGuard(work_dir.clone());
+
+    let unpacked = work_dir.join("unpacked");
+    let trees = unpack_trees(&file, &unpacked)?;
+    if trees.first().map(|(branch, _)| branch.as_str()) != Some(main_branch()) {
+        return Err(DriftersError::Config(format!(
+            "{} is not a drifters archive (no {} branch in it)",
+            file.display(),
+            main_branch()
+        )));
+    }
+
+    let repo_path = work_dir.join("repo");
+    init_repo(&repo_path)?;
+    set_remote_origin(&repo_path, &config.repo_url)?;
+    if remote_has_branch(&repo_path, main_branch())? {
+        return Err(DriftersError::Config(format!(
+            "{} already has a {} branch. restore-archive only fills an empty repository; \
+             point repo_url at a new one first.",
+            config.repo_url,
+            main_branch()
+        )));
+    }
+
+    println!("Archive contains:");
+    for (branch, _) in &trees {
+        println!("  {}", branch);
+    }
+    let msg = format!("Push these {} branch(es) to {}?", trees.len(), config.repo_url);
+    if !confirm_operation(&msg, true)? {
+        return Err(DriftersError::UserCancelled);
+    }
+
+    for (branch, tree) in &trees {
+        if branch != main_branch() {
+            // Machine branches start from main, like `init` creates them
+            checkout_branch(&repo_path, main_branch())?;
+            create_branch(&repo_path, branch)?;
+        }
+        replace_tree(tree, &repo_path)?;
+        commit_and_push(&repo_path, &format!("Restore {} from archive", branch))?;
+        println!("  {} {}", ok(), branch);
+    }
+
+    println!("\n{} Restored {} branch(es) to {}", ok(), trees.len(), config.repo_url);
+    Ok(())
+}
+
+/// Add `repo_path`'s drifters directories to the archive under `prefix/`.
+fn append_tree<W: std::io::Write>(
+    builder: &mut tar::Builder<W>,
+    repo_path: &Path,
+    prefix: &str,
+) -> Result<()> {
+    for dir in ARCHIVED_DIRS {
+        let source = repo_path.join(dir);
+        if source.is_dir() {
+            builder.append_dir_all(format!("{}/{}", prefix, dir), &source)?;
+        }
+    }
+    Ok(())
+}
+
+/// Unpack an archive into `dest` and return its branches with the directory
+/// holding each one's tree: `main` first, then machine branches by name.
+fn unpack_trees(file: &Path, dest: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut archive = tar::Archive::new(GzDecoder::new(fs::File::open(file)?));
+    archive.unpack(dest)?;
+
+    let mut trees = Vec::new();
+    if dest.join(main_branch()).is_dir() {
+        trees.push((main_branch().to_string(), dest.join(main_branch())));
+    }
+    let machines = dest.join("machines");
+    if machines.is_dir() {
+        let mut ids: Vec<String> = fs::read_dir(&machines)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().to_str().map(str::to_string))
+            .collect();
+        ids.sort();
+        for id in ids {
+            trees.push((format!("machines/{}", id), machines.join(&id)));
+        }
+    }
+    Ok(trees)
+}
+
+/// Make `repo_path`'s drifters directories exactly those in `tree`.
+fn replace_tree(tree: &Path, repo_path: &Path) -> Result<()> {
+    for dir in ARCHIVED_DIRS {
+        let target = repo_path.join(dir);
+        if target.exists() {
+            fs::remove_dir_all(&target)?;
+        }
+        if tree.join(dir).is_dir() {
+            copy_dir_all(&tree.join(dir), &target)?;
+        }
+    }
+    Ok(())
+}
+
+fn copy_dir_all(from: &Path, to: &Path) -> Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn test_archive_round_trips_branch_trees() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("main-checkout");
+        let laptop = dir.path().join("laptop-checkout");
+        write(&main.join(".drifters/sync-rules.toml"), "[apps.zsh]\n");
+        write(&main.join("apps/zsh/.zshrc"), "export A=1\n");
+        write(&main.join("README.md"), "not drifters data\n");
+        write(&laptop.join(".drifters/sync-rules.toml"), "[apps.zsh]\n");
+        write(&laptop.join("apps/zsh/.zshrc"), "export A=2\n");
+
+        let archive_path = dir.path().join("backup.tar.gz");
+        let file = fs::File::create(&archive_path).unwrap();
+        let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+        append_tree(&mut builder, &main, "main").unwrap();
+        append_tree(&mut builder, &laptop, "machines/laptop").unwrap();
+        builder.into_inner().unwrap().finish().unwrap();
+
+        let trees = unpack_trees(&archive_path, &dir.path().join("out")).unwrap();
+        let names: Vec<_> = trees.iter().map(|(b, _)| b.as_str()).collect();
+        assert_eq!(names, vec!["main", "machines/laptop"]);
+
+        // Restoring a tree over a checkout replaces its drifters data only
+        let restored = dir.path().join("restored");
+        write(&restored.join("apps/stale/file"), "gone after restore\n");
+        write(&restored.join("README.md"), "kept\n");
+        replace_tree(&trees[1].1, &restored).unwrap();
+        assert_eq!(
+            fs::read_to_string(restored.join("apps/zsh/.zshrc")).unwrap(),
+            "export A=2\n"
+        );
+        assert!(!restored.join("apps/stale").exists());
+        assert_eq!(fs::read_to_string(restored.join("README.md")).unwrap(), "kept\n");
+
+        replace_tree(&trees[0].1, &restored).unwrap();
+        assert_eq!(
+            fs::read_to_string(restored.join("apps/zsh/.zshrc")).unwrap(),
+            "export A=1\n"
+        );
+        assert!(!trees[0].1.join("README.md").exists());
+    }
+}
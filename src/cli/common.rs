@@ -1,7 +1,42 @@
-use crate::config::{LocalConfig, MachineRegistry};
+use crate::cli::style::ok;
+use crate::config::{
+    ActivityEntry, ActivityLog, LocalConfig, MachineRegistry, SyncRules, MAX_ACTIVITY_ENTRIES,
+};
 use crate::error::{DriftersError, Result};
+use crate::git::{checkout_or_create_branch, confirm_operation, current_branch, main_branch};
 use std::io::{self, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Load sync-rules.toml from a specific branch via `git show`, without
+/// switching branches. Rules always live on main, but commands often have a
+/// machine branch checked out.
+///
+/// Falls back to the working tree copy when the branch has no rules file yet
+/// (e.g. a freshly initialized repo).
+pub fn load_rules_from_branch(repo_path: &Path, branch: &str) -> Result<SyncRules> {
+    let spec = format!("{}:.drifters/sync-rules.toml", branch);
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(["show", &spec])
+        .output()?;
+
+    if !output.status.success() {
+        return SyncRules::load(repo_path);
+    }
+
+    let content = String::from_utf8_lossy(&output.stdout);
+    let rules: SyncRules = toml::from_str(&content)?;
+    Ok(rules)
+}
+
+/// Build the blocking HTTP client shared by every command that talks to the
+/// network (presets, shared app definitions).
+pub fn http_client() -> Result<reqwest::blocking::Client> {
+    Ok(reqwest::blocking::Client::builder()
+        .user_agent("drifters-cli")
+        .build()?)
+}
 
 /// Open a file using `editor`, falling back to `$EDITOR`, then the OS default.
 ///
@@ -22,7 +57,7 @@ pub fn open_file(path: &Path, editor: Option<&str>) -> Result<()> {
     })?;
 
     let editor_env = std::env::var("EDITOR").ok();
-    let editor = editor.or_else(|| editor_env.as_deref());
+    let editor = editor.or(editor_env.as_deref());
 
     if let Some(editor) = editor {
         println!("   Opening '{}' with '{}'...", path_str, editor);
@@ -102,19 +137,35 @@ pub fn open_file(path: &Path, editor: Option<&str>) -> Result<()> {
 /// Call this after `EphemeralRepoGuard::new()` in any command that depends on
 /// the machine ID being valid (push, pull, status, diff, merge, …).
 ///
+/// If the registry records that the ID was renamed, the user is offered the
+/// new one; accepting rewrites the local config, so `config.machine_id` is
+/// the current ID afterwards.
+///
 /// Returns `Ok(())` to let the caller proceed, or `Err(...)` if the user
 /// chooses to exit.
 pub fn verify_machine_registration(
-    config: &LocalConfig,
+    config: &mut LocalConfig,
     repo_path: &std::path::Path,
 ) -> Result<()> {
-    let registry = MachineRegistry::load(&repo_path.to_path_buf())?;
+    let registry = MachineRegistry::load(repo_path)?;
 
     // Happy path — ID is registered, nothing to do
     if registry.machines.contains_key(&config.machine_id) {
         return Ok(());
     }
 
+    // ── Renamed on another machine ────────────────────────────────────────────
+    if let Some(new_id) = registry.renamed_to(&config.machine_id) {
+        eprintln!(
+            "\n⚠️  This machine ('{}') was renamed to '{}' from another machine.",
+            config.machine_id, new_id
+        );
+        let question = format!("Switch this machine's ID to '{}'?", new_id);
+        if confirm_operation(&question, true)? {
+            return adopt_machine_id(config, repo_path, new_id);
+        }
+    }
+
     // ── Stale ID detected ─────────────────────────────────────────────────────
     eprintln!(
         "\n⚠️  Your machine ID '{}' is no longer registered in this repo.",
@@ -161,3 +212,150 @@ pub fn verify_machine_registration(
     }
 }
 
+/// Make `new_id` this machine's ID in drifters.toml. If the clone has the old
+/// ID's machine branch checked out (created from main just now, since the
+/// rename deleted it), the new ID's branch is checked out instead.
+fn adopt_machine_id(config: &mut LocalConfig, repo_path: &Path, new_id: &str) -> Result<()> {
+    let repo_path = repo_path.to_path_buf();
+    if current_branch(&repo_path)? == format!("machines/{}", config.machine_id) {
+        checkout_or_create_branch(&repo_path, &format!("machines/{}", new_id), main_branch())?;
+    }
+    config.machine_id = new_id.to_string();
+    config.save()?;
+    eprintln!("{} Updated local config machine ID to '{}'", ok(), new_id);
+    Ok(())
+}
+
+/// Fail unless `machine_id` is registered in the repo's machine registry.
+/// Used by commands that act on another machine via `--machine <id>`.
+pub fn require_registered_machine(repo_path: &Path, machine_id: &str) -> Result<()> {
+    let registry = MachineRegistry::load(repo_path)?;
+    if registry.machines.contains_key(machine_id) {
+        return Ok(());
+    }
+    let mut known: Vec<_> = registry.machines.keys().cloned().collect();
+    known.sort();
+    Err(DriftersError::Config(format!(
+        "Machine '{}' is not registered in this repo.\n\
+         Registered machines: {}",
+        machine_id,
+        if known.is_empty() {
+            "(none)".to_string()
+        } else {
+            known.join(", ")
+        }
+    )))
+}
+
+/// Refresh this machine's `last_sync` and OS in the working tree's
+/// `machines.toml` (main must be checked out). Nothing is committed here:
+/// callers fold it into a commit they are making anyway.
+///
+/// Returns `true` if the registry file changed.
+pub fn stage_heartbeat(config: &LocalConfig, repo_path: &Path) -> Result<bool> {
+    let mut registry = MachineRegistry::load(repo_path)?;
+    let changed = registry.record_heartbeat(
+        &config.machine_id,
+        &MachineRegistry::detect_os(),
+        chrono::Utc::now(),
+    );
+    if changed {
+        registry.save(repo_path)?;
+    }
+    Ok(changed)
+}
+
+/// Append this push to `.drifters/activity.toml` in `repo_path` (main),
+/// one entry per app with the number of files pushed for it. Like
+/// [`stage_heartbeat`], only writes the file for the caller to commit.
+pub fn stage_activity(
+    config: &LocalConfig,
+    repo_path: &Path,
+    pushed: &[(String, usize)],
+) -> Result<()> {
+    let mut log = ActivityLog::load(repo_path)?;
+    let now = chrono::Utc::now();
+    for (app, files) in pushed {
+        let entry = ActivityEntry {
+            machine: config.machine_id.clone(),
+            app: app.clone(),
+            files: *files,
+            timestamp: now,
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        };
+        log.record(entry, MAX_ACTIVITY_ENTRIES);
+    }
+    log.save(repo_path)
+}
+
+/// The commit message for an operation: `generated`, or with `--message`
+/// the user's message with `generated` below it, so the log still says
+/// which apps came from which machine.
+pub fn commit_message(generated: String, custom: Option<&str>) -> String {
+    match custom {
+        Some(custom) => format!("{}\n\n{}", custom.trim(), generated),
+        None => generated,
+    }
+}
+
+/// RAII guard that deletes a directory tree on Drop.
+pub struct TempDirGuard(pub PathBuf);
+
+impl Drop for TempDirGuard {
+    fn drop(&mut self) {
+        if self.0.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&self.0) {
+                log::warn!("Failed to clean up temp dir {:?}: {}", self.0, e);
+            } else {
+                log::debug!("Cleaned up temporary repo at {:?}", self.0);
+            }
+        }
+    }
+}
+
+/// Permission bits of a local file, for recording alongside it on push.
+/// Always `None` on platforms without Unix modes.
+#[cfg(unix)]
+pub fn file_mode(path: &Path) -> Option<u32> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path)
+        .ok()
+        .map(|meta| meta.permissions().mode() & 0o7777)
+}
+
+#[cfg(not(unix))]
+pub fn file_mode(_path: &Path) -> Option<u32> {
+    None
+}
+
+/// Apply permission bits recorded by [`file_mode`]. A no-op on platforms
+/// without Unix modes.
+#[cfg(unix)]
+pub fn set_file_mode(path: &Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn set_file_mode(_path: &Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+/// Make `path` a symlink to `link_target`, replacing whatever is there.
+#[cfg(unix)]
+pub fn replace_with_symlink(path: &Path, link_target: &Path) -> Result<()> {
+    if path.symlink_metadata().is_ok() {
+        std::fs::remove_file(path)?;
+    }
+    std::os::unix::fs::symlink(link_target, path)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn replace_with_symlink(path: &Path, _link_target: &Path) -> Result<()> {
+    Err(DriftersError::Config(format!(
+        "{} is synced as a symlink, which drifters can only recreate on Unix",
+        path.display()
+    )))
+}
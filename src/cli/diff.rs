@@ -1,17 +1,31 @@
-use crate::config::{resolve_fileset, LocalConfig, SyncRules};
-use crate::error::Result;
-use crate::git::{read_app_files, EphemeralRepoGuard};
+use crate::cli::common::{load_rules_from_branch, require_registered_machine};
+use crate::cli::diffview;
+use crate::config::{resolve_fileset, LocalConfig};
+use crate::error::{DriftersError, Result};
+use crate::git::{
+    ensure_full_history, fetch_branch, is_metadata_file, list_tree_with_sizes, main_branch,
+    read_app_files, remote_has_branch, resolve_commit, show_file_at, EphemeralRepoGuard,
+};
+use crate::parser::compare::content_equivalent_ignoring_whitespace;
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
-pub fn show_diff(app_name: Option<String>, against: Option<String>, tool: bool) -> Result<()> {
+pub fn show_diff(
+    app_name: Option<String>,
+    against: Option<String>,
+    tool: bool,
+    no_pager: bool,
+    since_last_sync: bool,
+    commit: Option<String>,
+) -> Result<()> {
     log::info!("Showing diff");
 
     // Load local config
-    let config = LocalConfig::load()?;
+    let mut config = LocalConfig::load()?;
 
     // Determine comparison branch
-    let compare_branch = against.unwrap_or_else(|| "main".to_string());
+    let compare_branch = against.unwrap_or_else(|| main_branch().to_string());
 
     // Set up ephemeral repo on the comparison branch
     println!("Fetching latest from repository...");
@@ -19,10 +33,11 @@ pub fn show_diff(app_name: Option<String>, against: Option<String>, tool: bool)
     let repo_path = repo_guard.path();
 
     // Guard: detect stale machine IDs
-    crate::cli::common::verify_machine_registration(&config, repo_path)?;
+    crate::cli::common::verify_machine_registration(&mut config, repo_path)?;
 
     // Load sync rules from main
-    let rules = load_rules_from_main(repo_path)?;
+    let rules = load_rules_from_branch(repo_path, main_branch())?;
+    rules.validate()?;
 
     if rules.apps.is_empty() {
         println!("No apps configured for sync.");
@@ -40,9 +55,37 @@ pub fn show_diff(app_name: Option<String>, against: Option<String>, tool: bool)
         rules.apps.keys().cloned().collect()
     };
 
-    println!("Comparing local files against branch '{}'", compare_branch);
+    // With --commit, files are compared against the branch as it was then
+    let commit = match commit {
+        Some(rev) => {
+            ensure_full_history(repo_path)?;
+            let hash = resolve_commit(repo_path, &rev).map_err(|_| {
+                DriftersError::Config(format!(
+                    "Commit '{}' not found on branch '{}'",
+                    rev, compare_branch
+                ))
+            })?;
+            Some(hash)
+        }
+        None => None,
+    };
+    let baseline = match &commit {
+        Some(hash) => format!("commit {}", &hash[..7.min(hash.len())]),
+        None => format!("'{}'", compare_branch),
+    };
+
+    if since_last_sync {
+        ensure_full_history(repo_path)?;
+        println!("Showing changes on '{}' since this machine last pulled", compare_branch);
+    } else if commit.is_some() {
+        println!("Comparing local files against {} of '{}'", baseline, compare_branch);
+    } else {
+        println!("Comparing local files against branch '{}'", compare_branch);
+    }
 
     let mut total_changes = 0;
+    let mut changed_since_sync = 0;
+    let mut whitespace_only = 0;
 
     for app in &apps_to_diff {
         let app_config = rules.apps.get(app).unwrap();
@@ -51,9 +94,36 @@ pub fn show_diff(app_name: Option<String>, against: Option<String>, tool: bool)
         println!("App: {}", app);
         println!("{}", "=".repeat(60));
 
+        if since_last_sync {
+            // The marker may be gone if history was rewritten; fall back then too
+            let marker = config
+                .last_pulled
+                .get(app)
+                .and_then(|commit| resolve_commit(repo_path, commit).ok());
+            match marker {
+                Some(commit) => {
+                    let short = &commit[..7.min(commit.len())];
+                    let changes = app_changes_since(repo_path, &commit, "HEAD", app)?;
+                    if changes.is_empty() {
+                        println!("  No changes on main since last sync ({})", short);
+                    }
+                    for (filename, old, new) in &changes {
+                        println!("\n{} (since {})", filename, short);
+                        println!("{}", "-".repeat(60));
+                        diffview::print(&diffview::full_diff(old, new), no_pager)?;
+                    }
+                    changed_since_sync += changes.len();
+                    continue;
+                }
+                None => println!("  No last-sync marker for '{}'; showing full diff", app),
+            }
+        }
+
         // Resolve fileset for THIS machine
         let fileset = resolve_fileset(
             app_config,
+            &rules.ignore,
+            &rules.groups,
             &config.machine_id,
             std::env::consts::OS,
         )?;
@@ -63,8 +133,11 @@ pub fn show_diff(app_name: Option<String>, against: Option<String>, tool: bool)
             continue;
         }
 
-        // Read files from the comparison branch
-        let remote_files = read_app_files(repo_path, app)?;
+        // Read files from the comparison branch, or as they were at --commit
+        let remote_files = match &commit {
+            Some(hash) => app_files_at(repo_path, hash, app)?,
+            None => read_app_files(repo_path, app)?,
+        };
 
         for local_path in fileset {
             let filename = local_path
@@ -74,18 +147,32 @@ pub fn show_diff(app_name: Option<String>, against: Option<String>, tool: bool)
 
             let remote_content = match remote_files.get(filename) {
                 Some(content) => content.clone(),
+                // Not there yet at --commit: all of the local file is new since
+                None if commit.is_some() && local_path.exists() => String::new(),
                 None => continue,
             };
 
             // Compare with local
             let local_content = if local_path.exists() {
-                fs::read_to_string(&local_path)?
+                match String::from_utf8(fs::read(&local_path)?) {
+                    Ok(content) => content,
+                    Err(_) => {
+                        log::debug!("Skipping binary file {}", local_path.display());
+                        continue;
+                    }
+                }
             } else {
                 String::new()
             };
 
             // Show diff if different
             if local_content != remote_content {
+                if content_equivalent_ignoring_whitespace(&local_content, &remote_content) {
+                    log::info!("{} differs from {} only in whitespace", filename, baseline);
+                    println!("\n{} ({}) - whitespace/line endings only", filename, local_path.display());
+                    whitespace_only += 1;
+                    continue;
+                }
                 total_changes += 1;
 
                 if tool {
@@ -94,57 +181,179 @@ pub fn show_diff(app_name: Option<String>, against: Option<String>, tool: bool)
                 } else {
                     println!("\n{} ({})", filename, local_path.display());
                     println!("{}", "-".repeat(60));
-                    show_file_diff(&local_content, &remote_content);
+                    // Against a commit, show how the local file moved on from it
+                    let diff = match commit {
+                        Some(_) => diffview::full_diff(&remote_content, &local_content),
+                        None => diffview::full_diff(&local_content, &remote_content),
+                    };
+                    diffview::print(&diff, no_pager)?;
                 }
             }
         }
     }
 
     println!("\n{}", "=".repeat(60));
-    if total_changes == 0 {
-        println!("All configs are up to date with '{}'", compare_branch);
-    } else {
-        println!("{} file(s) differ from '{}'", total_changes, compare_branch);
-        println!("\nRun 'drifters pull-app' to apply changes from main");
+    if changed_since_sync > 0 {
+        println!("{} file(s) changed on main since last sync", changed_since_sync);
+    }
+    if whitespace_only > 0 {
+        println!(
+            "{} file(s) differ from {} only in whitespace/line endings",
+            whitespace_only, baseline
+        );
+    }
+    if total_changes == 0 && changed_since_sync == 0 && whitespace_only == 0 {
+        println!("All configs are up to date with {}", baseline);
+    } else if total_changes > 0 {
+        println!("{} file(s) differ from {}", total_changes, baseline);
+        if commit.is_none() {
+            println!("\nRun 'drifters pull-app' to apply changes from main");
+        }
     }
 
     Ok(())
 }
 
-fn load_rules_from_main(repo_path: &Path) -> Result<SyncRules> {
-    let output = std::process::Command::new("git")
-        .arg("-C")
-        .arg(repo_path)
-        .args(["show", "main:.drifters/sync-rules.toml"])
-        .output()?;
+/// A file two machines disagree on: (filename, first machine's content,
+/// second machine's content), `None` where a machine doesn't have it.
+type MachineDifference = (String, Option<String>, Option<String>);
+
+/// Compare what two machines last pushed for an app, file by file.
+pub fn show_machine_diff(
+    app_name: String,
+    machine_a: String,
+    machine_b: String,
+    no_pager: bool,
+) -> Result<()> {
+    let config = LocalConfig::load()?;
+
+    println!("Fetching latest from repository...");
+    let repo_guard = EphemeralRepoGuard::new(&config)?;
+    let repo_path = repo_guard.path();
 
-    if !output.status.success() {
-        return SyncRules::load(&repo_path.to_path_buf());
+    let rules = load_rules_from_branch(repo_path, main_branch())?;
+    if !rules.apps.contains_key(&app_name) {
+        return Err(DriftersError::AppNotFound(app_name));
     }
 
-    let content = String::from_utf8_lossy(&output.stdout);
-    let rules: SyncRules = toml::from_str(&content)?;
-    Ok(rules)
+    let mut refs = Vec::new();
+    for machine in [&machine_a, &machine_b] {
+        require_registered_machine(repo_path, machine)?;
+        let branch = format!("machines/{}", machine);
+        if !remote_has_branch(repo_path, &branch)? {
+            return Err(DriftersError::Config(format!(
+                "Machine '{}' has not pushed anything yet (no branch '{}')",
+                machine, branch
+            )));
+        }
+        fetch_branch(repo_path, &branch)?;
+        refs.push(format!("origin/{}", branch));
+    }
+
+    println!("Comparing '{}' on '{}' and '{}'", app_name, machine_a, machine_b);
+    let differences = machine_differences(repo_path, &refs[0], &refs[1], &app_name)?;
+    for (filename, a, b) in &differences {
+        match (a, b) {
+            (Some(_), None) => println!("\n{} - only on '{}'", filename, machine_a),
+            (None, Some(_)) => println!("\n{} - only on '{}'", filename, machine_b),
+            (a, b) => {
+                println!("\n{} ('{}' -> '{}')", filename, machine_a, machine_b);
+                println!("{}", "-".repeat(60));
+                let (a, b) = (a.as_deref().unwrap_or(""), b.as_deref().unwrap_or(""));
+                diffview::print(&diffview::full_diff(a, b), no_pager)?;
+            }
+        }
+    }
+
+    println!("\n{}", "=".repeat(60));
+    if differences.is_empty() {
+        println!("'{}' and '{}' pushed the same files for '{}'", machine_a, machine_b, app_name);
+    } else {
+        println!("{} file(s) differ", differences.len());
+    }
+    Ok(())
 }
 
-fn show_file_diff(old: &str, new: &str) {
-    use similar::TextDiff;
+/// Files of `app` that differ between two machine branches.
+fn machine_differences(
+    repo_path: &std::path::PathBuf,
+    ref_a: &str,
+    ref_b: &str,
+    app: &str,
+) -> Result<Vec<MachineDifference>> {
+    let prefix = format!("apps/{}/", app);
+    let mut paths: Vec<String> = list_tree_with_sizes(repo_path, ref_a)?
+        .into_iter()
+        .chain(list_tree_with_sizes(repo_path, ref_b)?)
+        .map(|(path, _)| path)
+        .filter(|path| path.starts_with(&prefix) && !is_metadata_file(&path[prefix.len()..]))
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut differences = Vec::new();
+    for path in paths {
+        let a = show_file_at(repo_path, ref_a, &path).ok();
+        let b = show_file_at(repo_path, ref_b, &path).ok();
+        if a != b {
+            differences.push((path[prefix.len()..].to_string(), a, b));
+        }
+    }
+    Ok(differences)
+}
 
-    let diff = TextDiff::from_lines(old, new);
+/// Files of `app` that differ between two commits, as
+/// (filename, content at `since`, content at `until`). A file missing on
+/// one side has empty content there.
+fn app_changes_since(
+    repo_path: &std::path::PathBuf,
+    since: &str,
+    until: &str,
+    app: &str,
+) -> Result<Vec<(String, String, String)>> {
+    let prefix = format!("apps/{}/", app);
+    let mut paths: Vec<String> = list_tree_with_sizes(repo_path, since)?
+        .into_iter()
+        .chain(list_tree_with_sizes(repo_path, until)?)
+        .filter(|(path, _)| path.starts_with(&prefix))
+        .map(|(path, _)| path)
+        .filter(|path| !is_metadata_file(&path[prefix.len()..]))
+        .collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut changes = Vec::new();
+    for path in paths {
+        let old = show_file_at(repo_path, since, &path).unwrap_or_default();
+        let new = show_file_at(repo_path, until, &path).unwrap_or_default();
+        if old != new {
+            changes.push((path[prefix.len()..].to_string(), old, new));
+        }
+    }
+    Ok(changes)
+}
 
-    for change in diff.iter_all_changes() {
-        match change.tag() {
-            similar::ChangeTag::Delete => {
-                print!("  \x1b[31m-{}\x1b[0m", change);
-            }
-            similar::ChangeTag::Insert => {
-                print!("  \x1b[32m+{}\x1b[0m", change);
-            }
-            similar::ChangeTag::Equal => {
-                print!("   {}", change);
+/// Text files of `app` as they were at `commit`, by filename.
+fn app_files_at(
+    repo_path: &std::path::PathBuf,
+    commit: &str,
+    app: &str,
+) -> Result<HashMap<String, String>> {
+    let prefix = format!("apps/{}/", app);
+    let mut files = HashMap::new();
+    for (path, _) in list_tree_with_sizes(repo_path, commit)? {
+        let Some(filename) = path.strip_prefix(&prefix) else { continue };
+        if filename.contains('/') || is_metadata_file(filename) {
+            continue;
+        }
+        match show_file_at(repo_path, commit, &path) {
+            Ok(content) => {
+                files.insert(filename.to_string(), content);
             }
+            Err(e) => log::debug!("Skipping {} at {}: {}", path, commit, e),
         }
     }
+    Ok(files)
 }
 
 /// Open a diff in the user's configured git difftool.
@@ -184,3 +393,112 @@ fn open_in_difftool(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn git(repo: &Path, args: &[&str]) -> String {
+        let out = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        String::from_utf8_lossy(&out.stdout).trim().to_string()
+    }
+
+    #[test]
+    fn test_app_changes_since_commit_range() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().to_path_buf();
+        git(&repo, &["init", "-q", "-b", "main"]);
+        fs::create_dir_all(repo.join("apps/zsh")).unwrap();
+        fs::create_dir_all(repo.join("apps/zed")).unwrap();
+        fs::write(repo.join("apps/zsh/.zshrc"), "a=1\n").unwrap();
+        fs::write(repo.join("apps/zsh/aliases"), "ll\n").unwrap();
+        fs::write(repo.join("apps/zed/settings.json"), "{}\n").unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-qm", "first"]);
+        let marker = git(&repo, &["rev-parse", "HEAD"]);
+
+        // Another machine's changes land on main afterwards
+        fs::write(repo.join("apps/zsh/.zshrc"), "a=2\n").unwrap();
+        fs::remove_file(repo.join("apps/zsh/aliases")).unwrap();
+        fs::write(repo.join("apps/zsh/env"), "X=1\n").unwrap();
+        fs::write(repo.join("apps/zed/settings.json"), "{\"a\": 1}\n").unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-qm", "second"]);
+
+        let changes = app_changes_since(&repo, &marker, "HEAD", "zsh").unwrap();
+        assert_eq!(
+            changes,
+            vec![
+                (".zshrc".to_string(), "a=1\n".to_string(), "a=2\n".to_string()),
+                ("aliases".to_string(), "ll\n".to_string(), String::new()),
+                ("env".to_string(), String::new(), "X=1\n".to_string()),
+            ]
+        );
+        assert!(app_changes_since(&repo, "HEAD", "HEAD", "zsh").unwrap().is_empty());
+        assert!(resolve_commit(&repo, &marker).is_ok());
+        assert!(resolve_commit(&repo, "0000000000000000000000000000000000000000").is_err());
+    }
+
+    #[test]
+    fn test_machine_differences_reports_one_sided_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().to_path_buf();
+        git(&repo, &["init", "-q", "-b", "machines/laptop"]);
+        fs::create_dir_all(repo.join("apps/zsh")).unwrap();
+        fs::write(repo.join("apps/zsh/.zshrc"), "a=1\n").unwrap();
+        fs::write(repo.join("apps/zsh/aliases"), "ll\n").unwrap();
+        fs::write(repo.join("apps/zsh/.drifters-modes.toml"), "\".zshrc\" = \"644\"\n").unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-qm", "laptop"]);
+
+        git(&repo, &["checkout", "-q", "-b", "machines/desktop"]);
+        fs::write(repo.join("apps/zsh/.zshrc"), "a=2\n").unwrap();
+        fs::remove_file(repo.join("apps/zsh/aliases")).unwrap();
+        fs::write(repo.join("apps/zsh/env"), "X=1\n").unwrap();
+        fs::write(repo.join("apps/zsh/.drifters-modes.toml"), "\".zshrc\" = \"600\"\n").unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-qm", "desktop"]);
+
+        let some = |s: &str| Some(s.to_string());
+        assert_eq!(
+            machine_differences(&repo, "machines/laptop", "machines/desktop", "zsh").unwrap(),
+            vec![
+                (".zshrc".to_string(), some("a=1\n"), some("a=2\n")),
+                ("aliases".to_string(), some("ll\n"), None),
+                ("env".to_string(), None, some("X=1\n")),
+            ]
+        );
+        assert!(machine_differences(&repo, "HEAD", "HEAD", "zsh").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_app_files_at_reads_a_past_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().to_path_buf();
+        git(&repo, &["init", "-q", "-b", "main"]);
+        fs::create_dir_all(repo.join("apps/zsh")).unwrap();
+        fs::write(repo.join("apps/zsh/.zshrc"), "a=1\n").unwrap();
+        fs::write(repo.join("apps/zsh/.drifters-modes.toml"), "\".zshrc\" = \"644\"\n").unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-qm", "known good"]);
+        let known_good = git(&repo, &["rev-parse", "HEAD"]);
+
+        fs::write(repo.join("apps/zsh/.zshrc"), "a=2\n").unwrap();
+        fs::write(repo.join("apps/zsh/env"), "X=1\n").unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-qm", "later"]);
+
+        let files = app_files_at(&repo, &known_good, "zsh").unwrap();
+        assert_eq!(files.len(), 1, "{:?}", files);
+        assert_eq!(files[".zshrc"], "a=1\n");
+        assert!(app_files_at(&repo, &known_good, "zed").unwrap().is_empty());
+    }
+}
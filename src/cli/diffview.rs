@@ -0,0 +1,156 @@
+use crate::cli::pager::print_paged;
+use crate::error::Result;
+use similar::{ChangeTag, TextDiff};
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set from `--no-color`.
+static NO_COLOR: AtomicBool = AtomicBool::new(false);
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const RESET: &str = "\x1b[0m";
+
+/// Turn diff colours off for this process, whatever the terminal.
+pub fn set_no_color(disabled: bool) {
+    NO_COLOR.store(disabled, Ordering::Relaxed);
+}
+
+/// Whether diffs are coloured: only on a terminal, and neither
+/// `--no-color` nor a non-empty `NO_COLOR` (<https://no-color.org>) says
+/// otherwise. Piped output never gets escape codes.
+pub fn color_enabled() -> bool {
+    use_color(
+        NO_COLOR.load(Ordering::Relaxed),
+        std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()),
+        std::io::stdout().is_terminal(),
+    )
+}
+
+fn use_color(flag: bool, env: bool, is_tty: bool) -> bool {
+    !flag && !env && is_tty
+}
+
+/// `line` (without its newline) in `color`, if colours are on.
+fn paint(out: &mut String, line: &str, color: Option<&str>) {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    match color {
+        Some(color) => out.push_str(&format!("{}{}{}\n", color, line, RESET)),
+        None => {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+}
+
+/// The changed lines between two versions of a file, without context, as
+/// pull and push previews show them.
+pub fn changed_lines(old: &str, new: &str) -> String {
+    render_changed_lines(old, new, color_enabled())
+}
+
+fn render_changed_lines(old: &str, new: &str, color: bool) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    let mut out = String::new();
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Delete => {
+                paint(&mut out, &format!("    - {}", change), color.then_some(RED))
+            }
+            ChangeTag::Insert => {
+                paint(&mut out, &format!("    + {}", change), color.then_some(GREEN))
+            }
+            ChangeTag::Equal => {}
+        }
+    }
+    if out.is_empty() {
+        return "    (no changes)\n".to_string();
+    }
+    out
+}
+
+/// The whole file with its changes marked. Never truncated; long output is
+/// left to the pager.
+pub fn full_diff(old: &str, new: &str) -> String {
+    render_full_diff(old, new, color_enabled())
+}
+
+fn render_full_diff(old: &str, new: &str, color: bool) -> String {
+    let diff = TextDiff::from_lines(old, new);
+    let mut out = String::new();
+    for change in diff.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Delete => paint(&mut out, &format!("  -{}", change), color.then_some(RED)),
+            ChangeTag::Insert => {
+                paint(&mut out, &format!("  +{}", change), color.then_some(GREEN))
+            }
+            ChangeTag::Equal => paint(&mut out, &format!("   {}", change), None),
+        }
+    }
+    out
+}
+
+/// `git diff --stat` output with its `+`/`-` bars coloured like git does.
+pub fn stat(text: &str) -> String {
+    render_stat(text, color_enabled())
+}
+
+fn render_stat(text: &str, color: bool) -> String {
+    if !color {
+        return text.to_string();
+    }
+    text.lines()
+        .map(|line| {
+            // " path | 3 ++-": only the bars after the count are coloured
+            let bars = line
+                .rsplit_once(' ')
+                .filter(|(head, bars)| {
+                    head.contains(" | ") && bars.chars().all(|c| c == '+' || c == '-')
+                });
+            match bars {
+                Some((head, bars)) => {
+                    let bars = bars
+                        .replace('+', &format!("{}+{}", GREEN, RESET))
+                        .replace('-', &format!("{}-{}", RED, RESET));
+                    format!("{} {}", head, bars)
+                }
+                None => line.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Print a rendered diff, through the pager when it is long.
+pub fn print(text: &str, no_pager: bool) -> Result<()> {
+    print_paged(text, no_pager)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_only_on_a_terminal_without_opt_outs() {
+        assert!(use_color(false, false, true));
+        assert!(!use_color(true, false, true));
+        assert!(!use_color(false, true, true));
+        assert!(!use_color(false, false, false));
+
+        let old = "a\nb\n";
+        let new = "a\nc";
+        assert_eq!(render_changed_lines(old, new, false), "    - b\n    + c\n");
+        assert_eq!(
+            render_changed_lines(old, new, true),
+            "\x1b[31m    - b\x1b[0m\n\x1b[32m    + c\x1b[0m\n"
+        );
+        assert_eq!(render_changed_lines(old, old, true), "    (no changes)\n");
+        assert_eq!(render_full_diff(old, new, false), "   a\n  -b\n  +c\n");
+
+        let stat = " apps/zsh/.zshrc | 3 ++-\n apps/a/b | Bin 0 -> 3 bytes\n 1 file changed";
+        assert_eq!(render_stat(stat, false), stat);
+        let colored = render_stat(stat, true);
+        assert!(colored.contains("3 \x1b[32m+\x1b[0m\x1b[32m+\x1b[0m\x1b[31m-\x1b[0m\n"));
+        assert!(colored.contains("Bin 0 -> 3 bytes"));
+    }
+}
@@ -0,0 +1,290 @@
+use crate::cli::style::decorate;
+use crate::config::{LocalConfig, MachineRegistry};
+use crate::error::{DriftersError, Result};
+use crate::git::{
+    check_remote_access, git_identity, latest_commit, lock_state, EphemeralRepoGuard, LockState,
+};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// Commits this far (or less) in the future are put down to ordinary clock
+/// drift between machines.
+const CLOCK_SKEW_TOLERANCE_SECS: i64 = 5 * 60;
+
+/// Result of one doctor check.
+#[derive(Debug, PartialEq)]
+enum Check {
+    Pass(String),
+    /// Works, but worth fixing: what's wrong, and how to fix it
+    Warn(String, String),
+    /// drifters can't work until this is fixed
+    Fail(String, String),
+}
+
+impl Check {
+    fn print(&self) {
+        let line = match self {
+            Check::Pass(what) => format!("✓ {}", what),
+            Check::Warn(what, hint) => format!("⚠ {}\n  → {}", what, hint),
+            Check::Fail(what, hint) => format!("✗ {}\n  → {}", what, hint),
+        };
+        println!("{}", decorate(&line));
+    }
+}
+
+/// Diagnose problems with this machine's drifters setup. Every check is
+/// printed with a hint for fixing it; fails if any critical one does.
+pub fn run_doctor() -> Result<()> {
+    println!("\nDrifters Doctor");
+    println!("{}", "=".repeat(60));
+
+    let mut checks = Vec::new();
+    let mut run = |check: Check| {
+        check.print();
+        let passed = !matches!(check, Check::Fail(..));
+        checks.push(check);
+        passed
+    };
+
+    let config = match LocalConfig::load() {
+        Ok(config) => {
+            run(Check::Pass("Config: drifters.toml loaded".to_string()));
+            Some(config)
+        }
+        Err(DriftersError::RepoNotInitialized) => {
+            run(Check::Fail(
+                "Config: drifters.toml does not exist".to_string(),
+                "Run 'drifters init <repo-url>' to set up this machine".to_string(),
+            ));
+            None
+        }
+        Err(e) => {
+            run(Check::Fail(
+                format!("Config: drifters.toml could not be read: {}", e),
+                "Fix it with 'drifters edit-config'".to_string(),
+            ));
+            None
+        }
+    };
+
+    if let Some(config) = config {
+        run(check_identity(&config));
+        let editor_env = std::env::var("EDITOR").ok();
+        let path_var = std::env::var_os("PATH").unwrap_or_default();
+        let editor = config.editor.as_deref().or(editor_env.as_deref());
+        run(check_editor(editor, &path_var));
+
+        let lock_ok = run(check_lock(lock_state()?));
+        let remote_ok = run(match check_remote_access(&config.repo_url) {
+            Ok(()) => Check::Pass(format!("Repository: {} is reachable", config.repo_url)),
+            Err(e) => Check::Fail(
+                format!(
+                    "Repository: cannot read {}\n  {}",
+                    config.repo_url,
+                    e.to_string().lines().collect::<Vec<_>>().join("\n  ")
+                ),
+                "Check repo_url in drifters.toml, and that 'git ls-remote <repo-url>' works \
+                 (SSH key loaded, access granted)"
+                    .to_string(),
+            ),
+        });
+
+        // The remaining checks need the repository itself
+        if lock_ok && remote_ok {
+            println!("\nFetching latest from repository...");
+            let repo_guard = EphemeralRepoGuard::new(&config)?;
+            let repo_path = repo_guard.path();
+            run(check_registration(&config, repo_path)?);
+            run(check_clock(repo_path)?);
+        }
+    }
+
+    let failed = checks.iter().filter(|c| matches!(c, Check::Fail(..))).count();
+    let warned = checks.iter().filter(|c| matches!(c, Check::Warn(..))).count();
+    println!();
+    if failed > 0 {
+        return Err(DriftersError::Config(format!(
+            "{} check(s) failed; drifters will not work until they are fixed",
+            failed
+        )));
+    }
+    println!("All critical checks passed ({} warning(s))", warned);
+    Ok(())
+}
+
+/// Commits need a name and email, from drifters' config or git's.
+fn check_identity(config: &LocalConfig) -> Check {
+    let dir = LocalConfig::config_file_path()
+        .ok()
+        .and_then(|p| p.parent().map(PathBuf::from))
+        .unwrap_or_else(std::env::temp_dir);
+    let (git_name, git_email) = git_identity(&dir);
+    let name = config.git_author_name.clone().or(git_name);
+    let email = config.git_author_email.clone().or(git_email);
+    match (name, email) {
+        (Some(name), Some(email)) => Check::Pass(format!("Git identity: {} <{}>", name, email)),
+        _ => Check::Warn(
+            "Git identity: user.name / user.email not set; commits are made as \
+             \"Drifters User <drifters@localhost>\""
+                .to_string(),
+            "Run 'drifters set-author <name> <email>' or set them with 'git config --global'"
+                .to_string(),
+        ),
+    }
+}
+
+/// The editor (from the config or $EDITOR) must be something that can be
+/// run, or edit commands fail.
+fn check_editor(editor: Option<&str>, path_var: &OsStr) -> Check {
+    let Some(editor) = editor else {
+        return Check::Pass("Editor: none set; the OS default app is used".to_string());
+    };
+    match find_executable(editor, path_var) {
+        Some(path) => Check::Pass(format!("Editor: {} ({})", editor, path.display())),
+        None => Check::Warn(
+            format!("Editor: '{}' was not found on PATH", editor),
+            "Run 'drifters set-editor <full path>', or install its command-line launcher"
+                .to_string(),
+        ),
+    }
+}
+
+/// `name` itself if it is a path to a file, else the first match on `path_var`.
+fn find_executable(name: &str, path_var: &OsStr) -> Option<PathBuf> {
+    let candidate = Path::new(name);
+    if candidate.components().count() > 1 {
+        return candidate.is_file().then(|| candidate.to_path_buf());
+    }
+    std::env::split_paths(path_var).find_map(|dir| {
+        let path = dir.join(name);
+        if path.is_file() {
+            return Some(path);
+        }
+        let exe = path.with_extension("exe");
+        (cfg!(windows) && exe.is_file()).then_some(exe)
+    })
+}
+
+fn check_lock(state: LockState) -> Check {
+    let pid = |pid: Option<u32>| pid.map(|p| format!(" (PID {})", p)).unwrap_or_default();
+    match state {
+        LockState::Free => Check::Pass("Lock: not held".to_string()),
+        LockState::Held(holder) => Check::Warn(
+            format!("Lock: held by a running drifters process{}", pid(holder)),
+            "Wait for it to finish; repository checks are skipped meanwhile".to_string(),
+        ),
+        LockState::Abandoned(holder) => Check::Fail(
+            format!("Lock: left behind by a process that is gone{}", pid(holder)),
+            "Run 'drifters unlock', or re-run with --force-unlock".to_string(),
+        ),
+    }
+}
+
+/// Same test as `verify_machine_registration`, without its prompt.
+fn check_registration(config: &LocalConfig, repo_path: &Path) -> Result<Check> {
+    let registry = MachineRegistry::load(repo_path)?;
+    if registry.machines.contains_key(&config.machine_id) {
+        return Ok(Check::Pass(format!("Machine: '{}' is registered", config.machine_id)));
+    }
+    if let Some(new_id) = registry.renamed_to(&config.machine_id) {
+        return Ok(Check::Warn(
+            format!("Machine: '{}' was renamed to '{}'", config.machine_id, new_id),
+            "Run any command that syncs (e.g. 'drifters status') to switch to the new ID"
+                .to_string(),
+        ));
+    }
+    let mut known: Vec<_> = registry.machines.keys().cloned().collect();
+    known.sort();
+    Ok(Check::Fail(
+        format!(
+            "Machine: '{}' is not registered in the repository (registered: {})",
+            config.machine_id,
+            if known.is_empty() { "none".to_string() } else { known.join(", ") }
+        ),
+        "It was probably renamed or removed on another machine; set machine_id in \
+         drifters.toml to the new ID, or remove drifters.toml and re-run 'drifters init'"
+            .to_string(),
+    ))
+}
+
+fn check_clock(repo_path: &PathBuf) -> Result<Check> {
+    let now = chrono::Utc::now().timestamp();
+    Ok(match latest_commit(repo_path)? {
+        None => Check::Pass("Clock: repository has no commits to compare against".to_string()),
+        Some((committed, subject)) => match clock_skew(now, committed) {
+            None => Check::Pass("Clock: consistent with the latest repository commit".to_string()),
+            Some(ahead) => Check::Warn(
+                format!(
+                    "Clock: the latest commit (\"{}\") is {} ahead of this machine's clock.\n  \
+                     Either this clock is behind or the machine that made it is ahead;\n  \
+                     commit order and last-sync times will be misleading until it is fixed.",
+                    subject,
+                    format_duration(ahead)
+                ),
+                "Enable network time sync (NTP) on both machines".to_string(),
+            ),
+        },
+    })
+}
+
+/// How far `latest_commit` (Unix seconds) lies in the future of `now`, if
+/// more than the tolerance. A commit cannot legitimately be newer than the
+/// present, so this means some machine's clock is wrong. Old commits prove
+/// nothing (the repo may just be quiet) and are never reported.
+fn clock_skew(now: i64, latest_commit: i64) -> Option<i64> {
+    let ahead = latest_commit - now;
+    (ahead > CLOCK_SKEW_TOLERANCE_SECS).then_some(ahead)
+}
+
+/// Human-readable duration, at the coarsest sensible unit.
+fn format_duration(secs: i64) -> String {
+    match secs {
+        s if s >= 86_400 => format!("{}d {}h", s / 86_400, s % 86_400 / 3_600),
+        s if s >= 3_600 => format!("{}h {}m", s / 3_600, s % 3_600 / 60),
+        s => format!("{}m", s / 60),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_skew_only_flags_commits_from_the_future() {
+        let now = 1_780_000_000;
+        // A commit from a machine whose clock runs two days fast
+        let skewed = now + 2 * 86_400 + 3 * 3_600;
+        assert_eq!(clock_skew(now, skewed), Some(2 * 86_400 + 3 * 3_600));
+        assert_eq!(format_duration(skewed - now), "2d 3h");
+
+        // Small drift and old commits are fine
+        assert_eq!(clock_skew(now, now + 60), None);
+        assert_eq!(clock_skew(now, now - 30 * 86_400), None);
+    }
+
+    #[test]
+    fn test_editor_and_lock_checks() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("zed"), "").unwrap();
+        let path_var = std::env::join_paths([dir.path()]).unwrap();
+
+        assert!(matches!(check_editor(None, &path_var), Check::Pass(_)));
+        assert_eq!(
+            check_editor(Some("zed"), &path_var),
+            Check::Pass(format!("Editor: zed ({})", dir.path().join("zed").display()))
+        );
+        assert!(matches!(check_editor(Some("code"), &path_var), Check::Warn(..)));
+        let full = dir.path().join("zed").to_string_lossy().to_string();
+        assert!(matches!(check_editor(Some(&full), OsStr::new("")), Check::Pass(_)));
+
+        assert!(matches!(check_lock(LockState::Free), Check::Pass(_)));
+        assert!(matches!(check_lock(LockState::Held(Some(42))), Check::Warn(..)));
+        match check_lock(LockState::Abandoned(Some(42))) {
+            Check::Fail(what, hint) => {
+                assert!(what.contains("PID 42"));
+                assert!(hint.contains("drifters unlock"));
+            }
+            other => panic!("{:?}", other),
+        }
+    }
+}
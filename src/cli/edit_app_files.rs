@@ -18,7 +18,13 @@ pub fn edit_app_files(app_name: &str) -> Result<()> {
         .ok_or_else(|| DriftersError::AppNotFound(app_name.to_string()))?;
 
     // Resolve files present on this machine
-    let fileset = resolve_fileset(app_config, &config.machine_id, std::env::consts::OS)?;
+    let fileset = resolve_fileset(
+        app_config,
+        &rules.ignore,
+        &rules.groups,
+        &config.machine_id,
+        std::env::consts::OS,
+    )?;
 
     let existing: Vec<_> = fileset.into_iter().filter(|p| p.exists()).collect();
 
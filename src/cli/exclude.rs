@@ -1,9 +1,41 @@
-use crate::config::{LocalConfig, MachineOverride, SyncRules};
+use crate::cli::common::require_registered_machine;
+use crate::config::{check_globs, LocalConfig, MachineOverride, SyncRules};
 use crate::error::{DriftersError, Result};
 use crate::git::{commit_and_push, EphemeralRepoGuard};
 
-pub fn exclude_file(app_name: String, filename: String) -> Result<()> {
-    log::info!("Excluding {} from {} on this machine", filename, app_name);
+/// Where an exclusion is recorded.
+#[derive(Debug, PartialEq)]
+enum ExcludeScope {
+    /// A per-machine override (`[apps.<app>.machines.<id>] exclude`)
+    Machine(String),
+    /// The app's base `exclude` list, applied on every machine
+    AllMachines,
+}
+
+impl ExcludeScope {
+    fn describe(&self) -> String {
+        match self {
+            ExcludeScope::Machine(id) => format!("machine '{}'", id),
+            ExcludeScope::AllMachines => "all machines".to_string(),
+        }
+    }
+}
+
+/// Exclude a file from an app, on this machine by default.
+///
+/// * `--machine <id>` — exclude on the named (registered) machine instead.
+/// * `--all-machines` — add the exclusion to the app's base `exclude` list.
+pub fn exclude_file(
+    app_name: String,
+    filename: String,
+    machine: Option<String>,
+    all_machines: bool,
+) -> Result<()> {
+    if machine.is_some() && all_machines {
+        return Err(DriftersError::Config(
+            "Cannot use --machine and --all-machines together.".to_string(),
+        ));
+    }
 
     // Load local config
     let config = LocalConfig::load()?;
@@ -13,56 +45,125 @@ pub fn exclude_file(app_name: String, filename: String) -> Result<()> {
     let repo_guard = EphemeralRepoGuard::new(&config)?;
     let repo_path = repo_guard.path();
 
+    let scope = if all_machines {
+        ExcludeScope::AllMachines
+    } else if let Some(id) = machine {
+        require_registered_machine(repo_path, &id)?;
+        ExcludeScope::Machine(id)
+    } else {
+        ExcludeScope::Machine(config.machine_id.clone())
+    };
+    log::info!("Excluding {} from {} on {}", filename, app_name, scope.describe());
+
     // Load sync rules
     let mut rules = SyncRules::load(repo_path)?;
 
-    // Check if app exists
-    let app_config = rules
-        .apps
-        .get_mut(&app_name)
-        .ok_or_else(|| DriftersError::AppNotFound(app_name.clone()))?;
-
-    // Add exclusion for this machine
-    let machine_override = app_config
-        .machines
-        .entry(config.machine_id.clone())
-        .or_insert_with(MachineOverride::default);
-
-    // Add the filename as an exclude pattern
-    let exclude_pattern = format!("**/{}", filename);
-
-    if machine_override.exclude.contains(&exclude_pattern) {
+    if !add_exclusion(&mut rules, &app_name, &filename, &scope)? {
         println!(
-            "File '{}' is already excluded for {} on machine '{}'",
-            filename, app_name, config.machine_id
+            "File '{}' is already excluded for {} on {}",
+            filename,
+            app_name,
+            scope.describe()
         );
         return Ok(());
     }
 
-    machine_override.exclude.push(exclude_pattern);
-
     // Save rules
     rules.save(repo_path)?;
     println!(
-        "\n✓ Excluded '{}' from {} on machine '{}'",
-        filename, app_name, config.machine_id
+        "\n✓ Excluded '{}' from {} on {}",
+        filename,
+        app_name,
+        scope.describe()
     );
 
     // Commit and push
     println!("\nCommitting changes...");
+    let target = match &scope {
+        ExcludeScope::Machine(id) => id.as_str(),
+        ExcludeScope::AllMachines => "all machines",
+    };
     commit_and_push(
         repo_path,
-        &format!(
-            "Exclude {} from {} on {}",
-            filename, app_name, config.machine_id
-        ),
+        &format!("Exclude {} from {} on {}", filename, app_name, target),
     )?;
 
     println!("✓ Changes committed and pushed");
     println!(
-        "\nThis file will no longer be synced to machine '{}'",
-        config.machine_id
+        "\nThis file will no longer be synced to {}",
+        scope.describe()
     );
 
     Ok(())
 }
+
+/// Add `**/<filename>` to the exclude list `scope` points at.
+/// Returns `false` if it was already there.
+fn add_exclusion(
+    rules: &mut SyncRules,
+    app_name: &str,
+    filename: &str,
+    scope: &ExcludeScope,
+) -> Result<bool> {
+    let app_config = rules
+        .apps
+        .get_mut(app_name)
+        .ok_or_else(|| DriftersError::AppNotFound(app_name.to_string()))?;
+
+    let excludes = match scope {
+        ExcludeScope::Machine(id) => {
+            &mut app_config
+                .machines
+                .entry(id.clone())
+                .or_insert_with(MachineOverride::default)
+                .exclude
+        }
+        ExcludeScope::AllMachines => &mut app_config.exclude,
+    };
+
+    let exclude_pattern = format!("**/{}", filename);
+    check_globs(&format!("{}.exclude", app_name), std::slice::from_ref(&exclude_pattern))?;
+    if excludes.contains(&exclude_pattern) {
+        return Ok(false);
+    }
+    excludes.push(exclude_pattern);
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+
+    fn rules_with_app() -> SyncRules {
+        let mut rules = SyncRules::new();
+        rules.apps.insert("zed".to_string(), AppConfig::default());
+        rules
+    }
+
+    #[test]
+    fn test_exclude_named_machine_targets_its_override() {
+        let mut rules = rules_with_app();
+        let scope = ExcludeScope::Machine("desktop".to_string());
+        assert!(add_exclusion(&mut rules, "zed", "keymap.json", &scope).unwrap());
+        assert!(!add_exclusion(&mut rules, "zed", "keymap.json", &scope).unwrap());
+
+        let app = &rules.apps["zed"];
+        assert_eq!(app.machines["desktop"].exclude, vec!["**/keymap.json"]);
+        assert!(app.exclude.is_empty());
+        assert_eq!(app.machines.len(), 1);
+
+        let missing = add_exclusion(&mut rules, "nope", "x", &scope);
+        assert!(matches!(missing, Err(DriftersError::AppNotFound(_))));
+    }
+
+    #[test]
+    fn test_exclude_all_machines_edits_base_list() {
+        let mut rules = rules_with_app();
+        assert!(add_exclusion(&mut rules, "zed", "keymap.json", &ExcludeScope::AllMachines).unwrap());
+
+        let app = &rules.apps["zed"];
+        assert_eq!(app.exclude, vec!["**/keymap.json"]);
+        assert!(app.machines.is_empty());
+    }
+}
@@ -1,41 +1,56 @@
 use crate::config::{LocalConfig, SyncRules};
 use crate::error::{DriftersError, Result};
-use crate::git::EphemeralRepoGuard;
+use crate::git::{commit_and_push, EphemeralRepoGuard};
 use std::fs;
-use std::path::PathBuf;
-
-pub fn export_app(app_name: String, file_path: Option<PathBuf>) -> Result<()> {
+use std::path::{Path, PathBuf};
+
+/// Export an app definition.
+///
+/// By default this only writes a local file (`./<app>.toml` unless `--file`
+/// is given) and never touches the shared repo. With `to_repo` the definition
+/// is written to `.drifters/apps/<app>.toml` in the repo and committed. With
+/// `stdout` it is printed, and nothing else is, so it can be piped.
+pub fn export_app(
+    app_name: String,
+    file_path: Option<PathBuf>,
+    to_repo: bool,
+    stdout: bool,
+) -> Result<()> {
     // Load local config and repo
     let config = LocalConfig::load()?;
     let repo_guard = EphemeralRepoGuard::new(&config)?;
     let repo_path = repo_guard.path();
 
-    // Determine file path: use provided or default to <app>.toml in current directory
-    let actual_file_path = match file_path {
-        Some(path) => path,
-        None => std::env::current_dir()?.join(format!("{}.toml", app_name)),
-    };
-
-    log::info!("Exporting app '{}' to {:?}", app_name, actual_file_path);
-
     // Load sync rules
     let rules = SyncRules::load(repo_path)?;
-
-    // Get the app config
-    let app_config = rules
-        .apps
-        .get(&app_name)
-        .ok_or_else(|| DriftersError::AppNotFound(app_name.clone()))?;
-
-    // Create a new SyncRules with just this app
-    let mut export_rules = SyncRules::new();
-    export_rules.apps.insert(app_name.clone(), app_config.clone());
-
-    // Serialize to TOML
-    let toml_content = toml::to_string_pretty(&export_rules)?;
-
-    // Write to file
-    fs::write(&actual_file_path, toml_content)?;
+    let toml_content = render_app_export(&rules, &app_name)?;
+
+    if stdout {
+        print!("{}", toml_content);
+        return Ok(());
+    }
+
+    if to_repo {
+        let relative = Path::new(".drifters")
+            .join("apps")
+            .join(format!("{}.toml", app_name));
+        log::info!("Exporting app '{}' into repo at {:?}", app_name, relative);
+
+        let target = repo_path.join(&relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&target, toml_content)?;
+
+        println!("\n✓ Exported '{}' to {} in the repo", app_name, relative.display());
+        println!("\nCommitting changes...");
+        commit_and_push(repo_path, &format!("Export {} app definition", app_name))?;
+        println!("✓ Changes committed and pushed");
+        return Ok(());
+    }
+
+    let actual_file_path =
+        export_to_file(&toml_content, &app_name, file_path, &std::env::current_dir()?)?;
 
     println!("\n✓ Exported '{}' to {:?}", app_name, actual_file_path);
 
@@ -47,12 +62,50 @@ pub fn export_app(app_name: String, file_path: Option<PathBuf>) -> Result<()> {
     Ok(())
 }
 
-pub fn export_rules(file_path: Option<PathBuf>) -> Result<()> {
+/// Write a local (non-repo) app export to the given path, or to
+/// `<cwd>/<app>.toml`, and return where it went. Takes no repo, so the
+/// default export can never commit anything.
+fn export_to_file(
+    toml_content: &str,
+    app_name: &str,
+    file_path: Option<PathBuf>,
+    cwd: &Path,
+) -> Result<PathBuf> {
+    let target = file_path.unwrap_or_else(|| cwd.join(format!("{}.toml", app_name)));
+    log::info!("Exporting app '{}' to {:?}", app_name, target);
+    fs::write(&target, toml_content)?;
+    Ok(target)
+}
+
+/// Serialize a single app as a standalone `[apps.<name>]` TOML document.
+pub fn render_app_export(rules: &SyncRules, app_name: &str) -> Result<String> {
+    let app_config = rules
+        .apps
+        .get(app_name)
+        .ok_or_else(|| DriftersError::AppNotFound(app_name.to_string()))?;
+
+    // Create a new SyncRules with just this app
+    let mut export_rules = SyncRules::new();
+    export_rules
+        .apps
+        .insert(app_name.to_string(), app_config.clone());
+
+    Ok(toml::to_string_pretty(&export_rules)?)
+}
+
+/// Export the whole sync-rules.toml to a file (`./sync-rules.toml` unless
+/// `--file` is given), or with `stdout` print it and nothing else.
+pub fn export_rules(file_path: Option<PathBuf>, stdout: bool) -> Result<()> {
     // Load local config and repo
     let config = LocalConfig::load()?;
     let repo_guard = EphemeralRepoGuard::new(&config)?;
     let repo_path = repo_guard.path();
 
+    if stdout {
+        print!("{}", toml::to_string_pretty(&SyncRules::load(repo_path)?)?);
+        return Ok(());
+    }
+
     // Determine file path: use provided or default to sync-rules.toml in current directory
     let actual_file_path = match file_path {
         Some(path) => path,
@@ -80,3 +133,67 @@ pub fn export_rules(file_path: Option<PathBuf>) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
+    use std::process::Command;
+
+    fn git(repo: &Path, args: &[&str]) -> String {
+        let out = Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(["-c", "user.name=t", "-c", "user.email=t@t"])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        String::from_utf8_lossy(&out.stdout).trim().to_string()
+    }
+
+    #[test]
+    fn test_default_export_writes_cwd_and_does_not_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        let repo = dir.path().join("repo");
+        let cwd = dir.path().join("cwd");
+        fs::create_dir_all(&cwd).unwrap();
+        git(dir.path(), &["init", "-q", "--bare", "-b", "main", &remote.to_string_lossy()]);
+        git(dir.path(), &["clone", "-q", &remote.to_string_lossy(), &repo.to_string_lossy()]);
+
+        let mut rules = SyncRules::new();
+        rules.apps.insert(
+            "zed".to_string(),
+            AppConfig {
+                include: vec!["~/.config/zed/settings.json".to_string()],
+                ..Default::default()
+            },
+        );
+        rules.save(&repo).unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-qm", "rules"]);
+        git(&repo, &["push", "-q", "origin", "HEAD:main"]);
+        let head = git(&repo, &["rev-parse", "HEAD"]);
+        let remote_head = git(&remote, &["rev-parse", "main"]);
+
+        let rules = SyncRules::load(&repo).unwrap();
+        let content = render_app_export(&rules, "zed").unwrap();
+        let target = export_to_file(&content, "zed", None, &cwd).unwrap();
+        assert_eq!(target, cwd.join("zed.toml"));
+        let written = fs::read_to_string(&target).unwrap();
+        assert!(written.contains("[apps.zed]"));
+        assert!(written.contains("settings.json"));
+
+        // Nothing was written to, committed in or pushed from the repo
+        assert!(!repo.join(".drifters/apps/zed.toml").exists());
+        assert_eq!(git(&repo, &["status", "--porcelain"]), "");
+        assert_eq!(git(&repo, &["rev-parse", "HEAD"]), head);
+        assert_eq!(git(&remote, &["rev-parse", "main"]), remote_head);
+
+        // An explicit --file wins over the cwd default
+        let explicit = cwd.join("elsewhere.toml");
+        let target = export_to_file(&content, "zed", Some(explicit.clone()), &cwd).unwrap();
+        assert_eq!(target, explicit);
+    }
+}
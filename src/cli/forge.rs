@@ -0,0 +1,343 @@
+use crate::config::LocalConfig;
+use crate::error::{DriftersError, Result};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+
+// drifters' own repository, where presets and releases live
+// Expected format: https://<host>/<owner>/<repo>
+const CARGO_REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
+
+/// One entry of a repository directory listing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirEntry {
+    pub name: String,
+    pub is_file: bool,
+}
+
+/// A file attached to a release.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Asset {
+    pub name: String,
+    pub url: String,
+}
+
+/// The newest release of a repository.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Release {
+    /// Tag with any leading `v` removed
+    pub version: String,
+    /// Release page, for people
+    pub html_url: String,
+    pub assets: Vec<Asset>,
+}
+
+impl Release {
+    /// Download URL of the asset called `name`.
+    pub fn asset_url(&self, name: &str) -> Option<&str> {
+        self.assets.iter().find(|a| a.name == name).map(|a| a.url.as_str())
+    }
+}
+
+/// The parts of a code forge's API that presets and self-update use.
+pub trait Forge {
+    /// Web address of the repository, for messages.
+    fn repo_url(&self) -> String;
+    /// Files and directories directly under `path`.
+    fn list_dir(&self, path: &str) -> Result<Vec<DirEntry>>;
+    /// Content of the file at `path` on the default branch.
+    fn fetch_file(&self, path: &str) -> Result<String>;
+    /// The latest published release.
+    fn latest_release(&self) -> Result<Release>;
+}
+
+/// The forge hosting drifters itself, using `forge_api_url` from the local
+/// config when one is set.
+pub fn drifters_forge(client: Client) -> Result<Box<dyn Forge>> {
+    let api_url = LocalConfig::load().ok().and_then(|c| c.forge_api_url);
+    forge_for(CARGO_REPOSITORY, api_url.as_deref(), client)
+}
+
+/// The forge for `repo_url`, detected from its host. `api_url` overrides the
+/// API base, for self-hosted instances; one ending in `/api/v4` is GitLab.
+pub fn forge_for(repo_url: &str, api_url: Option<&str>, client: Client) -> Result<Box<dyn Forge>> {
+    let (host, project) = parse_repo_url(repo_url)?;
+    let api_url = api_url.map(|url| url.trim_end_matches('/').to_string());
+    match forge_kind(&host, api_url.as_deref()) {
+        Some(ForgeKind::GitHub) => Ok(Box::new(GitHub {
+            api: api_url.unwrap_or_else(|| "https://api.github.com".to_string()),
+            web: format!("https://{}/{}", host, project),
+            project,
+            client,
+        })),
+        Some(ForgeKind::GitLab) => Ok(Box::new(GitLab {
+            api: api_url.unwrap_or_else(|| format!("https://{}/api/v4", host)),
+            web: format!("https://{}/{}", host, project),
+            id: project.replace('/', "%2F"),
+            client,
+        })),
+        None => Err(DriftersError::Config(format!(
+            "Don't know which forge hosts {}: only GitHub and GitLab are supported. \
+             For a self-hosted instance, set forge_api_url in drifters.toml.",
+            repo_url
+        ))),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ForgeKind {
+    GitHub,
+    GitLab,
+}
+
+fn forge_kind(host: &str, api_url: Option<&str>) -> Option<ForgeKind> {
+    if host == "github.com" {
+        return Some(ForgeKind::GitHub);
+    }
+    if host == "gitlab.com" || host.starts_with("gitlab.") {
+        return Some(ForgeKind::GitLab);
+    }
+    // Self-hosted: GitLab's API lives under /api/v4, GitHub Enterprise's
+    // under /api/v3
+    api_url.map(|api| {
+        if api.ends_with("/api/v4") {
+            ForgeKind::GitLab
+        } else {
+            ForgeKind::GitHub
+        }
+    })
+}
+
+/// Split a repository URL (`https://host/owner/repo` or `git@host:owner/repo`)
+/// into host and project path. GitLab project paths may include subgroups.
+fn parse_repo_url(url: &str) -> Result<(String, String)> {
+    let invalid = || DriftersError::Config(format!("Invalid repository URL: {}", url));
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))
+        .map(|rest| rest.split_once('/'))
+        .or_else(|| url.strip_prefix("git@").map(|rest| rest.split_once(':')))
+        .flatten()
+        .ok_or_else(invalid)?;
+    let (host, path) = rest;
+    let path = path.trim_end_matches('/').trim_end_matches(".git");
+    if host.is_empty() || path.split('/').filter(|part| !part.is_empty()).count() < 2 {
+        return Err(invalid());
+    }
+    Ok((host.to_string(), path.to_string()))
+}
+
+/// GET `url` and fail with the status and URL if it isn't a success.
+fn get(client: &Client, url: &str) -> Result<reqwest::blocking::Response> {
+    let response = client.get(url).send()?;
+    if !response.status().is_success() {
+        return Err(DriftersError::Config(format!(
+            "Request failed ({})\nURL: {}",
+            response.status(),
+            url
+        )));
+    }
+    Ok(response)
+}
+
+/// Both APIs return file content base64 encoded, wrapped at 60 columns.
+fn decode_content(content: &str) -> Result<String> {
+    use base64::Engine;
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(content.replace('\n', ""))
+        .map_err(|e| DriftersError::Config(format!("Failed to decode base64 content: {}", e)))?;
+    String::from_utf8(bytes)
+        .map_err(|e| DriftersError::Config(format!("Failed to decode UTF-8 content: {}", e)))
+}
+
+#[derive(Deserialize)]
+struct FileContent {
+    content: String,
+}
+
+// ─── GitHub ──────────────────────────────────────────────────────────────────
+
+struct GitHub {
+    api: String,
+    web: String,
+    /// `owner/repo`
+    project: String,
+    client: Client,
+}
+
+impl Forge for GitHub {
+    fn repo_url(&self) -> String {
+        self.web.clone()
+    }
+
+    fn list_dir(&self, path: &str) -> Result<Vec<DirEntry>> {
+        #[derive(Deserialize)]
+        struct Item {
+            name: String,
+            #[serde(rename = "type")]
+            file_type: String,
+        }
+
+        let url = format!("{}/repos/{}/contents/{}", self.api, self.project, path);
+        let items: Vec<Item> = get(&self.client, &url)?.json()?;
+        Ok(items
+            .into_iter()
+            .map(|item| DirEntry { is_file: item.file_type == "file", name: item.name })
+            .collect())
+    }
+
+    fn fetch_file(&self, path: &str) -> Result<String> {
+        let url = format!("{}/repos/{}/contents/{}", self.api, self.project, path);
+        let file: FileContent = get(&self.client, &url)?.json()?;
+        decode_content(&file.content)
+    }
+
+    fn latest_release(&self) -> Result<Release> {
+        #[derive(Deserialize)]
+        struct GitHubAsset {
+            name: String,
+            browser_download_url: String,
+        }
+        #[derive(Deserialize)]
+        struct GitHubRelease {
+            tag_name: String,
+            html_url: String,
+            #[serde(default)]
+            assets: Vec<GitHubAsset>,
+        }
+
+        let url = format!("{}/repos/{}/releases/latest", self.api, self.project);
+        let release: GitHubRelease = get(&self.client, &url)?.json()?;
+        Ok(Release {
+            version: release.tag_name.trim_start_matches('v').to_string(),
+            html_url: release.html_url,
+            assets: release
+                .assets
+                .into_iter()
+                .map(|a| Asset { name: a.name, url: a.browser_download_url })
+                .collect(),
+        })
+    }
+}
+
+// ─── GitLab ──────────────────────────────────────────────────────────────────
+
+struct GitLab {
+    api: String,
+    web: String,
+    /// URL-encoded project path, as the API takes it
+    id: String,
+    client: Client,
+}
+
+impl Forge for GitLab {
+    fn repo_url(&self) -> String {
+        self.web.clone()
+    }
+
+    fn list_dir(&self, path: &str) -> Result<Vec<DirEntry>> {
+        #[derive(Deserialize)]
+        struct Item {
+            name: String,
+            #[serde(rename = "type")]
+            item_type: String,
+        }
+
+        let url = format!(
+            "{}/projects/{}/repository/tree?path={}&per_page=100",
+            self.api, self.id, path
+        );
+        let items: Vec<Item> = get(&self.client, &url)?.json()?;
+        Ok(items
+            .into_iter()
+            .map(|item| DirEntry { is_file: item.item_type == "blob", name: item.name })
+            .collect())
+    }
+
+    fn fetch_file(&self, path: &str) -> Result<String> {
+        let url = format!(
+            "{}/projects/{}/repository/files/{}?ref=HEAD",
+            self.api,
+            self.id,
+            path.replace('/', "%2F")
+        );
+        let file: FileContent = get(&self.client, &url)?.json()?;
+        decode_content(&file.content)
+    }
+
+    fn latest_release(&self) -> Result<Release> {
+        #[derive(Deserialize)]
+        struct GitLabLink {
+            name: String,
+            url: String,
+            direct_asset_url: Option<String>,
+        }
+        #[derive(Deserialize, Default)]
+        struct GitLabAssets {
+            #[serde(default)]
+            links: Vec<GitLabLink>,
+        }
+        #[derive(Deserialize)]
+        struct GitLabReleaseLinks {
+            #[serde(rename = "self")]
+            html: String,
+        }
+        #[derive(Deserialize)]
+        struct GitLabRelease {
+            tag_name: String,
+            #[serde(rename = "_links")]
+            links: GitLabReleaseLinks,
+            #[serde(default)]
+            assets: GitLabAssets,
+        }
+
+        let url = format!("{}/projects/{}/releases/permalink/latest", self.api, self.id);
+        let release: GitLabRelease = get(&self.client, &url)?.json()?;
+        Ok(Release {
+            version: release.tag_name.trim_start_matches('v').to_string(),
+            html_url: release.links.html,
+            assets: release
+                .assets
+                .links
+                .into_iter()
+                .map(|l| Asset { name: l.name, url: l.direct_asset_url.unwrap_or(l.url) })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forge_is_detected_from_host_or_api_url() {
+        // drifters' own repository, from Cargo.toml
+        assert_eq!(
+            parse_repo_url(CARGO_REPOSITORY).unwrap(),
+            ("github.com".to_string(), "tjirsch/drifters".to_string())
+        );
+        assert_eq!(
+            parse_repo_url("git@gitlab.com:team/dotfiles/presets.git").unwrap(),
+            ("gitlab.com".to_string(), "team/dotfiles/presets".to_string())
+        );
+        assert!(parse_repo_url("https://github.com/tjirsch").is_err());
+
+        assert_eq!(forge_kind("github.com", None), Some(ForgeKind::GitHub));
+        assert_eq!(forge_kind("gitlab.com", None), Some(ForgeKind::GitLab));
+        assert_eq!(forge_kind("gitlab.example.com", None), Some(ForgeKind::GitLab));
+        assert_eq!(forge_kind("git.example.com", None), None);
+        assert_eq!(
+            forge_kind("git.example.com", Some("https://git.example.com/api/v4")),
+            Some(ForgeKind::GitLab)
+        );
+        assert_eq!(
+            forge_kind("ghe.example.com", Some("https://ghe.example.com/api/v3")),
+            Some(ForgeKind::GitHub)
+        );
+
+        let client = Client::new();
+        let forge = forge_for("https://gitlab.com/team/presets/", None, client).unwrap();
+        assert_eq!(forge.repo_url(), "https://gitlab.com/team/presets");
+        assert!(forge_for("https://bitbucket.org/team/presets", None, Client::new()).is_err());
+    }
+}
@@ -1,6 +1,7 @@
-use crate::config::LocalConfig;
-use crate::error::Result;
-use crate::git::EphemeralRepoGuard;
+use crate::config::{resolve_fileset, LocalConfig, SyncRules};
+use crate::error::{DriftersError, Result};
+use crate::git::{ensure_full_history, main_branch, EphemeralRepoGuard};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 pub fn show_history_rules(limit: usize) -> Result<()> {
@@ -10,6 +11,7 @@ pub fn show_history_rules(limit: usize) -> Result<()> {
     let config = LocalConfig::load()?;
     let repo_guard = EphemeralRepoGuard::new(&config)?;
     let repo_path = repo_guard.path();
+    ensure_full_history(repo_path)?;
 
     println!("\nSync Rules History");
     println!("{}", "=".repeat(60));
@@ -58,6 +60,7 @@ pub fn show_history_app(app_name: String, limit: usize) -> Result<()> {
     let config = LocalConfig::load()?;
     let repo_guard = EphemeralRepoGuard::new(&config)?;
     let repo_path = repo_guard.path();
+    ensure_full_history(repo_path)?;
 
     println!("\nHistory for App: {}", app_name);
     println!("{}", "=".repeat(60));
@@ -103,11 +106,122 @@ pub fn show_history_app(app_name: String, limit: usize) -> Result<()> {
     Ok(())
 }
 
+/// Show the commits that changed one of an app's files, on main and on
+/// every machine branch, newest first.
+pub fn show_history_file(app_name: String, filename: String, limit: usize) -> Result<()> {
+    log::info!("Showing history of {}/{}", app_name, filename);
+
+    // Load local config and repo
+    let config = LocalConfig::load()?;
+    let repo_guard = EphemeralRepoGuard::new(&config)?;
+    let repo_path = repo_guard.path();
+    ensure_full_history(repo_path)?;
+
+    let rules = SyncRules::load(repo_path)?;
+    let app_config = rules
+        .apps
+        .get(&app_name)
+        .ok_or_else(|| DriftersError::AppNotFound(app_name.clone()))?;
+    let fileset = resolve_fileset(
+        app_config,
+        &rules.ignore,
+        &rules.groups,
+        &config.machine_id,
+        std::env::consts::OS,
+    )?;
+
+    println!("\nHistory for {}/{}", app_name, filename);
+    println!("{}", "=".repeat(60));
+
+    // Every branch the file is synced through; the subject names the
+    // machine that pushed or merged each change
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .arg("log")
+        .arg("--format=%h %cs %s")
+        .arg(format!("-{}", limit))
+        .arg(format!("origin/{}", main_branch()))
+        .arg("--remotes=origin/machines/*")
+        .arg("--")
+        .arg(file_pathspec(&app_name, &filename))
+        .output()?;
+
+    if !output.status.success() {
+        let err = String::from_utf8_lossy(&output.stderr);
+        eprintln!("Failed to get git log");
+        eprintln!("Repository: {:?}", repo_path);
+        eprintln!("Error: {}", err);
+        return Err(DriftersError::Config("Unable to retrieve git history".to_string()));
+    }
+    let log_output = String::from_utf8_lossy(&output.stdout);
+    check_app_file(&app_name, &filename, &fileset, !log_output.trim().is_empty())?;
+    if log_output.trim().is_empty() {
+        println!("No history found for {}/{} (not pushed yet)", app_name, filename);
+    } else {
+        println!("{}", log_output);
+    }
+
+    println!("\nTo see details:");
+    println!("  drifters history file {} {} --commit <hash>", app_name, filename);
+
+    Ok(())
+}
+
+/// Show what a commit changed in one of an app's files.
+pub fn show_file_commit_diff(commit: String, app_name: String, filename: String) -> Result<()> {
+    let config = LocalConfig::load()?;
+    let repo_guard = EphemeralRepoGuard::new(&config)?;
+    let repo_path = repo_guard.path();
+    ensure_full_history(repo_path)?;
+
+    let title = format!("Changes in commit {} ({}/{})", commit, app_name, filename);
+    print_commit_diff(repo_path, &commit, &title, &file_pathspec(&app_name, &filename))
+}
+
+/// Where drifters keeps an app's file in the repo, on every branch.
+fn file_pathspec(app_name: &str, filename: &str) -> String {
+    format!("apps/{}/{}", app_name, filename)
+}
+
+/// Check that `filename` is one of the app's files: in its fileset on this
+/// machine or, for files only other machines have, in the repo's history.
+/// Files are stored by name, so a path is pointed at its name.
+fn check_app_file(
+    app_name: &str,
+    filename: &str,
+    fileset: &[PathBuf],
+    has_history: bool,
+) -> Result<()> {
+    let names: Vec<String> = fileset
+        .iter()
+        .filter_map(|path| path.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .collect();
+    if has_history || names.iter().any(|name| name == filename) {
+        return Ok(());
+    }
+
+    let mut message = format!("'{}' is not a file of app '{}'", filename, app_name);
+    if let Some(name) = Path::new(filename).file_name().filter(|name| *name != filename) {
+        message.push_str(&format!(
+            "; files are named without their directory, try '{}'",
+            name.to_string_lossy()
+        ));
+    } else if names.is_empty() {
+        message.push_str(" (it has no files on this machine)");
+    } else {
+        message.push_str(&format!(". Its files on this machine: {}", names.join(", ")));
+    }
+    Err(DriftersError::Config(message))
+}
+
 pub fn show_commit_diff(commit: String, app_name: Option<String>) -> Result<()> {
     // Load local config and repo
     let config = LocalConfig::load()?;
     let repo_guard = EphemeralRepoGuard::new(&config)?;
     let repo_path = repo_guard.path();
+    ensure_full_history(repo_path)?;
 
     let title = if let Some(app) = &app_name {
         format!("Changes in commit {} (app: {})", commit, app)
@@ -115,6 +229,11 @@ pub fn show_commit_diff(commit: String, app_name: Option<String>) -> Result<()>
         format!("Changes in commit {}", commit)
     };
 
+    print_commit_diff(repo_path, &commit, &title, ".drifters/sync-rules.toml")
+}
+
+/// Print `title` and what `commit` changed under `pathspec`.
+fn print_commit_diff(repo_path: &Path, commit: &str, title: &str, pathspec: &str) -> Result<()> {
     println!("\n{}", title);
     println!("{}", "=".repeat(60));
 
@@ -123,9 +242,9 @@ pub fn show_commit_diff(commit: String, app_name: Option<String>) -> Result<()>
         .arg("-C")
         .arg(repo_path)
         .arg("show")
-        .arg(&commit)
+        .arg(commit)
         .arg("--")
-        .arg(".drifters/sync-rules.toml")
+        .arg(pathspec)
         .output()?;
 
     if output.status.success() {
@@ -144,3 +263,23 @@ pub fn show_commit_diff(commit: String, app_name: Option<String>) -> Result<()>
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_app_file_accepts_fileset_or_history_and_explains_misses() {
+        let fileset = vec![PathBuf::from("/home/me/.zshrc"), PathBuf::from("/home/me/.zshenv")];
+        assert!(check_app_file("zsh", ".zshrc", &fileset, false).is_ok());
+        // Only another machine has it, but it was pushed
+        assert!(check_app_file("zsh", ".zprofile", &fileset, true).is_ok());
+
+        let err = check_app_file("zsh", ".zprofile", &fileset, false).unwrap_err().to_string();
+        assert!(err.contains("Its files on this machine: .zshrc, .zshenv"), "{}", err);
+        let err = check_app_file("zsh", "~/.zshrc", &fileset, false).unwrap_err().to_string();
+        assert!(err.contains("try '.zshrc'"), "{}", err);
+        let err = check_app_file("zsh", ".zshrc", &[], false).unwrap_err().to_string();
+        assert!(err.contains("no files on this machine"), "{}", err);
+    }
+}
@@ -1,20 +1,141 @@
 use crate::error::Result;
 
+/// State file the background pull writes its output to. The next prompt reads
+/// and removes it. Expressed relative to `$HOME` so the snippet stays valid if
+/// the home directory moves.
+const HOOK_STATE_FILE: &str = "$HOME/.config/drifters/hook-pull.log";
+
 pub fn generate_hook() -> Result<()> {
     // Output shell code that will be eval'd
-    println!(
+    print!("{}", hook_script());
+    Ok(())
+}
+
+/// Build the bash/zsh snippet emitted by `drifters hook`.
+///
+/// The pull runs fully detached so the prompt never waits on the network. Its
+/// output goes to a temp file that is renamed into place only once the pull
+/// has finished, so the precmd reporter never reads a half-written result.
+fn hook_script() -> String {
+    format!(
         r#"# Drifters auto-sync hook
-# This runs in the background when you start a new shell
+# Pulls in the background when you start a new shell and reports the result
+# at the next prompt, so shell startup never blocks on the network.
+
+_drifters_hook_state="{state}"
 
 drifters_auto_sync() {{
-    # Run in background, suppress all output
-    (drifters pull-app >/dev/null 2>&1 &)
+    mkdir -p "$(dirname "$_drifters_hook_state")"
+    rm -f "$_drifters_hook_state"
+    # Detached subshell: no job-control noise, survives the parent prompt
+    ( ( drifters pull-app </dev/null >"$_drifters_hook_state.tmp" 2>&1
+        mv -f "$_drifters_hook_state.tmp" "$_drifters_hook_state" ) >/dev/null 2>&1 & )
+}}
+
+_drifters_hook_report() {{
+    [ -f "$_drifters_hook_state" ] || return 0
+    if grep -q "Successfully pulled" "$_drifters_hook_state"; then
+        # Only the message: the marker before it is `✓` or, with --plain, `[ok]`
+        echo "drifters: $(grep -o "Successfully pulled.*" "$_drifters_hook_state")"
+    elif grep -q "^Error:" "$_drifters_hook_state"; then
+        # Keep the log for inspection, but move it aside so we report only once
+        mv -f "$_drifters_hook_state" "${{_drifters_hook_state%.log}}.failed"
+        echo "drifters: background pull failed — see ${{_drifters_hook_state%.log}}.failed"
+        return 0
+    fi
+    rm -f "$_drifters_hook_state"
 }}
 
+if [ -n "$ZSH_VERSION" ]; then
+    autoload -Uz add-zsh-hook
+    add-zsh-hook precmd _drifters_hook_report
+elif [ -n "$BASH_VERSION" ]; then
+    case ";$PROMPT_COMMAND;" in
+        *";_drifters_hook_report;"*) ;;
+        *) PROMPT_COMMAND="_drifters_hook_report${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}" ;;
+    esac
+fi
+
 # Run on shell startup
 drifters_auto_sync
-"#
-    );
+"#,
+        state = HOOK_STATE_FILE
+    )
+}
 
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hook_backgrounds_pull() {
+        let script = hook_script();
+        let pull_line = script
+            .lines()
+            .find(|l| l.contains("drifters pull-app"))
+            .expect("hook must run pull-app");
+        // stdin detached so a prompt can never block, output captured to state
+        assert!(pull_line.contains("</dev/null"));
+        assert!(pull_line.contains("$_drifters_hook_state.tmp"));
+        assert!(script.contains(">/dev/null 2>&1 & )"));
+    }
+
+    #[test]
+    fn test_hook_reports_from_state_file_at_next_prompt() {
+        let script = hook_script();
+        assert!(script.contains(&format!("_drifters_hook_state=\"{}\"", HOOK_STATE_FILE)));
+        assert!(script.contains("[ -f \"$_drifters_hook_state\" ] || return 0"));
+        assert!(script.contains("add-zsh-hook precmd _drifters_hook_report"));
+        assert!(script.contains("PROMPT_COMMAND=\"_drifters_hook_report"));
+    }
+
+    #[test]
+    fn test_hook_failure_report_clears_state_file() {
+        let script = hook_script();
+        let report = script
+            .split("_drifters_hook_report() {")
+            .nth(1)
+            .and_then(|rest| rest.split("\n}\n").next())
+            .expect("hook must define the reporter");
+        let error_branch = report
+            .split("elif grep -q \"^Error:\"")
+            .nth(1)
+            .and_then(|rest| rest.split("return 0").next())
+            .expect("reporter must handle a failed pull");
+        // The state file must be moved aside before returning, otherwise the
+        // failure is reported again at every prompt
+        assert!(error_branch
+            .contains("mv -f \"$_drifters_hook_state\" \"${_drifters_hook_state%.log}.failed\""));
+    }
+
+    /// Run the reporter from the generated snippet against a state file.
+    #[cfg(unix)]
+    fn run_report(state: &str) -> String {
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("hook-pull.log");
+        std::fs::write(&state_path, state).unwrap();
+        let script = hook_script();
+        let start = script.find("_drifters_hook_report() {").unwrap();
+        let end = start + script[start..].find("\n}\n").unwrap() + 3;
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(format!("{}_drifters_hook_report", &script[start..end]))
+            .env("_drifters_hook_state", &state_path)
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_hook_report_strips_plain_and_fancy_markers() {
+        assert_eq!(
+            run_report("Pulling...\n\n✓ Successfully pulled 2 file(s)\n"),
+            "drifters: Successfully pulled 2 file(s)\n"
+        );
+        assert_eq!(
+            run_report("Pulling...\n\n[ok] Successfully pulled 2 file(s)\n"),
+            "drifters: Successfully pulled 2 file(s)\n"
+        );
+    }
 }
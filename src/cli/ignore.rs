@@ -0,0 +1,101 @@
+use crate::cli::style::ok;
+use crate::config::{LocalConfig, SyncRules, DEFAULT_IGNORE};
+use crate::error::{DriftersError, Result};
+use crate::git::{commit_and_push, EphemeralRepoGuard};
+
+/// Add a pattern to the repo-wide ignore list, so no app syncs files
+/// matching it on any machine.
+pub fn add_ignore(pattern: String) -> Result<()> {
+    let pattern = pattern.trim().to_string();
+    if pattern.is_empty() {
+        return Err(DriftersError::Config("Ignore pattern cannot be empty.".to_string()));
+    }
+    glob::Pattern::new(&pattern).map_err(|e| {
+        DriftersError::Config(format!("Invalid ignore pattern '{}': {}", pattern, e))
+    })?;
+
+    // Load local config
+    let config = LocalConfig::load()?;
+
+    // Set up ephemeral repo
+    println!("Setting up repository...");
+    let repo_guard = EphemeralRepoGuard::new(&config)?;
+    let repo_path = repo_guard.path();
+
+    // Load sync rules
+    let mut rules = SyncRules::load(repo_path)?;
+
+    if !add_pattern(&mut rules, &pattern) {
+        println!("'{}' is already ignored — no changes made.", pattern);
+        return Ok(());
+    }
+
+    // Save rules
+    rules.save(repo_path)?;
+    println!("\n{} Added '{}' to the global ignore list", ok(), pattern);
+
+    // Commit and push
+    println!("\nCommitting changes...");
+    commit_and_push(
+        repo_path,
+        &format!("Ignore {} from {}", pattern, config.machine_id),
+    )?;
+
+    println!("{} Changes committed and pushed", ok());
+    println!("\nFiles matching it will no longer be pushed by any app, on any machine");
+
+    Ok(())
+}
+
+/// Show the built-in and the repo's ignore patterns.
+pub fn list_ignore() -> Result<()> {
+    let config = LocalConfig::load()?;
+
+    println!("Fetching latest sync rules...");
+    let repo_guard = EphemeralRepoGuard::new(&config)?;
+    let rules = SyncRules::load(repo_guard.path())?;
+
+    println!("\nBuilt-in:");
+    for pattern in DEFAULT_IGNORE {
+        println!("  - {}", pattern);
+    }
+    println!("\nFrom sync-rules.toml:");
+    if rules.ignore.is_empty() {
+        println!("  (none — add one with 'drifters ignore add <pattern>')");
+    }
+    for pattern in &rules.ignore {
+        println!("  - {}", pattern);
+    }
+
+    Ok(())
+}
+
+/// Append `pattern` to the rules' ignore list. Returns `false` if it is
+/// already there or built in.
+fn add_pattern(rules: &mut SyncRules, pattern: &str) -> bool {
+    if DEFAULT_IGNORE.contains(&pattern) || rules.ignore.iter().any(|p| p == pattern) {
+        return false;
+    }
+    rules.ignore.push(pattern.to_string());
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_pattern_skips_duplicates_and_round_trips() {
+        let mut rules = SyncRules::new();
+        assert!(add_pattern(&mut rules, "credentials.json"));
+        assert!(!add_pattern(&mut rules, "credentials.json"));
+        assert!(!add_pattern(&mut rules, "*.pem"), "built-in patterns are not repeated");
+
+        let dir = tempfile::tempdir().unwrap();
+        rules.apps.insert("zsh".to_string(), Default::default());
+        rules.save(dir.path()).unwrap();
+        let saved = std::fs::read_to_string(dir.path().join(".drifters/sync-rules.toml")).unwrap();
+        assert!(saved.starts_with("ignore = [\"credentials.json\"]"), "{}", saved);
+        assert_eq!(SyncRules::load(dir.path()).unwrap().ignore, vec!["credentials.json"]);
+    }
+}
@@ -1,44 +1,65 @@
-use crate::config::{LocalConfig, SyncRules};
+use crate::cli::common::http_client;
+use crate::cli::style::{ok, warn};
+use crate::config::{AppConfig, LocalConfig, MachineRegistry, SyncRules, Warning};
 use crate::error::{DriftersError, Result};
-use crate::git::{commit_and_push, EphemeralRepoGuard};
+use crate::git::{commit_and_push, confirm_operation, EphemeralRepoGuard};
 use std::fs;
-use std::path::PathBuf;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 
-pub fn import_app(app_name: String, file_path: Option<PathBuf>) -> Result<()> {
-    // Load local config and repo
-    let config = LocalConfig::load()?;
-    let repo_guard = EphemeralRepoGuard::new(&config)?;
-    let repo_path = repo_guard.path();
+/// Largest app definition we are willing to download with `--from-url`.
+/// Real definitions are a few KiB; anything bigger is almost certainly the
+/// wrong URL (an HTML page, a binary, …).
+const MAX_URL_IMPORT_BYTES: usize = 256 * 1024;
 
-    // Determine file path: use provided or default to <app>.toml in current directory
-    let actual_file_path = match file_path {
-        Some(path) => path,
-        None => {
-            std::env::current_dir()?.join(format!("{}.toml", app_name))
+pub fn import_app(
+    app_name: String,
+    file_path: Option<PathBuf>,
+    from_url: Option<String>,
+    yes: bool,
+) -> Result<()> {
+    // Resolve the app definition first so a bad file or URL fails before we
+    // take the repository lock.
+    let via_url = from_url.is_some();
+    let (app_config, source) = match from_url {
+        Some(url) => {
+            log::info!("Importing app '{}' from {}", app_name, url);
+            println!("Fetching app definition from {}...", url);
+            (fetch_app_from_url(&app_name, &url)?, url)
         }
-    };
+        None => {
+            // Determine file path: use provided or default to <app>.toml in current directory
+            let actual_file_path = match file_path {
+                Some(path) => path,
+                None => {
+                    std::env::current_dir()?.join(format!("{}.toml", app_name))
+                }
+            };
 
-    log::info!("Importing app '{}' from {:?}", app_name, actual_file_path);
+            log::info!("Importing app '{}' from {:?}", app_name, actual_file_path);
 
-    // Load the app definition from file
-    let file_content = fs::read_to_string(&actual_file_path)?;
-    let file_rules: SyncRules = toml::from_str(&file_content)?;
+            // Load the app definition from file
+            let (file_content, source) = read_source(&actual_file_path)?;
+            (parse_app_definition(&app_name, &file_content, &source)?, source)
+        }
+    };
 
-    // Get the app config from the file
-    let app_config = file_rules
-        .apps
-        .get(&app_name)
-        .ok_or_else(|| {
-            DriftersError::Config(format!(
-                "App '{}' not found in file {:?}",
-                app_name, actual_file_path
-            ))
-        })?
-        .clone();
+    // Load local config and repo
+    let config = LocalConfig::load()?;
+    let repo_guard = EphemeralRepoGuard::new(&config)?;
+    let repo_path = repo_guard.path();
 
     // Load current sync rules
     let mut rules = SyncRules::load(repo_path)?;
 
+    // Only the imported app is checked: warnings about the rest of the
+    // rules are not this import's doing
+    let mut imported = SyncRules { groups: rules.groups.clone(), ..SyncRules::new() };
+    imported.apps.insert(app_name.clone(), app_config.clone());
+    if !confirm_warnings(&imported, repo_path, yes)? {
+        return Ok(());
+    }
+
     // Check if app already exists
     let is_update = rules.apps.contains_key(&app_name);
 
@@ -49,11 +70,12 @@ pub fn import_app(app_name: String, file_path: Option<PathBuf>) -> Result<()> {
     rules.save(repo_path)?;
 
     let action = if is_update { "Updated" } else { "Added" };
-    println!("\n✓ {} '{}' from {:?}", action, app_name, actual_file_path);
+    println!("\n✓ {} '{}' from {}", action, app_name, source);
 
     // Commit and push
     println!("\nCommitting changes...");
-    let message = format!("{} {} app from file", action, app_name);
+    let origin = if via_url { "URL" } else if source == "stdin" { "stdin" } else { "file" };
+    let message = format!("{} {} app from {}", action, app_name, origin);
     commit_and_push(repo_path, &message)?;
 
     println!("✓ Changes committed and pushed");
@@ -65,12 +87,9 @@ pub fn import_app(app_name: String, file_path: Option<PathBuf>) -> Result<()> {
     Ok(())
 }
 
-pub fn import_rules(file_path: Option<PathBuf>) -> Result<()> {
-    // Load local config and repo
-    let config = LocalConfig::load()?;
-    let repo_guard = EphemeralRepoGuard::new(&config)?;
-    let repo_path = repo_guard.path();
-
+/// Replace the repo's sync-rules.toml with a file's (`./sync-rules.toml`
+/// unless one is given; `-` reads stdin).
+pub fn import_rules(file_path: Option<PathBuf>, yes: bool) -> Result<()> {
     // Determine file path: use provided or default to sync-rules.toml in current directory
     let actual_file_path = match file_path {
         Some(path) => path,
@@ -79,23 +98,219 @@ pub fn import_rules(file_path: Option<PathBuf>) -> Result<()> {
 
     log::info!("Importing rules from {:?}", actual_file_path);
 
-    // Load the rules from file
-    let file_content = fs::read_to_string(&actual_file_path)?;
+    // Read and parse the rules before taking the repository lock, so a bad
+    // file (or a stdin that never ends) doesn't hold it
+    let (file_content, source) = read_source(&actual_file_path)?;
     let new_rules: SyncRules = toml::from_str(&file_content)?;
 
+    // Load local config and repo
+    let config = LocalConfig::load()?;
+    let repo_guard = EphemeralRepoGuard::new(&config)?;
+    let repo_path = repo_guard.path();
+
+    if !confirm_warnings(&new_rules, repo_path, yes)? {
+        return Ok(());
+    }
+
     // Save new rules (overwrites existing)
     new_rules.save(repo_path)?;
 
-    println!("\n✓ Imported rules from {:?}", actual_file_path);
+    println!("\n{} Imported rules from {}", ok(), source);
     println!("  {} app(s) imported", new_rules.apps.len());
 
     // Commit and push
     println!("\nCommitting changes...");
-    let message = "Import sync rules from file";
-    commit_and_push(repo_path, message)?;
+    let origin = if source == "stdin" { "stdin" } else { "file" };
+    commit_and_push(repo_path, &format!("Import sync rules from {}", origin))?;
 
     println!("✓ Changes committed and pushed");
     println!("\nRun 'drifters merge-app' to apply the new rules");
 
     Ok(())
 }
+
+/// Validate imported rules, failing on problems. If they have warnings
+/// (see [`SyncRules::validate`] and [`SyncRules::machine_warnings`]), list
+/// them and, unless `yes`, ask whether to import anyway. Returns whether to
+/// go ahead.
+fn confirm_warnings(rules: &SyncRules, repo_path: &Path, yes: bool) -> Result<bool> {
+    let mut warnings: Vec<Warning> = rules.validate()?;
+    warnings.extend(rules.machine_warnings(&MachineRegistry::load(repo_path)?));
+    if warnings.is_empty() {
+        return Ok(true);
+    }
+
+    println!("\n{} The imported rules look off:", warn());
+    for warning in &warnings {
+        println!("  - {}", warning);
+    }
+    if yes {
+        return Ok(true);
+    }
+    if !confirm_operation("\nImport anyway?", false)? {
+        println!("Cancelled; nothing was imported. Pass --yes to import as is.");
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Read a file to import, or stdin for `-`. Returns the content and how to
+/// name its source in messages.
+fn read_source(path: &Path) -> Result<(String, String)> {
+    if path == Path::new("-") {
+        let mut content = String::new();
+        std::io::stdin().read_to_string(&mut content)?;
+        return Ok((content, "stdin".to_string()));
+    }
+    Ok((fs::read_to_string(path)?, format!("{:?}", path)))
+}
+
+/// Download a shared app definition (gist, raw file, …) and extract `app_name`.
+fn fetch_app_from_url(app_name: &str, url: &str) -> Result<AppConfig> {
+    if !url.starts_with("https://") {
+        return Err(DriftersError::Config(format!(
+            "Refusing to import from '{}': only https:// URLs are supported",
+            url
+        )));
+    }
+
+    let client = http_client()?;
+    let response = client.get(url).send()?;
+
+    if !response.status().is_success() {
+        return Err(DriftersError::Config(format!(
+            "Failed to fetch app definition from {}: HTTP {}",
+            url,
+            response.status()
+        )));
+    }
+
+    if let Some(len) = response.content_length() {
+        if len as usize > MAX_URL_IMPORT_BYTES {
+            return Err(DriftersError::Config(format!(
+                "App definition at {} is too large ({} bytes, limit {})",
+                url, len, MAX_URL_IMPORT_BYTES
+            )));
+        }
+    }
+
+    let content_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string());
+
+    // Content-Length is only a hint (absent for chunked responses, and the
+    // server may lie), so cap what we actually read as well.
+    let mut body = Vec::new();
+    response
+        .take(MAX_URL_IMPORT_BYTES as u64 + 1)
+        .read_to_end(&mut body)?;
+    if body.len() > MAX_URL_IMPORT_BYTES {
+        return Err(DriftersError::Config(format!(
+            "App definition at {} is too large (more than {} bytes)",
+            url, MAX_URL_IMPORT_BYTES
+        )));
+    }
+
+    parse_fetched_app(app_name, content_type.as_deref(), &body, url)
+}
+
+/// Validate a downloaded response body and parse the app definition from it.
+///
+/// Split out from `fetch_app_from_url` so the checks can be exercised without
+/// a network round-trip.
+fn parse_fetched_app(
+    app_name: &str,
+    content_type: Option<&str>,
+    body: &[u8],
+    url: &str,
+) -> Result<AppConfig> {
+    if let Some(ct) = content_type {
+        let mime = ct.split(';').next().unwrap_or("").trim().to_lowercase();
+        let acceptable = mime.starts_with("text/plain")
+            || mime == "application/toml"
+            || mime == "text/x-toml"
+            || mime == "application/octet-stream";
+        if !acceptable {
+            return Err(DriftersError::Config(format!(
+                "Unexpected content type '{}' from {} — use the raw file URL \
+                 (e.g. the \"Raw\" link of a gist), not the HTML page",
+                mime, url
+            )));
+        }
+    }
+
+    if body.len() > MAX_URL_IMPORT_BYTES {
+        return Err(DriftersError::Config(format!(
+            "App definition at {} is too large ({} bytes, limit {})",
+            url,
+            body.len(),
+            MAX_URL_IMPORT_BYTES
+        )));
+    }
+
+    let content = std::str::from_utf8(body).map_err(|e| {
+        DriftersError::Config(format!("App definition at {} is not valid UTF-8: {}", url, e))
+    })?;
+
+    parse_app_definition(app_name, content, url)
+}
+
+/// Parse a `SyncRules`-shaped TOML document and pull out one app.
+fn parse_app_definition(app_name: &str, content: &str, source: &str) -> Result<AppConfig> {
+    let rules: SyncRules = toml::from_str(content)?;
+    rules.apps.get(app_name).cloned().ok_or_else(|| {
+        DriftersError::Config(format!("App '{}' not found in {}", app_name, source))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHARED_ZED: &str = r#"
+[apps.zed]
+include = ["~/.config/zed/settings.json"]
+exclude = ["~/.config/zed/db/**"]
+"#;
+
+    #[test]
+    fn test_parse_fetched_app_raw_text() {
+        let app = parse_fetched_app(
+            "zed",
+            Some("text/plain; charset=utf-8"),
+            SHARED_ZED.as_bytes(),
+            "https://example.com/zed.toml",
+        )
+        .unwrap();
+        assert_eq!(app.include, vec!["~/.config/zed/settings.json"]);
+        assert_eq!(app.exclude, vec!["~/.config/zed/db/**"]);
+    }
+
+    #[test]
+    fn test_parse_fetched_app_rejects_html_and_missing_app() {
+        let html = parse_fetched_app(
+            "zed",
+            Some("text/html"),
+            b"<html></html>",
+            "https://gist.github.com/u/abc",
+        );
+        assert!(html.unwrap_err().to_string().contains("content type"));
+
+        let missing = parse_fetched_app(
+            "vscode",
+            Some("text/plain"),
+            SHARED_ZED.as_bytes(),
+            "https://example.com/zed.toml",
+        );
+        assert!(missing.unwrap_err().to_string().contains("not found"));
+    }
+
+    #[test]
+    fn test_parse_fetched_app_rejects_oversized_body() {
+        let body = vec![b'#'; MAX_URL_IMPORT_BYTES + 1];
+        let result = parse_fetched_app("zed", None, &body, "https://example.com/big.toml");
+        assert!(result.unwrap_err().to_string().contains("too large"));
+    }
+}
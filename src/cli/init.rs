@@ -1,34 +1,29 @@
-use crate::config::{LocalConfig, MachineRegistry, SyncRules};
+use crate::cli::common::TempDirGuard;
+use crate::cli::style::ok;
+use crate::config::{validate_machine_id, LocalConfig, MachineRegistry, SyncRules};
 use crate::error::{DriftersError, Result};
-use crate::git::{clone_repo, commit_and_push, create_branch, init_repo, set_remote_origin};
+use crate::git::{
+    clone_repo, commit_and_push, create_branch, git_identity, init_repo, main_branch,
+    prompt_attempts, set_commit_author, set_main_branch, set_remote_origin,
+};
 use std::io::{self, Write};
 use std::path::PathBuf;
 
-/// RAII guard that deletes a directory tree on Drop.
-struct TempDirGuard(PathBuf);
-
-impl Drop for TempDirGuard {
-    fn drop(&mut self) {
-        if self.0.exists() {
-            if let Err(e) = std::fs::remove_dir_all(&self.0) {
-                log::warn!("Failed to clean up temp dir {:?}: {}", self.0, e);
-            } else {
-                log::debug!("Cleaned up temporary repo at {:?}", self.0);
-            }
-        }
-    }
-}
-
-pub fn initialize(repo_url: String) -> Result<()> {
+pub fn initialize(repo_url: String, branch: Option<String>) -> Result<()> {
     log::info!("Initializing drifters with repo: {}", repo_url);
 
     // Check if already initialized
-    if let Ok(_) = LocalConfig::load() {
+    if LocalConfig::load().is_ok() {
         return Err(DriftersError::Config(
             "Drifters already initialized. Check ~/.config/drifters/drifters.toml".to_string(),
         ));
     }
 
+    if let Some(branch) = &branch {
+        validate_branch(branch)?;
+        set_main_branch(branch);
+    }
+
     // Detect machine ID (hostname)
     let detected_id = LocalConfig::detect_machine_id();
     println!("Detected machine: {} ({})", detected_id, std::env::consts::OS);
@@ -44,7 +39,7 @@ pub fn initialize(repo_url: String) -> Result<()> {
     if !repo_path.exists() {
         println!("Cloning repository...");
 
-        match clone_repo(&repo_url, &repo_path) {
+        match clone_repo(&repo_url, &repo_path, false) {
             Ok(_) => {
                 println!("✓ Repository cloned successfully");
             }
@@ -78,7 +73,12 @@ pub fn initialize(repo_url: String) -> Result<()> {
     let machine_branch = format!("machines/{}", machine_id);
 
     // Create local config
-    let local_config = LocalConfig::new(machine_id.clone(), repo_url.clone());
+    let mut local_config = LocalConfig::new(machine_id.clone(), repo_url.clone());
+    local_config.branch = main_branch().to_string();
+    let (name, email) = prompt_missing_identity(&repo_path)?;
+    set_commit_author(name.as_deref(), email.as_deref());
+    local_config.git_author_name = name;
+    local_config.git_author_email = email;
     local_config.save()?;
     println!("✓ Local config saved to {:?}", LocalConfig::config_file_path()?);
 
@@ -96,9 +96,9 @@ pub fn initialize(repo_url: String) -> Result<()> {
     }
 
     // Commit and push to main first (machine registration must be on main)
-    println!("\nCommitting changes to main...");
+    println!("\nCommitting changes to {}...", main_branch());
     commit_and_push(&repo_path, &format!("Initialize drifters on {}", machine_id))?;
-    println!("✓ Changes committed and pushed to main");
+    println!("{} Changes committed and pushed to {}", ok(), main_branch());
 
     // Create the machine branch from main
     println!("Creating machine branch '{}'...", machine_branch);
@@ -137,7 +137,10 @@ pub fn initialize(repo_url: String) -> Result<()> {
 }
 
 fn resolve_machine_id(detected: &str, registry: &MachineRegistry) -> Result<String> {
-    if !registry.machines.contains_key(detected) {
+    if let Err(e) = validate_machine_id(detected) {
+        println!("⚠️  {} (detected from hostname)", e);
+        println!("Please choose a different ID for this machine.");
+    } else if !registry.machines.contains_key(detected) {
         print!("Use machine ID '{}' ? [Y/n]: ", detected);
         io::stdout().flush()?;
         let mut input = String::new();
@@ -155,29 +158,70 @@ fn resolve_machine_id(detected: &str, registry: &MachineRegistry) -> Result<Stri
         println!("Please choose a unique ID for this machine.");
     }
 
-    for attempt in 1..=3usize {
-        print!("Enter a unique machine ID (attempt {}/3): ", attempt);
+    let attempts = prompt_attempts();
+    for attempt in 1..=attempts {
+        print!("Enter a unique machine ID (attempt {}/{}): ", attempt, attempts);
         io::stdout().flush()?;
         let mut custom = String::new();
         io::stdin().read_line(&mut custom)?;
-        let custom = custom.trim().to_string();
-
-        if custom.is_empty() {
-            eprintln!("  Machine ID cannot be empty.");
-        } else if custom.contains('/') || custom.contains('\\') {
-            eprintln!("  Machine ID cannot contain '/' or '\\'.");
-        } else if registry.machines.contains_key(&custom) {
-            eprintln!("  '{}' is already taken.", custom);
-        } else {
-            return Ok(custom);
+
+        match validate_machine_id(&custom) {
+            Err(e) => eprintln!("  {}", e),
+            Ok(custom) if registry.machines.contains_key(&custom) => {
+                eprintln!("  '{}' is already taken.", custom);
+            }
+            Ok(custom) => return Ok(custom),
+        }
+    }
+
+    Err(DriftersError::Config(format!(
+        "Could not choose a unique machine ID after {} attempts. \
+         Re-run `drifters init` and pick a different ID.",
+        attempts
+    )))
+}
+
+/// Ask for whatever git can't say about who is committing, so commits don't
+/// go out as "Drifters User". Empty answers leave that part unset.
+fn prompt_missing_identity(repo_path: &PathBuf) -> Result<(Option<String>, Option<String>)> {
+    let (git_name, git_email) = git_identity(repo_path);
+    let ask = |missing: bool, prompt: &str| -> Result<Option<String>> {
+        if !missing {
+            return Ok(None);
         }
+        print!("{}", prompt);
+        io::stdout().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        Ok(Some(input.trim().to_string()).filter(|s| !s.is_empty()))
+    };
+
+    if git_name.is_none() || git_email.is_none() {
+        println!("git has no user.name / user.email; drifters' commits need an author.");
     }
+    let name = ask(git_name.is_none(), "Author name for drifters' commits: ")?;
+    let email = ask(git_email.is_none(), "Author email for drifters' commits: ")?;
+    Ok((name, email))
+}
 
-    Err(DriftersError::Config(
-        "Could not choose a unique machine ID after 3 attempts. \
-         Re-run `drifters init` and pick a different ID."
-            .to_string(),
-    ))
+/// A usable shared branch name: valid for git, and not one of the
+/// per-machine branches.
+fn validate_branch(branch: &str) -> Result<()> {
+    let valid = std::process::Command::new("git")
+        .args(["check-ref-format", "--branch", branch])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false);
+    if !valid {
+        return Err(DriftersError::Config(format!("'{}' is not a valid branch name", branch)));
+    }
+    if branch.starts_with("machines/") {
+        return Err(DriftersError::Config(format!(
+            "'{}' is reserved for machine branches; pick another branch name",
+            branch
+        )));
+    }
+    Ok(())
 }
 
 fn get_repo_path() -> Result<PathBuf> {
@@ -1,6 +1,9 @@
-use crate::config::{resolve_fileset, LocalConfig, SyncRules};
+use crate::cli::export::render_app_export;
+use crate::config::{resolve_fileset_with_ignored, LocalConfig, SyncMode, SyncRules};
 use crate::error::Result;
 use crate::git::EphemeralRepoGuard;
+use crate::parser::sections::{comment_syntax_for, describe_sections};
+use std::fs;
 
 pub fn list_apps(filter_app: Option<String>) -> Result<()> {
     log::info!("Listing apps");
@@ -79,16 +82,33 @@ pub fn list_apps(filter_app: Option<String>) -> Result<()> {
             }
         }
 
-        // Show machine-specific overrides for this machine
-        if let Some(machine_override) = app_config.machines.get(&config.machine_id) {
+        // Show which files sync whole rather than around exclude sections
+        if app_config.sync_mode != SyncMode::Markers {
+            println!("  Sync mode: {}", app_config.sync_mode);
+        }
+        if !app_config.file_modes.is_empty() {
+            println!("  Sync mode by file:");
+            for (pattern, mode) in &app_config.file_modes {
+                println!("    - {}: {}", pattern, mode);
+            }
+        }
+
+        // Show the group and machine-specific overrides for this machine
+        for (key, machine_override) in app_config.overrides_for(&rules.groups, &config.machine_id)
+        {
+            let scope = if key == config.machine_id {
+                format!("on this machine ({})", key)
+            } else {
+                format!("via group '{}'", key)
+            };
             if !machine_override.include.is_empty() {
-                println!("  Include on this machine ({}):", config.machine_id);
+                println!("  Include {}:", scope);
                 for pattern in &machine_override.include {
                     println!("    - {}", pattern);
                 }
             }
             if !machine_override.exclude.is_empty() {
-                println!("  Excluded on this machine ({}):", config.machine_id);
+                println!("  Excluded {}:", scope);
                 for pattern in &machine_override.exclude {
                     println!("    - {}", pattern);
                 }
@@ -96,8 +116,10 @@ pub fn list_apps(filter_app: Option<String>) -> Result<()> {
         }
 
         // Show resolved fileset for this machine
-        let fileset = resolve_fileset(
+        let (fileset, ignored) = resolve_fileset_with_ignored(
             app_config,
+            &rules.ignore,
+            &rules.groups,
             &config.machine_id,
             std::env::consts::OS,
         )?;
@@ -106,7 +128,20 @@ pub fn list_apps(filter_app: Option<String>) -> Result<()> {
             println!("  Resolved files ({}):", fileset.len());
             for (i, file) in fileset.iter().enumerate() {
                 if i < 5 {
-                    println!("    - {}", file.display());
+                    let name = file.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                    // Sections are declared by tags in the file, not in the rules
+                    let how = match app_config.sync_mode_for(name) {
+                        SyncMode::Full => Some("full".to_string()),
+                        SyncMode::Markers => fs::read_to_string(file).ok().and_then(|content| {
+                            let comment =
+                                comment_syntax_for(app_config.comment_syntax.as_deref(), name);
+                            describe_sections(&content, &comment)
+                        }),
+                    };
+                    match how {
+                        Some(how) => println!("    - {} ({})", file.display(), how),
+                        None => println!("    - {}", file.display()),
+                    }
                 } else if i == 5 {
                     println!("    ... and {} more", fileset.len() - 5);
                     break;
@@ -115,6 +150,12 @@ pub fn list_apps(filter_app: Option<String>) -> Result<()> {
         } else {
             println!("  (no files match for this machine/OS)");
         }
+        if !ignored.is_empty() {
+            println!("  Skipped by the global ignore list ({}):", ignored.len());
+            for file in &ignored {
+                println!("    - {}", file.display());
+            }
+        }
     }
 
     println!("\n{}", "=".repeat(60));
@@ -125,7 +166,9 @@ pub fn list_apps(filter_app: Option<String>) -> Result<()> {
     Ok(())
 }
 
-pub fn list_rules() -> Result<()> {
+/// Print sync-rules.toml as it is in the repo, or with `app_name` only that
+/// app's rules, serialized as a standalone `[apps.<name>]` document.
+pub fn list_rules(app_name: Option<String>) -> Result<()> {
     log::info!("Listing rules");
 
     // Load local config
@@ -136,6 +179,16 @@ pub fn list_rules() -> Result<()> {
     let repo_guard = EphemeralRepoGuard::new(&config)?;
     let repo_path = repo_guard.path();
 
+    if let Some(app_name) = app_name {
+        let rules_content = render_app_export(&SyncRules::load(repo_path)?, &app_name)?;
+        println!("\n{}", "=".repeat(60));
+        println!("Rules for app '{}':", app_name);
+        println!("{}", "=".repeat(60));
+        println!("{}", rules_content);
+        println!("{}", "=".repeat(60));
+        return Ok(());
+    }
+
     // Read the raw sync-rules.toml file
     let rules_path = repo_path.join(".drifters").join("sync-rules.toml");
 
@@ -0,0 +1,131 @@
+use crate::cli::style::ok;
+use crate::config::{LocalConfig, MachineRegistry};
+use crate::error::Result;
+use crate::git::{
+    confirm_operation, delete_remote_branches, list_machine_branches, EphemeralRepoGuard,
+};
+use chrono::{DateTime, Utc};
+
+/// List every registered machine with its OS and how long ago it last
+/// pushed or pulled, most recent first. `last_sync` is refreshed at most
+/// once per heartbeat interval, so ages under an hour are approximate.
+pub fn list_machines() -> Result<()> {
+    let config = LocalConfig::load()?;
+
+    println!("Fetching latest registry...");
+    let registry = {
+        let repo_guard = EphemeralRepoGuard::new(&config)?;
+        MachineRegistry::load(repo_guard.path())?
+    };
+
+    if registry.machines.is_empty() {
+        println!("\nNo machines registered.");
+        return Ok(());
+    }
+    println!("\nMachines ({}):", registry.machines.len());
+    print!("{}", render(&registry, &config.machine_id, Utc::now()));
+    Ok(())
+}
+
+/// Delete the machine branches no registered machine owns, after listing
+/// them and asking (`yes` skips the question). Branches of registered
+/// machines are never touched, whether or not they have pushed.
+pub fn prune_machines(yes: bool) -> Result<()> {
+    let config = LocalConfig::load()?;
+
+    println!("Fetching latest registry...");
+    let repo_guard = EphemeralRepoGuard::new(&config)?;
+    let repo_path = repo_guard.path();
+    let registry = MachineRegistry::load(repo_path)?;
+    let branches = list_machine_branches(repo_path)?;
+    let orphans = registry.orphaned_branches(&branches);
+
+    if orphans.is_empty() {
+        println!("\nEvery machine branch belongs to a registered machine. Nothing to prune.");
+        return Ok(());
+    }
+    println!("\nMachine branches of no registered machine ({}):", orphans.len());
+    for branch in &orphans {
+        println!("  {}", branch);
+    }
+
+    let msg = format!("Delete these {} branch(es) from the remote?", orphans.len());
+    if !yes && !confirm_operation(&msg, false)? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+    delete_remote_branches(repo_path, &orphans)?;
+    println!("{} Deleted {} branch(es)", ok(), orphans.len());
+    Ok(())
+}
+
+fn render(registry: &MachineRegistry, this_machine: &str, now: DateTime<Utc>) -> String {
+    let mut machines: Vec<_> = registry.machines.iter().collect();
+    // Most recently synced first; never-synced machines last
+    machines.sort_by(|a, b| b.1.last_sync.cmp(&a.1.last_sync).then_with(|| a.0.cmp(b.0)));
+    let width = machines.iter().map(|(id, _)| id.len()).max().unwrap_or(0);
+
+    let mut out = String::new();
+    for (id, info) in machines {
+        let marker = if id == this_machine { "*" } else { " " };
+        let synced = match info.last_sync {
+            Some(last) => format!("synced {}", format_age(now.signed_duration_since(last))),
+            None => "never synced".to_string(),
+        };
+        out.push_str(&format!(
+            "  {} {:<width$}  {:<8} {}\n",
+            marker,
+            id,
+            info.os,
+            synced,
+            width = width
+        ));
+    }
+    out
+}
+
+/// "just now", "5 minutes ago", "3 days ago", ...
+fn format_age(age: chrono::Duration) -> String {
+    let minutes = age.num_minutes();
+    let (count, unit) = match minutes {
+        ..=0 => return "just now".to_string(),
+        1..=59 => (minutes, "minute"),
+        60..=1439 => (minutes / 60, "hour"),
+        1440..=20159 => (minutes / 1440, "day"),
+        20160..=43199 => (minutes / 10080, "week"),
+        _ => (minutes / 43200, "month"),
+    };
+    format!("{} {}{} ago", count, unit, if count == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_sorts_by_last_sync_with_humanized_ages() {
+        let now = DateTime::parse_from_rfc3339("2026-03-01T12:00:00Z").unwrap().to_utc();
+        let mut registry = MachineRegistry::new();
+        registry.register_machine("laptop".to_string(), "macos".to_string());
+        registry.register_machine("server".to_string(), "linux".to_string());
+        registry.register_machine("old".to_string(), "windows".to_string());
+        let machines = &mut registry.machines;
+        machines.get_mut("laptop").unwrap().last_sync = Some(now - chrono::Duration::hours(2));
+        machines.get_mut("server").unwrap().last_sync = Some(now - chrono::Duration::days(40));
+        machines.get_mut("old").unwrap().last_sync = None;
+
+        assert_eq!(
+            render(&registry, "laptop", now),
+            "  * laptop  macos    synced 2 hours ago\n\
+             \x20   server  linux    synced 1 month ago\n\
+             \x20   old     windows  never synced\n"
+        );
+
+        assert_eq!(format_age(chrono::Duration::seconds(30)), "just now");
+        assert_eq!(format_age(chrono::Duration::minutes(1)), "1 minute ago");
+        assert_eq!(format_age(chrono::Duration::days(3)), "3 days ago");
+        assert_eq!(format_age(chrono::Duration::days(15)), "2 weeks ago");
+        // A clock behind the other machine's is not "-5 minutes ago"
+        assert_eq!(format_age(chrono::Duration::minutes(-5)), "just now");
+    }
+}
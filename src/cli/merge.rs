@@ -1,23 +1,31 @@
-use crate::config::{LocalConfig, SyncRules};
+use crate::cli::common::{commit_message, stage_heartbeat};
+use crate::cli::diffview;
+use crate::cli::style::{ok, warn};
+use crate::config::{AppConfig, AutoApply, LocalConfig, MergeStrategy, SyncRules};
 use crate::error::{DriftersError, Result};
 use crate::git::{
     checkout_branch, checkout_paths, commit_and_push, commit_merge, confirm_operation,
-    fetch_branch, merge_branch, merge_dry_run, run_mergetool, EphemeralRepoGuard,
+    ensure_full_history, fetch_branch, file_blob_history, file_last_commits, has_staged_changes,
+    main_branch, merge_branch, merge_dry_run, merge_file, read_app_file_bytes, read_app_tombstones,
+    read_blob, run_mergetool, show_file_at, stage_path, write_app_tombstones, EphemeralRepoGuard,
 };
+use crate::parser::conflict::has_conflict_markers;
+use crate::parser::json_merge::merge_json;
+use crate::sync::write_atomic;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
 
 pub fn merge_command(
     app_name: Option<String>,
     from: Option<String>,
     dry_run: bool,
+    message: Option<String>,
 ) -> Result<()> {
     log::info!("Merging machine branch into main");
 
     // Load config
-    let local_config = LocalConfig::load()?;
-
-    // Determine source machine
-    let source_machine = from.unwrap_or_else(|| local_config.machine_id.clone());
-    let source_branch = format!("machines/{}", source_machine);
+    let mut local_config = LocalConfig::load()?;
 
     // Set up ephemeral repo on main
     println!("Setting up repository...");
@@ -25,10 +33,14 @@ pub fn merge_command(
     let repo_path = repo_guard.path();
 
     // Guard: detect stale machine IDs
-    crate::cli::common::verify_machine_registration(&local_config, repo_path)?;
+    crate::cli::common::verify_machine_registration(&mut local_config, repo_path)?;
+    // The guard may have switched to a renamed ID
+    let source_machine = from.unwrap_or_else(|| local_config.machine_id.clone());
+    let source_branch = format!("machines/{}", source_machine);
 
     // Check if the source machine is singular
     let rules = SyncRules::load(repo_path)?;
+    rules.validate()?;
     if is_singular_machine(&source_machine, &rules) {
         println!(
             "Machine '{}' is marked as singular — its branch should not be merged into main.",
@@ -38,8 +50,9 @@ pub fn merge_command(
         return Ok(());
     }
 
-    // Make sure we're on main
-    checkout_branch(repo_path, "main")?;
+    // Make sure we're on main, with the history merge bases come from
+    checkout_branch(repo_path, main_branch())?;
+    ensure_full_history(repo_path)?;
 
     // Fetch the source branch so git knows about it (clone only gets main)
     fetch_branch(repo_path, &source_branch)?;
@@ -60,7 +73,7 @@ pub fn merge_command(
                 println!("\nNo changes to merge for '{}' from '{}'.", name, source_branch);
             } else {
                 println!("\nChanges for '{}' from '{}':", name, source_branch);
-                println!("{}", diff);
+                diffview::print(&format!("{}\n", diffview::stat(&diff)), false)?;
             }
             return Ok(());
         }
@@ -69,18 +82,22 @@ pub fn merge_command(
             "\nMerge '{}' from '{}' into main?",
             name, source_branch
         );
-        if !confirm_operation("Proceed?", true)? {
+        let preview = || diff_paths(repo_path, &merge_ref, &pathspec);
+        if !confirm_merge(local_config.auto_apply, preview)? {
             println!("Cancelled.");
             return Ok(());
         }
 
         println!("\nMerging '{}' from '{}'...", name, source_branch);
-        checkout_paths(repo_path, &merge_ref, &pathspec)?;
+        merge_app_files(repo_path, &source_machine, name, &rules.apps[name])?;
+        if has_staged_changes(repo_path)? {
+            stage_heartbeat(&local_config, repo_path)?;
+        }
         commit_and_push(
             repo_path,
-            &format!("Merge {} from {}", name, source_branch),
+            &commit_message(format!("Merge {} from {}", name, source_branch), message.as_deref()),
         )?;
-        println!("✓ Successfully merged '{}' from '{}' into main.", name, source_branch);
+        println!("{} Successfully merged '{}' from '{}' into main.", ok(), name, source_branch);
     } else {
         // ── Full branch merge ───────────────────────────────────────────────
         // Check for no_merge apps
@@ -91,15 +108,24 @@ pub fn merge_command(
             .map(|(name, _)| name)
             .collect();
 
-        if !no_merge_apps.is_empty() {
-            let mut names: Vec<&&String> = no_merge_apps.iter().collect();
-            names.sort();
-            println!("The following apps are marked no_merge and will not be included:");
-            for name in &names {
-                println!("  - {}", name);
+        // Apps with their own merge strategy need per-file handling, so they
+        // also rule out a whole-branch git merge
+        let per_file_merge = rules
+            .apps
+            .values()
+            .any(|config| config.merge_strategy != MergeStrategy::Text);
+
+        if !no_merge_apps.is_empty() || per_file_merge {
+            if !no_merge_apps.is_empty() {
+                let mut names: Vec<&&String> = no_merge_apps.iter().collect();
+                names.sort();
+                println!("The following apps are marked no_merge and will not be included:");
+                for name in &names {
+                    println!("  - {}", name);
+                }
+                println!("\nTo merge them individually, run: drifters merge-app <app-name>");
+                println!("To include them in full merges, remove `no_merge = true` from sync-rules.toml.\n");
             }
-            println!("\nTo merge them individually, run: drifters merge-app <app-name>");
-            println!("To include them in full merges, remove `no_merge = true` from sync-rules.toml.\n");
 
             // Collect mergeable app names for selective merge
             let mergeable_apps: Vec<String> = rules
@@ -122,7 +148,7 @@ pub fn merge_command(
                     let diff = diff_paths(repo_path, &merge_ref, &pathspec)?;
                     if !diff.is_empty() {
                         println!("\nChanges for '{}':", app);
-                        println!("{}", diff);
+                        diffview::print(&format!("{}\n", diffview::stat(&diff)), false)?;
                     }
                 }
                 return Ok(());
@@ -133,21 +159,39 @@ pub fn merge_command(
                 mergeable_apps.len(),
                 source_branch
             );
-            if !confirm_operation("Proceed?", true)? {
+            let preview = || {
+                let diffs = mergeable_apps
+                    .iter()
+                    .map(|app| diff_paths(repo_path, &merge_ref, &format!("apps/{}/", app)))
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(diffs.into_iter().filter(|d| !d.is_empty()).collect::<Vec<_>>().join("\n"))
+            };
+            if !confirm_merge(local_config.auto_apply, preview)? {
                 println!("Cancelled.");
                 return Ok(());
             }
 
             println!("\nMerging selectively from '{}'...", source_branch);
             for app in &mergeable_apps {
-                let pathspec = format!("apps/{}/", app);
-                checkout_paths(repo_path, &merge_ref, &pathspec)?;
+                merge_app_files(repo_path, &source_machine, app, &rules.apps[app])?;
+            }
+            if has_staged_changes(repo_path)? {
+                stage_heartbeat(&local_config, repo_path)?;
             }
-            commit_and_push(
-                repo_path,
-                &format!("Merge {} app(s) from {} (excluding no_merge)", mergeable_apps.len(), source_branch),
-            )?;
-            println!("✓ Successfully merged {} app(s) into main.", mergeable_apps.len());
+            let message = commit_message(
+                if no_merge_apps.is_empty() {
+                    format!("Merge {} app(s) from {}", mergeable_apps.len(), source_branch)
+                } else {
+                    format!(
+                        "Merge {} app(s) from {} (excluding no_merge)",
+                        mergeable_apps.len(),
+                        source_branch
+                    )
+                },
+                message.as_deref(),
+            );
+            commit_and_push(repo_path, &message)?;
+            println!("{} Successfully merged {} app(s) into main.", ok(), mergeable_apps.len());
         } else {
             // No no_merge apps — full git merge
             if dry_run {
@@ -158,10 +202,10 @@ pub fn merge_command(
                             println!("\nNo changes to merge from '{}'.", source_branch);
                         } else if clean {
                             println!("\nClean merge from '{}':", source_branch);
-                            println!("{}", diff);
+                            diffview::print(&format!("{}\n", diffview::stat(&diff)), false)?;
                         } else {
                             println!("\nMerge from '{}' would have conflicts:", source_branch);
-                            println!("{}", diff);
+                            diffview::print(&format!("{}\n", diffview::stat(&diff)), false)?;
                         }
                     }
                     Err(e) => {
@@ -175,39 +219,44 @@ pub fn merge_command(
                 "\nMerge '{}' into main?",
                 source_branch
             );
-            if !confirm_operation("Proceed?", true)? {
+            let preview = || Ok(merge_dry_run(repo_path, &merge_ref)?.1);
+            if !confirm_merge(local_config.auto_apply, preview)? {
                 println!("Cancelled.");
                 return Ok(());
             }
 
             println!("\nMerging '{}' into main...", source_branch);
-            match merge_branch(repo_path, &merge_ref) {
+            // Without --message, git's own "Merge remote-tracking branch ..."
+            let merge_message = message
+                .as_deref()
+                .map(|m| commit_message(format!("Merge {} into main", source_branch), Some(m)));
+            match merge_branch(repo_path, &merge_ref, merge_message.as_deref()) {
                 Ok(()) => {
-                    println!("✓ Clean merge — no conflicts.");
+                    println!("{} Clean merge — no conflicts.", ok());
                 }
                 Err(DriftersError::MergeConflict(msg)) => {
-                    println!("\n⚠️  Merge conflicts detected:");
+                    println!("\n{}  Merge conflicts detected:", warn());
                     println!("{}", msg);
                     println!("\nLaunching mergetool to resolve conflicts...");
 
                     run_mergetool(repo_path)?;
 
-                    let merge_msg = format!(
-                        "Merge {} into main (conflicts resolved)",
-                        source_branch
+                    let merge_msg = commit_message(
+                        format!("Merge {} into main (conflicts resolved)", source_branch),
+                        message.as_deref(),
                     );
                     commit_merge(repo_path, &merge_msg)?;
-                    println!("✓ Conflicts resolved and committed.");
+                    println!("{} Conflicts resolved and committed.", ok());
                 }
                 Err(e) => return Err(e),
             }
 
             // Push main
-            println!("\nPushing main...");
+            println!("\nPushing {}...", main_branch());
             let output = std::process::Command::new("git")
                 .arg("-C")
                 .arg(repo_path)
-                .args(["push", "-u", "origin", "main"])
+                .args(["push", "-u", "origin", main_branch()])
                 .output()?;
 
             if !output.status.success() {
@@ -217,14 +266,255 @@ pub fn merge_command(
                 )));
             }
 
-            println!("✓ Successfully merged '{}' into main.", source_branch);
+            println!("{} Successfully merged '{}' into main.", ok(), source_branch);
         }
     }
 
     Ok(())
 }
 
+/// Bring one app's files from `merge_ref` into main's working tree, staged.
+///
+/// By default the machine's version of each file replaces main's. Files
+/// present on both sides can be merged instead, per the app's
+/// `merge_strategy`:
+///
+/// * `json` — `.json` files are deep-merged, clashing values going to
+///   whichever side last committed the file (ties: see [`machine_side_wins`]);
+///   a file that doesn't parse keeps the machine's version.
+/// * `three-way` — a line-level merge against the newest version both sides
+///   have had. Overlapping edits are committed with conflict markers, which
+///   `pull-app` refuses to apply; the next merge of a resolved version
+///   replaces them.
+///
+/// Whatever the strategy, files deleted on either side (see
+/// [`apply_tombstones`]) are then removed, unless edited since.
+fn merge_app_files(
+    repo_path: &PathBuf,
+    source_machine: &str,
+    app_name: &str,
+    app_config: &AppConfig,
+) -> Result<()> {
+    let merge_ref = &format!("origin/machines/{}", source_machine);
+    let pathspec = format!("apps/{}/", app_name);
+    // Read before the machine's tombstones replace them
+    let main_tombstones = read_app_tombstones(repo_path, app_name)?;
+    if app_config.merge_strategy == MergeStrategy::Text {
+        checkout_paths(repo_path, merge_ref, &pathspec)?;
+        return apply_tombstones(repo_path, merge_ref, app_name, main_tombstones);
+    }
+
+    // Main's files, read before the machine's versions replace them
+    let main_files = read_app_file_bytes(repo_path, app_name)?;
+    checkout_paths(repo_path, merge_ref, &pathspec)?;
+
+    // Each side's last commit of every file, from one `git log` per side
+    let machine_commits = file_last_commits(repo_path, merge_ref, &pathspec)?;
+    let main_commits = file_last_commits(repo_path, "HEAD", &pathspec)?;
+    let machine_newer = |path: &str| -> Result<bool> {
+        Ok(machine_side_wins(
+            machine_commits.get(path).map(|(time, _)| (*time, source_machine.to_string())),
+            main_commits.get(path).cloned(),
+            &app_config.priority,
+        ))
+    };
+
+    let mut names: Vec<_> = main_files.keys().collect();
+    names.sort();
+    for filename in names {
+        let main_bytes = &main_files[filename];
+        let path = format!("{}{}", pathspec, filename);
+        let machine_bytes = fs::read(repo_path.join(&path))?;
+        if &machine_bytes == main_bytes {
+            continue;
+        }
+
+        // Binary files can't be merged; the newer version wins whole
+        let (Ok(main_content), Ok(machine_content)) =
+            (std::str::from_utf8(main_bytes), std::str::from_utf8(&machine_bytes))
+        else {
+            if machine_newer(&path)? {
+                println!("  {} {}: binary, taking the machine's newer version", ok(), path);
+            } else {
+                write_atomic(&repo_path.join(&path), main_bytes)?;
+                stage_path(repo_path, &path)?;
+                println!("  {} {}: binary, keeping main's newer version", ok(), path);
+            }
+            continue;
+        };
+
+        let merged = match app_config.merge_strategy {
+            MergeStrategy::Text => unreachable!("handled above"),
+            MergeStrategy::Json => {
+                if !filename.ends_with(".json") {
+                    continue;
+                }
+                match merge_json(main_content, machine_content, machine_newer(&path)?) {
+                    Ok(merged) => {
+                        println!("  {} {}: merged JSON key by key", ok(), path);
+                        merged
+                    }
+                    Err(e) => {
+                        println!(
+                            "  {}  {}: not valid JSON ({}), taking the machine's version",
+                            warn(),
+                            path,
+                            e
+                        );
+                        continue;
+                    }
+                }
+            }
+            MergeStrategy::ThreeWay => {
+                if has_conflict_markers(main_content) {
+                    // The machine's version resolves main's unresolved conflict
+                    println!("  {} {}: replaced unresolved conflict on main", ok(), path);
+                    continue;
+                }
+                let base = match common_base(
+                    &file_blob_history(repo_path, "HEAD", &path)?,
+                    &file_blob_history(repo_path, merge_ref, &path)?,
+                ) {
+                    Some(blob) => read_blob(repo_path, blob)?,
+                    None => String::new(),
+                };
+                let (merged, conflicts) = merge_file(
+                    repo_path,
+                    main_content,
+                    &base,
+                    machine_content,
+                    [main_branch(), "base", merge_ref],
+                )?;
+                if conflicts {
+                    println!(
+                        "  {}  {}: conflicting edits, written with conflict markers.\n     \
+                         Resolve the file locally, then push-app and merge-app again.",
+                        warn(),
+                        path
+                    );
+                } else {
+                    println!("  {} {}: merged cleanly (three-way)", ok(), path);
+                }
+                merged
+            }
+        };
+        write_atomic(&repo_path.join(&path), merged)?;
+        stage_path(repo_path, &path)?;
+    }
+    apply_tombstones(repo_path, merge_ref, app_name, main_tombstones)
+}
+
+/// Remove the files of an app that a machine deleted, once the machine's
+/// files are checked out over main's (which never deletes anything).
+///
+/// Tombstones from main and from the machine are combined, the later
+/// deletion counting. A tombstoned file still in the tree is removed unless
+/// either side committed a version of it after the deletion, in which case
+/// the edit wins and the tombstone is dropped. The combined tombstones stay
+/// on main so that `pull-app` deletes the files on other machines too.
+fn apply_tombstones(
+    repo_path: &PathBuf,
+    merge_ref: &str,
+    app_name: &str,
+    main_tombstones: BTreeMap<String, i64>,
+) -> Result<()> {
+    // The machine's tombstones, or main's when the machine has none
+    let mut tombstones = read_app_tombstones(repo_path, app_name)?;
+    for (filename, deleted_at) in main_tombstones {
+        let slot = tombstones.entry(filename).or_insert(deleted_at);
+        *slot = (*slot).max(deleted_at);
+    }
+    if tombstones.is_empty() {
+        return Ok(());
+    }
+
+    let pathspec = format!("apps/{}/", app_name);
+    let machine_commits = file_last_commits(repo_path, merge_ref, &pathspec)?;
+    let main_commits = file_last_commits(repo_path, "HEAD", &pathspec)?;
+    let mut kept = BTreeMap::new();
+    for (filename, deleted_at) in tombstones {
+        let path = format!("apps/{}/{}", app_name, filename);
+        if !repo_path.join(&path).exists() {
+            kept.insert(filename, deleted_at);
+            continue;
+        }
+        // The machine's last commit of the file counts only if the file is
+        // still there: otherwise it is the deletion itself
+        let machine_version = match show_file_at(repo_path, merge_ref, &path) {
+            Ok(_) => machine_commits.get(&path).map(|(time, _)| *time),
+            Err(_) => None,
+        };
+        let main_version = main_commits.get(&path).map(|(time, _)| *time);
+        if deletion_wins(deleted_at, machine_version.max(main_version)) {
+            fs::remove_file(repo_path.join(&path))?;
+            println!("  {} {}: deleted", ok(), path);
+            kept.insert(filename, deleted_at);
+        } else {
+            println!("  {} {}: edited after it was deleted, keeping it", ok(), path);
+        }
+    }
+
+    write_app_tombstones(repo_path, app_name, &kept)?;
+    stage_path(repo_path, &format!("apps/{}/", app_name))
+}
+
+/// Whether a deletion at `deleted_at` beats the newest committed version of
+/// the file (if any): only an edit committed after the deletion survives it.
+fn deletion_wins(deleted_at: i64, newest_version: Option<i64>) -> bool {
+    newest_version.is_none_or(|time| time <= deleted_at)
+}
+
+/// Whether the merged machine's side of a clash beats main's, given each
+/// side's last commit (time, machine). The newer commit wins. On a tie, the
+/// side whose machine ranks higher in `priority` wins; when neither is
+/// listed (or there is no list), the machine being merged wins as usual.
+fn machine_side_wins(
+    machine: Option<(i64, String)>,
+    main: Option<(i64, Option<String>)>,
+    priority: &[String],
+) -> bool {
+    let (Some((machine_time, machine_id)), Some((main_time, main_machine))) = (machine, main)
+    else {
+        return true;
+    };
+    if machine_time != main_time {
+        return machine_time > main_time;
+    }
+
+    let rank = |id: Option<&str>| {
+        id.and_then(|id| priority.iter().position(|p| p == id))
+            .unwrap_or(usize::MAX)
+    };
+    rank(Some(&machine_id)) <= rank(main_machine.as_deref())
+}
+
+/// The newest version in main's history of a file that the machine branch
+/// has also had: the point the two last agreed on, used as the merge base.
+/// (Machine branches never merge main back, so git's merge-base would stay
+/// at the fork point.)
+fn common_base<'a>(main_history: &'a [String], machine_history: &[String]) -> Option<&'a str> {
+    main_history
+        .iter()
+        .find(|blob| machine_history.contains(blob))
+        .map(String::as_str)
+}
+
 /// Show diff of specific paths between main and a ref.
+/// Whether to go ahead with a merge: asked under `auto_apply = "prompt"`;
+/// under `"diff-then-apply"` the changes from `preview` are shown instead.
+fn confirm_merge(policy: AutoApply, preview: impl FnOnce() -> Result<String>) -> Result<bool> {
+    if policy.asks() {
+        return confirm_operation("Proceed?", true);
+    }
+    if policy.shows_diff() {
+        let diff = preview()?;
+        if !diff.is_empty() {
+            diffview::print(&format!("{}\n", diffview::stat(&diff)), false)?;
+        }
+    }
+    Ok(true)
+}
+
 fn diff_paths(
     repo_path: &std::path::Path,
     source_ref: &str,
@@ -238,14 +528,93 @@ fn diff_paths(
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
-/// Check if a machine is marked as singular in sync-rules.
+/// Check if a machine is marked as singular in sync-rules, by its own
+/// override or one of its groups'.
 fn is_singular_machine(machine_id: &str, rules: &SyncRules) -> bool {
-    for app_config in rules.apps.values() {
-        if let Some(override_config) = app_config.machines.get(machine_id) {
-            if override_config.singular {
-                return true;
-            }
-        }
+    rules.apps.values().any(|app_config| {
+        app_config
+            .overrides_for(&rules.groups, machine_id)
+            .iter()
+            .any(|(_, override_config)| override_config.singular)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn blobs(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_priority_breaks_timestamp_ties() {
+        let priority = vec!["desktop".to_string(), "laptop".to_string()];
+        let laptop = |t| Some((t, "laptop".to_string()));
+        let main_from = |t, m: &str| Some((t, Some(m.to_string())));
+
+        // Newer commit wins regardless of priority
+        assert!(machine_side_wins(laptop(200), main_from(100, "desktop"), &priority));
+        assert!(!machine_side_wins(laptop(100), main_from(200, "desktop"), &priority));
+
+        // Tie: without a list the merged machine wins; the list overrides it
+        assert!(machine_side_wins(laptop(100), main_from(100, "desktop"), &[]));
+        assert!(!machine_side_wins(laptop(100), main_from(100, "desktop"), &priority));
+        assert!(machine_side_wins(laptop(100), main_from(100, "work"), &priority));
+        assert!(machine_side_wins(laptop(100), Some((100, None)), &priority));
+    }
+
+    #[test]
+    fn test_deletion_wins_unless_edited_later() {
+        let deleted_at = 1_780_000_000;
+        assert!(deletion_wins(deleted_at, None));
+        assert!(deletion_wins(deleted_at, Some(deleted_at - 3_600)));
+        assert!(deletion_wins(deleted_at, Some(deleted_at)));
+        // Another machine edited the file after it was deleted here
+        assert!(!deletion_wins(deleted_at, Some(deleted_at + 60)));
+    }
+
+    #[test]
+    fn test_common_base_is_newest_shared_version() {
+        let main = blobs(&["m3", "s2", "m1", "s1"]);
+        let machine = blobs(&["x2", "s1", "s2"]);
+        assert_eq!(common_base(&main, &machine), Some("s2"));
+        assert_eq!(common_base(&main, &blobs(&["x1"])), None);
+    }
+
+    #[test]
+    fn test_three_way_merge_clean_and_conflicting() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().to_path_buf();
+        Command::new("git").arg("init").arg("-q").arg(&repo).status().unwrap();
+
+        let base = "a = 1\nb = 2\nc = 3\n";
+
+        // Edits to different lines combine
+        let (merged, conflicts) = merge_file(
+            &repo,
+            "a = 10\nb = 2\nc = 3\n",
+            base,
+            "a = 1\nb = 2\nc = 30\n",
+            ["main", "base", "machines/m1"],
+        )
+        .unwrap();
+        assert!(!conflicts);
+        assert_eq!(merged, "a = 10\nb = 2\nc = 30\n");
+
+        // Edits to the same line conflict, with standard markers
+        let (merged, conflicts) = merge_file(
+            &repo,
+            "a = 1\nb = 20\nc = 3\n",
+            base,
+            "a = 1\nb = 21\nc = 3\n",
+            ["main", "base", "machines/m1"],
+        )
+        .unwrap();
+        assert!(conflicts);
+        assert!(has_conflict_markers(&merged));
+        assert!(merged.contains("<<<<<<< main\nb = 20\n=======\nb = 21\n>>>>>>> machines/m1\n"));
+        assert!(!repo.join(".git").join("drifters-merge").exists());
     }
-    false
 }
@@ -1,19 +1,27 @@
 pub mod add;
+pub mod archive;
 pub mod common;
 pub mod completion;
 pub mod diff;
+pub mod diffview;
+pub mod doctor;
 pub mod edit_app_files;
 pub mod edit_config;
 pub mod edit_rules;
 pub mod exclude;
 pub mod export;
+pub mod forge;
 pub mod history;
 pub mod hook;
+pub mod ignore;
 pub mod import;
 pub mod init;
 pub mod list;
+pub mod machines;
 pub mod merge;
 pub mod open_readme;
+pub mod pager;
+pub mod passphrase;
 pub mod presets;
 pub mod pull;
 pub mod push;
@@ -21,7 +29,14 @@ pub mod remove;
 pub mod remove_machine;
 pub mod rename_app;
 pub mod rename_machine;
+pub mod report;
 pub mod restore;
 pub mod self_update;
+pub mod stats;
 pub mod status;
+pub mod style;
+pub mod tree;
+pub mod undo;
 pub mod unlock;
+pub mod verify;
+pub mod watch;
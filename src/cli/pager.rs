@@ -0,0 +1,78 @@
+use crate::error::Result;
+use std::io::{IsTerminal, Write};
+use std::process::{Command, Stdio};
+
+/// Diffs longer than this many lines go through the pager (when on a TTY).
+pub const PAGER_THRESHOLD_LINES: usize = 100;
+
+/// Pager used when `$PAGER` is unset. `-R` keeps the ANSI colours of diffs.
+const DEFAULT_PAGER: &str = "less -R";
+
+/// Decide whether text of `line_count` lines should be paged.
+///
+/// Mirrors git: only page when writing to a terminal, only when the output is
+/// long, and never when the user asked for `--no-pager`.
+fn should_page(is_tty: bool, line_count: usize, no_pager: bool) -> bool {
+    !no_pager && is_tty && line_count > PAGER_THRESHOLD_LINES
+}
+
+/// Print `text` in full, piping it through the user's pager when it is long
+/// and stdout is a terminal.
+///
+/// If the pager cannot be started the text is printed directly, so output is
+/// never lost.
+pub fn print_paged(text: &str, no_pager: bool) -> Result<()> {
+    let is_tty = std::io::stdout().is_terminal();
+    if should_page(is_tty, text.lines().count(), no_pager) && run_pager(text).is_ok() {
+        return Ok(());
+    }
+
+    print!("{}", text);
+    std::io::stdout().flush()?;
+    Ok(())
+}
+
+fn run_pager(text: &str) -> std::io::Result<()> {
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PAGER.to_string());
+
+    let mut parts = pager.split_whitespace();
+    let program = parts.next().unwrap_or("less");
+
+    log::debug!("Paging output through '{}'", pager);
+    let mut child = Command::new(program)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The user quitting the pager early closes the pipe; that's not an error
+        match stdin.write_all(text.as_bytes()) {
+            Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => return Err(e),
+            _ => {}
+        }
+    }
+
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_page_only_long_output_on_tty() {
+        let long = PAGER_THRESHOLD_LINES + 1;
+
+        assert!(should_page(true, long, false));
+        // Short output is printed directly even on a terminal
+        assert!(!should_page(true, PAGER_THRESHOLD_LINES, false));
+        // Piped/redirected output is never paged
+        assert!(!should_page(false, long, false));
+        // --no-pager always wins
+        assert!(!should_page(true, long, true));
+    }
+}
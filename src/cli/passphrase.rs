@@ -0,0 +1,75 @@
+use crate::cli::style::ok;
+use crate::config::LocalConfig;
+use crate::crypto::{generate_salt, load_salt, save_salt, SecretKey};
+use crate::error::{DriftersError, Result};
+use crate::git::{commit_and_push, confirm_operation, EphemeralRepoGuard};
+
+/// Environment variable read instead of prompting, for unattended setup.
+const PASSPHRASE_ENV: &str = "DRIFTERS_PASSPHRASE";
+
+/// Derive this machine's key for `drifters::encrypt` sections from a
+/// passphrase, or with `clear` delete it. Every machine needs the same
+/// passphrase; it is asked for twice and never stored. The first machine to
+/// set one also creates the repo's salt, which the others then read.
+pub fn set_passphrase(clear: bool) -> Result<()> {
+    let existing = SecretKey::load()?;
+
+    if clear {
+        if SecretKey::remove()? {
+            println!("{} Encryption key removed", ok());
+        } else {
+            println!("No encryption key is set.");
+        }
+        return Ok(());
+    }
+
+    let passphrase = match std::env::var(PASSPHRASE_ENV) {
+        Ok(passphrase) => passphrase,
+        Err(_) => {
+            let passphrase = rpassword::prompt_password("Passphrase: ")?;
+            if rpassword::prompt_password("Repeat passphrase: ")? != passphrase {
+                return Err(DriftersError::Config("The passphrases don't match".to_string()));
+            }
+            passphrase
+        }
+    };
+    let config = LocalConfig::load()?;
+    let repo_guard = EphemeralRepoGuard::new(&config)?;
+    let repo_path = repo_guard.path();
+    let (salt, new_salt) = match load_salt(repo_path)? {
+        Some(salt) => (salt, false),
+        None => (generate_salt()?, true),
+    };
+
+    println!("Deriving the key...");
+    let key = SecretKey::from_passphrase(&passphrase, &salt)?;
+
+    if let Some(existing) = existing.filter(|e| e.fingerprint() != key.fingerprint()) {
+        let msg = format!(
+            "This machine already has a different key ({}); files encrypted with it won't \
+             decrypt with the new one. Replace it?",
+            existing.fingerprint()
+        );
+        if !confirm_operation(&msg, false)? {
+            return Err(DriftersError::UserCancelled);
+        }
+    }
+
+    if new_salt {
+        save_salt(repo_path, &salt)?;
+        commit_and_push(
+            repo_path,
+            &format!("Add encryption salt from {}", config.machine_id),
+        )?;
+    }
+
+    key.save()?;
+    println!(
+        "{} Encryption key {} saved to {}",
+        ok(),
+        key.fingerprint(),
+        SecretKey::path()?.display()
+    );
+    println!("Machines that show the same key ID can read each other's encrypted sections.");
+    Ok(())
+}
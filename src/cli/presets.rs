@@ -1,81 +1,32 @@
-use crate::config::{AppConfig, LocalConfig, SyncRules};
+use crate::cli::common::http_client;
+use crate::cli::forge::{drifters_forge, Forge};
+use crate::config::{resolve_fileset, AppConfig, LocalConfig, SyncRules};
 use crate::error::{DriftersError, Result};
 use crate::git::{commit_and_push, EphemeralRepoGuard};
-use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::io::{self, Write};
 
-// Parse repository from Cargo.toml at compile time
-// Expected format: https://github.com/owner/repo
-const CARGO_REPOSITORY: &str = env!("CARGO_PKG_REPOSITORY");
-
-#[derive(Deserialize)]
-struct GitHubContent {
-    name: String,
-    #[serde(rename = "type")]
-    file_type: String,
-}
-
-/// Parse GitHub owner and repo from repository URL
-fn parse_github_repo() -> Result<(String, String)> {
-    let url = CARGO_REPOSITORY;
-
-    // Remove https://github.com/ prefix
-    let path = url
-        .strip_prefix("https://github.com/")
-        .or_else(|| url.strip_prefix("http://github.com/"))
-        .ok_or_else(|| {
-            DriftersError::Config(format!(
-                "Repository URL in Cargo.toml is not a GitHub URL: {}",
-                url
-            ))
-        })?;
-
-    // Split into owner/repo
-    let parts: Vec<&str> = path.trim_end_matches('/').split('/').collect();
-    if parts.len() < 2 {
-        return Err(DriftersError::Config(format!(
-            "Invalid GitHub repository URL: {}",
-            url
-        )));
-    }
-
-    Ok((parts[0].to_string(), parts[1].to_string()))
-}
-
-pub fn list_presets() -> Result<()> {
-    println!("Fetching available presets from GitHub...\n");
-
-    let (owner, repo) = parse_github_repo()?;
-    let url = format!(
-        "https://api.github.com/repos/{}/{}/contents/presets",
-        owner, repo
-    );
-
-    let client = reqwest::blocking::Client::builder()
-        .user_agent("drifters-cli")
-        .build()?;
-
-    let response = client.get(&url).send()?;
-
-    if !response.status().is_success() {
-        eprintln!("Failed to fetch presets from GitHub");
-        eprintln!("Repository: https://github.com/{}/{}", owner, repo);
-        eprintln!("URL: {}", url);
-        eprintln!("Status: {}", response.status());
-        return Err(DriftersError::Config(format!(
-            "Unable to access presets from https://github.com/{}/{}",
-            owner, repo
-        )));
-    }
-
-    let contents: Vec<GitHubContent> = response.json()?;
-
-    let presets: Vec<String> = contents
+/// Names of the presets in the repository's `presets/` directory.
+fn preset_names(forge: &dyn Forge) -> Result<Vec<String>> {
+    Ok(forge
+        .list_dir("presets")?
         .into_iter()
-        .filter(|item| item.file_type == "file" && item.name.ends_with(".toml"))
+        .filter(|item| item.is_file && item.name.ends_with(".toml"))
         .map(|item| item.name.trim_end_matches(".toml").to_string())
         .filter(|name| name != "README")
-        .collect();
+        .collect())
+}
+
+pub fn list_presets() -> Result<()> {
+    let forge = drifters_forge(http_client()?)?;
+    println!("Fetching available presets from {}...\n", forge.repo_url());
+
+    let presets = preset_names(forge.as_ref()).map_err(|e| {
+        eprintln!("Failed to fetch presets");
+        eprintln!("Repository: {}", forge.repo_url());
+        eprintln!("{}", e);
+        DriftersError::Config(format!("Unable to access presets from {}", forge.repo_url()))
+    })?;
 
     if presets.is_empty() {
         println!("No presets found");
@@ -96,50 +47,16 @@ pub fn list_presets() -> Result<()> {
 }
 
 pub fn load_preset(preset_name: String) -> Result<()> {
-    println!("Loading preset '{}' from GitHub...", preset_name);
+    let forge = drifters_forge(http_client()?)?;
+    println!("Loading preset '{}' from {}...", preset_name, forge.repo_url());
 
-    let (owner, repo) = parse_github_repo()?;
     let file_path = format!("presets/{}.toml", preset_name);
-    let url = format!(
-        "https://api.github.com/repos/{}/{}/contents/{}",
-        owner, repo, file_path
-    );
-
-    let client = reqwest::blocking::Client::builder()
-        .user_agent("drifters-cli")
-        .build()?;
-
-    let response = client.get(&url).send()?;
-
-    if !response.status().is_success() {
-        eprintln!("Failed to fetch preset '{}' from GitHub", preset_name);
-        eprintln!("Repository: https://github.com/{}/{}", owner, repo);
+    let preset_content = forge.fetch_file(&file_path).map_err(|e| {
+        eprintln!("Failed to fetch preset '{}'", preset_name);
+        eprintln!("Repository: {}", forge.repo_url());
         eprintln!("File: {}", file_path);
-        eprintln!("URL: {}", url);
-        eprintln!("Status: {}", response.status());
-        return Err(DriftersError::Config(format!(
-            "Preset '{}' not found or inaccessible",
-            preset_name
-        )));
-    }
-
-    #[derive(Deserialize)]
-    struct FileContent {
-        content: String,
-    }
-
-    let file_content: FileContent = response.json()?;
-
-    // Decode base64 content (GitHub API returns file content as base64)
-    use base64::Engine;
-    let decoded_bytes = base64::engine::general_purpose::STANDARD
-        .decode(file_content.content.replace('\n', ""))
-        .map_err(|e| {
-            DriftersError::Config(format!("Failed to decode base64 content: {}", e))
-        })?;
-
-    let preset_content = String::from_utf8(decoded_bytes).map_err(|e| {
-        DriftersError::Config(format!("Failed to decode UTF-8 content: {}", e))
+        eprintln!("{}", e);
+        DriftersError::Config(format!("Preset '{}' not found or inaccessible", preset_name))
     })?;
 
     // Parse the preset
@@ -192,7 +109,7 @@ pub fn load_preset(preset_name: String) -> Result<()> {
 }
 
 pub fn discover_presets() -> Result<()> {
-    let (owner, repo) = parse_github_repo()?;
+    let forge = drifters_forge(http_client()?)?;
 
     // ── 1. Connect to repo and load current rules ─────────────────────────
     let config = LocalConfig::load()?;
@@ -201,29 +118,14 @@ pub fn discover_presets() -> Result<()> {
     let mut rules = SyncRules::load(repo_path)?;
 
     // ── 2. Fetch the list of available presets ────────────────────────────
-    println!("Fetching available presets from GitHub...");
-    let client = reqwest::blocking::Client::builder()
-        .user_agent("drifters-cli")
-        .build()?;
-
-    let list_url = format!(
-        "https://api.github.com/repos/{}/{}/contents/presets",
-        owner, repo
-    );
-    let list_resp = client.get(&list_url).send()?;
-    if !list_resp.status().is_success() {
-        return Err(DriftersError::Config(format!(
-            "Unable to fetch presets from https://github.com/{}/{}",
-            owner, repo
-        )));
-    }
-    let contents: Vec<GitHubContent> = list_resp.json()?;
-    let preset_names: Vec<String> = contents
-        .into_iter()
-        .filter(|i| i.file_type == "file" && i.name.ends_with(".toml"))
-        .map(|i| i.name.trim_end_matches(".toml").to_string())
-        .filter(|n| n != "README")
-        .collect();
+    println!("Fetching available presets from {}...", forge.repo_url());
+    let preset_names = preset_names(forge.as_ref()).map_err(|e| {
+        DriftersError::Config(format!(
+            "Unable to fetch presets from {}: {}",
+            forge.repo_url(),
+            e
+        ))
+    })?;
 
     if preset_names.is_empty() {
         println!("No presets found in the repository.");
@@ -233,15 +135,9 @@ pub fn discover_presets() -> Result<()> {
     println!("Checking {} preset(s) for installed apps...", preset_names.len());
 
     // ── 3. For each preset not already configured, check if its files exist ─
-    let home = dirs::home_dir().unwrap_or_default();
     let mut detected: Vec<(String, AppConfig)> = Vec::new();
     let mut already_configured = 0usize;
 
-    #[derive(Deserialize)]
-    struct FileContent {
-        content: String,
-    }
-
     for preset_name in &preset_names {
         if rules.apps.contains_key(preset_name) {
             already_configured += 1;
@@ -249,30 +145,9 @@ pub fn discover_presets() -> Result<()> {
         }
 
         // Fetch the preset TOML
-        let file_url = format!(
-            "https://api.github.com/repos/{}/{}/contents/presets/{}.toml",
-            owner, repo, preset_name
-        );
-        let resp = match client.get(&file_url).send() {
-            Ok(r) if r.status().is_success() => r,
-            _ => continue, // skip on any fetch error
-        };
-        let file_content: FileContent = match resp.json() {
-            Ok(fc) => fc,
-            Err(_) => continue,
-        };
-
-        // Decode base64
-        use base64::Engine;
-        let decoded_bytes = match base64::engine::general_purpose::STANDARD
-            .decode(file_content.content.replace('\n', ""))
-        {
-            Ok(b) => b,
-            Err(_) => continue,
-        };
-        let toml_str = match String::from_utf8(decoded_bytes) {
+        let toml_str = match forge.fetch_file(&format!("presets/{}.toml", preset_name)) {
             Ok(s) => s,
-            Err(_) => continue,
+            Err(_) => continue, // skip on any fetch error
         };
         let preset_rules: SyncRules = match toml::from_str(&toml_str) {
             Ok(r) => r,
@@ -283,23 +158,8 @@ pub fn discover_presets() -> Result<()> {
             None => continue,
         };
 
-        // Collect include patterns for the current OS
-        let mut patterns = app_config.include.clone();
-        match std::env::consts::OS {
-            "macos"   => patterns.extend(app_config.include_macos.clone()),
-            "linux"   => patterns.extend(app_config.include_linux.clone()),
-            "windows" => patterns.extend(app_config.include_windows.clone()),
-            _ => {}
-        }
-
-        // Check whether any of the patterns match a file on disk
-        let present = patterns.iter().any(|p| {
-            let expanded = p.replace('~', &home.to_string_lossy());
-            glob::glob(&expanded)
-                .ok()
-                .and_then(|mut m| m.next())
-                .is_some()
-        });
+        // Check whether the preset would sync anything on this machine
+        let present = preset_detected(&app_config, &rules.ignore, &config.machine_id)?;
 
         if present {
             detected.push((preset_name.clone(), app_config));
@@ -351,22 +211,38 @@ pub fn discover_presets() -> Result<()> {
     Ok(())
 }
 
+/// A preset counts as installed when its fileset for this machine and OS is
+/// non-empty — the same resolution `push-app` uses, so files matched only by
+/// the preset's own `exclude` patterns don't trigger detection. Presets have
+/// no machine overrides, so groups don't come into it.
+fn preset_detected(app_config: &AppConfig, ignore: &[String], machine_id: &str) -> Result<bool> {
+    let no_groups = BTreeMap::new();
+    let fileset =
+        resolve_fileset(app_config, ignore, &no_groups, machine_id, std::env::consts::OS)?;
+    Ok(!fileset.is_empty())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
-    fn test_parse_github_repo() {
-        // This test verifies that the Cargo.toml repository URL is valid
-        let result = parse_github_repo();
-        assert!(result.is_ok(), "Failed to parse repository URL from Cargo.toml");
-
-        let (owner, repo) = result.unwrap();
-        assert!(!owner.is_empty(), "Owner should not be empty");
-        assert!(!repo.is_empty(), "Repo should not be empty");
-
-        // For this project, it should be tjirsch/drifters
-        assert_eq!(owner, "tjirsch");
-        assert_eq!(repo, "drifters");
+    fn test_preset_with_only_excluded_files_not_detected() {
+        let dir = tempfile::tempdir().unwrap();
+        let base = dir.path().to_string_lossy().to_string();
+        std::fs::create_dir_all(dir.path().join("cache")).unwrap();
+        std::fs::write(dir.path().join("cache/state.json"), "{}").unwrap();
+
+        let mut app_config = AppConfig {
+            include: vec![format!("{}/cache/*.json", base)],
+            exclude: vec![format!("{}/cache/**", base)],
+            ..Default::default()
+        };
+        assert!(!preset_detected(&app_config, &[], "m1").unwrap());
+
+        // A real, non-excluded config file does trigger detection
+        std::fs::write(dir.path().join("settings.json"), "{}").unwrap();
+        app_config.include.push(format!("{}/settings.json", base));
+        assert!(preset_detected(&app_config, &[], "m1").unwrap());
     }
 }
@@ -1,38 +1,113 @@
-use crate::config::{resolve_fileset, LocalConfig, SyncRules};
+use crate::cli::common::{
+    load_rules_from_branch, replace_with_symlink, require_registered_machine, set_file_mode,
+    stage_heartbeat,
+};
+use crate::cli::diffview;
+use crate::cli::style::ok;
+use crate::config::{
+    check_fileset, resolve_fileset, set_home_override, AutoApply, LineEndings, LocalConfig,
+    SyncMode, UndoJournal,
+};
+use crate::crypto::{open_sections, SecretKey};
 use crate::error::{DriftersError, Result};
-use crate::git::{confirm_operation, read_app_files, EphemeralRepoGuard};
-use crate::parser::sections::{detect_comment_syntax, merge_synced_content};
+use crate::git::{
+    checkout_or_create_branch, commit_and_push, confirm_operation, main_branch,
+    read_app_file_bytes, read_app_links, read_app_modes, read_app_tombstones, remote_has_branch,
+    resolve_commit, EphemeralRepoGuard,
+};
+use crate::parser::conflict::has_conflict_markers;
+use crate::parser::redact::{compile_redactions, restore_redacted};
+use crate::parser::sections::{comment_syntax_for, merge_synced_content, CommentSyntax, Merged};
+use regex::Regex;
+use crate::sync::write_atomic;
+use std::collections::BTreeMap;
 use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// Pull backups older than this are deleted on the next backed-up pull.
+const BACKUP_RETENTION_DAYS: i64 = 30;
+
+/// Suffix of backup files: `<file>.drifters-bak-<unix seconds>`.
+const BACKUP_SUFFIX: &str = ".drifters-bak-";
+
+/// Flags of `pull-app`.
+#[derive(Debug, Default)]
+pub struct PullOptions {
+    pub dry_run: bool,
+    /// Machine whose branch to pull from instead of main: its last push is
+    /// copied as is, bypassing what merges into main resolved
+    pub from: Option<String>,
+    pub no_pager: bool,
+    /// Write under this directory instead of the real config paths
+    pub output_dir: Option<PathBuf>,
+    /// Resolve `~` against this directory
+    pub home: Option<PathBuf>,
+    pub backup: bool,
+    /// Apply everything without showing diffs or asking, including
+    /// deleting files deleted on other machines and dropping local-only
+    /// exclude sections
+    pub yolo: bool,
+}
 
-pub fn pull_command(
-    app_name: Option<String>,
-    dry_run: bool,
-    from: Option<String>,
-) -> Result<()> {
+pub fn pull_command(app_name: Option<String>, options: PullOptions) -> Result<()> {
+    let PullOptions {
+        dry_run,
+        from,
+        no_pager,
+        output_dir,
+        home,
+        backup,
+        yolo,
+    } = options;
     log::info!("Pulling configs (dry_run: {}, from: {:?})", dry_run, from);
 
+    let other_home = home.is_some();
+    if let Some(dir) = home {
+        if !dir.is_dir() {
+            return Err(DriftersError::Config(format!(
+                "--home directory does not exist: {}",
+                dir.display()
+            )));
+        }
+        println!("(Treating {} as the home directory)", dir.display());
+        set_home_override(dir);
+    }
+
     // Load local config
-    let config = LocalConfig::load()?;
+    let mut config = LocalConfig::load()?;
+    let key = SecretKey::load()?;
+    let policy = if yolo { AutoApply::Silent } else { config.auto_apply };
 
     // Determine source branch
     let source_branch = match &from {
         Some(machine) => format!("machines/{}", machine),
-        None => "main".to_string(),
+        None => main_branch().to_string(),
     };
 
-    // Set up ephemeral repo on the source branch
+    // Set up ephemeral repo on main, where the machine registry lives
     println!("Setting up repository...");
-    let repo_guard = EphemeralRepoGuard::new_on_branch(&config, &source_branch)?;
+    let repo_guard = EphemeralRepoGuard::new(&config)?;
     let repo_path = repo_guard.path();
 
-    // Guard: detect stale machine IDs (only relevant when pulling from main;
-    // --from pulls from a specific machine branch where machines.toml may not exist)
-    if from.is_none() {
-        crate::cli::common::verify_machine_registration(&config, repo_path)?;
+    match &from {
+        // Guard: detect stale machine IDs
+        None => crate::cli::common::verify_machine_registration(&mut config, repo_path)?,
+        // A machine branch is copied as is, so it has to be one that exists
+        Some(machine) => {
+            require_registered_machine(repo_path, machine)?;
+            if !remote_has_branch(repo_path, &source_branch)? {
+                return Err(DriftersError::Config(format!(
+                    "Machine '{}' has not pushed anything yet (no branch '{}')",
+                    machine, source_branch
+                )));
+            }
+            checkout_or_create_branch(repo_path, &source_branch, main_branch())?;
+        }
     }
 
     // Load sync rules (from main via git show, since rules always live on main)
-    let rules = load_rules_from_branch(repo_path, "main")?;
+    let rules = load_rules_from_branch(repo_path, main_branch())?;
+    rules.validate()?;
 
     if rules.apps.is_empty() {
         println!("No apps configured for sync.");
@@ -55,26 +130,59 @@ pub fn pull_command(
         println!("(Dry run - no changes will be applied)");
     }
 
+    let home = crate::config::home_dir().unwrap_or_default();
+    if let Some(dir) = &output_dir {
+        println!(
+            "(Sandbox - writing results under {} instead of the real paths)",
+            dir.display()
+        );
+    }
+
     println!("Pulling from branch '{}'...", source_branch);
 
-    let mut pulled_files = 0;
+    let backup = (backup || config.backup_on_pull) && !dry_run;
+    let now = chrono::Utc::now().timestamp();
+    // Changes to real files are journaled for `drifters undo`
+    let mut journal = if dry_run || output_dir.is_some() {
+        None
+    } else {
+        Some(UndoJournal::start(UndoJournal::default_dir()?))
+    };
+
+    let mut summaries: BTreeMap<String, AppSummary> = BTreeMap::new();
     let mut warnings = Vec::new();
 
+    // Only a real pull from main into this home moves the --since-last-sync marker
+    let head = if from.is_none() && !dry_run && output_dir.is_none() && !other_home {
+        Some(resolve_commit(repo_path, "HEAD")?)
+    } else {
+        None
+    };
+    // Deletions and the synced-files record only concern this machine's
+    // own config paths
+    let in_place = output_dir.is_none() && !other_home;
+    let mut synced_changed = false;
+
     for app in &apps_to_pull {
         let app_config = rules.apps.get(app).unwrap();
 
         println!("\nPulling configs for '{}'...", app);
 
+        let redactions = compile_redactions(&app_config.redact)?;
+
         // Resolve fileset for THIS machine using current OS
         let fileset = resolve_fileset(
             app_config,
+            &rules.ignore,
+            &rules.groups,
             &config.machine_id,
             std::env::consts::OS,
         )?;
 
-        if fileset.is_empty() {
-            log::warn!("No files in fileset for app '{}'", app);
-            warnings.push(format!("No files in fileset for app '{}'", app));
+        if let Some(warning) = check_fileset(app, app_config, &fileset)? {
+            log::warn!("{}", warning);
+            warnings.push(warning);
+            summaries.entry(app.clone()).or_default().warned += 1;
             continue;
         }
 
@@ -83,9 +191,14 @@ pub fn pull_command(
             println!("  Skipping '{}': no local files found on this machine", app);
             continue;
         }
+        let summary = summaries.entry(app.clone()).or_default();
+        let warnings_before = warnings.len();
 
         // Read app files from the source branch
-        let remote_files = read_app_files(repo_path, app)?;
+        let remote_files = read_app_file_bytes(repo_path, app)?;
+        let remote_modes = read_app_modes(repo_path, app)?;
+        let remote_links = read_app_links(repo_path, app)?;
+        let tombstones = read_app_tombstones(repo_path, app)?;
 
         for local_path in fileset {
             let filename = local_path
@@ -93,83 +206,319 @@ pub fn pull_command(
                 .and_then(|s| s.to_str())
                 .unwrap_or("unknown");
 
+            // Pushed as a symlink: recreate the link, never write content
+            if let Some(link) = remote_links.get(filename) {
+                let target = match &output_dir {
+                    Some(dir) => sandbox_path(&local_path, dir, &home),
+                    None => local_path.clone(),
+                };
+                let existed = fs::symlink_metadata(&target).is_ok();
+                if fs::read_link(&target).ok().as_deref() == Some(Path::new(link)) {
+                    log::debug!("{} is up to date", filename);
+                } else if dry_run {
+                    println!("  {} ({}) - would link to {}", filename, target.display(), link);
+                    summary.applied(existed);
+                } else if approve(
+                    policy,
+                    &format!("Replace {} with a symlink to {}?", filename, link),
+                )? {
+                    if let Some(parent) = target.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    let relink = || replace_with_symlink(&target, Path::new(link));
+                    match journaled(&mut journal, &target, relink) {
+                        Ok(()) => {
+                            println!("  {} {} -> {}", ok(), target.display(), link);
+                            summary.applied(existed);
+                        }
+                        Err(e) => warnings.push(e.to_string()),
+                    }
+                } else {
+                    summary.skipped += 1;
+                }
+                continue;
+            }
+
             // Look up this file in the remote branch's app directory
             let remote_content = match remote_files.get(filename) {
                 Some(content) => content.clone(),
                 None => {
                     log::debug!("No remote version for {}", filename);
+                    // Deleted on another machine: offer to delete it here,
+                    // but only if this machine ever synced it
+                    let path_key = local_path.to_string_lossy().to_string();
+                    let synced = config
+                        .synced_files
+                        .get(app.as_str())
+                        .is_some_and(|paths| paths.contains(&path_key));
+                    if !(in_place && synced && tombstones.contains_key(filename)) {
+                        continue;
+                    }
+                    if dry_run {
+                        println!(
+                            "  {} ({}) - deleted on another machine, would be deleted",
+                            filename,
+                            local_path.display()
+                        );
+                        summary.deleted += 1;
+                        continue;
+                    }
+                    let msg = format!(
+                        "{} was deleted on another machine. Delete {}?",
+                        filename,
+                        local_path.display()
+                    );
+                    if yolo || confirm_operation(&msg, false)? {
+                        if backup {
+                            if let Some(saved) = backup_if_changed(&local_path, b"", now)? {
+                                println!("    (previous version saved to {})", saved.display());
+                            }
+                        }
+                        journaled(&mut journal, &local_path, || {
+                            Ok(fs::remove_file(&local_path)?)
+                        })?;
+                        println!("  {} {} deleted", ok(), local_path.display());
+                        summary.deleted += 1;
+                        if let Some(paths) = config.synced_files.get_mut(app.as_str()) {
+                            paths.remove(&path_key);
+                        }
+                        synced_changed = true;
+                    } else {
+                        summary.skipped += 1;
+                    }
                     continue;
                 }
             };
 
-            // If file exists locally, merge sections if needed
-            let final_content = if local_path.exists() {
-                let local_content = fs::read_to_string(&local_path)?;
-
-                // Merge: preserve local exclude sections, update everything else
-                let comment = detect_comment_syntax(filename);
-                let merged_with_local = merge_synced_content(
-                    &local_content,
-                    &remote_content,
-                    comment,
-                )?;
-
-                if merged_with_local == local_content {
-                    log::debug!("{} is up to date", filename);
-                    None
-                } else if dry_run {
-                    println!("\n  Changes in {} ({}):", filename, local_path.display());
-                    show_simple_diff(&local_content, &merged_with_local);
-                    println!("    (dry-run: would apply)");
-                    pulled_files += 1;
-                    None
-                } else {
-                    // Show diff and ask for confirmation
-                    println!("\n  Changes in {} ({}):", filename, local_path.display());
-                    show_simple_diff(&local_content, &merged_with_local);
-                    let msg = format!("Apply changes to {}?", filename);
-                    if confirm_operation(&msg, true)? {
-                        Some(merged_with_local)
-                    } else {
+            // Binary files have no sections to merge: the branch's bytes
+            // replace the local file
+            let final_content = match String::from_utf8(remote_content) {
+                Err(binary) => {
+                    let remote_bytes = binary.into_bytes();
+                    if fs::read(&local_path).ok().as_ref() == Some(&remote_bytes) {
+                        log::debug!("{} is up to date", filename);
+                        None
+                    } else if dry_run {
+                        println!(
+                            "  {} ({}) - binary, would be replaced ({} bytes)",
+                            filename,
+                            local_path.display(),
+                            remote_bytes.len()
+                        );
+                        summary.applied(local_path.exists());
                         None
+                    } else {
+                        let msg = format!(
+                            "Replace binary file {} with the remote version ({} bytes)?",
+                            filename,
+                            remote_bytes.len()
+                        );
+                        let approved = approve(policy, &msg)?;
+                        summary.skipped += usize::from(!approved);
+                        approved.then_some(remote_bytes)
                     }
                 }
-            } else {
-                // File doesn't exist locally - create it
-                if dry_run {
-                    println!("  {} ({}) - would be created from remote", filename, local_path.display());
-                    pulled_files += 1;
-                    None
-                } else {
-                    let msg = format!("Create {} from remote?", filename);
-                    if confirm_operation(&msg, true)? {
-                        Some(remote_content)
+                Ok(remote_content) => {
+                    // A three-way merge left conflict markers on the branch; never
+                    // write those into a live config
+                    if has_conflict_markers(&remote_content) {
+                        let warning = format!(
+                            "{}/{}: unresolved merge conflict on the branch; not applied. \
+                             Resolve it locally, then push-app and merge-app.",
+                            app, filename
+                        );
+                        log::warn!("{}", warning);
+                        warnings.push(warning);
+                        continue;
+                    }
+
+                    // Without this machine having the right key, an encrypted
+                    // file is skipped rather than written with ciphertext
+                    let comment =
+                        comment_syntax_for(app_config.comment_syntax.as_deref(), filename);
+                    let remote_content =
+                        match open_sections(&remote_content, &comment, key.as_ref(), filename) {
+                            Ok(content) => content,
+                            Err(DriftersError::Config(msg)) => {
+                                let warning = format!("{}/{}", app, msg);
+                                log::warn!("{}", warning);
+                                warnings.push(warning);
+                                continue;
+                            }
+                            Err(e) => return Err(e),
+                        };
+
+                    // If file exists locally, merge sections if needed
+                    if local_path.exists() {
+                        let local_content = fs::read_to_string(&local_path)?;
+                        let Merged { content: merged_with_local, dropped_sections } =
+                            merge_into_local(
+                                &local_content,
+                                &remote_content,
+                                &comment,
+                                &redactions,
+                                app_config.line_endings,
+                                app_config.sync_mode_for(filename),
+                            )?;
+                        // Local-only exclude sections have nowhere to go in
+                        // the pulled file, and their content exists nowhere else
+                        let dropped_warning = (dropped_sections > 0).then(|| {
+                            format!(
+                                "{}/{}: {} local exclude section(s) have no matching block \
+                                 in the pulled file, so applying it drops them. Push the \
+                                 file first to keep them.",
+                                app, filename, dropped_sections
+                            )
+                        });
+                        // Compared without line endings, which are not worth
+                        // a line-by-line diff
+                        let diff = || {
+                            let endings = app_config.line_endings;
+                            let (old, new) = (
+                                endings.for_repo(&local_content),
+                                endings.for_repo(&merged_with_local),
+                            );
+                            if old == new {
+                                "    (line endings only)\n".to_string()
+                            } else {
+                                diffview::changed_lines(&old, &new)
+                            }
+                        };
+
+                        if merged_with_local == local_content {
+                            log::debug!("{} is up to date", filename);
+                            None
+                        } else if dry_run {
+                            println!("\n  Changes in {} ({}):", filename, local_path.display());
+                            diffview::print(&diff(), no_pager)?;
+                            println!("    (dry-run: would apply)");
+                            warnings.extend(dropped_warning);
+                            summary.applied(true);
+                            None
+                        } else {
+                            if policy.shows_diff() {
+                                println!("\n  Changes in {} ({}):", filename, local_path.display());
+                                diffview::print(&diff(), no_pager)?;
+                            }
+                            let confirmed = match dropped_warning {
+                                Some(warning) => {
+                                    log::warn!("{}", warning);
+                                    warnings.push(warning);
+                                    let msg = format!(
+                                        "Apply changes to {} and drop {} local exclude \
+                                         section(s)?",
+                                        filename, dropped_sections
+                                    );
+                                    yolo || confirm_operation(&msg, false)?
+                                }
+                                None => {
+                                    approve(policy, &format!("Apply changes to {}?", filename))?
+                                }
+                            };
+                            if confirmed {
+                                Some(merged_with_local.into_bytes())
+                            } else {
+                                summary.skipped += 1;
+                                None
+                            }
+                        }
                     } else {
-                        None
+                        // File doesn't exist locally - create it
+                        if dry_run {
+                            println!(
+                                "  {} ({}) - would be created from remote",
+                                filename,
+                                local_path.display()
+                            );
+                            summary.applied(false);
+                            None
+                        } else {
+                            let msg = format!("Create {} from remote?", filename);
+                            if approve(policy, &msg)? {
+                                let content = app_config.line_endings.for_local(&remote_content);
+                                Some(content.into_owned().into_bytes())
+                            } else {
+                                summary.skipped += 1;
+                                None
+                            }
+                        }
                     }
                 }
             };
 
             if let Some(content) = final_content {
+                let target = match &output_dir {
+                    Some(dir) => sandbox_path(&local_path, dir, &home),
+                    None => local_path.clone(),
+                };
+
                 // Create parent directories if needed
-                if let Some(parent) = local_path.parent() {
+                if let Some(parent) = target.parent() {
                     fs::create_dir_all(parent)?;
                 }
 
-                fs::write(&local_path, content)?;
-                println!("  ✓ {} ({})", filename, local_path.display());
-                pulled_files += 1;
-            } else if !dry_run {
-                log::debug!("Skipped {}", filename);
+                summary.applied(fs::symlink_metadata(&target).is_ok());
+                if backup {
+                    if let Some(saved) = backup_if_changed(&target, &content, now)? {
+                        println!("    (previous version saved to {})", saved.display());
+                    }
+                }
+                journaled(&mut journal, &target, || {
+                    write_atomic(&target, content)?;
+                    if let Some(&mode) = remote_modes.get(filename) {
+                        set_file_mode(&target, mode)?;
+                    }
+                    Ok(())
+                })?;
+                if in_place {
+                    let path_key = local_path.to_string_lossy().to_string();
+                    synced_changed |=
+                        config.synced_files.entry(app.clone()).or_default().insert(path_key);
+                }
+                println!("  {} {} ({})", ok(), filename, target.display());
             }
+
+            if backup {
+                let target = match &output_dir {
+                    Some(dir) => sandbox_path(&local_path, dir, &home),
+                    None => local_path.clone(),
+                };
+                prune_backups(&target, now - BACKUP_RETENTION_DAYS * 86_400)?;
+            }
+        }
+
+        summary.warned += warnings.len() - warnings_before;
+
+        if let Some(head) = &head {
+            config.last_pulled.insert(app.clone(), head.clone());
+        }
+    }
+
+    if head.is_some() || synced_changed {
+        config.save()?;
+    }
+
+    // Heartbeat: a real pull from main counts as a sync too. Throttled like
+    // push's, so most pulls commit nothing.
+    if head.is_some() && stage_heartbeat(&config, repo_path)? {
+        let message = format!("Record sync from {}", config.machine_id);
+        if let Err(e) = commit_and_push(repo_path, &message) {
+            log::warn!("Could not record this sync on main: {}", e);
         }
     }
 
-    if pulled_files == 0 && warnings.is_empty() {
+    if summaries.values().all(AppSummary::is_empty) && warnings.is_empty() {
         println!("\nAll configs are up to date");
         return Ok(());
     }
 
+    println!("\n{}:", if dry_run { "Summary (dry run)" } else { "Summary" });
+    let width = summaries.keys().map(String::len).max().unwrap_or(0);
+    for (app, summary) in &summaries {
+        println!("  {:<width$}  {}", app, summary.describe(), width = width);
+    }
+    let pulled_files: usize = summaries.values().map(AppSummary::changed).sum();
+
     // Show warnings
     if !warnings.is_empty() {
         println!("\nWarnings:");
@@ -185,56 +534,321 @@ pub fn pull_command(
                 pulled_files
             );
         } else {
-            println!("\n✓ Successfully pulled {} file(s)", pulled_files);
+            println!("\n{} Successfully pulled {} file(s)", ok(), pulled_files);
+            if journal.is_some_and(|j| !j.is_empty()) {
+                println!("Run 'drifters undo' to put them back as they were");
+            }
         }
     }
 
     Ok(())
 }
 
-/// Load sync-rules.toml from a specific branch via git show.
-fn load_rules_from_branch(
-    repo_path: &std::path::Path,
-    branch: &str,
-) -> Result<SyncRules> {
-    let spec = format!("{}:.drifters/sync-rules.toml", branch);
-    let output = std::process::Command::new("git")
-        .arg("-C")
-        .arg(repo_path)
-        .args(["show", &spec])
-        .output()?;
-
-    if !output.status.success() {
-        // Fallback: try reading from the current branch
-        return SyncRules::load(&repo_path.to_path_buf());
-    }
-
-    let content = String::from_utf8_lossy(&output.stdout);
-    let rules: SyncRules = toml::from_str(&content)?;
-    Ok(rules)
+/// What a pull did with one app's files, for the summary at the end.
+#[derive(Debug, Default, PartialEq)]
+struct AppSummary {
+    updated: usize,
+    created: usize,
+    deleted: usize,
+    /// Changes that were declined
+    skipped: usize,
+    warned: usize,
 }
 
-/// Show a simple diff between two strings.
-fn show_simple_diff(old: &str, new: &str) {
-    use similar::TextDiff;
+impl AppSummary {
+    /// Count a file written (or, in a dry run, one that would be).
+    fn applied(&mut self, existed: bool) {
+        if existed {
+            self.updated += 1;
+        } else {
+            self.created += 1;
+        }
+    }
+
+    fn changed(&self) -> usize {
+        self.updated + self.created + self.deleted
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// "2 updated, 1 skipped", or "up to date".
+    fn describe(&self) -> String {
+        let counts = [
+            (self.updated, "updated"),
+            (self.created, "created"),
+            (self.deleted, "deleted"),
+            (self.skipped, "skipped"),
+            (self.warned, "warning(s)"),
+        ];
+        let parts: Vec<String> = counts
+            .iter()
+            .filter(|(count, _)| *count > 0)
+            .map(|(count, label)| format!("{} {}", count, label))
+            .collect();
+        if parts.is_empty() {
+            "up to date".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
 
-    let diff = TextDiff::from_lines(old, new);
+/// Whether to apply a change: asked under `auto_apply = "prompt"`, applied
+/// without asking otherwise.
+fn approve(policy: AutoApply, msg: &str) -> Result<bool> {
+    if policy.asks() {
+        confirm_operation(msg, true)
+    } else {
+        Ok(true)
+    }
+}
 
-    let changed_lines: Vec<_> = diff
-        .iter_all_changes()
-        .filter(|c| c.tag() != similar::ChangeTag::Equal)
-        .collect();
+/// Run `change` on `target`, through `journal` when this pull is journaled.
+fn journaled(
+    journal: &mut Option<UndoJournal>,
+    target: &Path,
+    change: impl FnOnce() -> Result<()>,
+) -> Result<()> {
+    match journal {
+        Some(journal) => journal.record(target, change),
+        None => change(),
+    }
+}
 
-    if changed_lines.is_empty() {
-        println!("    (no changes)");
-        return;
+/// Copy `target` to `<target>.drifters-bak-<now>` before it is overwritten
+/// with `new_content`. Nothing is written if the file doesn't exist yet or
+/// wouldn't change. Returns the backup's path.
+fn backup_if_changed(
+    target: &Path,
+    new_content: impl AsRef<[u8]>,
+    now: i64,
+) -> Result<Option<PathBuf>> {
+    match fs::read(target) {
+        Ok(old) if old != new_content.as_ref() => {
+            let mut name = target.as_os_str().to_owned();
+            name.push(format!("{}{}", BACKUP_SUFFIX, now));
+            let backup = PathBuf::from(name);
+            fs::write(&backup, old)?;
+            Ok(Some(backup))
+        }
+        _ => Ok(None),
     }
+}
 
-    for change in &changed_lines {
-        match change.tag() {
-            similar::ChangeTag::Delete => print!("    - {}", change),
-            similar::ChangeTag::Insert => print!("    + {}", change),
-            similar::ChangeTag::Equal => {}
+/// Delete `target`'s pull backups taken before `cutoff` (Unix seconds).
+fn prune_backups(target: &Path, cutoff: i64) -> Result<()> {
+    let (Some(dir), Some(name)) = (target.parent(), target.file_name().and_then(|n| n.to_str()))
+    else {
+        return Ok(());
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(());
+    };
+    let prefix = format!("{}{}", name, BACKUP_SUFFIX);
+    for entry in entries.flatten() {
+        let taken = entry
+            .file_name()
+            .to_str()
+            .and_then(|n| n.strip_prefix(&prefix))
+            .and_then(|epoch| epoch.parse::<i64>().ok());
+        if taken.is_some_and(|taken| taken < cutoff) {
+            fs::remove_file(entry.path())?;
+            log::debug!("Pruned old backup {}", entry.path().display());
         }
     }
+    Ok(())
+}
+
+/// Map a real config path into `output_dir`, keeping its structure: paths
+/// under the home directory keep their home-relative layout
+/// (`~/.config/x` → `<dir>/.config/x`), anything else its absolute layout
+/// minus the root (`/etc/x` → `<dir>/etc/x`).
+fn sandbox_path(path: &Path, output_dir: &Path, home: &Path) -> PathBuf {
+    let relative: PathBuf = match path.strip_prefix(home) {
+        Ok(rel) if !home.as_os_str().is_empty() => rel.to_path_buf(),
+        _ => path
+            .components()
+            .filter(|c| matches!(c, Component::Normal(_)))
+            .collect(),
+    };
+    output_dir.join(relative)
+}
+
+/// The local file after taking in `repo_content` from the repo: local
+/// exclude sections (unless the file syncs in `full` mode) and this
+/// machine's values for redacted lines are kept, and line endings are
+/// converted as the app asks.
+pub(crate) fn merge_into_local(
+    local_content: &str,
+    repo_content: &str,
+    comment: &CommentSyntax,
+    redactions: &[Regex],
+    line_endings: LineEndings,
+    mode: SyncMode,
+) -> Result<Merged> {
+    let local_content = line_endings.for_repo(local_content);
+    let merged = match mode {
+        SyncMode::Markers => merge_synced_content(&local_content, repo_content, comment)?,
+        SyncMode::Full => Merged { content: repo_content.to_string(), dropped_sections: 0 },
+    };
+    let content = restore_redacted(&merged.content, &local_content, redactions);
+    Ok(Merged { content: line_endings.for_local(&content).into_owned(), ..merged })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backups_only_on_change_and_pruned_by_age() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("settings.json");
+        let day = 86_400;
+
+        // Nothing to back up before the file exists, or when it won't change
+        assert!(backup_if_changed(&target, "{}", 10 * day).unwrap().is_none());
+        fs::write(&target, "{\"a\": 1}").unwrap();
+        assert!(backup_if_changed(&target, "{\"a\": 1}", 10 * day).unwrap().is_none());
+
+        let old = backup_if_changed(&target, "{\"a\": 2}", day).unwrap().unwrap();
+        let recent = backup_if_changed(&target, "{\"a\": 2}", 10 * day).unwrap().unwrap();
+        assert_eq!(recent, dir.path().join(format!("settings.json.drifters-bak-{}", 10 * day)));
+        assert_eq!(fs::read_to_string(&recent).unwrap(), "{\"a\": 1}");
+        let unrelated = dir.path().join("other.json.drifters-bak-0");
+        fs::write(&unrelated, "").unwrap();
+
+        prune_backups(&target, 5 * day).unwrap();
+        assert!(!old.exists());
+        assert!(recent.exists());
+        assert!(unrelated.exists());
+        assert!(target.exists());
+    }
+
+    #[test]
+    fn test_app_summary_lists_only_nonzero_counts() {
+        let mut summary = AppSummary::default();
+        assert!(summary.is_empty());
+        assert_eq!(summary.describe(), "up to date");
+
+        summary.applied(true);
+        summary.applied(true);
+        summary.applied(false);
+        summary.skipped += 1;
+        summary.warned += 1;
+        assert_eq!(summary.describe(), "2 updated, 1 created, 1 skipped, 1 warning(s)");
+        assert_eq!(summary.changed(), 3);
+    }
+
+    #[test]
+    fn test_sandbox_path_stays_under_output_dir() {
+        let home = Path::new("/home/me");
+        let out = Path::new("/tmp/sandbox");
+
+        assert_eq!(
+            sandbox_path(Path::new("/home/me/.config/zed/settings.json"), out, home),
+            PathBuf::from("/tmp/sandbox/.config/zed/settings.json")
+        );
+        assert_eq!(
+            sandbox_path(Path::new("/etc/hosts"), out, home),
+            PathBuf::from("/tmp/sandbox/etc/hosts")
+        );
+        // `..` can't be used to escape the sandbox
+        let escaped = sandbox_path(Path::new("/etc/../../root/x"), out, home);
+        assert!(escaped.starts_with(out));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_mode_survives_push_and_pull() {
+        use crate::cli::common::file_mode;
+        use std::collections::BTreeMap;
+
+        let local = tempfile::tempdir().unwrap();
+        let secret = local.path().join("credentials");
+        fs::write(&secret, "token = abc\n").unwrap();
+        set_file_mode(&secret, 0o600).unwrap();
+
+        // Push side: record the mode next to the app's files
+        let repo = tempfile::tempdir().unwrap();
+        let mut modes = BTreeMap::new();
+        modes.insert("credentials".to_string(), file_mode(&secret).unwrap());
+        crate::git::write_app_modes(repo.path(), "aws", &modes).unwrap();
+        fs::write(repo.path().join("apps/aws/credentials"), "token = abc\n").unwrap();
+        assert_eq!(read_app_file_bytes(repo.path(), "aws").unwrap().len(), 1);
+
+        // Pull side: a fresh file gets the recorded mode back
+        let fresh = tempfile::tempdir().unwrap();
+        let target = fresh.path().join("credentials");
+        fs::write(&target, "token = abc\n").unwrap();
+        let recorded = read_app_modes(repo.path(), "aws").unwrap();
+        set_file_mode(&target, recorded["credentials"]).unwrap();
+        assert_eq!(file_mode(&target), Some(0o600));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_recorded_on_push_is_recreated_on_pull() {
+        use std::collections::BTreeMap;
+
+        let repo = tempfile::tempdir().unwrap();
+        let mut updates = BTreeMap::new();
+        updates.insert("config".to_string(), Some("/home/me/dotfiles/app".to_string()));
+        crate::git::write_app_links(repo.path(), "app", &updates).unwrap();
+        let links = read_app_links(repo.path(), "app").unwrap();
+        assert!(read_app_file_bytes(repo.path(), "app").unwrap().is_empty());
+
+        // A regular file in the way becomes the link
+        let local = tempfile::tempdir().unwrap();
+        let target = local.path().join("config");
+        fs::write(&target, "stale copy\n").unwrap();
+        replace_with_symlink(&target, Path::new(&links["config"])).unwrap();
+        assert_eq!(fs::read_link(&target).unwrap(), Path::new("/home/me/dotfiles/app"));
+
+        // Pushed as a regular file again: the record goes away
+        updates.insert("config".to_string(), None);
+        crate::git::write_app_links(repo.path(), "app", &updates).unwrap();
+        assert!(read_app_links(repo.path(), "app").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_line_endings_normalized_around_local_exclude_sections() {
+        let comment = comment_syntax_for(None, ".zshrc");
+        // Edited on Windows: CRLF everywhere, including the kept section
+        let local = "export A=1\r\n# drifters::exclude::start\r\nexport LOCAL=1\r\n\
+                     # drifters::exclude::stop\r\n";
+        let repo = "export A=2\n# drifters::exclude::start\n# drifters::exclude::stop\n";
+
+        let merge = |local: &str, endings| {
+            merge_into_local(local, repo, &comment, &[], endings, SyncMode::Markers)
+                .unwrap()
+                .content
+        };
+        let merged = merge(local, LineEndings::Lf);
+        assert_eq!(
+            merged,
+            "export A=2\n# drifters::exclude::start\nexport LOCAL=1\n# drifters::exclude::stop\n"
+        );
+        let merged = merge(local, LineEndings::Crlf);
+        assert_eq!(merged, local.replace("A=1", "A=2"));
+        // Only the endings differ: still a change, but not line by line
+        let unchanged = merge(&merged, LineEndings::Lf);
+        assert_ne!(unchanged, merged);
+        assert_eq!(LineEndings::Lf.for_repo(&merged), unchanged);
+    }
+
+    #[test]
+    fn test_full_sync_mode_takes_the_repo_file_whole() {
+        let comment = comment_syntax_for(None, ".zshrc");
+        let local = "export A=1\n# drifters::exclude::start\nexport LOCAL=1\n\
+                     # drifters::exclude::stop\n";
+        let repo = "export A=2\n# drifters::exclude::start\nexport OTHER=1\n\
+                    # drifters::exclude::stop\n";
+        let merged =
+            merge_into_local(local, repo, &comment, &[], LineEndings::Preserve, SyncMode::Full)
+                .unwrap();
+        assert_eq!(merged.content, repo);
+        assert_eq!(merged.dropped_sections, 0);
+    }
 }
@@ -1,14 +1,86 @@
-use crate::config::{resolve_fileset, LocalConfig, SyncRules};
+use crate::cli::common::{
+    commit_message, file_mode, load_rules_from_branch, stage_activity, stage_heartbeat,
+};
+use crate::cli::diffview;
+use crate::cli::style::ok;
+use crate::config::{
+    check_fileset, resolve_fileset_with_ignored, AppConfig, LocalConfig, SyncMode,
+};
+use crate::crypto::{seal_sections, SecretKey};
 use crate::error::{DriftersError, Result};
-use crate::git::{check_file_safety, commit_and_push, confirm_operation, EphemeralRepoGuard};
-use crate::parser::sections::{detect_comment_syntax, extract_syncable_content};
+use crate::git::{
+    amend_and_push, check_file_safety, checkout_branch, commit_and_push, commit_info,
+    confirm_operation, ensure_full_history, is_ancestor, main_branch, messages_since,
+    read_app_links, read_app_tombstones, show_file_at, write_app_links, write_app_modes,
+    write_app_tombstones, CommitInfo, EphemeralRepoGuard,
+};
+use crate::parser::redact::{compile_redactions, redact_content};
+use crate::parser::sections::{comment_syntax_for, extract_syncable_content};
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
+use std::path::PathBuf;
+
+/// What to do with a matched file the first time push sees it.
+#[derive(Debug, PartialEq, Eq)]
+enum FirstTime {
+    /// Already on this machine's branch — push as usual
+    Tracked,
+    /// Never pushed and never declined — ask the user
+    Prompt,
+    /// Declined at an earlier prompt — skip quietly
+    Declined,
+    /// Never pushed, but `--include-untracked` was given
+    Include,
+}
+
+fn first_time_decision(
+    path_key: &str,
+    already_pushed: bool,
+    include_untracked: bool,
+    declined: &[String],
+) -> FirstTime {
+    if already_pushed {
+        FirstTime::Tracked
+    } else if include_untracked {
+        FirstTime::Include
+    } else if declined.iter().any(|d| d == path_key) {
+        FirstTime::Declined
+    } else {
+        FirstTime::Prompt
+    }
+}
 
-pub fn push_command(app_name: Option<String>) -> Result<()> {
+/// Push this machine's files to its branch.
+///
+/// Once an app has been pushed, a newly matched file that this machine has
+/// never pushed (e.g. a temp file picked up by a glob) is only included after
+/// a first-time prompt;
+/// declined files are remembered in drifters.toml. `include_untracked`
+/// includes all of them without asking.
+///
+/// With `dry_run`, files are resolved, checked and run through section
+/// extraction and redaction as usual, but nothing is written, prompted for
+/// or committed.
+///
+/// With `amend`, the changes are folded into this machine's previous push
+/// commit when [`amend_blocker`] finds that safe, instead of adding another
+/// commit.
+pub fn push_command(
+    app_name: Option<String>,
+    include_untracked: bool,
+    dry_run: bool,
+    amend: bool,
+    message: Option<String>,
+) -> Result<()> {
     log::info!("Pushing configs to machine branch");
+    if dry_run {
+        println!("(Dry run - nothing will be written or pushed)");
+    }
 
     // Load local config
-    let config = LocalConfig::load()?;
+    let mut config = LocalConfig::load()?;
+    let key = SecretKey::load()?;
     let machine_branch = format!("machines/{}", config.machine_id);
 
     // Set up ephemeral repo on this machine's branch
@@ -17,11 +89,14 @@ pub fn push_command(app_name: Option<String>) -> Result<()> {
     let repo_path = repo_guard.path();
 
     // Guard: detect stale machine IDs
-    crate::cli::common::verify_machine_registration(&config, repo_path)?;
+    crate::cli::common::verify_machine_registration(&mut config, repo_path)?;
+    // The guard may have switched to a renamed ID
+    let machine_branch = format!("machines/{}", config.machine_id);
 
     // Load sync rules from main (checkout main temporarily to read rules, then switch back)
     // sync-rules.toml lives on main, so we read it via git show
-    let rules = load_rules_from_main(repo_path)?;
+    let rules = load_rules_from_branch(repo_path, main_branch())?;
+    rules.validate()?;
 
     if rules.apps.is_empty() {
         println!("No apps configured for sync.");
@@ -41,26 +116,91 @@ pub fn push_command(app_name: Option<String>) -> Result<()> {
     };
 
     let mut pushed_files = 0;
+    let mut deleted_files = 0;
+    // (app, files pushed) for the activity log
+    let mut pushed_per_app = Vec::new();
     let mut warnings = Vec::new();
+    let mut declined_changed = false;
+    let mut synced_changed = false;
+    let now = chrono::Utc::now().timestamp();
 
     for app in &apps_to_push {
         let app_config = rules.apps.get(app).unwrap();
 
         println!("\nPushing configs for '{}'...", app);
 
+        let redactions = compile_redactions(&app_config.redact)?;
+
         // Resolve fileset for this machine using current OS
-        let fileset = resolve_fileset(
+        let (fileset, ignored) = resolve_fileset_with_ignored(
             app_config,
+            &rules.ignore,
+            &rules.groups,
             &config.machine_id,
             std::env::consts::OS,
         )?;
+        for path in &ignored {
+            println!("  - {} skipped (matches the global ignore list)", path.display());
+        }
 
-        if fileset.is_empty() {
-            log::warn!("No files in fileset for app '{}'", app);
-            warnings.push(format!("No files in fileset for app '{}'", app));
+        // Files this machine synced before and has since deleted are removed
+        // from the branch, with a tombstone so merge and pull delete them
+        // elsewhere too
+        let app_dir = repo_path.join("apps").join(app);
+        let mut tombstones = read_app_tombstones(repo_path, app)?;
+        let synced = config.synced_files.get(app).cloned().unwrap_or_default();
+        for gone in deleted_since_sync(&synced, &fileset) {
+            let filename = gone
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unknown")
+                .to_string();
+            let on_branch = app_dir.join(&filename).exists();
+            let repo_file = format!("apps/{}/{}", app, filename);
+            let origin_main = format!("origin/{}", main_branch());
+            let on_main = show_file_at(repo_path, &origin_main, &repo_file).is_ok();
+            if on_branch || on_main {
+                if dry_run {
+                    println!(
+                        "  - {} ({}) - deleted locally, would be deleted everywhere",
+                        filename,
+                        gone.display()
+                    );
+                    deleted_files += 1;
+                    continue;
+                }
+                if on_branch {
+                    fs::remove_file(app_dir.join(&filename))?;
+                }
+                tombstones.insert(filename.clone(), now);
+                println!("  - {} ({}) deleted", filename, gone.display());
+                deleted_files += 1;
+            }
+            if !dry_run {
+                if let Some(paths) = config.synced_files.get_mut(app) {
+                    paths.remove(gone.to_string_lossy().as_ref());
+                }
+                synced_changed = true;
+            }
+        }
+        if !dry_run {
+            write_app_tombstones(repo_path, app, &tombstones)?;
+        }
+
+        if let Some(warning) = check_fileset(app, app_config, &fileset)? {
+            log::warn!("{}", warning);
+            warnings.push(warning);
             continue;
         }
 
+        // On an app's very first push from this machine every file is new, so
+        // the first-time prompt only applies once the app has been pushed
+        let app_previously_pushed = repo_path.join("apps").join(app).exists();
+        let mut modes = BTreeMap::new();
+        let pushed_links = read_app_links(repo_path, app)?;
+        let mut link_updates = BTreeMap::new();
+        let pushed_before = pushed_files;
+
         for file_path in fileset {
             // Get filename
             let filename = file_path
@@ -68,7 +208,14 @@ pub fn push_command(app_name: Option<String>) -> Result<()> {
                 .and_then(|s| s.to_str())
                 .unwrap_or("unknown");
 
-            if !file_path.exists() {
+            // Only set for apps with follow_symlinks; others never see links
+            let link_target = if app_config.follow_symlinks {
+                fs::read_link(&file_path).ok()
+            } else {
+                None
+            };
+
+            if link_target.is_none() && !file_path.exists() {
                 log::warn!("File not found: {:?}", file_path);
                 warnings.push(format!("File not found: {:?}", file_path));
                 continue;
@@ -78,13 +225,88 @@ pub fn push_command(app_name: Option<String>) -> Result<()> {
             let dest_dir = repo_path
                 .join("apps")
                 .join(app);
+            let dest_path = dest_dir.join(filename);
 
-            fs::create_dir_all(&dest_dir)?;
+            // First-time check for files this machine has never pushed
+            let path_key = file_path.to_string_lossy().to_string();
+            match first_time_decision(
+                &path_key,
+                dest_path.exists() || pushed_links.contains_key(filename) || !app_previously_pushed,
+                include_untracked,
+                &config.declined_files,
+            ) {
+                FirstTime::Tracked => {}
+                FirstTime::Declined => {
+                    log::debug!("Skipping previously declined {:?}", file_path);
+                    continue;
+                }
+                FirstTime::Include => {
+                    let before = config.declined_files.len();
+                    config.declined_files.retain(|d| d != &path_key);
+                    declined_changed |= config.declined_files.len() != before;
+                }
+                FirstTime::Prompt if dry_run => {
+                    println!(
+                        "  ? {} ({}) - new file, push would ask whether to include it",
+                        filename,
+                        file_path.display()
+                    );
+                    continue;
+                }
+                FirstTime::Prompt => {
+                    let msg = format!(
+                        "New file {} matched for '{}' (never pushed). Include it?",
+                        file_path.display(),
+                        app
+                    );
+                    if !confirm_operation(&msg, true)? {
+                        println!("  - {} skipped; it won't be offered again", filename);
+                        config.declined_files.push(path_key);
+                        declined_changed = true;
+                        continue;
+                    }
+                }
+            }
 
-            let dest_path = dest_dir.join(filename);
+            // Symlinks are pushed as where they point, not their content
+            if let Some(link) = link_target {
+                let link = link.to_string_lossy().to_string();
+                if dry_run {
+                    let state = if pushed_links.get(filename) == Some(&link) {
+                        "unchanged"
+                    } else {
+                        "changed"
+                    };
+                    println!(
+                        "  {} ({}) - symlink to {}, {}",
+                        filename,
+                        file_path.display(),
+                        link,
+                        state
+                    );
+                } else {
+                    if dest_path.exists() {
+                        fs::remove_file(&dest_path)?;
+                    }
+                    println!("  {} {} ({} -> {})", ok(), filename, file_path.display(), link);
+                    link_updates.insert(filename.to_string(), Some(link));
+                    tombstones.remove(filename);
+                    synced_changed |=
+                        config.synced_files.entry(app.clone()).or_default().insert(path_key);
+                }
+                pushed_files += 1;
+                continue;
+            }
 
             // Safety check
-            if !check_file_safety(&file_path, &dest_path)? {
+            if dry_run {
+                if !check_file_safety(&file_path, &dest_path)? {
+                    warnings.push(format!(
+                        "{:?} appears risky; push would ask before including it",
+                        file_path
+                    ));
+                }
+            } else if !check_file_safety(&file_path, &dest_path)? {
                 let msg = format!(
                     "File {:?} appears risky to push. Continue?",
                     file_path
@@ -95,33 +317,88 @@ pub fn push_command(app_name: Option<String>) -> Result<()> {
                 }
             }
 
-            // Read file content
-            let content = fs::read_to_string(&file_path)?;
-
-            // Try to extract syncable content (excludes drifters::exclude sections)
-            let comment = detect_comment_syntax(filename);
-            let content_to_sync = match extract_syncable_content(&content, comment)? {
-                Some(syncable) => {
-                    log::debug!("Found section tags in {}, syncing non-excluded content", filename);
-                    syncable
+            // Binary files are pushed byte for byte, without section
+            // extraction or redaction
+            let content_to_sync = match String::from_utf8(fs::read(&file_path)?) {
+                Ok(content) => {
+                    syncable_text(&content, filename, app_config, &redactions, key.as_ref())?
+                        .into_bytes()
+                }
+                Err(_) if !app_config.redact.is_empty() => {
+                    return Err(DriftersError::Config(format!(
+                        "{} is a binary file, so the redact patterns of '{}' can't be applied \
+                         to it. Remove it from the app's include patterns, or the patterns \
+                         from the app.",
+                        file_path.display(),
+                        app
+                    )));
                 }
-                None => {
-                    // No tags found, sync entire file
-                    log::debug!("No section tags in {}, syncing entire file", filename);
-                    content.clone()
+                Err(binary) => {
+                    log::debug!("{} is binary, syncing it unchanged", filename);
+                    binary.into_bytes()
                 }
             };
 
+            if dry_run {
+                // Show exactly what would leave this machine, against what
+                // the branch has now
+                let pushed = fs::read(&dest_path).unwrap_or_default();
+                if pushed == content_to_sync {
+                    println!("  {} ({}) - unchanged", filename, file_path.display());
+                } else if let (Ok(old), Ok(new)) =
+                    (std::str::from_utf8(&pushed), std::str::from_utf8(&content_to_sync))
+                {
+                    println!("  {} ({}):", filename, file_path.display());
+                    print!("{}", diffview::changed_lines(old, new));
+                } else {
+                    println!(
+                        "  {} ({}) - binary, {} bytes",
+                        filename,
+                        file_path.display(),
+                        content_to_sync.len()
+                    );
+                }
+                pushed_files += 1;
+                continue;
+            }
+
             // Write to apps/[app]/[filename] on machine branch
+            fs::create_dir_all(&dest_dir)?;
             fs::write(&dest_path, &content_to_sync)?;
             log::debug!("Wrote content to {:?}", dest_path);
+            if let Some(mode) = file_mode(&file_path) {
+                modes.insert(filename.to_string(), mode);
+            }
+            if pushed_links.contains_key(filename) {
+                link_updates.insert(filename.to_string(), None);
+            }
+            tombstones.remove(filename);
+            synced_changed |=
+                config.synced_files.entry(app.clone()).or_default().insert(path_key);
 
-            println!("  ✓ {} ({})", filename, file_path.display());
+            println!("  {} {} ({})", ok(), filename, file_path.display());
             pushed_files += 1;
         }
+
+        write_app_modes(repo_path, app, &modes)?;
+        write_app_links(repo_path, app, &link_updates)?;
+        if !dry_run {
+            write_app_tombstones(repo_path, app, &tombstones)?;
+        }
+        if pushed_files > pushed_before {
+            pushed_per_app.push((app.clone(), pushed_files - pushed_before));
+        }
+    }
+
+    if declined_changed && !dry_run {
+        config.save()?;
     }
 
-    if pushed_files == 0 {
+    if pushed_files == 0 && deleted_files == 0 {
+        // Records of files gone everywhere already were dropped
+        if synced_changed && !dry_run {
+            config.save()?;
+        }
         println!("\nNo files to push");
         return Ok(());
     }
@@ -134,43 +411,258 @@ pub fn push_command(app_name: Option<String>) -> Result<()> {
         }
     }
 
+    if dry_run {
+        println!(
+            "\nDry run complete: would push {} file(s) for {} app(s) to branch '{}'",
+            pushed_files,
+            apps_to_push.len(),
+            machine_branch
+        );
+        if deleted_files > 0 {
+            println!("Would delete {} file(s) deleted on this machine", deleted_files);
+        }
+        return Ok(());
+    }
+
     // Confirm push
     println!("\nPushed {} file(s) for {} app(s) to branch '{}'", pushed_files, apps_to_push.len(), machine_branch);
+    if deleted_files > 0 {
+        println!("Deleted {} file(s) deleted on this machine", deleted_files);
+    }
     if !confirm_operation("Commit and push these changes?", true)? {
         return Err(DriftersError::UserCancelled);
     }
 
     // Commit and push
     println!("\nCommitting changes...");
-    let message = if apps_to_push.len() == 1 {
-        format!("Update {} configs from {}", apps_to_push[0], config.machine_id)
+    let message = commit_message(
+        if apps_to_push.len() == 1 {
+            format!("Update {} configs from {}", apps_to_push[0], config.machine_id)
+        } else {
+            format!("Update configs from {}", config.machine_id)
+        },
+        message.as_deref(),
+    );
+
+    let previous = if amend {
+        let previous = commit_info(repo_path, "HEAD")?;
+        ensure_full_history(repo_path)?;
+        let merged = match &previous {
+            Some(last) => was_merged(repo_path, last, &machine_branch)?,
+            None => false,
+        };
+        let now = chrono::Utc::now().timestamp();
+        match previous.as_ref().map_or(Some("this branch has no commits yet"), |last| {
+            amend_blocker(last, &message, &config.machine_id, merged, now)
+        }) {
+            None => previous,
+            Some(reason) => {
+                println!("Not amending: {}. Creating a new commit.", reason);
+                None
+            }
+        }
     } else {
-        format!("Update configs from {}", config.machine_id)
+        None
     };
 
-    commit_and_push(repo_path, &message)?;
+    match previous {
+        Some(last) => amend_and_push(repo_path, &message, &last.hash)?,
+        None => commit_and_push(repo_path, &message)?,
+    }
+    if synced_changed {
+        config.save()?;
+    }
 
-    println!("✓ Successfully pushed {} file(s) to branch '{}'", pushed_files, machine_branch);
+    println!(
+        "{} Successfully pushed {} file(s) to branch '{}'",
+        ok(),
+        pushed_files,
+        machine_branch
+    );
+
+    // Heartbeat: record this sync in the registry on main. Only reached after
+    // a real push, and throttled, so it never adds a commit on its own
+    // unless this machine also records activity.
+    checkout_branch(repo_path, main_branch())?;
+    let mut record = stage_heartbeat(&config, repo_path)?;
+    if config.record_activity {
+        stage_activity(&config, repo_path, &pushed_per_app)?;
+        record = true;
+    }
+    if record {
+        if let Err(e) = commit_and_push(repo_path, &format!("Record sync from {}", config.machine_id)) {
+            log::warn!("Could not record this sync on main: {}", e);
+        }
+    }
 
     Ok(())
 }
 
-/// Load sync-rules.toml from the main branch without switching branches.
-/// Uses `git show main:.drifters/sync-rules.toml`.
-fn load_rules_from_main(repo_path: &std::path::Path) -> Result<SyncRules> {
-    let output = std::process::Command::new("git")
-        .arg("-C")
-        .arg(repo_path)
-        .args(["show", "main:.drifters/sync-rules.toml"])
-        .output()?;
+/// The part of a text file that is pushed: everything outside
+/// `drifters::exclude` sections (all of it in `full` sync mode), with
+/// redacted lines replaced and `drifters::encrypt` sections encrypted.
+pub(crate) fn syncable_text(
+    content: &str,
+    filename: &str,
+    app_config: &AppConfig,
+    redactions: &[Regex],
+    key: Option<&SecretKey>,
+) -> Result<String> {
+    let comment = comment_syntax_for(app_config.comment_syntax.as_deref(), filename);
+    // Normalized first, so sections are found the same whatever the endings
+    let content = app_config.line_endings.for_repo(content);
+    let full = app_config.sync_mode_for(filename) == SyncMode::Full;
+    let sections = if full { None } else { extract_syncable_content(&content, &comment)? };
+    let content_to_sync = match sections {
+        Some(syncable) => {
+            log::debug!("Found section tags in {}, syncing non-excluded content", filename);
+            syncable
+        }
+        None => {
+            // No tags found (or not looked for), sync entire file
+            log::debug!("Syncing all of {}", filename);
+            content.to_string()
+        }
+    };
+    let redacted = redact_content(&content_to_sync, redactions);
+    seal_sections(&redacted, &comment, key, filename)
+}
+
+/// Paths this machine pushed or pulled before that no longer exist locally,
+/// so were deleted here. A path whose filename is still provided by another
+/// file of the current fileset is left alone: the app's file moved, it was
+/// not deleted.
+fn deleted_since_sync(synced: &BTreeSet<String>, fileset: &[PathBuf]) -> Vec<PathBuf> {
+    synced
+        .iter()
+        .map(PathBuf::from)
+        .filter(|path| path.symlink_metadata().is_err())
+        .filter(|path| !fileset.iter().any(|f| f.file_name() == path.file_name()))
+        .collect()
+}
+
+/// `--amend` only rewrites a commit this soon after it was made; older ones
+/// may well have been fetched elsewhere (`pull-app --from`, `diff`).
+const AMEND_WINDOW_SECS: i64 = 60 * 60;
+
+/// Why the branch's last commit must not be amended into, or `None` if it
+/// may: it has to be this machine's own push of the same apps (same
+/// subject), recent, and not yet merged into main, since rewriting a commit
+/// main already builds on would make it disappear from the branch's history
+/// while its content lives on in main.
+fn amend_blocker(
+    last: &CommitInfo,
+    message: &str,
+    machine_id: &str,
+    merged: bool,
+    now: i64,
+) -> Option<&'static str> {
+    if last.subject != message {
+        Some("the previous commit is not a push of the same app(s) from this machine")
+    } else if last.machine.as_deref().is_some_and(|m| m != machine_id) {
+        Some("the previous commit was made by another machine")
+    } else if merged {
+        Some("the previous commit has already been merged into main")
+    } else if now - last.time > AMEND_WINDOW_SECS {
+        Some("the previous commit is more than an hour old")
+    } else {
+        None
+    }
+}
+
+/// Whether main already contains `last`: either through a full merge, or a
+/// per-app merge from `machine_branch` made since.
+fn was_merged(repo_path: &PathBuf, last: &CommitInfo, machine_branch: &str) -> Result<bool> {
+    let main = format!("origin/{}", main_branch());
+    if is_ancestor(repo_path, &last.hash, &main)? {
+        return Ok(true);
+    }
+    let merged_from = format!("from {}", machine_branch);
+    Ok(messages_since(repo_path, &main, last.time)?
+        .iter()
+        .any(|message| message.contains(&merged_from)))
+}
 
-    if !output.status.success() {
-        // Fallback: try reading from the current branch (for new repos where main
-        // might have the file from init)
-        return SyncRules::load(&repo_path.to_path_buf());
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_never_pushed_file_triggers_first_time_prompt() {
+        let declined = vec!["/home/me/.config/app/tmp.json".to_string()];
+
+        assert_eq!(
+            first_time_decision("/home/me/.config/app/new.json", false, false, &declined),
+            FirstTime::Prompt
+        );
+        // Already on the branch: no prompt
+        assert_eq!(
+            first_time_decision("/home/me/.config/app/new.json", true, false, &declined),
+            FirstTime::Tracked
+        );
+        // Declined before: not asked again
+        assert_eq!(
+            first_time_decision("/home/me/.config/app/tmp.json", false, false, &declined),
+            FirstTime::Declined
+        );
+        // --include-untracked includes even previously declined files
+        assert_eq!(
+            first_time_decision("/home/me/.config/app/tmp.json", false, true, &declined),
+            FirstTime::Include
+        );
     }
 
-    let content = String::from_utf8_lossy(&output.stdout);
-    let rules: SyncRules = toml::from_str(&content)?;
-    Ok(rules)
+    #[test]
+    fn test_deleted_since_sync_only_reports_synced_files_now_gone() {
+        let dir = tempfile::tempdir().unwrap();
+        let kept = dir.path().join(".zshrc");
+        let moved = dir.path().join("new/.zprofile");
+        fs::create_dir_all(moved.parent().unwrap()).unwrap();
+        fs::write(&kept, "export A=1\n").unwrap();
+        fs::write(&moved, "export B=1\n").unwrap();
+        let path = |p: &std::path::Path| p.to_string_lossy().to_string();
+
+        let synced: BTreeSet<String> = [
+            path(&kept),
+            path(&dir.path().join(".zshenv")),
+            path(&dir.path().join(".zprofile")),
+        ]
+        .into_iter()
+        .collect();
+        let fileset = vec![kept.clone(), moved];
+        assert_eq!(
+            deleted_since_sync(&synced, &fileset),
+            vec![dir.path().join(".zshenv")],
+            "only the synced file that is gone and not provided elsewhere is deleted"
+        );
+
+        // A file this machine never pushed or pulled is never reported, even
+        // if it is missing locally: it may simply not have been pulled yet
+        assert!(deleted_since_sync(&BTreeSet::new(), &[]).is_empty());
+    }
+
+    #[test]
+    fn test_amend_only_into_recent_unmerged_own_push() {
+        let now = 1_780_000_000;
+        let last = CommitInfo {
+            hash: "abc123".to_string(),
+            time: now - 10 * 60,
+            subject: "Update zsh configs from laptop".to_string(),
+            machine: Some("laptop".to_string()),
+        };
+        let message = "Update zsh configs from laptop";
+
+        assert_eq!(amend_blocker(&last, message, "laptop", false, now), None);
+        // Commits without trailers are judged by their subject alone
+        let untrailered = CommitInfo { machine: None, ..last.clone() };
+        assert_eq!(amend_blocker(&untrailered, message, "laptop", false, now), None);
+
+        // A different app, another machine, merged, or too old: new commit
+        let other_app = "Update vim configs from laptop";
+        assert!(amend_blocker(&last, other_app, "laptop", false, now).is_some());
+        let foreign = CommitInfo { machine: Some("desktop".to_string()), ..last.clone() };
+        assert!(amend_blocker(&foreign, message, "laptop", false, now).is_some());
+        assert!(amend_blocker(&last, message, "laptop", true, now).is_some());
+        assert!(amend_blocker(&last, message, "laptop", false, now + 2 * 3600).is_some());
+    }
 }
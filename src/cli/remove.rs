@@ -1,15 +1,18 @@
 use crate::config::{LocalConfig, MachineRegistry, SyncRules};
 use crate::error::{DriftersError, Result};
 use crate::git::{
-    checkout_branch, commit_and_push, confirm_operation, EphemeralRepoGuard,
+    checkout_branch, commit_and_push, confirm_operation, main_branch, EphemeralRepoGuard,
 };
+use std::path::Path;
 
 /// Remove an app's configs.
 ///
 /// * No flags  — removes this machine's uploaded configs for the app from its branch.
 /// * `--machine <id>` — same but for the named machine's branch.
 /// * `--all`   — removes the app from every branch and from sync-rules.toml on main.
-pub fn remove_app(app_name: String, machine: Option<String>, all: bool) -> Result<()> {
+///
+/// Every variant asks for confirmation (default no) unless `yolo` is set.
+pub fn remove_app(app_name: String, machine: Option<String>, all: bool, yolo: bool) -> Result<()> {
     if machine.is_some() && all {
         return Err(DriftersError::Config(
             "Cannot use --machine and --all together. \
@@ -20,14 +23,14 @@ pub fn remove_app(app_name: String, machine: Option<String>, all: bool) -> Resul
 
     log::info!("Removing app '{}' (machine={:?}, all={})", app_name, machine, all);
 
-    let config = LocalConfig::load()?;
+    let mut config = LocalConfig::load()?;
 
     println!("Fetching latest repository...");
     let repo_guard = EphemeralRepoGuard::new(&config)?;
     let repo_path = repo_guard.path();
 
     // Guard: detect stale machine IDs
-    crate::cli::common::verify_machine_registration(&config, repo_path)?;
+    crate::cli::common::verify_machine_registration(&mut config, repo_path)?;
 
     let rules = SyncRules::load(repo_path)?;
 
@@ -36,29 +39,16 @@ pub fn remove_app(app_name: String, machine: Option<String>, all: bool) -> Resul
     }
 
     if all {
-        remove_from_all(&app_name, repo_path, &config)
+        remove_from_all(&app_name, repo_path, &config, yolo)
     } else {
         let target = match machine {
             Some(ref id) => {
-                let registry = MachineRegistry::load(repo_path)?;
-                if !registry.machines.contains_key(id) {
-                    let known: Vec<_> = registry.machines.keys().cloned().collect();
-                    return Err(DriftersError::Config(format!(
-                        "Machine '{}' is not registered in this repo.\n\
-                         Registered machines: {}",
-                        id,
-                        if known.is_empty() {
-                            "(none)".to_string()
-                        } else {
-                            known.join(", ")
-                        }
-                    )));
-                }
+                crate::cli::common::require_registered_machine(repo_path, id)?;
                 id.clone()
             }
             None => config.machine_id.clone(),
         };
-        remove_from_machine(&app_name, &target, &config.machine_id, repo_path)
+        remove_from_machine(&app_name, &target, &config.machine_id, repo_path, yolo)
     }
 }
 
@@ -68,12 +58,13 @@ fn remove_from_machine(
     target_machine: &str,
     local_machine: &str,
     repo_path: &std::path::Path,
+    yolo: bool,
 ) -> Result<()> {
     let repo_path_buf = repo_path.to_path_buf();
     let machine_branch = format!("machines/{}", target_machine);
 
     // Switch to the machine's branch
-    if crate::git::checkout_or_create_branch(&repo_path_buf, &machine_branch, "main").is_err() {
+    if crate::git::checkout_or_create_branch(&repo_path_buf, &machine_branch, main_branch()).is_err() {
         println!(
             "  No branch found for machine '{}' (nothing to delete)",
             target_machine
@@ -83,7 +74,17 @@ fn remove_from_machine(
 
     let app_dir = repo_path.join("apps").join(app_name);
     if app_dir.exists() {
-        std::fs::remove_dir_all(&app_dir)?;
+        let confirmed = delete_app_dir_confirmed(&app_dir, app_name, &machine_branch, |msg| {
+            if yolo {
+                Ok(true)
+            } else {
+                confirm_operation(msg, false)
+            }
+        })?;
+        if !confirmed {
+            println!("Cancelled.");
+            return Ok(());
+        }
         println!(
             "  Deleted uploaded configs for '{}' on branch '{}'",
             app_name, machine_branch
@@ -116,11 +117,56 @@ fn remove_from_machine(
     Ok(())
 }
 
+/// List the files under `app_dir`, ask `confirm` whether to delete them, and
+/// delete the directory only if confirmed. Returns whether it was deleted.
+fn delete_app_dir_confirmed(
+    app_dir: &Path,
+    app_name: &str,
+    branch: &str,
+    confirm: impl FnOnce(&str) -> Result<bool>,
+) -> Result<bool> {
+    let files = list_files(app_dir, app_dir)?;
+
+    eprintln!(
+        "\n⚠️  This will delete {} pushed file(s) for '{}' from branch '{}':",
+        files.len(),
+        app_name,
+        branch
+    );
+    for file in &files {
+        eprintln!("   • apps/{}/{}", app_name, file);
+    }
+    eprintln!("   Note: local config files on this machine are NOT deleted.");
+
+    if !confirm(&format!("Delete these files from '{}'?", branch))? {
+        return Ok(false);
+    }
+
+    std::fs::remove_dir_all(app_dir)?;
+    Ok(true)
+}
+
+/// Relative paths of all files below `dir`, sorted.
+fn list_files(root: &Path, dir: &Path) -> Result<Vec<String>> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(list_files(root, &path)?);
+        } else if let Ok(rel) = path.strip_prefix(root) {
+            files.push(rel.to_string_lossy().to_string());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
 /// Remove `app_name` from every machine and from sync-rules entirely.
 fn remove_from_all(
     app_name: &str,
     repo_path: &std::path::Path,
     _config: &LocalConfig,
+    yolo: bool,
 ) -> Result<()> {
     let repo_path_buf = repo_path.to_path_buf();
     eprintln!(
@@ -131,7 +177,7 @@ fn remove_from_all(
     eprintln!("   • Removes the app from sync-rules.toml");
     eprintln!("   Note: local config files on each machine are NOT deleted.");
 
-    if !confirm_operation(&format!("Remove '{}' from all machines?", app_name), false)? {
+    if !yolo && !confirm_operation(&format!("Remove '{}' from all machines?", app_name), false)? {
         println!("Cancelled.");
         return Ok(());
     }
@@ -156,7 +202,7 @@ fn remove_from_all(
     }
 
     // Remove from main
-    checkout_branch(&repo_path_buf, "main")?;
+    checkout_branch(&repo_path_buf, main_branch())?;
 
     // Delete apps/<app>/ from main if it exists
     let app_dir = repo_path.join("apps").join(app_name);
@@ -178,3 +224,42 @@ fn remove_from_all(
     println!("  Local config files on each machine have NOT been deleted.");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app_dir_fixture() -> (tempfile::TempDir, std::path::PathBuf) {
+        let tmp = tempfile::tempdir().unwrap();
+        let app_dir = tmp.path().join("apps").join("zed");
+        std::fs::create_dir_all(&app_dir).unwrap();
+        std::fs::write(app_dir.join("settings.json"), "{}").unwrap();
+        (tmp, app_dir)
+    }
+
+    #[test]
+    fn test_machine_removal_prompts_and_decline_keeps_files() {
+        let (_tmp, app_dir) = app_dir_fixture();
+
+        let mut asked = None;
+        let deleted = delete_app_dir_confirmed(&app_dir, "zed", "machines/m1", |msg| {
+            asked = Some(msg.to_string());
+            Ok(false)
+        })
+        .unwrap();
+
+        assert!(!deleted);
+        assert!(asked.unwrap().contains("machines/m1"));
+        assert!(app_dir.join("settings.json").exists());
+    }
+
+    #[test]
+    fn test_machine_removal_confirmed_deletes_dir() {
+        let (_tmp, app_dir) = app_dir_fixture();
+
+        let deleted = delete_app_dir_confirmed(&app_dir, "zed", "machines/m1", |_| Ok(true)).unwrap();
+
+        assert!(deleted);
+        assert!(!app_dir.exists());
+    }
+}
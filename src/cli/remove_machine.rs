@@ -93,9 +93,12 @@ pub fn remove_machine(machine_id: String) -> Result<()> {
             overrides_removed += 1;
         }
     }
+    for members in rules.groups.values_mut() {
+        members.retain(|member| *member != machine_id);
+    }
 
     // Remove entry from MachineRegistry
-    registry.machines.remove(&machine_id);
+    registry.remove_machine(&machine_id);
 
     // Persist changes
     registry.save(repo_path)?;
@@ -1,6 +1,9 @@
+use crate::cli::style::decorate;
 use crate::config::{LocalConfig, SyncRules};
 use crate::error::{DriftersError, Result};
-use crate::git::{commit_and_push, confirm_operation, EphemeralRepoGuard};
+use crate::git::{commit_and_push, confirm_operation, main_branch, EphemeralRepoGuard};
+use std::fmt::Write as _;
+use std::path::Path;
 
 /// Rename an app in sync-rules and in the repo directory structure.
 ///
@@ -9,8 +12,9 @@ use crate::git::{commit_and_push, confirm_operation, EphemeralRepoGuard};
 ///   • The app key in `.drifters/sync-rules.toml`
 ///
 /// This affects all machines — they will see the new name on their next
-/// `drifters push-app` or `drifters pull-app`.
-pub fn rename_app(old_name: String, new_name: String) -> Result<()> {
+/// `drifters push-app` or `drifters pull-app`. With `dry_run`, only shows
+/// what would change.
+pub fn rename_app(old_name: String, new_name: String, dry_run: bool) -> Result<()> {
     log::info!("Renaming app '{}' → '{}'", old_name, new_name);
 
     // ── Validate new_name before touching anything ────────────────────────────
@@ -55,16 +59,41 @@ pub fn rename_app(old_name: String, new_name: String) -> Result<()> {
     }
 
     // ── Confirm with user ─────────────────────────────────────────────────────
-    println!("\nRename app '{}' → '{}'", old_name, new_name);
-    println!("This will:");
-    println!(
-        "  • Rename apps/{old}/ → apps/{new}/ in the repo",
-        old = old_name,
-        new = new_name
-    );
-    println!("  • Update the app entry in sync-rules.toml");
-    println!("  Note: This affects all machines — they will see the new name on next sync.");
+    let old_app_dir = repo_path.join("apps").join(&old_name);
+    let file_count = count_files(&old_app_dir)?;
+
+    let mut plan = String::new();
+    writeln!(plan, "\nRename app '{}' → '{}'", old_name, new_name)?;
+    writeln!(plan, "{}", if dry_run { "This would:" } else { "This will:" })?;
+    if old_app_dir.exists() {
+        writeln!(
+            plan,
+            "  • Rename apps/{old}/ → apps/{new}/ on {branch} ({count} file(s))",
+            old = old_name,
+            new = new_name,
+            branch = main_branch(),
+            count = file_count
+        )?;
+    } else {
+        writeln!(
+            plan,
+            "  • (No apps/{}/ directory on {} to rename)",
+            old_name,
+            main_branch()
+        )?;
+    }
+    writeln!(plan, "  • Update the app entry in sync-rules.toml")?;
+    writeln!(plan, "  • Leave your local config (~/.config/drifters/drifters.toml) unchanged")?;
+    writeln!(
+        plan,
+        "  Note: This affects all machines — they will see the new name on next sync."
+    )?;
+    print!("{}", decorate(&plan));
 
+    if dry_run {
+        println!("\n(Dry run - nothing was changed)");
+        return Ok(());
+    }
     if !confirm_operation("Proceed with rename?", false)? {
         println!("Cancelled.");
         return Ok(());
@@ -86,7 +115,6 @@ pub fn rename_app(old_name: String, new_name: String) -> Result<()> {
     rules.save(&repo_path_buf)?;
 
     // ── Rename apps/<old>/ → apps/<new>/ (after TOML is safely persisted) ────
-    let old_app_dir = repo_path.join("apps").join(&old_name);
     let new_app_dir = repo_path.join("apps").join(&new_name);
 
     let dir_renamed = if old_app_dir.exists() {
@@ -116,3 +144,20 @@ pub fn rename_app(old_name: String, new_name: String) -> Result<()> {
 
     Ok(())
 }
+
+/// Number of files below `dir`, 0 if it doesn't exist.
+fn count_files(dir: &Path) -> Result<usize> {
+    if !dir.is_dir() {
+        return Ok(0);
+    }
+    let mut count = 0;
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            count += count_files(&entry.path())?;
+        } else {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
@@ -1,22 +1,18 @@
-use crate::config::{LocalConfig, MachineRegistry, SyncRules};
+use crate::cli::style::decorate;
+use crate::config::{validate_machine_id, LocalConfig, MachineRegistry, SyncRules};
 use crate::error::{DriftersError, Result};
-use crate::git::{commit_and_push, confirm_operation, EphemeralRepoGuard};
+use crate::git::{commit_and_push, confirm_operation, remote_has_branch, EphemeralRepoGuard};
+use std::fmt::Write as _;
 use std::io::{self, Write};
 
-pub fn rename_machine(old_id: String, new_id: String) -> Result<()> {
+/// Rename a machine: its branch, registry entry, overrides and group
+/// memberships, and the local config if it is this machine. With `dry_run`,
+/// only shows what would change.
+pub fn rename_machine(old_id: String, new_id: String, dry_run: bool) -> Result<()> {
     log::info!("Renaming machine '{}' → '{}'", old_id, new_id);
 
     // Validate new_id
-    if new_id.is_empty() {
-        return Err(DriftersError::Config(
-            "New machine ID cannot be empty.".to_string(),
-        ));
-    }
-    if new_id.contains('/') || new_id.contains('\\') {
-        return Err(DriftersError::Config(
-            "New machine ID cannot contain '/' or '\\'.".to_string(),
-        ));
-    }
+    let new_id = validate_machine_id(&new_id)?;
     if new_id == old_id {
         return Err(DriftersError::Config(format!(
             "New machine ID is the same as the current one ('{}').",
@@ -56,22 +52,66 @@ pub fn rename_machine(old_id: String, new_id: String) -> Result<()> {
     let old_branch = format!("machines/{}", old_id);
     let new_branch = format!("machines/{}", new_id);
 
-    println!(
-        "\nRename machine '{}' → '{}'",
-        old_id, new_id
-    );
-    println!("This will:");
-    println!("  • Rename branch '{}' → '{}'", old_branch, new_branch);
-    println!("  • Update the machine registry (.drifters/machines.toml)");
-    println!("  • Update machine overrides in sync-rules.toml");
+    let override_apps: Vec<&str> = rules
+        .apps
+        .iter()
+        .filter(|(_, app)| app.machines.contains_key(&old_id))
+        .map(|(name, _)| name.as_str())
+        .collect();
+    let member_of: Vec<&str> = rules
+        .groups
+        .iter()
+        .filter(|(_, members)| members.contains(&old_id))
+        .map(|(name, _)| name.as_str())
+        .collect();
+
+    let mut plan = String::new();
+    writeln!(plan, "\nRename machine '{}' → '{}'", old_id, new_id)?;
+    writeln!(plan, "{}", if dry_run { "This would:" } else { "This will:" })?;
+    if remote_has_branch(repo_path, &old_branch)? {
+        writeln!(plan, "  • Rename branch '{}' → '{}'", old_branch, new_branch)?;
+    } else {
+        writeln!(plan, "  • (No branch '{}' on the remote to rename)", old_branch)?;
+    }
+    writeln!(plan, "  • Update the machine registry (.drifters/machines.toml)")?;
+    if override_apps.is_empty() {
+        writeln!(plan, "  • (No machine overrides in sync-rules.toml to move)")?;
+    } else {
+        writeln!(
+            plan,
+            "  • Move {} machine override(s) in sync-rules.toml ({})",
+            override_apps.len(),
+            override_apps.join(", ")
+        )?;
+    }
+    if !member_of.is_empty() {
+        writeln!(
+            plan,
+            "  • Rename it in {} group(s) ({})",
+            member_of.len(),
+            member_of.join(", ")
+        )?;
+    }
     if old_id == config.machine_id {
-        println!(
+        writeln!(
+            plan,
             "  • Update your local config (~/.config/drifters/drifters.toml) to '{}'",
             new_id
-        );
+        )?;
+    } else {
+        writeln!(
+            plan,
+            "  • Let '{}' switch to the new ID the next time it runs drifters",
+            old_id
+        )?;
     }
+    print!("{}", decorate(&plan));
     io::stdout().flush()?;
 
+    if dry_run {
+        println!("\n(Dry run - nothing was changed)");
+        return Ok(());
+    }
     if !confirm_operation("Proceed with rename?", false)? {
         println!("Cancelled.");
         return Ok(());
@@ -123,16 +163,21 @@ pub fn rename_machine(old_id: String, new_id: String) -> Result<()> {
             overrides_renamed += 1;
         }
     }
+    for member in rules.groups.values_mut().flatten() {
+        if *member == old_id {
+            *member = new_id.clone();
+        }
+    }
 
-    // Rename entry in MachineRegistry
-    let mut machine_info = registry.machines.remove(&old_id).ok_or_else(|| {
+    // Rename entry in MachineRegistry, leaving a record for the renamed
+    // machine to find if this isn't it
+    let machine_info = registry.rename_machine(&old_id, &new_id).ok_or_else(|| {
         DriftersError::Config(format!(
             "Machine '{}' disappeared from registry during rename",
             old_id
         ))
     })?;
     machine_info.branch = Some(new_branch.clone());
-    registry.machines.insert(new_id.clone(), machine_info);
 
     // Persist changes
     registry.save(repo_path)?;
@@ -0,0 +1,42 @@
+use crate::config::{ActivityLog, LocalConfig};
+use crate::error::Result;
+use crate::git::EphemeralRepoGuard;
+
+/// Summarize the pushes recorded in `.drifters/activity.toml` over the last
+/// `days` days, per machine and app.
+pub fn show_report(days: u32) -> Result<()> {
+    let config = LocalConfig::load()?;
+
+    println!("Fetching latest from repository...");
+    let repo_guard = EphemeralRepoGuard::new(&config)?;
+    let repo_path = repo_guard.path();
+
+    let log = ActivityLog::load(repo_path)?;
+    let since = chrono::Utc::now() - chrono::Duration::days(i64::from(days));
+    let summary = log.summarize(since);
+
+    println!("\nDrifters Activity (last {} days)", days);
+    println!("{}", "=".repeat(60));
+    if summary.is_empty() {
+        println!("No activity recorded.");
+        if !config.record_activity {
+            println!("\nPushes from this machine are not recorded; to opt in, set");
+            println!("record_activity = true in ~/.config/drifters/drifters.toml");
+        }
+        return Ok(());
+    }
+
+    println!("{:<20} {:<16} {:>6} {:>6}  Last push", "Machine", "App", "Pushes", "Files");
+    for row in &summary {
+        println!(
+            "{:<20} {:<16} {:>6} {:>6}  {}",
+            row.machine,
+            row.app,
+            row.pushes,
+            row.files,
+            row.last.format("%Y-%m-%d %H:%M UTC")
+        );
+    }
+
+    Ok(())
+}
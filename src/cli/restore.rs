@@ -1,7 +1,18 @@
-use crate::config::{LocalConfig, SyncRules};
+use crate::cli::common::load_rules_from_branch;
+use crate::cli::pull::merge_into_local;
+use crate::cli::style::ok;
+use crate::config::{resolve_fileset, AppConfig, LocalConfig, SyncRules};
+use crate::crypto::{open_sections, SecretKey};
 use crate::error::{DriftersError, Result};
-use crate::git::{commit_and_push, EphemeralRepoGuard};
+use crate::git::{
+    checkout_or_create_branch, commit_and_push, confirm_operation, ensure_full_history,
+    main_branch, remote_has_branch, resolve_commit, show_file_at, EphemeralRepoGuard,
+};
+use crate::parser::redact::compile_redactions;
+use crate::parser::sections::{comment_syntax_for, CommentSyntax};
+use crate::sync::write_atomic;
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 pub fn restore_app(app_name: String, commit: String) -> Result<()> {
@@ -11,6 +22,7 @@ pub fn restore_app(app_name: String, commit: String) -> Result<()> {
     let config = LocalConfig::load()?;
     let repo_guard = EphemeralRepoGuard::new(&config)?;
     let repo_path = repo_guard.path();
+    ensure_full_history(repo_path)?;
 
     // Get the old version of sync-rules.toml
     let output = Command::new("git")
@@ -83,6 +95,7 @@ pub fn restore_rules(commit: String) -> Result<()> {
     let config = LocalConfig::load()?;
     let repo_guard = EphemeralRepoGuard::new(&config)?;
     let repo_path = repo_guard.path();
+    ensure_full_history(repo_path)?;
 
     // Get the old version of sync-rules.toml
     let output = Command::new("git")
@@ -127,3 +140,212 @@ pub fn restore_rules(commit: String) -> Result<()> {
 
     Ok(())
 }
+
+/// Restore one synced file's content from a commit onto this machine's
+/// branch (or `machine`'s), and with `apply` also write it to the local
+/// config path.
+pub fn restore_file(
+    app_name: String,
+    filename: String,
+    commit: String,
+    apply: bool,
+    machine: Option<String>,
+) -> Result<()> {
+    log::info!("Restoring {}/{} from commit {}", app_name, filename, commit);
+
+    let repo_file = app_file_repo_path(&app_name, &filename)?;
+    let short = &commit[..7.min(commit.len())];
+
+    // Load local config and repo, on the machine's branch
+    let config = LocalConfig::load()?;
+    let machine_id = machine.unwrap_or_else(|| config.machine_id.clone());
+    let other_machine = machine_id != config.machine_id;
+    if apply && other_machine {
+        return Err(DriftersError::Config(format!(
+            "--apply writes this machine's local file; it can't be combined with --machine {}",
+            machine_id
+        )));
+    }
+    let machine_branch = format!("machines/{}", machine_id);
+    let repo_guard = EphemeralRepoGuard::new(&config)?;
+    let repo_path = repo_guard.path();
+    // Another machine's branch is never created, only restored onto
+    if other_machine && !remote_has_branch(repo_path, &machine_branch)? {
+        return Err(DriftersError::Config(format!(
+            "Machine '{}' has no branch ({}) to restore onto",
+            machine_id, machine_branch
+        )));
+    }
+    checkout_or_create_branch(repo_path, &machine_branch, main_branch())?;
+    ensure_full_history(repo_path)?;
+
+    let hash = resolve_commit(repo_path, &commit)
+        .map_err(|_| DriftersError::Config(format!("Commit '{}' not found", commit)))?;
+    let old_content = show_file_at(repo_path, &hash, &repo_file).map_err(|_| {
+        DriftersError::Config(format!(
+            "{} did not exist in commit {}. Run 'drifters history file {} {}' to see \
+             the commits that have it.",
+            repo_file, short, app_name, filename
+        ))
+    })?;
+
+    let target = repo_path.join(&repo_file);
+    if fs::read_to_string(&target).ok().as_deref() == Some(old_content.as_str()) {
+        println!("{} is already at the version from commit {}", repo_file, short);
+    } else {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&target, &old_content)?;
+        println!("\n{} Restored {} from commit {}", ok(), repo_file, short);
+
+        println!("\nCommitting changes...");
+        let message = if other_machine {
+            format!("Restore {}/{} on {} from commit {}", app_name, filename, machine_id, short)
+        } else {
+            format!("Restore {}/{} from commit {}", app_name, filename, short)
+        };
+        commit_and_push(repo_path, &message)?;
+        println!("{} Changes committed and pushed to {}", ok(), machine_branch);
+    }
+
+    if other_machine {
+        println!(
+            "\nRun 'drifters merge-app {} --from {}' to bring it to main",
+            app_name, machine_id
+        );
+        return Ok(());
+    }
+    if !apply {
+        println!(
+            "\nRun 'drifters merge-app {}' to bring it to main, or re-run with --apply to write it locally",
+            app_name
+        );
+        return Ok(());
+    }
+
+    // Find where this file lives on this machine
+    let rules = load_rules_from_branch(repo_path, main_branch())?;
+    let app_config = rules
+        .apps
+        .get(&app_name)
+        .ok_or_else(|| DriftersError::AppNotFound(app_name.clone()))?;
+    let fileset = resolve_fileset(
+        app_config,
+        &rules.ignore,
+        &rules.groups,
+        &config.machine_id,
+        std::env::consts::OS,
+    )?;
+    let local_path = local_target(&fileset, &filename).ok_or_else(|| {
+        DriftersError::Config(format!(
+            "'{}' is not in the fileset of '{}' on this machine",
+            filename, app_name
+        ))
+    })?;
+
+    let comment = comment_syntax_for(app_config.comment_syntax.as_deref(), &filename);
+    let old_content =
+        open_sections(&old_content, &comment, SecretKey::load()?.as_ref(), &filename)?;
+
+    let msg = format!("Overwrite {} with the restored version?", local_path.display());
+    if !confirm_operation(&msg, false)? {
+        println!("Local file left unchanged");
+        return Ok(());
+    }
+    let redactions = compile_redactions(&app_config.redact)?;
+    apply_restored(local_path, &old_content, &filename, app_config, &comment, &redactions)?;
+    println!("{} {} ({})", ok(), filename, local_path.display());
+
+    Ok(())
+}
+
+/// Repo path of a synced file. Files are stored flat under `apps/<app>/`, so
+/// the filename must be a single path component.
+fn app_file_repo_path(app_name: &str, filename: &str) -> Result<String> {
+    let valid = |s: &str| !s.is_empty() && s != "." && s != ".." && !s.contains(['/', '\\']);
+    if !valid(app_name) || !valid(filename) {
+        return Err(DriftersError::Config(format!(
+            "Invalid app or file name: {}/{}",
+            app_name, filename
+        )));
+    }
+    Ok(format!("apps/{}/{}", app_name, filename))
+}
+
+/// The local path in `fileset` whose file name is `filename`.
+fn local_target<'a>(fileset: &'a [PathBuf], filename: &str) -> Option<&'a PathBuf> {
+    fileset
+        .iter()
+        .find(|p| p.file_name().and_then(|n| n.to_str()) == Some(filename))
+}
+
+/// Write restored repo content for `filename` to `local_path`, keeping the
+/// local exclude sections and redacted lines the same way `pull-app` does.
+fn apply_restored(
+    local_path: &Path,
+    content: &str,
+    filename: &str,
+    app_config: &AppConfig,
+    comment: &CommentSyntax,
+    redactions: &[regex::Regex],
+) -> Result<()> {
+    let line_endings = app_config.line_endings;
+    let final_content = if local_path.exists() {
+        let local_content = fs::read_to_string(local_path)?;
+        let mode = app_config.sync_mode_for(filename);
+        let merged =
+            merge_into_local(&local_content, content, comment, redactions, line_endings, mode)?;
+        if merged.dropped_sections > 0 {
+            println!(
+                "⚠ {} local exclude section(s) have no matching block in the restored \
+                 version and are dropped",
+                merged.dropped_sections
+            );
+        }
+        merged.content
+    } else {
+        if let Some(parent) = local_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        line_endings.for_local(content).into_owned()
+    };
+    write_atomic(local_path, final_content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore_file_path_and_apply() {
+        assert_eq!(app_file_repo_path("zsh", ".zshrc").unwrap(), "apps/zsh/.zshrc");
+        assert!(app_file_repo_path("zsh", "../machines.toml").is_err());
+        assert!(app_file_repo_path("zsh", "..").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let zshrc = dir.path().join(".zshrc");
+        let fileset = vec![dir.path().join("aliases.zsh"), zshrc.clone()];
+        assert_eq!(local_target(&fileset, ".zshrc"), Some(&zshrc));
+        assert_eq!(local_target(&fileset, "missing"), None);
+
+        // Local exclude sections and redacted values survive the restore
+        fs::write(
+            &zshrc,
+            "export A=new\n# drifters::exclude::start\nexport LOCAL=1\n# drifters::exclude::stop\ntoken=secret\n",
+        )
+        .unwrap();
+        let redactions = compile_redactions(&["^token=.*".to_string()]).unwrap();
+        let from_repo = "export A=old\n# drifters::exclude::start\n# drifters::exclude::stop\n\
+                         <redacted by drifters>\n";
+        let comment = comment_syntax_for(None, ".zshrc");
+        let app_config = AppConfig::default();
+        apply_restored(&zshrc, from_repo, ".zshrc", &app_config, &comment, &redactions).unwrap();
+        let restored = fs::read_to_string(&zshrc).unwrap();
+        assert!(restored.contains("export A=old"));
+        assert!(!restored.contains("export A=new"));
+        assert!(restored.contains("export LOCAL=1"));
+        assert!(restored.contains("token=secret"));
+    }
+}
@@ -1,75 +1,91 @@
-use crate::config::LocalConfig;
+use crate::cli::forge::{drifters_forge, Forge, Release};
+use crate::cli::style::{decorate, ok, warn};
+use crate::config::{LocalConfig, UpdateFrequency};
 use crate::error::Result;
-use serde::Deserialize;
+use chrono::{DateTime, Local, TimeZone};
+use std::path::Path;
+use std::process::{Command, ExitStatus};
 
-const REPO: &str = "tjirsch/drifters";
-const API_URL: &str = "https://api.github.com/repos";
+/// Installer script published with each release for this platform, next to
+/// a `.sha256` sidecar.
+#[cfg(windows)]
+const INSTALLER: &str = "drifters-installer.ps1";
+#[cfg(not(windows))]
+const INSTALLER: &str = "drifters-installer.sh";
 
-/// A release asset (file attached to a GitHub release)
-#[derive(Deserialize)]
-struct Asset {
-    name: String,
-    browser_download_url: String,
-}
+/// Name of the executable inside release archives.
+#[cfg(windows)]
+const BINARY: &str = "drifters.exe";
+#[cfg(not(windows))]
+const BINARY: &str = "drifters";
 
-/// Top-level release metadata returned by the GitHub Releases API
-#[derive(Deserialize)]
-struct Release {
-    tag_name: String,
-    html_url: String,
-    #[serde(default)]
-    assets: Vec<Asset>,
-}
+#[cfg(windows)]
+const RESTART_HINT: &str = "Please open a new terminal to use it.";
+#[cfg(not(windows))]
+const RESTART_HINT: &str = "Please restart your terminal or run: source ~/.profile";
 
-pub fn check_update_available(
-    client: &reqwest::blocking::Client,
-) -> Result<Option<(String, String)>> {
-    let url = format!("{}/{}/releases/latest", API_URL, REPO);
-    let response = client.get(&url).send()?;
-    if !response.status().is_success() {
-        return Ok(None);
-    }
-    let release: Release = response.json()?;
-    let latest_version = release.tag_name.trim_start_matches('v').to_string();
+pub fn check_update_available(forge: &dyn Forge) -> Result<Option<(String, String)>> {
+    let release = match forge.latest_release() {
+        Ok(release) => release,
+        Err(e) => {
+            log::debug!("Could not fetch the latest release: {}", e);
+            return Ok(None);
+        }
+    };
     let current = env!("CARGO_PKG_VERSION");
-    if compare_versions(current, &latest_version) < 0 {
-        Ok(Some((latest_version, release.html_url)))
+    if compare_versions(current, &release.version) < 0 {
+        Ok(Some((release.version, release.html_url)))
     } else {
         Ok(None)
     }
 }
 
+/// Format a Unix timestamp (seconds) as a readable datetime in `tz`, e.g.
+/// `2026-02-15 14:03:20 +01:00`. Out-of-range values are shown raw.
+fn format_epoch<Tz: TimeZone>(secs: u64, tz: &Tz) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    i64::try_from(secs)
+        .ok()
+        .and_then(|s| DateTime::from_timestamp(s, 0))
+        .map(|dt| dt.with_timezone(tz).format("%Y-%m-%d %H:%M:%S %:z").to_string())
+        .unwrap_or_else(|| format!("ts={}", secs))
+}
+
 pub fn maybe_check_for_updates(config: &mut LocalConfig) -> Result<()> {
-    let freq = config.self_update_frequency.as_str();
-    if freq == "never" {
-        return Ok(());
-    }
-    if freq == "daily" {
-        if let Some(last) = config.last_update_check {
-            let now = std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs();
-            if now.saturating_sub(last) < 86400 {
-                return Ok(());
-            }
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let interval = match config.self_update_frequency {
+        UpdateFrequency::Never => return Ok(()),
+        UpdateFrequency::Always => None,
+        UpdateFrequency::Every(interval) => Some(interval),
+    };
+    if let (Some(interval), Some(last)) = (interval, config.last_update_check) {
+        if now.saturating_sub(last) < interval.as_secs() {
+            log::debug!(
+                "Skipping update check ({}); last checked {}",
+                String::from(config.self_update_frequency),
+                format_epoch(last, &Local)
+            );
+            return Ok(());
         }
     }
     let client = reqwest::blocking::Client::builder()
         .user_agent("drifters-update-checker")
         .build()?;
-    let update = check_update_available(&client)?;
-    if freq == "daily" {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
+    let update = check_update_available(drifters_forge(client)?.as_ref())?;
+    if interval.is_some() {
         config.last_update_check = Some(now);
         let _ = config.save();
     }
+    // On stderr, so it never ends up in piped output
     if let Some((version, url)) = update {
-        println!(
-            "⚠️  Update available: {} (current: {}). Run `drifters self-update` to install. {}",
+        eprintln!(
+            "{}  Update available: {} (current: {}). Run `drifters self-update` to install. {}",
+            warn(),
             version,
             env!("CARGO_PKG_VERSION"),
             url
@@ -100,26 +116,19 @@ pub fn run_self_update(
     let client = reqwest::blocking::Client::builder()
         .user_agent("drifters-update-checker")
         .build()?;
+    let forge = drifters_forge(client.clone())?;
 
-    let url = format!("{}/{}/releases/latest", API_URL, REPO);
-    let response = client.get(&url).send()?;
-
-    if !response.status().is_success() {
-        eprintln!("Failed to fetch release information from GitHub");
-        eprintln!("Repository: {}", REPO);
-        eprintln!("URL: {}", url);
-        eprintln!("Status: {}", response.status());
-        return Err(crate::error::DriftersError::Config(
-            "Unable to check for updates".to_string(),
-        ));
-    }
-
-    let release: Release = response.json()?;
-    let latest_version = release.tag_name.trim_start_matches('v');
+    let release = forge.latest_release().map_err(|e| {
+        eprintln!("Failed to fetch release information");
+        eprintln!("Repository: {}", forge.repo_url());
+        eprintln!("{}", e);
+        crate::error::DriftersError::Config("Unable to check for updates".to_string())
+    })?;
+    let latest_version = release.version.as_str();
     println!("Latest version: {}", latest_version);
 
     if compare_versions(current_version, latest_version) < 0 {
-        println!("\n⚠️  A new version is available!");
+        println!("\n{}  A new version is available!", warn());
         println!("   Current: {}", current_version);
         println!("   Latest:  {}", latest_version);
         println!("   Release: {}", release.html_url);
@@ -127,155 +136,381 @@ pub fn run_self_update(
             println!("\nRun `drifters self-update` to install.");
             return Ok(());
         }
-        println!("\n📥 Installing update...");
+        println!("{}", decorate("\n📥 Installing update..."));
 
-        let installer_url = format!(
-            "https://github.com/{}/releases/latest/download/drifters-installer.sh",
-            REPO
-        );
+        let pinned = LocalConfig::load().ok().and_then(|c| c.self_update_checksum_url);
+        let checksums = Checksums {
+            client: &client,
+            release: &release,
+            pinned: pinned.as_deref(),
+            skip: skip_checksum,
+        };
+        match release_archive().filter(|archive| release.asset_url(archive).is_some()) {
+            Some(archive) => install_archive(&checksums, &archive)?,
+            None => install_with_script(&checksums)?,
+        }
 
-        // ── Download installer as raw bytes ──────────────────────────────────
-        let installer_bytes = client.get(&installer_url).send()?.bytes()?;
+        println!("{} Update installed successfully!", ok());
+        println!("   {}", RESTART_HINT);
 
-        // ── Checksum verification ─────────────────────────────────────────────
-        // Look for a SHA-256 sidecar uploaded alongside the installer.
-        let checksum_asset = release
-            .assets
-            .iter()
-            .find(|a| a.name == "drifters-installer.sh.sha256");
-
-        match checksum_asset {
-            Some(asset) => {
-                // Sidecar found — download and compare
-                let expected_raw = client
-                    .get(&asset.browser_download_url)
-                    .send()?
-                    .text()?;
-                // sha256sum output format: "<hex>  <filename>"
-                let expected = expected_raw
-                    .trim()
-                    .split_whitespace()
-                    .next()
-                    .unwrap_or("")
-                    .to_lowercase();
-
-                use sha2::{Digest, Sha256};
-                let actual = hex::encode(Sha256::digest(&installer_bytes));
+        if !no_download_readme {
+            let open_editor = if no_open_readme { None } else { editor };
+            match crate::cli::open_readme::run_open_readme(open_editor) {
+                Ok(()) => {}
+                Err(e) => eprintln!("{}  Could not download README: {}", warn(), e),
+            }
+        }
+    } else {
+        println!("{} You are running the latest version!", ok());
+    }
+
+    Ok(())
+}
+
+/// Download the release archive for this platform, verify it, and put the
+/// drifters binary inside in place of the running one.
+fn install_archive(checksums: &Checksums, archive: &str) -> Result<()> {
+    let url = checksums.release.asset_url(archive).unwrap_or_default();
+    let bytes = checksums.client.get(url).send()?.error_for_status()?.bytes()?;
+    checksums.verify(archive, &bytes)?;
+    replace_executable(&extract_binary(&bytes)?)
+}
+
+/// Download the installer script, verify it, and run it. The installer
+/// downloads the binary itself, so only the script is checked by drifters.
+/// Used when the release has no archive for this platform.
+fn install_with_script(checksums: &Checksums) -> Result<()> {
+    let release = checksums.release;
+    let installer_url = release
+        .asset_url(INSTALLER)
+        .ok_or_else(|| {
+            crate::error::DriftersError::Config(format!(
+                "Release {} has no build or {} for this platform. \
+                 Download it manually from {}",
+                release.version, INSTALLER, release.html_url
+            ))
+        })?
+        .to_string();
+
+    // ── Download installer as raw bytes ──────────────────────────────────────
+    let installer_bytes = checksums.client.get(&installer_url).send()?.bytes()?;
+    checksums.verify(INSTALLER, &installer_bytes)?;
+
+    // ── Write and execute ────────────────────────────────────────────────────
+    let temp_file = std::env::temp_dir().join(format!("{}-{}", std::process::id(), INSTALLER));
+    std::fs::write(&temp_file, &installer_bytes)?;
+    let status = run_installer(&temp_file);
+    let _ = std::fs::remove_file(&temp_file);
+    let status = status?;
+
+    if !status.success() {
+        eprintln!("Failed to run installer script");
+        eprintln!("Installer URL: {}", installer_url);
+        eprintln!("Exit code: {:?}", status.code());
+        return Err(crate::error::DriftersError::Config(
+            "Installer script execution failed".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// The dist archive holding this platform's build, e.g.
+/// `drifters-x86_64-unknown-linux-gnu.tar.gz`.
+fn release_archive() -> Option<String> {
+    let os = match std::env::consts::OS {
+        "linux" => "unknown-linux-gnu",
+        "macos" => "apple-darwin",
+        "windows" => "pc-windows-msvc",
+        _ => return None,
+    };
+    Some(format!("drifters-{}-{}.tar.gz", std::env::consts::ARCH, os))
+}
+
+/// The drifters executable from a release archive, which dist lays out as
+/// `drifters-<target>/drifters` next to the README and license.
+fn extract_binary(archive: &[u8]) -> Result<Vec<u8>> {
+    use std::io::Read;
+    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(archive));
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        if entry.path()?.file_name() == Some(std::ffi::OsStr::new(BINARY)) {
+            let mut binary = Vec::new();
+            entry.read_to_end(&mut binary)?;
+            return Ok(binary);
+        }
+    }
+    Err(crate::error::DriftersError::Config(format!("The release archive has no {}", BINARY)))
+}
+
+/// Where the expected SHA-256 digests of downloads come from.
+///
+/// By default that is the release itself: the `<asset>.sha256` sidecar, or
+/// dist's combined `sha256.sum`. Both are served by the same host as the
+/// download, so they catch corruption and a swapped file, but not a
+/// compromised release. `self_update_checksum_url` pins the digests to a
+/// file somewhere else instead, and then nothing is installed without one.
+struct Checksums<'a> {
+    client: &'a reqwest::blocking::Client,
+    release: &'a Release,
+    pinned: Option<&'a str>,
+    skip: bool,
+}
+
+impl Checksums<'_> {
+    /// Check `bytes`, the release asset `name`, against its published digest.
+    /// Without one the update is refused unless `skip` is set and no pin is
+    /// configured.
+    fn verify(&self, name: &str, bytes: &[u8]) -> Result<()> {
+        let sidecar = format!("{}.sha256", name);
+        let source = match self.pinned {
+            Some(url) => Some(url.replace("{version}", &self.release.version)),
+            None => self
+                .release
+                .asset_url(&sidecar)
+                .or_else(|| self.release.asset_url("sha256.sum"))
+                .map(str::to_string),
+        };
+        let expected = match &source {
+            Some(url) => {
+                let sums = self.client.get(url).send()?.error_for_status()?.text()?;
+                expected_digest(&sums, name)
+            }
+            None => None,
+        };
 
+        match (expected, source) {
+            (Some(expected), Some(source)) => {
+                let actual = sha256_hex(bytes);
                 if actual != expected {
                     return Err(crate::error::DriftersError::Config(format!(
-                        "Checksum mismatch — installer may have been tampered with.\n\
-                         Expected: {}\n\
+                        "Checksum mismatch — {} may have been tampered with.\n\
+                         Expected: {} (from {})\n\
                          Got:      {}\n\
                          Aborting. Download the release manually from {}",
-                        expected, actual, release.html_url
+                        name, expected, source, actual, self.release.html_url
                     )));
                 }
-                println!("✅ Checksum verified");
+                println!("{} Checksum verified ({})", ok(), name);
+            }
+            (None, Some(source)) if self.pinned.is_some() => {
+                // A pin is a promise not to trust the release on its own
+                return Err(crate::error::DriftersError::Config(format!(
+                    "{} has no checksum for {}, and self_update_checksum_url pins \
+                     checksums to it. Aborting; --skip-checksum does not override a pin.",
+                    source, name
+                )));
             }
-            None if skip_checksum => {
-                // No sidecar but user explicitly opted in — warn and continue
+            _ if self.skip => {
+                // No checksum but user explicitly opted in — warn and continue
                 eprintln!(
-                    "⚠️  No checksum file found in this release. \
-                     Proceeding without verification (--skip-checksum)."
+                    "{}  No checksum for {} found in this release. Proceeding without \
+                     verification (--skip-checksum): nothing but HTTPS vouches for \
+                     what gets installed.",
+                    warn(),
+                    name
                 );
             }
-            None => {
-                // No sidecar and no explicit bypass — refuse to install
-                return Err(crate::error::DriftersError::Config(
-                    "No checksum file (drifters-installer.sh.sha256) found in this release.\n\
-                     Cannot verify installer integrity. Aborting.\n\
-                     If you are confident in the download, re-run with --skip-checksum."
-                        .to_string(),
-                ));
+            _ => {
+                // No checksum and no explicit bypass — refuse to install
+                return Err(crate::error::DriftersError::Config(format!(
+                    "No checksum ({} or sha256.sum) found in this release.\n\
+                     Cannot verify the integrity of {}. Aborting.\n\
+                     If you trust the download channel on its own, re-run with \
+                     --skip-checksum.",
+                    sidecar, name
+                )));
             }
         }
+        Ok(())
+    }
+}
 
-        // ── Write and execute ─────────────────────────────────────────────────
-        let temp_file = std::env::temp_dir()
-            .join(format!("drifters-installer-{}.sh", std::process::id()));
-        std::fs::write(&temp_file, &installer_bytes)?;
-
-        #[cfg(unix)]
-        {
-            use std::os::unix::fs::PermissionsExt;
-            std::fs::set_permissions(&temp_file, std::fs::Permissions::from_mode(0o755))?;
-
-            let status = std::process::Command::new("sh").arg(&temp_file).status()?;
-            let _ = std::fs::remove_file(&temp_file);
-
-            if status.success() {
-                println!("✅ Update installed successfully!");
-                println!("   Please restart your terminal or run: source ~/.profile");
-
-                if !no_download_readme {
-                    let open_editor = if no_open_readme { None } else { editor };
-                    match crate::cli::open_readme::run_open_readme(open_editor) {
-                        Ok(()) => {}
-                        Err(e) => eprintln!("⚠️  Could not download README: {}", e),
-                    }
-                }
-            } else {
-                eprintln!("Failed to run installer script");
-                eprintln!("Installer URL: {}", installer_url);
-                eprintln!("Exit code: {:?}", status.code());
-                return Err(crate::error::DriftersError::Config(
-                    "Installer script execution failed".to_string(),
-                ));
-            }
-        }
+/// The digest for `name` in sha256sum output (`<hex>  <filename>`, with a
+/// `*` before the name in binary mode). A file with a single digest is a
+/// sidecar and applies whatever name it gives.
+fn expected_digest(sums: &str, name: &str) -> Option<String> {
+    let entries: Vec<Vec<&str>> = sums
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .filter(|fields| !fields.is_empty())
+        .collect();
+    let entry = match entries.as_slice() {
+        [only] => Some(only),
+        _ => entries
+            .iter()
+            .find(|fields| fields.get(1).map(|f| f.trim_start_matches('*')) == Some(name)),
+    };
+    entry.map(|fields| fields[0].to_lowercase())
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    hex::encode(Sha256::digest(bytes))
+}
 
-        #[cfg(windows)]
-        {
-            let _ = std::fs::remove_file(&temp_file);
-            return Err(crate::error::DriftersError::Config(
-                "Automatic installation on Windows is not yet supported. \
-                 Please download and run the installer manually."
-                    .to_string(),
-            ));
+/// Put `binary` in place of the running executable. It is written next to
+/// it and renamed over it, which Unix allows while the old one runs.
+#[cfg(unix)]
+fn replace_executable(binary: &[u8]) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let exe = std::env::current_exe()?;
+    let new = exe.with_extension("new");
+    std::fs::write(&new, binary)?;
+    std::fs::set_permissions(&new, std::fs::Permissions::from_mode(0o755))?;
+    std::fs::rename(&new, &exe).inspect_err(|_| {
+        let _ = std::fs::remove_file(&new);
+    })?;
+    Ok(())
+}
+
+/// Put `binary` in place of the running executable. Windows won't let the
+/// running drifters.exe be overwritten but does allow renaming it, so it is
+/// moved aside first and deleted once this process has exited.
+#[cfg(windows)]
+fn replace_executable(binary: &[u8]) -> Result<()> {
+    let exe = std::env::current_exe()?;
+    let new = exe.with_extension("exe.new");
+    std::fs::write(&new, binary)?;
+    let old = move_aside(&exe)?;
+    if let Err(e) = std::fs::rename(&new, &exe) {
+        std::fs::rename(&old, &exe)?;
+        let _ = std::fs::remove_file(&new);
+        return Err(e.into());
+    }
+    remove_after_exit(&old);
+    Ok(())
+}
+
+#[cfg(unix)]
+fn run_installer(script: &Path) -> Result<ExitStatus> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(script, std::fs::Permissions::from_mode(0o755))?;
+    Ok(Command::new("sh").arg(script).status()?)
+}
+
+/// Run the PowerShell installer with the running drifters.exe moved aside,
+/// since the installer can't overwrite it. If the installer fails or puts
+/// the new binary elsewhere, the old one is moved back.
+#[cfg(windows)]
+fn run_installer(script: &Path) -> Result<ExitStatus> {
+    let exe = std::env::current_exe()?;
+    let old = move_aside(&exe)?;
+
+    let status = Command::new("powershell")
+        .args(["-NoProfile", "-ExecutionPolicy", "Bypass", "-File"])
+        .arg(script)
+        .status();
+    if !matches!(&status, Ok(s) if s.success()) || !exe.exists() {
+        if !exe.exists() {
+            std::fs::rename(&old, &exe)?;
         }
-    } else {
-        println!("✅ You are running the latest version!");
+        return Ok(status?);
     }
+    remove_after_exit(&old);
+    Ok(status?)
+}
 
-    Ok(())
+/// Rename the running `exe` to `<exe>.old`, which Windows allows while it
+/// runs, freeing its name for the new binary.
+#[cfg(windows)]
+fn move_aside(exe: &Path) -> Result<std::path::PathBuf> {
+    let old = exe.with_extension("exe.old");
+    // Left behind by an earlier update whose cleanup didn't get to run
+    let _ = std::fs::remove_file(&old);
+    std::fs::rename(exe, &old)?;
+    Ok(old)
+}
+
+/// Start a hidden helper that waits for this process to exit, then deletes
+/// `path` (the old executable, locked until then).
+#[cfg(windows)]
+fn remove_after_exit(path: &Path) {
+    let cleanup = format!(
+        "Wait-Process -Id {} -ErrorAction SilentlyContinue; \
+         Remove-Item -LiteralPath '{}' -Force -ErrorAction SilentlyContinue",
+        std::process::id(),
+        path.display().to_string().replace('\'', "''")
+    );
+    if let Err(e) = Command::new("powershell")
+        .args(["-NoProfile", "-WindowStyle", "Hidden", "-Command", cleanup.as_str()])
+        .spawn()
+    {
+        log::warn!("Could not start cleanup of {}: {}", path.display(), e);
+    }
 }
 
+/// Compare two release versions by semver precedence: -1, 0 or 1. A leading
+/// `v` is ignored, a missing minor or patch number counts as 0, pre-releases
+/// order before their release and build metadata is ignored. A version that
+/// doesn't parse compares equal, so it never triggers an update.
 fn compare_versions(v1: &str, v2: &str) -> i32 {
-    let parse_version = |v: &str| -> Vec<u32> {
-        v.split('.')
-            .map(|s| {
-                s.parse::<u32>().unwrap_or_else(|_| {
-                    log::debug!(
-                        "Failed to parse version segment '{}' in '{}', treating as 0",
-                        s,
-                        v
-                    );
-                    0
-                })
-            })
-            .collect()
-    };
-    let v1_parts = parse_version(v1);
-    let v2_parts = parse_version(v2);
-    let max_len = v1_parts.len().max(v2_parts.len());
-    for i in 0..max_len {
-        let a = v1_parts.get(i).copied().unwrap_or(0);
-        let b = v2_parts.get(i).copied().unwrap_or(0);
-        if a < b {
-            return -1;
-        }
-        if a > b {
-            return 1;
-        }
+    match (parse_version(v1), parse_version(v2)) {
+        (Some(a), Some(b)) => a.cmp_precedence(&b) as i32,
+        _ => 0,
     }
-    0
+}
+
+fn parse_version(version: &str) -> Option<semver::Version> {
+    let version = version.trim().trim_start_matches('v');
+    let core_len = version.find(['-', '+']).unwrap_or(version.len());
+    let (core, suffix) = version.split_at(core_len);
+    let padding = ".0".repeat(2usize.saturating_sub(core.matches('.').count()));
+    semver::Version::parse(&format!("{}{}{}", core, padding, suffix))
+        .map_err(|e| log::debug!("Failed to parse version '{}': {}", version, e))
+        .ok()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_format_epoch() {
+        assert_eq!(format_epoch(1_771_164_200, &chrono::Utc), "2026-02-15 14:03:20 +00:00");
+        let plus_one = chrono::FixedOffset::east_opt(3600).unwrap();
+        assert_eq!(format_epoch(1_771_164_200, &plus_one), "2026-02-15 15:03:20 +01:00");
+        assert_eq!(format_epoch(u64::MAX, &chrono::Utc), format!("ts={}", u64::MAX));
+    }
+
+    #[test]
+    fn test_expected_digest_reads_sidecars_and_sum_files() {
+        let hello = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+        assert_eq!(sha256_hex(b"hello"), hello);
+
+        // A sidecar names the file it was made from, whatever that was
+        let sidecar = format!("{}  drifters-installer.ps1\n", hello.to_uppercase());
+        assert_eq!(expected_digest(&sidecar, "drifters-installer.ps1"), Some(hello.to_string()));
+        assert_eq!(expected_digest(hello, "drifters.tar.gz"), Some(hello.to_string()));
+
+        // A combined file is looked up by name
+        let sums = format!("aaaa  drifters-installer.sh\n{} *drifters.tar.gz\n", hello);
+        assert_eq!(expected_digest(&sums, "drifters.tar.gz"), Some(hello.to_string()));
+        assert_eq!(expected_digest(&sums, "drifters-installer.ps1"), None);
+        assert_eq!(expected_digest("", "drifters.tar.gz"), None);
+    }
+
+    #[test]
+    fn test_extract_binary_finds_the_executable_in_a_dist_archive() {
+        let mut builder = tar::Builder::new(flate2::write::GzEncoder::new(
+            Vec::new(),
+            flate2::Compression::default(),
+        ));
+        let binary = format!("drifters-x/{}", BINARY);
+        for (path, content) in [("drifters-x/README.md", "readme"), (binary.as_str(), "bin")] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder.append_data(&mut header, path, content.as_bytes()).unwrap();
+        }
+        let archive = builder.into_inner().unwrap().finish().unwrap();
+        assert_eq!(extract_binary(&archive).unwrap(), b"bin");
+
+        let empty = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let empty = tar::Builder::new(empty).into_inner().unwrap().finish().unwrap();
+        assert!(extract_binary(&empty).is_err());
+    }
+
     #[test]
     fn test_compare_versions() {
         assert_eq!(compare_versions("1.0.0", "1.0.1"), -1);
@@ -284,5 +519,14 @@ mod tests {
         assert_eq!(compare_versions("1.0", "1.0.0"), 0);
         assert_eq!(compare_versions("1.2.3", "1.10.0"), -1);
         assert_eq!(compare_versions("2.0.0", "1.99.99"), 1);
+
+        assert_eq!(compare_versions("1.2.0-rc1", "1.2.0"), -1);
+        assert_eq!(compare_versions("1.2.0-rc1", "1.2.0-rc2"), -1);
+        assert_eq!(compare_versions("1.2.0-rc.2", "1.2.0-rc.10"), -1);
+        assert_eq!(compare_versions("1.1.9", "1.2.0-rc1"), -1);
+        assert_eq!(compare_versions("1.2.0+build.5", "1.2.0+build.7"), 0);
+        assert_eq!(compare_versions("v1.2.0", "1.2.0"), 0);
+        assert_eq!(compare_versions("1.2-rc1", "1.2.0"), -1);
+        assert_eq!(compare_versions("1.2.0", "nightly"), 0);
     }
 }
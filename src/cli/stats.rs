@@ -0,0 +1,153 @@
+use crate::cli::tree::format_size;
+use crate::config::LocalConfig;
+use crate::error::Result;
+use crate::git::{list_all_branch_files, BranchFiles, EphemeralRepoGuard};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// How many of the largest synced files to list.
+const LARGEST_FILES_SHOWN: usize = 5;
+
+/// Aggregated sync footprint across all branches.
+#[derive(Debug, PartialEq)]
+struct SyncStats {
+    apps: usize,
+    files: usize,
+    bytes: u64,
+    /// (branch, path, size), largest first
+    largest: Vec<(String, String, u64)>,
+    /// (branch, files, bytes), in branch order
+    per_branch: Vec<(String, usize, u64)>,
+}
+
+/// Print a summary of what is being synced: apps, files and bytes per branch,
+/// the largest files, and the size of the repository itself.
+pub fn show_stats() -> Result<()> {
+    let config = LocalConfig::load()?;
+
+    println!("Fetching latest from repository...");
+    let repo_guard = EphemeralRepoGuard::new(&config)?;
+    let repo_path = repo_guard.path();
+
+    let branches = list_all_branch_files(repo_path)?;
+    let stats = aggregate(&branches);
+    let repo_bytes = dir_size(&repo_path.join(".git"))?;
+
+    println!("\nDrifters Stats");
+    println!("{}", "=".repeat(60));
+    println!("Apps:        {}", stats.apps);
+    println!("Files:       {} (across all branches)", stats.files);
+    println!("Synced size: {}", format_size(stats.bytes));
+    println!("Repo size:   {} (git history included)", format_size(repo_bytes));
+
+    println!("\nPer branch:");
+    for (branch, files, bytes) in &stats.per_branch {
+        println!("  {:<30} {:>5} file(s)  {:>10}", branch, files, format_size(*bytes));
+    }
+
+    if !stats.largest.is_empty() {
+        println!("\nLargest files:");
+        for (branch, path, size) in &stats.largest {
+            println!("  {:>10}  {} ({})", format_size(*size), path, branch);
+        }
+    }
+
+    Ok(())
+}
+
+/// Aggregate synced files (everything under `apps/`) over all branches.
+fn aggregate(branches: &[(String, BranchFiles)]) -> SyncStats {
+    let mut apps = BTreeSet::new();
+    let mut largest = Vec::new();
+    let mut per_branch = Vec::new();
+
+    for (branch, files) in branches {
+        let mut count = 0;
+        let mut bytes = 0;
+        for (path, size) in files {
+            let Some((app, _)) = path.strip_prefix("apps/").and_then(|p| p.split_once('/')) else {
+                continue;
+            };
+            apps.insert(app.to_string());
+            count += 1;
+            bytes += size;
+            largest.push((branch.clone(), path.clone(), *size));
+        }
+        per_branch.push((branch.clone(), count, bytes));
+    }
+
+    // Largest first; ties broken by branch then path for stable output
+    largest.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| (&a.0, &a.1).cmp(&(&b.0, &b.1))));
+    largest.truncate(LARGEST_FILES_SHOWN);
+
+    SyncStats {
+        apps: apps.len(),
+        files: per_branch.iter().map(|(_, n, _)| n).sum(),
+        bytes: per_branch.iter().map(|(_, _, b)| b).sum(),
+        largest,
+        per_branch,
+    }
+}
+
+/// Total size of all files below `dir`.
+fn dir_size(dir: &Path) -> Result<u64> {
+    let mut total = 0;
+    if !dir.exists() {
+        return Ok(0);
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let meta = entry.metadata()?;
+        total += if meta.is_dir() {
+            dir_size(&entry.path())?
+        } else {
+            meta.len()
+        };
+    }
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate_fixture_repo() {
+        let branches = vec![
+            (
+                "main".to_string(),
+                vec![
+                    (".drifters/sync-rules.toml".to_string(), 500),
+                    ("apps/zed/settings.json".to_string(), 300),
+                ],
+            ),
+            (
+                "machines/laptop".to_string(),
+                vec![
+                    ("apps/zed/settings.json".to_string(), 310),
+                    ("apps/zsh/.zshrc".to_string(), 4_000_000),
+                ],
+            ),
+            ("machines/desktop".to_string(), vec![]),
+        ];
+
+        let stats = aggregate(&branches);
+
+        assert_eq!(stats.apps, 2);
+        assert_eq!(stats.files, 3);
+        // Metadata outside apps/ is not counted
+        assert_eq!(stats.bytes, 300 + 310 + 4_000_000);
+        assert_eq!(
+            stats.largest[0],
+            ("machines/laptop".to_string(), "apps/zsh/.zshrc".to_string(), 4_000_000)
+        );
+        assert_eq!(
+            stats.per_branch,
+            vec![
+                ("main".to_string(), 1, 300),
+                ("machines/laptop".to_string(), 2, 4_000_310),
+                ("machines/desktop".to_string(), 0, 0),
+            ]
+        );
+    }
+}
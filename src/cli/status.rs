@@ -1,68 +1,176 @@
-use crate::config::{resolve_fileset, LocalConfig, SyncRules};
+use crate::cli::push::syncable_text;
+use crate::cli::style::decorate;
+use crate::config::{check_fileset, resolve_fileset, LocalConfig, MachineRegistry, SyncRules};
+use crate::crypto::SecretKey;
 use crate::error::Result;
 use crate::git::{
-    checkout_branch, list_branches, read_app_files, EphemeralRepoGuard,
+    checkout_branch, list_branches, main_branch, read_app_file_bytes, EphemeralRepoGuard,
 };
+use crate::parser::compare::content_equivalent_ignoring_whitespace;
+use crate::parser::conflict::has_conflict_markers;
+use crate::parser::redact::compile_redactions;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
 use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
 
-pub fn show_status() -> Result<()> {
+/// In `--watch` mode the repo is re-fetched at most this often; renders in
+/// between reuse the last fetched snapshot and only re-read local files.
+const WATCH_REMOTE_REFRESH: Duration = Duration::from_secs(60);
+
+/// Files per app, keyed by app name then filename; raw bytes so binary
+/// files compare too.
+type AppFiles = HashMap<String, HashMap<String, Vec<u8>>>;
+
+/// Everything `status` needs from the repo, fetched once so it can be
+/// rendered repeatedly without touching the network.
+struct StatusSnapshot {
+    machine_id: String,
+    repo_url: String,
+    rules: SyncRules,
+    machine_branches: Vec<String>,
+    /// Machine branches no registered machine owns
+    orphaned_branches: Vec<String>,
+    /// `None` when this machine has no branch yet
+    machine_files: Option<AppFiles>,
+    main_files: AppFiles,
+    /// This machine's encryption key, to seal local files as push would
+    key: Option<SecretKey>,
+}
+
+/// Sync state of one local file, as reported by `status` (and `--json`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum FileState {
+    /// Pushed to this machine's branch as it is locally
+    UpToDate,
+    /// Changed locally (or never pushed) since the last push
+    LocalAhead,
+    /// Missing locally but available on main
+    RemoteAhead,
+    /// Neither local nor on main
+    Missing,
+    /// Main's version has unresolved conflict markers from a three-way merge
+    Conflict,
+}
+
+/// One file of an app's fileset on this machine.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FileStatus {
+    pub app: String,
+    pub filename: String,
+    pub path: PathBuf,
+    pub state: FileState,
+    /// Whether this machine's branch has the file
+    pub on_branch: bool,
+    /// For `LocalAhead`: the local changes are whitespace only
+    pub whitespace_only: bool,
+}
+
+/// `status --json` document.
+#[derive(Debug, Serialize)]
+struct StatusReport<'a> {
+    machine: &'a str,
+    branch: String,
+    apps: Vec<AppStatusReport>,
+}
+
+#[derive(Debug, Serialize)]
+struct AppStatusReport {
+    name: String,
+    files: Vec<FileStatus>,
+}
+
+pub fn show_status(watch: Option<u64>, json: bool) -> Result<()> {
     log::info!("Showing status");
 
     // Load local config
-    let config = LocalConfig::load()?;
-    let machine_branch = format!("machines/{}", config.machine_id);
+    let mut config = LocalConfig::load()?;
+
+    if json {
+        let snapshot = fetch_snapshot(&mut config)?;
+        println!("{}", render_json(&snapshot)?);
+        return check_required_apps(&snapshot);
+    }
+
+    let Some(interval) = watch else {
+        println!("Fetching latest sync rules...");
+        let snapshot = fetch_snapshot(&mut config)?;
+        print!("{}", decorate(&render_status(&snapshot)?));
+        return check_required_apps(&snapshot);
+    };
+
+    let interval = Duration::from_secs(interval.max(1));
+    let mut snapshot = fetch_snapshot(&mut config)?;
+    let mut fetched_at = Instant::now();
+
+    // Runs until interrupted; the repo lock is only held inside
+    // fetch_snapshot, so Ctrl-C while waiting leaves nothing behind.
+    loop {
+        if fetched_at.elapsed() >= WATCH_REMOTE_REFRESH {
+            match fetch_snapshot(&mut config) {
+                Ok(fresh) => {
+                    snapshot = fresh;
+                    fetched_at = Instant::now();
+                }
+                Err(e) => log::warn!("Refreshing status failed, showing cached data: {}", e),
+            }
+        }
+
+        let rendered = render_status(&snapshot)?;
+        // Clear screen and move the cursor home before redrawing
+        print!("\x1b[2J\x1b[H{}", decorate(&rendered));
+        let footer = format!(
+            "\nRefreshing every {}s (repo fetched {}s ago) — Ctrl-C to exit",
+            interval.as_secs(),
+            fetched_at.elapsed().as_secs()
+        );
+        println!("{}", decorate(&footer));
+        std::thread::sleep(interval);
+    }
+}
 
+/// Clone the repo, read both this machine's branch and main, and release the
+/// repo lock again before returning.
+fn fetch_snapshot(config: &mut LocalConfig) -> Result<StatusSnapshot> {
     // Set up ephemeral repo
-    println!("Fetching latest sync rules...");
-    let repo_guard = EphemeralRepoGuard::new(&config)?;
+    let repo_guard = EphemeralRepoGuard::new(config)?;
     let repo_path = repo_guard.path();
 
     // Guard: detect stale machine IDs
-    crate::cli::common::verify_machine_registration(&config, repo_path)?;
+    crate::cli::common::verify_machine_registration(config, repo_path)?;
+    let machine_branch = format!("machines/{}", config.machine_id);
 
     // Load sync rules from main
     let rules = SyncRules::load(repo_path)?;
+    rules.validate()?;
 
-    println!("\nDrifters Status");
-    println!("{}", "=".repeat(60));
-    println!("Machine: {} ({})", config.machine_id, std::env::consts::OS);
-    println!("Branch:  {}", machine_branch);
-    println!("Repository: {}", config.repo_url);
-
-    // Show available branches
+    // Available machine branches, local and remote-tracking, deduplicated
     let branches = list_branches(repo_path).unwrap_or_default();
-    let machine_branches: Vec<_> = branches
+    let mut machine_branches: Vec<String> = branches
         .iter()
         .filter(|b| b.starts_with("machines/") || b.starts_with("origin/machines/"))
         .filter(|b| !b.contains("HEAD"))
+        .map(|b| b.strip_prefix("origin/").unwrap_or(b).to_string())
+        .collect();
+    machine_branches.sort();
+    machine_branches.dedup();
+    let orphaned_branches = MachineRegistry::load(repo_path)?
+        .orphaned_branches(&machine_branches)
+        .into_iter()
+        .map(str::to_string)
         .collect();
-    if !machine_branches.is_empty() {
-        println!("Machine branches: {}", machine_branches
-            .iter()
-            .map(|b| b.strip_prefix("origin/").unwrap_or(b))
-            .collect::<std::collections::HashSet<_>>()
-            .into_iter()
-            .collect::<Vec<_>>()
-            .join(", "));
-    }
-
-    println!("{}", "=".repeat(60));
-
-    if rules.apps.is_empty() {
-        println!("No apps configured for sync.");
-        println!("\nUse 'drifters add-app <app>' to add apps");
-        return Ok(());
-    }
 
     // Check what's on this machine's branch
     let machine_files = if checkout_branch(repo_path, &machine_branch).is_ok() {
-        let mut all_files = std::collections::HashMap::new();
+        let mut all_files = HashMap::new();
         for app_name in rules.apps.keys() {
-            let files = read_app_files(repo_path, app_name)?;
+            let files = read_app_file_bytes(repo_path, app_name)?;
             all_files.insert(app_name.clone(), files);
         }
         // Switch back to main
-        let _ = checkout_branch(repo_path, "main");
+        let _ = checkout_branch(repo_path, main_branch());
         Some(all_files)
     } else {
         None
@@ -70,80 +178,458 @@ pub fn show_status() -> Result<()> {
 
     // Check what's on main
     let main_files = {
-        let _ = checkout_branch(repo_path, "main");
-        let mut all_files = std::collections::HashMap::new();
+        let _ = checkout_branch(repo_path, main_branch());
+        let mut all_files = HashMap::new();
         for app_name in rules.apps.keys() {
-            let files = read_app_files(repo_path, app_name)?;
+            let files = read_app_file_bytes(repo_path, app_name)?;
             all_files.insert(app_name.clone(), files);
         }
         all_files
     };
 
-    for (app_name, app_config) in &rules.apps {
-        println!("\n{}", app_name);
+    Ok(StatusSnapshot {
+        machine_id: config.machine_id.clone(),
+        repo_url: config.repo_url.clone(),
+        rules,
+        machine_branches,
+        orphaned_branches,
+        machine_files,
+        main_files,
+        key: SecretKey::load()?,
+    })
+}
 
+/// Fail if any `required` app has no files on this machine.
+fn check_required_apps(snapshot: &StatusSnapshot) -> Result<()> {
+    let mut app_names: Vec<_> = snapshot.rules.apps.keys().collect();
+    app_names.sort();
+    for app_name in app_names {
+        let app_config = &snapshot.rules.apps[app_name];
         let fileset = resolve_fileset(
             app_config,
-            &config.machine_id,
+            &snapshot.rules.ignore,
+            &snapshot.rules.groups,
+            &snapshot.machine_id,
+            std::env::consts::OS,
+        )?;
+        check_fileset(app_name, app_config, &fileset)?;
+    }
+    Ok(())
+}
+
+/// Render the status report for a snapshot.
+///
+/// Only reads local files — no prompts, no network, no repo access — so it is
+/// safe to call in a loop.
+fn render_status(snapshot: &StatusSnapshot) -> Result<String> {
+    let mut out = String::new();
+    let machine_branch = format!("machines/{}", snapshot.machine_id);
+
+    writeln!(out, "\nDrifters Status")?;
+    writeln!(out, "{}", "=".repeat(60))?;
+    writeln!(out, "Machine: {} ({})", snapshot.machine_id, std::env::consts::OS)?;
+    writeln!(out, "Branch:  {}", machine_branch)?;
+    writeln!(out, "Repository: {}", snapshot.repo_url)?;
+
+    // Show available branches
+    if !snapshot.machine_branches.is_empty() {
+        writeln!(out, "Machine branches: {}", snapshot.machine_branches.join(", "))?;
+    }
+
+    if !snapshot.orphaned_branches.is_empty() {
+        writeln!(
+            out,
+            "⚠ {} branch(es) of no registered machine: {}. Run 'drifters machines prune' \
+             to delete them.",
+            snapshot.orphaned_branches.len(),
+            snapshot.orphaned_branches.join(", ")
+        )?;
+    }
+
+    writeln!(out, "{}", "=".repeat(60))?;
+
+    if snapshot.rules.apps.is_empty() {
+        writeln!(out, "No apps configured for sync.")?;
+        writeln!(out, "\nUse 'drifters add-app <app>' to add apps")?;
+        return Ok(out);
+    }
+
+    let mut app_names: Vec<_> = snapshot.rules.apps.keys().collect();
+    app_names.sort();
+
+    for app_name in app_names {
+        let app_config = &snapshot.rules.apps[app_name];
+        writeln!(out, "\n{}", app_name)?;
+
+        let fileset = resolve_fileset(
+            app_config,
+            &snapshot.rules.ignore,
+            &snapshot.rules.groups,
+            &snapshot.machine_id,
             std::env::consts::OS,
         )?;
 
         if fileset.is_empty() {
-            println!("  (no files in fileset for this machine)");
+            if app_config.required {
+                writeln!(out, "  ✗ required app has no files on this machine")?;
+            } else {
+                writeln!(out, "  (no files in fileset for this machine)")?;
+            }
             continue;
         }
 
-        for file_path in &fileset {
+        for file in file_statuses(snapshot, app_name, &fileset)? {
+            let state = match file.state {
+                FileState::UpToDate => "✓ up to date on branch",
+                FileState::LocalAhead if !file.on_branch => "↑ not yet pushed to branch",
+                FileState::LocalAhead if file.whitespace_only => {
+                    "≈ whitespace-only changes not pushed"
+                }
+                FileState::LocalAhead => "↑ local changes not pushed",
+                FileState::RemoteAhead => "↓ available on main",
+                FileState::Missing if file.on_branch => "⚠ on branch but missing locally",
+                FileState::Missing => "⚠ missing everywhere",
+                FileState::Conflict => "⚠ unresolved conflict on main",
+            };
+            writeln!(out, "  {} ({}) - {}", file.filename, file.path.display(), state)?;
+        }
+    }
+
+    writeln!(out, "\n{}", "=".repeat(60))?;
+    writeln!(out, "Total apps: {}", snapshot.rules.apps.len())?;
+    writeln!(out, "\nLegend:")?;
+    writeln!(out, "  ✓ up to date on branch")?;
+    writeln!(out, "  ↑ local changes not pushed")?;
+    writeln!(out, "  ≈ only whitespace/line endings differ")?;
+    writeln!(out, "  ↓ remote changes available")?;
+    writeln!(out, "  ⚠ warning/missing")?;
+    writeln!(out, "\nWorkflow:")?;
+    writeln!(out, "  drifters push-app    — push local changes to your machine branch")?;
+    writeln!(out, "  drifters merge-app   — merge your branch into main")?;
+    writeln!(out, "  drifters pull-app    — pull from main to local")?;
+
+    Ok(out)
+}
+
+/// Render the snapshot as the `status --json` document.
+fn render_json(snapshot: &StatusSnapshot) -> Result<String> {
+    let mut app_names: Vec<_> = snapshot.rules.apps.keys().collect();
+    app_names.sort();
+
+    let mut apps = Vec::new();
+    for app_name in app_names {
+        let fileset = resolve_fileset(
+            &snapshot.rules.apps[app_name],
+            &snapshot.rules.ignore,
+            &snapshot.rules.groups,
+            &snapshot.machine_id,
+            std::env::consts::OS,
+        )?;
+        apps.push(AppStatusReport {
+            name: app_name.clone(),
+            files: file_statuses(snapshot, app_name, &fileset)?,
+        });
+    }
+
+    let report = StatusReport {
+        machine: &snapshot.machine_id,
+        branch: format!("machines/{}", snapshot.machine_id),
+        apps,
+    };
+    Ok(serde_json::to_string_pretty(&report)?)
+}
+
+/// The state of each file of an app's fileset, comparing the local file
+/// with this machine's branch and main. Text files are compared as push
+/// would write them (sections, redaction, encryption, line endings), so a
+/// redacted line alone doesn't count as a local change.
+fn file_statuses(
+    snapshot: &StatusSnapshot,
+    app_name: &str,
+    fileset: &[PathBuf],
+) -> Result<Vec<FileStatus>> {
+    let app_config = &snapshot.rules.apps[app_name];
+    let redactions = compile_redactions(&app_config.redact)?;
+    Ok(fileset
+        .iter()
+        .map(|file_path| {
             let filename = file_path
                 .file_name()
                 .and_then(|s| s.to_str())
                 .unwrap_or("unknown");
 
             let local_exists = file_path.exists();
-            let on_branch = machine_files
+            let on_branch = snapshot
+                .machine_files
                 .as_ref()
                 .and_then(|mf| mf.get(app_name))
                 .and_then(|files| files.get(filename));
-            let on_main = main_files
+            let on_main = snapshot
+                .main_files
                 .get(app_name)
                 .and_then(|files| files.get(filename));
 
-            match (local_exists, on_branch.is_some(), on_main.is_some()) {
-                (true, true, _) => {
-                    let local_content = fs::read_to_string(file_path).unwrap_or_default();
-                    if on_branch.map(|c| c == &local_content).unwrap_or(false) {
-                        println!("  {} ({}) - ✓ up to date on branch", filename, file_path.display());
+            let mut whitespace_only = false;
+            let conflicted = on_main
+                .and_then(|main| std::str::from_utf8(main).ok())
+                .is_some_and(has_conflict_markers);
+            let state = match (local_exists, on_branch, on_main.is_some()) {
+                _ if conflicted => FileState::Conflict,
+                (true, Some(pushed), _) => {
+                    let local_content = fs::read(file_path).unwrap_or_default();
+                    // Raw bytes for binary files, or when it can't be
+                    // prepared (e.g. encrypted sections without a key)
+                    let local_content = std::str::from_utf8(&local_content)
+                        .ok()
+                        .and_then(|text| {
+                            syncable_text(
+                                text,
+                                filename,
+                                app_config,
+                                &redactions,
+                                snapshot.key.as_ref(),
+                            )
+                            .ok()
+                        })
+                        .map(String::into_bytes)
+                        .unwrap_or(local_content);
+                    if pushed == &local_content {
+                        FileState::UpToDate
                     } else {
-                        println!("  {} ({}) - ↑ local changes not pushed", filename, file_path.display());
+                        whitespace_only = whitespace_only_change(pushed, &local_content);
+                        FileState::LocalAhead
                     }
                 }
-                (true, false, _) => {
-                    println!("  {} ({}) - ↑ not yet pushed to branch", filename, file_path.display());
-                }
-                (false, _, true) => {
-                    println!("  {} ({}) - ↓ available on main", filename, file_path.display());
-                }
-                (false, true, false) => {
-                    println!("  {} ({}) - ⚠ on branch but missing locally", filename, file_path.display());
-                }
-                (false, false, false) => {
-                    println!("  {} ({}) - ⚠ missing everywhere", filename, file_path.display());
-                }
+                (true, None, _) => FileState::LocalAhead,
+                (false, _, true) => FileState::RemoteAhead,
+                (false, _, false) => FileState::Missing,
+            };
+
+            FileStatus {
+                app: app_name.to_string(),
+                filename: filename.to_string(),
+                path: file_path.clone(),
+                state,
+                on_branch: on_branch.is_some(),
+                whitespace_only,
             }
-        }
+        })
+        .collect())
+}
+
+/// Whether two versions differ only in whitespace. Never true for binary
+/// content.
+fn whitespace_only_change(old: &[u8], new: &[u8]) -> bool {
+    match (std::str::from_utf8(old), std::str::from_utf8(new)) {
+        (Ok(old), Ok(new)) => content_equivalent_ignoring_whitespace(old, new),
+        _ => false,
     }
+}
 
-    println!("\n{}", "=".repeat(60));
-    println!("Total apps: {}", rules.apps.len());
-    println!("\nLegend:");
-    println!("  ✓ up to date on branch");
-    println!("  ↑ local changes not pushed");
-    println!("  ↓ remote changes available");
-    println!("  ⚠ warning/missing");
-    println!("\nWorkflow:");
-    println!("  drifters push-app    — push local changes to your machine branch");
-    println!("  drifters merge-app   — merge your branch into main");
-    println!("  drifters pull-app    — pull from main to local");
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::AppConfig;
 
-    Ok(())
+    #[test]
+    fn test_render_status_is_repeatable_without_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        let local = dir.path().join("settings.json");
+        fs::write(&local, "{\"a\": 1}\n").unwrap();
+
+        let mut rules = SyncRules::new();
+        rules.apps.insert(
+            "app".to_string(),
+            AppConfig {
+                include: vec![local.to_string_lossy().to_string()],
+                ..Default::default()
+            },
+        );
+        let mut pushed = HashMap::new();
+        pushed.insert("settings.json".to_string(), b"{\"a\": 1}\n".to_vec());
+        let mut machine_files = HashMap::new();
+        machine_files.insert("app".to_string(), pushed);
+
+        let snapshot = StatusSnapshot {
+            machine_id: "m1".to_string(),
+            repo_url: "file:///nowhere".to_string(),
+            rules,
+            machine_branches: vec!["machines/m1".to_string()],
+            orphaned_branches: vec![],
+            machine_files: Some(machine_files),
+            main_files: HashMap::new(),
+            key: None,
+        };
+
+        // Rendering only reads local files, so a watch loop can call it freely
+        let first = render_status(&snapshot).unwrap();
+        assert!(first.contains("settings.json"));
+        assert!(first.contains("✓ up to date on branch"));
+        assert_eq!(first, render_status(&snapshot).unwrap());
+
+        // A local edit shows up on the next render without re-fetching
+        fs::write(&local, "{\"a\": 2}\n").unwrap();
+        assert!(render_status(&snapshot).unwrap().contains("↑ local changes not pushed"));
+
+        // --plain output is pure ASCII
+        let rendered = render_status(&snapshot).unwrap();
+        let plain = crate::cli::style::to_ascii(&rendered);
+        assert!(plain.is_ascii(), "non-ASCII in plain status: {}", plain);
+        assert!(plain.contains("[up] local changes not pushed"));
+    }
+
+    #[test]
+    fn test_json_reports_machine_readable_states() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name);
+        fs::write(path("pushed"), "a\n").unwrap();
+        fs::write(path("edited"), "new\n").unwrap();
+        fs::write(path("merged"), "x\n").unwrap();
+
+        let mut rules = SyncRules::new();
+        rules.apps.insert(
+            "app".to_string(),
+            AppConfig {
+                include: ["pushed", "edited", "merged", "on-main", "gone"]
+                    .iter()
+                    .map(|name| path(name).to_string_lossy().to_string())
+                    .collect(),
+                ..Default::default()
+            },
+        );
+        let files = |entries: &[(&str, &str)]| {
+            entries
+                .iter()
+                .map(|(name, content)| (name.to_string(), content.as_bytes().to_vec()))
+                .collect::<HashMap<_, _>>()
+        };
+        let conflicted = "<<<<<<< main\nx\n=======\ny\n>>>>>>> m2\n";
+        let snapshot = StatusSnapshot {
+            machine_id: "m1".to_string(),
+            repo_url: "file:///nowhere".to_string(),
+            rules,
+            machine_branches: vec!["machines/m1".to_string()],
+            orphaned_branches: vec![],
+            machine_files: Some(HashMap::from([(
+                "app".to_string(),
+                files(&[("pushed", "a\n"), ("edited", "old\n"), ("merged", "x\n")]),
+            )])),
+            main_files: HashMap::from([(
+                "app".to_string(),
+                files(&[("merged", conflicted), ("on-main", "m\n")]),
+            )]),
+            key: None,
+        };
+
+        // A resolved fileset only lists files that exist locally; pass all
+        // five to cover the states of missing ones too
+        let fileset: Vec<_> = ["pushed", "edited", "merged", "on-main", "gone"]
+            .iter()
+            .map(|name| path(name))
+            .collect();
+        let states: Vec<_> = file_statuses(&snapshot, "app", &fileset)
+            .unwrap()
+            .into_iter()
+            .map(|f| (f.filename, f.state))
+            .collect();
+        let expected = [
+            ("pushed", FileState::UpToDate),
+            ("edited", FileState::LocalAhead),
+            ("merged", FileState::Conflict),
+            ("on-main", FileState::RemoteAhead),
+            ("gone", FileState::Missing),
+        ];
+        assert_eq!(states, expected.map(|(name, state)| (name.to_string(), state)));
+
+        let report: serde_json::Value =
+            serde_json::from_str(&render_json(&snapshot).unwrap()).unwrap();
+        assert_eq!(report["branch"], "machines/m1");
+        assert_eq!(report["apps"][0]["name"], "app");
+        let edited = &report["apps"][0]["files"][0];
+        assert_eq!(edited["filename"], "edited");
+        assert_eq!(edited["state"], "LocalAhead");
+    }
+
+    #[test]
+    fn test_redacted_file_is_up_to_date() {
+        let dir = tempfile::tempdir().unwrap();
+        let gitconfig = dir.path().join("gitconfig");
+        fs::write(&gitconfig, "[user]\n  name = me\n  token = abc123\n").unwrap();
+
+        let mut rules = SyncRules::new();
+        rules.apps.insert(
+            "git".to_string(),
+            AppConfig {
+                include: vec![gitconfig.to_string_lossy().to_string()],
+                redact: vec![r"^\s*token\s*=".to_string()],
+                ..Default::default()
+            },
+        );
+        // What push-app wrote: the token line replaced by the placeholder
+        let pushed = "[user]\n  name = me\n<redacted by drifters>\n";
+        let snapshot = StatusSnapshot {
+            machine_id: "m1".to_string(),
+            repo_url: "file:///nowhere".to_string(),
+            rules,
+            machine_branches: vec!["machines/m1".to_string()],
+            orphaned_branches: vec![],
+            machine_files: Some(HashMap::from([(
+                "git".to_string(),
+                HashMap::from([("gitconfig".to_string(), pushed.as_bytes().to_vec())]),
+            )])),
+            main_files: HashMap::new(),
+            key: None,
+        };
+        let fileset = vec![gitconfig.clone()];
+
+        let states = file_statuses(&snapshot, "git", &fileset).unwrap();
+        assert_eq!(states[0].state, FileState::UpToDate);
+
+        // Changing the redacted value alone is still nothing to push
+        fs::write(&gitconfig, "[user]\n  name = me\n  token = xyz789\n").unwrap();
+        let states = file_statuses(&snapshot, "git", &fileset).unwrap();
+        assert_eq!(states[0].state, FileState::UpToDate);
+
+        fs::write(&gitconfig, "[user]\n  name = you\n  token = xyz789\n").unwrap();
+        let states = file_statuses(&snapshot, "git", &fileset).unwrap();
+        assert_eq!(states[0].state, FileState::LocalAhead);
+    }
+
+    #[test]
+    fn test_binary_files_compare_by_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let keyring = dir.path().join("pubring.kbx");
+        let bytes = vec![0x00, 0x9f, 0xff, b'\n', 0x01];
+        fs::write(&keyring, &bytes).unwrap();
+
+        // Not UTF-8: read_app_files leaves it out, the byte reader keeps it
+        let repo = tempfile::tempdir().unwrap();
+        fs::create_dir_all(repo.path().join("apps/gnupg")).unwrap();
+        fs::write(repo.path().join("apps/gnupg/pubring.kbx"), &bytes).unwrap();
+        assert!(crate::git::read_app_files(repo.path(), "gnupg").unwrap().is_empty());
+        let pushed = read_app_file_bytes(repo.path(), "gnupg").unwrap();
+
+        let mut rules = SyncRules::new();
+        rules.apps.insert(
+            "gnupg".to_string(),
+            AppConfig {
+                include: vec![keyring.to_string_lossy().to_string()],
+                ..Default::default()
+            },
+        );
+        let snapshot = StatusSnapshot {
+            machine_id: "m1".to_string(),
+            repo_url: "file:///nowhere".to_string(),
+            rules,
+            machine_branches: vec!["machines/m1".to_string()],
+            orphaned_branches: vec![],
+            machine_files: Some(HashMap::from([("gnupg".to_string(), pushed)])),
+            main_files: HashMap::new(),
+            key: None,
+        };
+        assert!(render_status(&snapshot).unwrap().contains("✓ up to date on branch"));
+
+        fs::write(&keyring, [0x00, 0x9f, 0xff, b' ', b'\n', 0x01]).unwrap();
+        assert!(render_status(&snapshot).unwrap().contains("↑ local changes not pushed"));
+    }
 }
@@ -0,0 +1,107 @@
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set from `--plain` or `plain_output = true` in drifters.toml.
+static PLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Unicode decorations and their ASCII stand-ins for plain output.
+/// Longer sequences come first so `⚠️` is replaced before `⚠`.
+const REPLACEMENTS: &[(&str, &str)] = &[
+    ("⚠️", "[warn]"),
+    ("⚠", "[warn]"),
+    ("✅", "[ok]"),
+    ("✓", "[ok]"),
+    ("✗", "[fail]"),
+    ("↑", "[up]"),
+    ("↓", "[down]"),
+    ("→", "->"),
+    ("≈", "[~]"),
+    ("⏳", "[wait]"),
+    ("📥", "[download]"),
+    ("—", "-"),
+    ("•", "*"),
+    ("├── ", "|-- "),
+    ("└── ", "`-- "),
+    ("│   ", "|   "),
+];
+
+/// Enable or disable plain (ASCII-only) decorations for this process.
+pub fn set_plain(enabled: bool) {
+    PLAIN.store(enabled, Ordering::Relaxed);
+}
+
+fn plain() -> bool {
+    PLAIN.load(Ordering::Relaxed)
+}
+
+/// Success marker: `✓`, or `[ok]` in plain mode.
+pub fn ok() -> &'static str {
+    if plain() {
+        "[ok]"
+    } else {
+        "✓"
+    }
+}
+
+/// Warning marker: `⚠️`, or `[warn]` in plain mode.
+pub fn warn() -> &'static str {
+    if plain() {
+        "[warn]"
+    } else {
+        "⚠️"
+    }
+}
+
+/// Return `text` with decorations replaced by ASCII when plain mode is on.
+pub fn decorate(text: &str) -> Cow<'_, str> {
+    if plain() {
+        to_ascii(text)
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+/// Replace known decorations with their ASCII stand-ins, regardless of mode.
+pub fn to_ascii(text: &str) -> Cow<'_, str> {
+    if text.is_ascii() {
+        return Cow::Borrowed(text);
+    }
+    let mut out = text.to_string();
+    for (fancy, ascii) in REPLACEMENTS {
+        if out.contains(fancy) {
+            out = out.replace(fancy, ascii);
+        }
+    }
+    Cow::Owned(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_ascii_replaces_status_decorations() {
+        let rendered = "\
+  a.toml (/h/a.toml) - ✓ up to date on branch
+  b.toml (/h/b.toml) - ↑ local changes not pushed
+  c.toml (/h/c.toml) - ↓ available on main
+  d.toml (/h/d.toml) - ⚠ missing everywhere
+📥 Installing update...
+✅ Update installed successfully!
+  • Rename branch 'machines/a' → 'machines/b'
+⚠️  Merge conflicts detected
+  drifters push-app    — push local changes
+├── zed/
+│   └── settings.json (3 B)
+";
+        let plain = to_ascii(rendered);
+        assert!(plain.is_ascii(), "non-ASCII left in: {}", plain);
+        assert!(plain.contains("[ok] up to date"));
+        assert!(plain.contains("[up] local changes"));
+        assert!(plain.contains("[warn]  Merge conflicts"));
+        assert!(plain.contains("[download] Installing update"));
+        assert!(plain.contains("[ok] Update installed"));
+        assert!(plain.contains("  * Rename branch 'machines/a' -> 'machines/b'"));
+        assert!(plain.contains("|-- zed/"));
+    }
+}
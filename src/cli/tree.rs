@@ -0,0 +1,183 @@
+use crate::cli::style::decorate;
+use crate::config::LocalConfig;
+use crate::error::Result;
+use crate::git::{list_all_branch_files, BranchFiles, EphemeralRepoGuard};
+use std::collections::BTreeMap;
+
+/// One node of the rendered tree.
+struct Node {
+    label: String,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn leaf(label: String) -> Self {
+        Node {
+            label,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// One app's files grouped by branch, in branch order: (branch, [(file, size)]).
+type AppBranches<'a> = Vec<(&'a str, Vec<(&'a str, u64)>)>;
+
+/// Show the repo's drifters layout: the `.drifters/` metadata on main, then
+/// every app with the files each branch (main and each machine) holds for it.
+///
+/// Read-only; nothing is checked out or committed.
+pub fn show_tree() -> Result<()> {
+    let config = LocalConfig::load()?;
+    let current_branch = format!("machines/{}", config.machine_id);
+
+    println!("Fetching latest from repository...");
+    let repo_guard = EphemeralRepoGuard::new(&config)?;
+    let repo_path = repo_guard.path();
+
+    let branches = list_all_branch_files(repo_path)?;
+    let main_files = &branches[0].1;
+
+    println!("\n{}", config.repo_url);
+    let tree = render_tree(main_files, &branches, &current_branch);
+    print!("{}", decorate(&tree));
+    Ok(())
+}
+
+/// Build the tree text. `metadata` is main's file list (only `.drifters/` is
+/// shown from it); `branches` pairs each branch name with its file list.
+fn render_tree(
+    metadata: &[(String, u64)],
+    branches: &[(String, BranchFiles)],
+    current_branch: &str,
+) -> String {
+    let drifters_dir = Node {
+        label: ".drifters/".to_string(),
+        children: metadata
+            .iter()
+            .filter_map(|(path, size)| {
+                let name = path.strip_prefix(".drifters/")?;
+                Some(Node::leaf(format!("{} ({})", name, format_size(*size))))
+            })
+            .collect(),
+    };
+
+    // app → branch → files; branches keep the order they were given in
+    let mut apps: BTreeMap<&str, AppBranches> = BTreeMap::new();
+    for (branch, files) in branches {
+        for (path, size) in files {
+            let Some(rest) = path.strip_prefix("apps/") else {
+                continue;
+            };
+            let Some((app, file)) = rest.split_once('/') else {
+                continue;
+            };
+            let per_branch = apps.entry(app).or_default();
+            match per_branch.last_mut() {
+                Some((b, files)) if *b == branch.as_str() => files.push((file, *size)),
+                _ => per_branch.push((branch.as_str(), vec![(file, *size)])),
+            }
+        }
+    }
+
+    let apps_dir = Node {
+        label: "apps/".to_string(),
+        children: apps
+            .into_iter()
+            .map(|(app, per_branch)| Node {
+                label: format!("{}/", app),
+                children: per_branch
+                    .into_iter()
+                    .map(|(branch, files)| Node {
+                        label: if branch == current_branch {
+                            format!("{}  (this machine)", branch)
+                        } else {
+                            branch.to_string()
+                        },
+                        children: files
+                            .into_iter()
+                            .map(|(file, size)| {
+                                Node::leaf(format!("{} ({})", file, format_size(size)))
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect(),
+    };
+
+    let mut out = String::new();
+    for root in [drifters_dir, apps_dir] {
+        out.push_str(&root.label);
+        out.push('\n');
+        render_children(&root.children, "", &mut out);
+    }
+    out
+}
+
+fn render_children(children: &[Node], prefix: &str, out: &mut String) {
+    for (i, child) in children.iter().enumerate() {
+        let last = i + 1 == children.len();
+        out.push_str(prefix);
+        out.push_str(if last { "└── " } else { "├── " });
+        out.push_str(&child.label);
+        out.push('\n');
+
+        let child_prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+        render_children(&child.children, &child_prefix, out);
+    }
+}
+
+pub(crate) fn format_size(bytes: u64) -> String {
+    const KIB: u64 = 1024;
+    const MIB: u64 = 1024 * KIB;
+    if bytes >= MIB {
+        format!("{:.1} MiB", bytes as f64 / MIB as f64)
+    } else if bytes >= KIB {
+        format!("{:.1} KiB", bytes as f64 / KIB as f64)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_tree_fixture_layout() {
+        let main = vec![
+            (".drifters/machines.toml".to_string(), 120),
+            (".drifters/sync-rules.toml".to_string(), 2048),
+            ("apps/zed/settings.json".to_string(), 300),
+        ];
+        let laptop = vec![
+            (".drifters/sync-rules.toml".to_string(), 2048),
+            ("apps/zed/keymap.json".to_string(), 10),
+            ("apps/zed/settings.json".to_string(), 310),
+            ("apps/zsh/.zshrc".to_string(), 5),
+        ];
+        let branches = vec![
+            ("main".to_string(), main.clone()),
+            ("machines/laptop".to_string(), laptop),
+        ];
+
+        let tree = render_tree(&main, &branches, "machines/laptop");
+
+        let expected = "\
+.drifters/
+├── machines.toml (120 B)
+└── sync-rules.toml (2.0 KiB)
+apps/
+├── zed/
+│   ├── main
+│   │   └── settings.json (300 B)
+│   └── machines/laptop  (this machine)
+│       ├── keymap.json (10 B)
+│       └── settings.json (310 B)
+└── zsh/
+    └── machines/laptop  (this machine)
+        └── .zshrc (5 B)
+";
+        assert_eq!(tree, expected);
+    }
+}
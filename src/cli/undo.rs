@@ -0,0 +1,120 @@
+use crate::cli::common::replace_with_symlink;
+use crate::cli::style::ok;
+use crate::config::{FileState, JournalEntry, UndoJournal};
+use crate::error::Result;
+use crate::git::confirm_operation;
+use crate::sync::write_atomic;
+use std::fs;
+
+/// Put the local files the last pull changed back as they were, from the
+/// journal the pull left in `~/.config/drifters/undo/`. Files edited since
+/// the pull are left alone. The journal is deleted afterwards, so a pull
+/// can only be undone once.
+pub fn undo_command(yes: bool) -> Result<()> {
+    let Some(journal) = UndoJournal::load(UndoJournal::default_dir()?)? else {
+        println!("Nothing to undo: no pull has changed local files since the last undo.");
+        return Ok(());
+    };
+
+    match journal.taken() {
+        Some(taken) => println!(
+            "The last pull ({}) changed {} file(s):",
+            taken.format("%Y-%m-%d %H:%M UTC"),
+            journal.entries().len()
+        ),
+        None => println!("The last pull changed {} file(s):", journal.entries().len()),
+    }
+    let mut revertible = Vec::new();
+    for entry in journal.entries() {
+        let action = match &entry.before {
+            FileState::Missing => "delete (created by the pull)".to_string(),
+            FileState::File(_) => "restore".to_string(),
+            FileState::Link(target) => format!("relink to {}", target.display()),
+        };
+        if FileState::of(&entry.path)? == entry.after {
+            println!("  {} - {}", entry.path.display(), action);
+            revertible.push(entry);
+        } else {
+            println!("  {} - changed since the pull, skipped", entry.path.display());
+        }
+    }
+
+    if revertible.is_empty() {
+        println!("\nNothing to revert.");
+        return journal.clear();
+    }
+    let msg = format!("Revert {} file(s)?", revertible.len());
+    if !yes && !confirm_operation(&msg, false)? {
+        println!("Cancelled.");
+        return Ok(());
+    }
+
+    for entry in &revertible {
+        revert(&journal, entry)?;
+    }
+    let reverted = revertible.len();
+    journal.clear()?;
+    println!("\n{} Reverted {} file(s)", ok(), reverted);
+    Ok(())
+}
+
+/// Put `entry.path` back to its state before the pull.
+fn revert(journal: &UndoJournal, entry: &JournalEntry) -> Result<()> {
+    let path = &entry.path;
+    let is_link = fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink());
+    match &entry.before {
+        FileState::Missing => {
+            if fs::symlink_metadata(path).is_ok() {
+                fs::remove_file(path)?;
+            }
+        }
+        FileState::File(hash) => {
+            let content = journal.saved_content(hash)?;
+            // The pull made it a symlink; write a regular file, not through the link
+            if is_link {
+                fs::remove_file(path)?;
+            }
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            write_atomic(path, content)?;
+        }
+        FileState::Link(target) => {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            replace_with_symlink(path, target)?;
+        }
+    }
+    log::debug!("Reverted {}", path.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revert_restores_edited_and_deletes_created_files() {
+        let home = tempfile::tempdir().unwrap();
+        let dir = home.path().join("undo");
+        let edited = home.path().join("settings.json");
+        let deleted = home.path().join("old.conf");
+        let created = home.path().join("new.conf");
+        fs::write(&edited, "mine\n").unwrap();
+        fs::write(&deleted, "keep me\n").unwrap();
+
+        let mut journal = UndoJournal::start(dir.clone());
+        journal.record(&edited, || Ok(fs::write(&edited, "theirs\n")?)).unwrap();
+        journal.record(&deleted, || Ok(fs::remove_file(&deleted)?)).unwrap();
+        journal.record(&created, || Ok(fs::write(&created, "new\n")?)).unwrap();
+
+        let journal = UndoJournal::load(dir).unwrap().unwrap();
+        for entry in journal.entries() {
+            revert(&journal, entry).unwrap();
+        }
+        assert_eq!(fs::read_to_string(&edited).unwrap(), "mine\n");
+        assert_eq!(fs::read_to_string(&deleted).unwrap(), "keep me\n");
+        assert!(!created.exists());
+    }
+}
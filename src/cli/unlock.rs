@@ -1,13 +1,21 @@
+use crate::cli::style::ok;
 use crate::config::LocalConfig;
 use crate::error::Result;
-use crate::git::confirm_operation;
+use crate::git::{cleanup_ephemeral_repo, confirm_operation};
 
+/// Remove the repo lock after confirmation, and the working clone with it,
+/// cached or left behind, so the next command starts from a fresh clone.
 pub fn unlock() -> Result<()> {
     let temp_repo = LocalConfig::get_temp_repo_path()?;
     let lock_path = temp_repo.with_extension("lock");
 
     if !lock_path.exists() {
         println!("No lock file found. Nothing to unlock.");
+        if temp_repo.exists() {
+            // Nothing is using it, so a cached clone can go without asking
+            cleanup_ephemeral_repo()?;
+            println!("{} Cached repository removed", ok());
+        }
         return Ok(());
     }
 
@@ -42,7 +50,7 @@ pub fn unlock() -> Result<()> {
 
     if temp_repo.exists() {
         println!("Cleaning up leftover temporary repository...");
-        std::fs::remove_dir_all(&temp_repo)?;
+        cleanup_ephemeral_repo()?;
         println!("✓ Temporary repository removed");
     }
 
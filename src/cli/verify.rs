@@ -0,0 +1,195 @@
+use crate::cli::common::load_rules_from_branch;
+use crate::cli::push::syncable_text;
+use crate::cli::style::decorate;
+use crate::config::{resolve_fileset, LocalConfig};
+use crate::crypto::SecretKey;
+use crate::error::{DriftersError, Result};
+use crate::git::{main_branch, read_app_file_bytes, EphemeralRepoGuard};
+use crate::parser::redact::compile_redactions;
+use sha2::{Digest, Sha256};
+use std::fs;
+
+/// Result of comparing one local file against this machine's pushed copy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum VerifyState {
+    /// Local content is exactly what was pushed
+    Match,
+    /// Both exist but the content differs
+    Mismatch,
+    /// Missing locally, or never pushed from this machine
+    Missing,
+}
+
+/// Read-only integrity check: does what is on disk equal what this machine
+/// last pushed to its branch?
+///
+/// No merge logic is involved and other machines are ignored. Files with
+/// `drifters::exclude` sections or `redact` patterns are compared on their
+/// syncable content, i.e. exactly what `push-app` would write into the repo.
+pub fn verify_command(app_name: Option<String>) -> Result<()> {
+    log::info!("Verifying local files against this machine's branch");
+
+    let mut config = LocalConfig::load()?;
+    let key = SecretKey::load()?;
+    let machine_branch = format!("machines/{}", config.machine_id);
+
+    println!("Fetching latest from repository...");
+    let repo_guard = EphemeralRepoGuard::new_on_branch(&config, &machine_branch)?;
+    let repo_path = repo_guard.path();
+
+    // Guard: detect stale machine IDs
+    crate::cli::common::verify_machine_registration(&mut config, repo_path)?;
+    // The guard may have switched to a renamed ID
+    let machine_branch = format!("machines/{}", config.machine_id);
+
+    let rules = load_rules_from_branch(repo_path, main_branch())?;
+    rules.validate()?;
+
+    if rules.apps.is_empty() {
+        println!("No apps configured for sync.");
+        return Ok(());
+    }
+
+    let mut apps_to_verify: Vec<_> = if let Some(name) = app_name {
+        if rules.apps.contains_key(&name) {
+            vec![name]
+        } else {
+            return Err(DriftersError::AppNotFound(name));
+        }
+    } else {
+        rules.apps.keys().cloned().collect()
+    };
+    apps_to_verify.sort();
+
+    println!("Verifying local files against branch '{}'", machine_branch);
+
+    let mut matched = 0usize;
+    let mut mismatched = 0usize;
+    let mut missing = 0usize;
+
+    for app in &apps_to_verify {
+        let app_config = rules.apps.get(app).unwrap();
+
+        println!("\n{}", app);
+
+        let redactions = compile_redactions(&app_config.redact)?;
+
+        let fileset = resolve_fileset(
+            app_config,
+            &rules.ignore,
+            &rules.groups,
+            &config.machine_id,
+            std::env::consts::OS,
+        )?;
+        if fileset.is_empty() {
+            println!("  (no files in fileset for this machine)");
+            continue;
+        }
+
+        let pushed_files = read_app_file_bytes(repo_path, app)?;
+
+        for local_path in &fileset {
+            let filename = local_path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("unknown");
+
+            // Binary files are pushed as is, so they are compared as is
+            let local_content = if local_path.exists() {
+                match String::from_utf8(fs::read(local_path)?) {
+                    Ok(content) => Some(
+                        syncable_text(&content, filename, app_config, &redactions, key.as_ref())?
+                            .into_bytes(),
+                    ),
+                    Err(binary) => Some(binary.into_bytes()),
+                }
+            } else {
+                None
+            };
+            let pushed = pushed_files.get(filename).map(Vec::as_slice);
+
+            match classify(local_content.as_deref(), pushed) {
+                VerifyState::Match => {
+                    matched += 1;
+                    let line = format!(
+                        "  ✓ {} ({}) - matches pushed version [{}]",
+                        filename,
+                        local_path.display(),
+                        short_hash(pushed.unwrap_or_default())
+                    );
+                    println!("{}", decorate(&line));
+                }
+                VerifyState::Mismatch => {
+                    mismatched += 1;
+                    let line = format!(
+                        "  ✗ {} ({}) - differs: local {} vs pushed {}",
+                        filename,
+                        local_path.display(),
+                        short_hash(local_content.as_deref().unwrap_or_default()),
+                        short_hash(pushed.unwrap_or_default())
+                    );
+                    println!("{}", decorate(&line));
+                }
+                VerifyState::Missing => {
+                    missing += 1;
+                    let why = if local_content.is_none() {
+                        "missing locally"
+                    } else {
+                        "never pushed from this machine"
+                    };
+                    let line = format!("  ⚠ {} ({}) - {}", filename, local_path.display(), why);
+                    println!("{}", decorate(&line));
+                }
+            }
+        }
+    }
+
+    println!(
+        "\n{} matching, {} differing, {} missing",
+        matched, mismatched, missing
+    );
+
+    if mismatched > 0 || missing > 0 {
+        return Err(DriftersError::Config(format!(
+            "{} file(s) do not match branch '{}'. Run 'drifters push-app' to update it.",
+            mismatched + missing,
+            machine_branch
+        )));
+    }
+
+    println!("{}", decorate("✓ Everything on disk matches what this machine pushed."));
+    Ok(())
+}
+
+/// Classify one file given its local syncable content and the pushed content.
+fn classify<T: AsRef<[u8]>>(local: Option<T>, pushed: Option<T>) -> VerifyState {
+    match (local, pushed) {
+        (Some(l), Some(p)) if content_hash(&l) == content_hash(&p) => VerifyState::Match,
+        (Some(_), Some(_)) => VerifyState::Mismatch,
+        _ => VerifyState::Missing,
+    }
+}
+
+fn content_hash(content: impl AsRef<[u8]>) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+fn short_hash(content: impl AsRef<[u8]>) -> String {
+    content_hash(content)[..12].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_match_mismatch_missing() {
+        assert_eq!(classify(Some("a = 1\n"), Some("a = 1\n")), VerifyState::Match);
+        assert_eq!(classify(Some("a = 1\n"), Some("a = 2\n")), VerifyState::Mismatch);
+        // Trailing newline differences are real byte differences
+        assert_eq!(classify(Some("a = 1"), Some("a = 1\n")), VerifyState::Mismatch);
+        assert_eq!(classify(None, Some("a = 1\n")), VerifyState::Missing);
+        assert_eq!(classify(Some("a = 1\n"), None), VerifyState::Missing);
+        assert_eq!(classify::<&[u8]>(None, None), VerifyState::Missing);
+    }
+}
@@ -0,0 +1,167 @@
+use crate::cli::push::push_command;
+use crate::config::{resolve_fileset, LocalConfig, SyncRules};
+use crate::error::{DriftersError, Result};
+use crate::git::{set_unattended, EphemeralRepoGuard};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+/// Push every app shortly after its files change, until interrupted.
+///
+/// Each file's directory is watched rather than the file itself, so saves
+/// that write a temporary file and rename it over the original (which
+/// replace the watched inode) are seen like in-place writes. Changes are
+/// collected until none arrive for `interval` seconds, then pushed in one
+/// commit through the regular `push-app` pipeline, with every prompt taking
+/// its default answer. The fileset is resolved again after each push, so
+/// new files and rule changes are picked up.
+pub fn watch_command(interval: u64) -> Result<()> {
+    let config = LocalConfig::load()?;
+    let interval = Duration::from_secs(interval.max(1));
+    set_unattended(true);
+
+    let (tx, rx) = mpsc::channel();
+    let mut current_watcher: Option<notify::RecommendedWatcher> = None;
+    loop {
+        let files = watched_files(&config)?;
+        if files.is_empty() {
+            return Err(DriftersError::Config(
+                "No files to watch: no app has files on this machine".to_string(),
+            ));
+        }
+
+        // The previous watcher is only dropped once this one is running, and
+        // both send into the same channel, so edits made during a push are
+        // not lost
+        let mut watcher = notify::recommended_watcher(tx.clone())
+            .map_err(|e| DriftersError::Config(format!("Could not watch files: {}", e)))?;
+        let dirs: BTreeSet<PathBuf> = files
+            .iter()
+            .filter_map(|file| file.parent().map(PathBuf::from))
+            .collect();
+        for dir in &dirs {
+            if let Err(e) = watcher.watch(dir, RecursiveMode::NonRecursive) {
+                log::warn!("Not watching {}: {}", dir.display(), e);
+            }
+        }
+        drop(current_watcher.replace(watcher));
+        println!(
+            "\nWatching {} file(s) in {} dir(s); pushing {}s after the last change. \
+             Ctrl-C to stop.",
+            files.len(),
+            dirs.len(),
+            interval.as_secs()
+        );
+
+        let changed = next_batch(&rx, &files, interval)?;
+        println!("\nChanged:");
+        for path in &changed {
+            println!("  {}", path.display());
+        }
+        // A failed push (lock held too long, network down) is reported and
+        // retried with the next change
+        if let Err(e) = push_command(None, false, false, false, None) {
+            eprintln!("Push failed: {}", e);
+        }
+    }
+}
+
+/// Every file of every app on this machine, read under the repo lock.
+fn watched_files(config: &LocalConfig) -> Result<BTreeSet<PathBuf>> {
+    let rules = {
+        let repo_guard = EphemeralRepoGuard::new(config)?;
+        SyncRules::load(repo_guard.path())?
+    };
+    rules.validate()?;
+
+    let mut files = BTreeSet::new();
+    for app_config in rules.apps.values() {
+        files.extend(resolve_fileset(
+            app_config,
+            &rules.ignore,
+            &rules.groups,
+            &config.machine_id,
+            std::env::consts::OS,
+        )?);
+    }
+    Ok(files)
+}
+
+/// Block until one of `files` changes, then keep collecting changes until
+/// none arrive for `quiet`. Returns the changed files.
+fn next_batch(
+    rx: &Receiver<notify::Result<Event>>,
+    files: &BTreeSet<PathBuf>,
+    quiet: Duration,
+) -> Result<BTreeSet<PathBuf>> {
+    let mut changed = BTreeSet::new();
+    loop {
+        let received = if changed.is_empty() {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            rx.recv_timeout(quiet)
+        };
+        match received {
+            Ok(Ok(event)) => changed.extend(changed_files(&event, files)),
+            Ok(Err(e)) => log::warn!("File watcher error: {}", e),
+            Err(RecvTimeoutError::Timeout) => return Ok(changed),
+            Err(RecvTimeoutError::Disconnected) => {
+                return Err(DriftersError::Config("File watcher stopped".to_string()));
+            }
+        }
+    }
+}
+
+/// The watched files an event touched. Content changes, creations (the
+/// rename at the end of an atomic save), renames and deletions count;
+/// reads and metadata-only events from other files in the directory don't.
+fn changed_files(event: &Event, files: &BTreeSet<PathBuf>) -> Vec<PathBuf> {
+    if !matches!(
+        event.kind,
+        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+    ) {
+        return Vec::new();
+    }
+    event
+        .paths
+        .iter()
+        .filter(|path| files.contains(*path))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{AccessKind, CreateKind, ModifyKind, RenameMode};
+
+    #[test]
+    fn test_changed_files_sees_atomic_saves_and_batches_edits() {
+        let zshrc = PathBuf::from("/home/me/.zshrc");
+        let files = BTreeSet::from([zshrc.clone()]);
+
+        // An editor's atomic save: write a temp file, rename it over .zshrc
+        let temp = Event::new(EventKind::Create(CreateKind::File))
+            .add_path(PathBuf::from("/home/me/.zshrc.swp"));
+        let rename = Event::new(EventKind::Modify(ModifyKind::Name(RenameMode::Both)))
+            .add_path(PathBuf::from("/home/me/.zshrc.swp"))
+            .add_path(zshrc.clone());
+        assert!(changed_files(&temp, &files).is_empty());
+        assert_eq!(changed_files(&rename, &files), vec![zshrc.clone()]);
+
+        // Reading the file (as push does) is not a change
+        let read = Event::new(EventKind::Access(AccessKind::Any)).add_path(zshrc.clone());
+        assert!(changed_files(&read, &files).is_empty());
+
+        // Rapid edits end up in one batch once the channel goes quiet
+        let (tx, rx) = mpsc::channel();
+        for _ in 0..3 {
+            tx.send(Ok(rename.clone())).unwrap();
+        }
+        tx.send(Ok(temp)).unwrap();
+        let batch = next_batch(&rx, &files, Duration::from_millis(50)).unwrap();
+        assert_eq!(batch, files);
+    }
+}
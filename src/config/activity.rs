@@ -0,0 +1,153 @@
+use crate::error::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// `activity.toml` keeps at most this many entries; older ones are pruned
+/// as new ones are recorded.
+pub const MAX_ACTIVITY_ENTRIES: usize = 500;
+
+/// Log of pushes, kept in `.drifters/activity.toml` on main by machines
+/// that opt in with `record_activity`. Nothing leaves the repository.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ActivityLog {
+    #[serde(default, rename = "entry", skip_serializing_if = "Vec::is_empty")]
+    pub entries: Vec<ActivityEntry>,
+}
+
+/// One app pushed from one machine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActivityEntry {
+    pub machine: String,
+    pub app: String,
+    pub files: usize,
+    pub timestamp: DateTime<Utc>,
+    /// drifters version that made the push
+    pub version: String,
+}
+
+/// Activity of one app on one machine, as summarized by
+/// [`ActivityLog::summarize`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivitySummary {
+    pub machine: String,
+    pub app: String,
+    pub pushes: usize,
+    pub files: usize,
+    pub last: DateTime<Utc>,
+}
+
+impl ActivityLog {
+    pub fn load(repo_path: &Path) -> Result<Self> {
+        let path = repo_path.join(".drifters").join("activity.toml");
+
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&path)?;
+        Ok(toml::from_str(&contents)?)
+    }
+
+    pub fn save(&self, repo_path: &Path) -> Result<()> {
+        let drifters_dir = repo_path.join(".drifters");
+        std::fs::create_dir_all(&drifters_dir)?;
+
+        let path = drifters_dir.join("activity.toml");
+        let contents = toml::to_string_pretty(self)?;
+        crate::sync::write_atomic(&path, contents)?;
+        Ok(())
+    }
+
+    /// Append an entry, pruning the oldest beyond `max_entries`.
+    pub fn record(&mut self, entry: ActivityEntry, max_entries: usize) {
+        self.entries.push(entry);
+        if self.entries.len() > max_entries {
+            let excess = self.entries.len() - max_entries;
+            self.entries.drain(..excess);
+        }
+    }
+
+    /// Pushes since `since`, totalled per machine and app, sorted by machine
+    /// then app.
+    pub fn summarize(&self, since: DateTime<Utc>) -> Vec<ActivitySummary> {
+        let mut summaries: BTreeMap<(&str, &str), ActivitySummary> = BTreeMap::new();
+        for entry in self.entries.iter().filter(|e| e.timestamp >= since) {
+            let summary = summaries
+                .entry((&entry.machine, &entry.app))
+                .or_insert_with(|| ActivitySummary {
+                    machine: entry.machine.clone(),
+                    app: entry.app.clone(),
+                    pushes: 0,
+                    files: 0,
+                    last: entry.timestamp,
+                });
+            summary.pushes += 1;
+            summary.files += entry.files;
+            summary.last = summary.last.max(entry.timestamp);
+        }
+        summaries.into_values().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(machine: &str, app: &str, files: usize, minute: i64) -> ActivityEntry {
+        ActivityEntry {
+            machine: machine.to_string(),
+            app: app.to_string(),
+            files,
+            timestamp: DateTime::from_timestamp(1_780_000_000 + minute * 60, 0).unwrap(),
+            version: "0.1.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_record_appends_and_prunes_oldest() {
+        let mut log = ActivityLog::default();
+        for minute in 0..5 {
+            log.record(entry("laptop", "zsh", 1, minute), 3);
+        }
+        let kept: Vec<_> = log.entries.iter().map(|e| e.timestamp.timestamp()).collect();
+        assert_eq!(
+            kept,
+            vec![1_780_000_120, 1_780_000_180, 1_780_000_240],
+            "only the newest three entries survive"
+        );
+
+        // Survives a save/load round trip
+        let dir = tempfile::tempdir().unwrap();
+        log.save(dir.path()).unwrap();
+        assert_eq!(ActivityLog::load(dir.path()).unwrap().entries, log.entries);
+        assert!(ActivityLog::load(&dir.path().join("none")).unwrap().entries.is_empty());
+    }
+
+    #[test]
+    fn test_summarize_totals_per_machine_and_app_since_cutoff() {
+        let mut log = ActivityLog::default();
+        for e in [
+            entry("laptop", "zsh", 2, 0),
+            entry("laptop", "zsh", 1, 10),
+            entry("desktop", "zsh", 3, 20),
+            entry("laptop", "vim", 4, 30),
+            entry("laptop", "zsh", 5, 40),
+        ] {
+            log.record(e, MAX_ACTIVITY_ENTRIES);
+        }
+
+        let since = DateTime::from_timestamp(1_780_000_000 + 5 * 60, 0).unwrap();
+        let summary = log.summarize(since);
+        let rows: Vec<_> = summary
+            .iter()
+            .map(|s| (s.machine.as_str(), s.app.as_str(), s.pushes, s.files))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![("desktop", "zsh", 1, 3), ("laptop", "vim", 1, 4), ("laptop", "zsh", 2, 6)]
+        );
+        assert_eq!(summary[2].last.timestamp(), 1_780_000_000 + 40 * 60);
+    }
+}
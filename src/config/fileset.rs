@@ -1,14 +1,86 @@
 use crate::config::sync_rules::AppConfig;
-use crate::error::Result;
-use std::path::PathBuf;
+use crate::error::{DriftersError, Result};
+use crate::sync::map_bounded;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 
-/// Resolve the fileset for a given app on a specific machine/OS
-/// Applies three-level hierarchy: Machine > OS > App
+/// Set from `pull-app --home`: the directory `~` stands for in this process.
+static HOME_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Treat `dir` as the home directory for all `~` expansion in this process.
+/// Only the first call takes effect.
+pub fn set_home_override(dir: PathBuf) {
+    let _ = HOME_OVERRIDE.set(dir);
+}
+
+/// The home directory `~` expands to: the `--home` override if set,
+/// otherwise the real one.
+pub fn home_dir() -> Option<PathBuf> {
+    HOME_OVERRIDE.get().cloned().or_else(dirs::home_dir)
+}
+
+/// Files no glob ever picks up, whatever the app's patterns: the usual
+/// private keys and secrets. Applied on top of the rules' `ignore` list.
+pub const DEFAULT_IGNORE: [&str; 4] = ["*.pem", "*.key", "id_rsa", ".env"];
+
+/// An app's own ignore file, in gitignore syntax, read from the deepest
+/// directory all of its include patterns lie in. It is synced like any
+/// other file the patterns match, so the rules travel with the app.
+pub const IGNORE_FILE: &str = ".driftersignore";
+
+/// Resolve the fileset for a given app on a specific machine/OS.
+///
+/// Patterns are collected level by level: the app's own, then its OS
+/// rules, then the overrides of every group in `groups` that lists
+/// `machine_id` (by group name), then the machine's own override. Every
+/// level only adds patterns, so an exclude from any level drops a file
+/// included at any other. Files an [`IGNORE_FILE`] next to the app's
+/// configs ignores are dropped after that, and files matched by the global
+/// `ignore` patterns (and [`DEFAULT_IGNORE`]) last.
 pub fn resolve_fileset(
     app_config: &AppConfig,
+    ignore: &[String],
+    groups: &BTreeMap<String, Vec<String>>,
+    machine_id: &str,
+    os: &str,
+) -> Result<Vec<PathBuf>> {
+    resolve_fileset_in(app_config, ignore, groups, machine_id, os, home_dir().as_deref())
+}
+
+/// Like [`resolve_fileset`], also returning the files the app's patterns
+/// matched but the global ignore list dropped, so they can be reported.
+pub fn resolve_fileset_with_ignored(
+    app_config: &AppConfig,
+    ignore: &[String],
+    groups: &BTreeMap<String, Vec<String>>,
+    machine_id: &str,
+    os: &str,
+) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+    resolve(app_config, ignore, groups, machine_id, os, home_dir().as_deref())
+}
+
+/// Like [`resolve_fileset`], expanding `~` to `home` instead.
+pub fn resolve_fileset_in(
+    app_config: &AppConfig,
+    ignore: &[String],
+    groups: &BTreeMap<String, Vec<String>>,
     machine_id: &str,
     os: &str,
+    home: Option<&Path>,
 ) -> Result<Vec<PathBuf>> {
+    Ok(resolve(app_config, ignore, groups, machine_id, os, home)?.0)
+}
+
+/// The fileset and the files dropped from it by the global ignore list.
+fn resolve(
+    app_config: &AppConfig,
+    ignore: &[String],
+    groups: &BTreeMap<String, Vec<String>>,
+    machine_id: &str,
+    os: &str,
+    home: Option<&Path>,
+) -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
     let mut include_patterns: Vec<String> = Vec::new();
     let mut exclude_patterns: Vec<String> = Vec::new();
 
@@ -35,77 +107,279 @@ pub fn resolve_fileset(
         }
     }
 
-    // 3. Apply machine-specific overrides
-    if let Some(machine_override) = app_config.machines.get(machine_id) {
+    // 3. Apply group overrides, then machine-specific ones
+    for (_, machine_override) in app_config.overrides_for(groups, machine_id) {
         include_patterns.extend(machine_override.include.iter().cloned());
         exclude_patterns.extend(machine_override.exclude.iter().cloned());
     }
 
-    // 4. Expand globs and apply exclusions
-    let mut files: Vec<PathBuf> = Vec::new();
-
-    for pattern in include_patterns {
-        let expanded_pattern = expand_tilde(&pattern);
-
-        match glob::glob(&expanded_pattern) {
-            Ok(paths) => {
-                for path_result in paths {
-                    match path_result {
-                        Ok(path) => {
-                            if !matches_any_pattern(&path, &exclude_patterns) {
-                                files.push(path);
-                            }
-                        }
-                        Err(e) => {
-                            log::warn!("Error reading glob path: {}", e);
-                        }
-                    }
-                }
-            }
-            Err(e) => {
-                log::warn!("Invalid glob pattern '{}': {}", expanded_pattern, e);
-            }
-        }
-    }
+    // 4. Expand globs and apply exclusions, one pattern per worker: each
+    // walks its own part of the filesystem
+    let options = glob::MatchOptions {
+        require_literal_leading_dot: !app_config.matches_hidden(),
+        ..glob::MatchOptions::new()
+    };
+    let mut files: Vec<PathBuf> = map_bounded(&include_patterns, |pattern| {
+        expand_pattern(pattern, &exclude_patterns, app_config, options, home)
+    })
+    .into_iter()
+    .flatten()
+    .collect();
 
     // Remove duplicates
     files.sort();
     files.dedup();
 
-    Ok(files)
+    // 5. The app's .driftersignore, after the rules' excludes
+    if let Some((dir, ignore_file)) = app_ignore_file(&include_patterns, home) {
+        files.retain(|path| {
+            let ignored = path.starts_with(&dir)
+                && ignore_file.matched_path_or_any_parents(path, path.is_dir()).is_ignore();
+            if ignored {
+                log::debug!("Ignoring {} (matches {})", path.display(), IGNORE_FILE);
+            }
+            !ignored
+        });
+    }
+
+    // 6. The global ignore list always has the last word
+    let (ignored, files): (Vec<_>, Vec<_>) =
+        files.into_iter().partition(|path| is_ignored(path, ignore, home));
+    for path in &ignored {
+        log::info!("Ignoring {} (matches the global ignore list)", path.display());
+    }
+
+    Ok((files, ignored))
 }
 
-/// Check if a path matches any of the exclude patterns
-fn matches_any_pattern(path: &PathBuf, patterns: &[String]) -> bool {
-    for pattern in patterns {
-        let expanded_pattern = expand_tilde(pattern);
+/// The files one include pattern matches, less those an exclude pattern
+/// matches and, unless the app follows them, symlinks.
+fn expand_pattern(
+    pattern: &str,
+    exclude_patterns: &[String],
+    app_config: &AppConfig,
+    options: glob::MatchOptions,
+    home: Option<&Path>,
+) -> Vec<PathBuf> {
+    let expanded_pattern = expand_tilde(pattern, home);
+    let paths = match glob::glob_with(&expanded_pattern, options) {
+        Ok(paths) => paths,
+        Err(e) => {
+            log::warn!("Invalid glob pattern '{}': {}", expanded_pattern, e);
+            return Vec::new();
+        }
+    };
 
-        // Try glob match
-        if let Ok(glob_pattern) = glob::Pattern::new(&expanded_pattern) {
-            if glob_pattern.matches_path(path) {
-                return true;
+    let mut files = Vec::new();
+    for path_result in paths {
+        match path_result {
+            Ok(path) => {
+                if matches_any_pattern(&path, exclude_patterns, home) {
+                    continue;
+                }
+                if !app_config.follow_symlinks && is_symlink(&path) {
+                    log::warn!(
+                        "Skipping symlink {} (set follow_symlinks = true on the \
+                         app to sync it as a link)",
+                        path.display()
+                    );
+                    continue;
+                }
+                files.push(path);
+            }
+            Err(e) => {
+                log::warn!("Error reading glob path: {}", e);
             }
         }
+    }
+    files
+}
 
-        // Also check simple path match
-        if path.to_str().map(|p| p.contains(pattern)).unwrap_or(false) {
-            return true;
+/// The [`IGNORE_FILE`] for an app with these include patterns, with the
+/// directory it is in, if there is one.
+fn app_ignore_file(
+    include_patterns: &[String],
+    home: Option<&Path>,
+) -> Option<(PathBuf, ignore::gitignore::Gitignore)> {
+    let dir = common_base_dir(include_patterns, home)?;
+    let path = dir.join(IGNORE_FILE);
+    if !path.is_file() {
+        return None;
+    }
+    let mut builder = ignore::gitignore::GitignoreBuilder::new(&dir);
+    // Invalid lines are reported; the valid ones still apply
+    if let Some(e) = builder.add(&path) {
+        log::warn!("{}: {}", path.display(), e);
+    }
+    match builder.build() {
+        Ok(ignore_file) => Some((dir, ignore_file)),
+        Err(e) => {
+            log::warn!("Ignoring {}: {}", path.display(), e);
+            None
         }
     }
+}
 
-    false
+/// The deepest directory every pattern's matches lie in: each pattern's
+/// path up to its first component with a wildcard (or a literal file's
+/// parent), shortened to what they all share. `None` when that is the
+/// filesystem root.
+fn common_base_dir(patterns: &[String], home: Option<&Path>) -> Option<PathBuf> {
+    let mut common: Option<PathBuf> = None;
+    for pattern in patterns.iter().filter(|p| !p.is_empty()) {
+        let expanded = PathBuf::from(expand_tilde(pattern, home));
+        let mut base = PathBuf::new();
+        let mut has_wildcard = false;
+        for component in expanded.components() {
+            if component.as_os_str().to_string_lossy().contains(['*', '?', '[']) {
+                has_wildcard = true;
+                break;
+            }
+            base.push(component);
+        }
+        if !has_wildcard {
+            base.pop();
+        }
+        common = Some(match common {
+            None => base,
+            Some(common) => common
+                .components()
+                .zip(base.components())
+                .take_while(|(a, b)| a == b)
+                .map(|(a, _)| a)
+                .collect(),
+        });
+    }
+    common.filter(|dir| dir.parent().is_some())
 }
 
-/// Expand tilde (~) to home directory
-pub fn expand_tilde(path: &str) -> String {
-    if path.starts_with("~/") {
-        if let Some(home) = dirs::home_dir() {
-            return home.join(&path[2..]).to_string_lossy().to_string();
+/// Whether a global ignore pattern (or a [`DEFAULT_IGNORE`] one) matches
+/// `path`. Patterns without a `/` match the file name in any directory, so
+/// `*.pem` catches keys wherever an app's globs reach; patterns with one
+/// match the whole path.
+fn is_ignored(path: &Path, ignore: &[String], home: Option<&Path>) -> bool {
+    let file_name = path.file_name().map(Path::new);
+    DEFAULT_IGNORE
+        .iter()
+        .copied()
+        .chain(ignore.iter().map(String::as_str))
+        .any(|pattern| {
+            let Ok(glob_pattern) = glob::Pattern::new(&expand_tilde(pattern, home)) else {
+                return false;
+            };
+            let options = glob::MatchOptions {
+                require_literal_leading_dot: false,
+                ..glob::MatchOptions::new()
+            };
+            match (pattern.contains('/'), file_name) {
+                (true, _) => glob_pattern.matches_path_with(path, options),
+                (false, Some(name)) => glob_pattern.matches_path_with(name, options),
+                (false, None) => false,
+            }
+        })
+}
+
+/// Check a resolved fileset for an app that matched nothing on this machine.
+///
+/// Returns `Ok(None)` when there are files, a warning for an ordinary app,
+/// and an error for an app marked `required = true`.
+pub fn check_fileset(
+    app_name: &str,
+    app_config: &AppConfig,
+    fileset: &[PathBuf],
+) -> Result<Option<String>> {
+    if !fileset.is_empty() {
+        return Ok(None);
+    }
+    if app_config.required {
+        return Err(DriftersError::Config(format!(
+            "Required app '{}' has no files on this machine. \
+             Check that its configs exist and its include patterns match.",
+            app_name
+        )));
+    }
+    Ok(Some(format!("No files in fileset for app '{}'", app_name)))
+}
+
+fn is_symlink(path: &Path) -> bool {
+    path.symlink_metadata()
+        .is_ok_and(|meta| meta.file_type().is_symlink())
+}
+
+/// Check if a path matches any of the exclude patterns: as a glob over the
+/// whole path, as a directory the path lies in, or, for a pattern without
+/// a separator, as a glob over a single path component (`cache` excludes
+/// everything in a `cache` directory, but not `catalog`).
+fn matches_any_pattern(path: &Path, patterns: &[String], home: Option<&Path>) -> bool {
+    patterns.iter().filter(|pattern| !pattern.is_empty()).any(|pattern| {
+        let expanded_pattern = expand_tilde(pattern, home);
+        let Ok(glob_pattern) = glob::Pattern::new(&expanded_pattern) else {
+            return false;
+        };
+        if glob_pattern.matches_path(path) || path.starts_with(&expanded_pattern) {
+            return true;
+        }
+        !expanded_pattern.contains(['/', '\\'])
+            && path
+                .components()
+                .any(|component| glob_pattern.matches(&component.as_os_str().to_string_lossy()))
+    })
+}
+
+/// Expand tilde (`~`, `~/…`) to `home`, and `~user`, `~user/…` to that
+/// user's home directory. An unknown user is left literal with a warning.
+fn expand_tilde(path: &str, home: Option<&Path>) -> String {
+    if path == "~" {
+        if let Some(home) = home {
+            return home.to_string_lossy().to_string();
+        }
+        return path.to_string();
+    }
+    if let Some(rest) = path.strip_prefix("~/") {
+        if let Some(home) = home {
+            return home.join(rest).to_string_lossy().to_string();
+        }
+        return path.to_string();
+    }
+
+    if let Some(user_path) = path.strip_prefix('~') {
+        let (user, rest) = user_path.split_once('/').unwrap_or((user_path, ""));
+        if user.is_empty() {
+            return path.to_string();
+        }
+        match user_home_dir(user) {
+            Some(user_home) if rest.is_empty() => return user_home.to_string_lossy().to_string(),
+            Some(user_home) => return user_home.join(rest).to_string_lossy().to_string(),
+            None => log::warn!("Unknown user '~{}' in pattern '{}', leaving it as is", user, path),
         }
     }
     path.to_string()
 }
 
+/// Home directory of another user, from `/etc/passwd`.
+#[cfg(unix)]
+fn user_home_dir(user: &str) -> Option<PathBuf> {
+    let passwd = std::fs::read_to_string("/etc/passwd").ok()?;
+    home_from_passwd(&passwd, user)
+}
+
+/// `~user` has no equivalent on Windows.
+#[cfg(not(unix))]
+fn user_home_dir(_user: &str) -> Option<PathBuf> {
+    None
+}
+
+/// Look up `user`'s home directory (6th field) in passwd-format text.
+#[cfg_attr(not(unix), allow(dead_code))]
+fn home_from_passwd(passwd: &str, user: &str) -> Option<PathBuf> {
+    passwd
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.len() >= 6 && fields[0] == user)
+        .map(|fields| PathBuf::from(fields[5]))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -113,11 +387,38 @@ mod tests {
 
     #[test]
     fn test_expand_tilde() {
-        let expanded = expand_tilde("~/test/path");
+        let expanded = expand_tilde("~/test/path", home_dir().as_deref());
         assert!(expanded.contains("test/path"));
         assert!(!expanded.starts_with("~"));
     }
 
+    #[test]
+    fn test_expand_tilde_other_users() {
+        let home = Path::new("/home/me");
+        assert_eq!(expand_tilde("~", Some(home)), "/home/me");
+        assert_eq!(expand_tilde("~/.zshrc", Some(home)), "/home/me/.zshrc");
+        assert_eq!(expand_tilde("~", None), "~");
+
+        #[cfg(unix)]
+        {
+            let root_home = user_home_dir("root").expect("root is in /etc/passwd");
+            assert_eq!(
+                expand_tilde("~root/.bashrc", Some(home)),
+                root_home.join(".bashrc").to_string_lossy()
+            );
+            assert_eq!(expand_tilde("~root", Some(home)), root_home.to_string_lossy());
+        }
+        // Unknown users stay literal (with a warning)
+        assert_eq!(
+            expand_tilde("~nobody_such_user/.zshrc", Some(home)),
+            "~nobody_such_user/.zshrc"
+        );
+
+        let passwd = "# comment\nroot:x:0:0:root:/root:/bin/bash\nsvc:x:998:998::/srv/svc:/usr/sbin/nologin\n";
+        assert_eq!(home_from_passwd(passwd, "svc"), Some(PathBuf::from("/srv/svc")));
+        assert_eq!(home_from_passwd(passwd, "sv"), None);
+    }
+
     #[test]
     fn test_resolve_fileset_basic() {
         let config = AppConfig {
@@ -130,14 +431,236 @@ mod tests {
             exclude_linux: vec![],
             exclude_windows: vec![],
             no_merge: false,
+            redact: vec![],
+            comment_syntax: None,
+            match_hidden: None,
+            follow_symlinks: false,
+            merge_strategy: Default::default(),
+            line_endings: Default::default(),
+            sync_mode: Default::default(),
+            file_modes: Default::default(),
+            priority: vec![],
+            required: false,
             machines: Default::default(),
         };
 
         // This will return empty if ~/test/ doesn't exist, which is fine for a unit test
-        let result = resolve_fileset(&config, "machine1", "linux");
+        let result = resolve_fileset(&config, &[], &BTreeMap::new(), "machine1", "linux");
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_resolve_fileset_in_alternate_home() {
+        let new_home = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(new_home.path().join(".config/zed")).unwrap();
+        std::fs::write(new_home.path().join(".config/zed/settings.json"), "{}").unwrap();
+        std::fs::write(new_home.path().join(".config/zed/keymap.json"), "[]").unwrap();
+
+        let config = AppConfig {
+            include: vec!["~/.config/zed/*.json".to_string()],
+            exclude: vec!["~/.config/zed/keymap.json".to_string()],
+            ..Default::default()
+        };
+
+        let files =
+            resolve_fileset_in(&config, &[], &BTreeMap::new(), "m1", "linux", Some(new_home.path()))
+                .unwrap();
+        assert_eq!(files, vec![new_home.path().join(".config/zed/settings.json")]);
+    }
+
+    #[test]
+    fn test_driftersignore_next_to_the_configs_applies_after_excludes() {
+        let home = tempfile::tempdir().unwrap();
+        let dir = home.path().join(".config/app");
+        std::fs::create_dir_all(dir.join("cache")).unwrap();
+        std::fs::create_dir_all(dir.join("logs")).unwrap();
+        for name in ["settings.json", "cache/index", "logs/keep.log", "logs/run.log", "x.bak"] {
+            std::fs::write(dir.join(name), "x").unwrap();
+        }
+        std::fs::write(dir.join(IGNORE_FILE), "# generated files\ncache/\n*.log\n!keep.log\n")
+            .unwrap();
+
+        let config = AppConfig {
+            include: vec!["~/.config/app/**/*".to_string(), "~/.config/app/x.bak".to_string()],
+            exclude: vec!["*.bak".to_string()],
+            ..Default::default()
+        };
+        let files =
+            resolve_fileset_in(&config, &[], &BTreeMap::new(), "m1", "linux", Some(home.path()))
+                .unwrap();
+        let files: Vec<_> = files.into_iter().filter(|f| f.is_file()).collect();
+        assert_eq!(
+            files,
+            vec![dir.join(IGNORE_FILE), dir.join("logs/keep.log"), dir.join("settings.json")]
+        );
+
+        let base = |patterns: &[&str]| {
+            let patterns: Vec<String> = patterns.iter().map(|p| p.to_string()).collect();
+            common_base_dir(&patterns, Some(Path::new("/home/me")))
+        };
+        assert_eq!(
+            base(&["~/.config/app/*.json", "~/.config/app/themes/**"]),
+            Some(PathBuf::from("/home/me/.config/app"))
+        );
+        assert_eq!(base(&["~/.zshrc", "~/.zsh/*"]), Some(PathBuf::from("/home/me")));
+        assert_eq!(base(&["/etc/hosts", "~/.zshrc"]), None);
+    }
+
+    #[test]
+    fn test_global_ignore_drops_secrets_whatever_the_app_includes() {
+        let home = tempfile::tempdir().unwrap();
+        let dir = home.path().join(".config/app");
+        std::fs::create_dir_all(dir.join("certs")).unwrap();
+        for name in ["settings.json", "credentials.json", ".env", "certs/client.pem", "state.db"] {
+            std::fs::write(dir.join(name), "x").unwrap();
+        }
+
+        let config = AppConfig {
+            include: vec!["~/.config/app/**/*".to_string()],
+            ..Default::default()
+        };
+        let ignore = vec!["credentials.json".to_string(), "~/.config/app/*.db".to_string()];
+        let (files, ignored) =
+            resolve(&config, &ignore, &BTreeMap::new(), "m1", "linux", Some(home.path())).unwrap();
+        let files: Vec<_> = files.into_iter().filter(|f| f.is_file()).collect();
+        assert_eq!(files, vec![dir.join("settings.json")]);
+        assert_eq!(
+            ignored,
+            vec![
+                dir.join(".env"),
+                dir.join("certs/client.pem"),
+                dir.join("credentials.json"),
+                dir.join("state.db"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exclude_patterns_match_components_not_substrings() {
+        let home = Path::new("/home/me");
+        let catalog = Path::new("/home/me/.config/catalog/settings");
+        let log_file = Path::new("/home/me/.config/app/log");
+        let patterns = |p: &[&str]| p.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        // Regression: `log` used to exclude anything containing "log"
+        assert!(!matches_any_pattern(catalog, &patterns(&["log"]), Some(home)));
+        assert!(matches_any_pattern(log_file, &patterns(&["log"]), Some(home)));
+        assert!(matches_any_pattern(catalog, &patterns(&["*log*"]), Some(home)));
+
+        // Literal paths still match the file itself, and what's under a directory
+        assert!(matches_any_pattern(log_file, &patterns(&["~/.config/app/log"]), Some(home)));
+        assert!(matches_any_pattern(catalog, &patterns(&["~/.config/catalog"]), Some(home)));
+        assert!(!matches_any_pattern(catalog, &patterns(&["~/.config/cat"]), Some(home)));
+        assert!(!matches_any_pattern(catalog, &patterns(&[""]), Some(home)));
+    }
+
+    #[test]
+    fn test_match_hidden_controls_dotfiles_under_wildcards() {
+        let home = tempfile::tempdir().unwrap();
+        let dir = home.path().join(".config/app");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("config.toml"), "").unwrap();
+        std::fs::write(dir.join(".secrets"), "").unwrap();
+
+        let mut config = AppConfig {
+            include: vec!["~/.config/app/*".to_string()],
+            ..Default::default()
+        };
+        let groups = BTreeMap::new();
+        let resolve = |config: &AppConfig| {
+            resolve_fileset_in(config, &[], &groups, "m1", "linux", Some(home.path())).unwrap()
+        };
+
+        // Default: `*` matches dotfiles
+        assert_eq!(resolve(&config), vec![dir.join(".secrets"), dir.join("config.toml")]);
+        config.match_hidden = Some(true);
+        assert_eq!(resolve(&config).len(), 2);
+
+        config.match_hidden = Some(false);
+        assert_eq!(resolve(&config), vec![dir.join("config.toml")]);
+        // An explicit leading dot still matches
+        config.include.push("~/.config/app/.secrets".to_string());
+        assert_eq!(resolve(&config), vec![dir.join(".secrets"), dir.join("config.toml")]);
+    }
+
+    #[test]
+    fn test_group_overrides_apply_to_members_and_excludes_win() {
+        let home = tempfile::tempdir().unwrap();
+        for name in [".zshrc", ".zshrc.laptop", ".zshrc.server"] {
+            std::fs::write(home.path().join(name), "").unwrap();
+        }
+        let rules: crate::config::SyncRules = toml::from_str(
+            r#"
+[groups]
+laptops = ["air", "pro"]
+
+[apps.zsh]
+include = ["~/.zshrc"]
+
+[apps.zsh.machines.laptops]
+include = ["~/.zshrc.laptop"]
+
+[apps.zsh.machines.air]
+exclude = ["~/.zshrc.laptop"]
+
+[apps.zsh.machines.db]
+include = ["~/.zshrc.server"]
+"#,
+        )
+        .unwrap();
+        let resolve = |machine_id| {
+            let app = &rules.apps["zsh"];
+            resolve_fileset_in(app, &[], &rules.groups, machine_id, "linux", Some(home.path()))
+                .unwrap()
+        };
+        let file = |name| home.path().join(name);
+
+        assert_eq!(resolve("pro"), vec![file(".zshrc"), file(".zshrc.laptop")]);
+        // The machine's own exclude drops what its group includes
+        assert_eq!(resolve("air"), vec![file(".zshrc")]);
+        assert_eq!(resolve("db"), vec![file(".zshrc"), file(".zshrc.server")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlinks_skipped_unless_follow_symlinks() {
+        let home = tempfile::tempdir().unwrap();
+        let dir = home.path().join(".config/app");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(home.path().join("dotfiles-config"), "").unwrap();
+        std::fs::write(dir.join("keys.toml"), "").unwrap();
+        std::os::unix::fs::symlink(home.path().join("dotfiles-config"), dir.join("config"))
+            .unwrap();
+
+        let mut config = AppConfig {
+            include: vec!["~/.config/app/*".to_string()],
+            ..Default::default()
+        };
+        let groups = BTreeMap::new();
+        let resolve = |config: &AppConfig| {
+            resolve_fileset_in(config, &[], &groups, "m1", "linux", Some(home.path())).unwrap()
+        };
+
+        assert_eq!(resolve(&config), vec![dir.join("keys.toml")]);
+        config.follow_symlinks = true;
+        assert_eq!(resolve(&config), vec![dir.join("config"), dir.join("keys.toml")]);
+    }
+
+    #[test]
+    fn test_check_fileset_required_errors_optional_warns() {
+        let mut config = AppConfig::default();
+        assert!(check_fileset("zsh", &config, &[PathBuf::from("/x/.zshrc")])
+            .unwrap()
+            .is_none());
+
+        let warning = check_fileset("zsh", &config, &[]).unwrap();
+        assert!(warning.unwrap().contains("zsh"));
+
+        config.required = true;
+        let err = check_fileset("zsh", &config, &[]).unwrap_err();
+        assert!(err.to_string().contains("Required app 'zsh'"));
+    }
+
     #[test]
     fn test_resolve_fileset_os_specific() {
         let config = AppConfig {
@@ -150,10 +673,20 @@ mod tests {
             exclude_linux: vec![],
             exclude_windows: vec![],
             no_merge: false,
+            redact: vec![],
+            comment_syntax: None,
+            match_hidden: None,
+            follow_symlinks: false,
+            merge_strategy: Default::default(),
+            line_endings: Default::default(),
+            sync_mode: Default::default(),
+            file_modes: Default::default(),
+            priority: vec![],
+            required: false,
             machines: Default::default(),
         };
 
-        let result = resolve_fileset(&config, "machine1", "macos").unwrap();
+        let result = resolve_fileset(&config, &[], &BTreeMap::new(), "machine1", "macos").unwrap();
         // Results will be empty if files don't exist, but no errors
         assert!(result.is_empty() || result.iter().any(|p| p.to_str().unwrap().contains("mac-only")));
     }
@@ -0,0 +1,190 @@
+use crate::error::{DriftersError, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// What was at a path before or after a pull changed it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileState {
+    Missing,
+    /// A regular file; the SHA-256 of its content
+    File(String),
+    /// A symlink and its target
+    Link(PathBuf),
+}
+
+impl FileState {
+    /// The current state of `path`. Symlinks are not followed.
+    pub fn of(path: &Path) -> Result<Self> {
+        match fs::symlink_metadata(path) {
+            Ok(meta) if meta.file_type().is_symlink() => Ok(Self::Link(fs::read_link(path)?)),
+            Ok(_) => Ok(Self::File(content_hash(&fs::read(path)?))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::Missing),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// One local file changed by the journaled pull.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub path: PathBuf,
+    pub before: FileState,
+    pub after: FileState,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct JournalFile {
+    taken: Option<DateTime<Utc>>,
+    #[serde(default, rename = "file")]
+    entries: Vec<JournalEntry>,
+}
+
+/// The local files the most recent pull changed, kept in
+/// `~/.config/drifters/undo/` so `drifters undo` can put them back.
+///
+/// `journal.toml` lists each path with its state before and after the
+/// pull; the content a file had before is saved next to it, named by its
+/// hash. A run replaces the previous run's journal when it changes its
+/// first file, so a pull that changes nothing keeps the last one undoable.
+#[derive(Debug)]
+pub struct UndoJournal {
+    dir: PathBuf,
+    file: JournalFile,
+    started: bool,
+}
+
+impl UndoJournal {
+    /// `~/.config/drifters/undo`
+    pub fn default_dir() -> Result<PathBuf> {
+        let home = dirs::home_dir()
+            .ok_or_else(|| DriftersError::Config("Could not find home directory".to_string()))?;
+        Ok(home.join(".config").join("drifters").join("undo"))
+    }
+
+    /// A new journal in `dir`. Nothing is written until the first
+    /// [`record`](Self::record).
+    pub fn start(dir: PathBuf) -> Self {
+        Self { dir, file: JournalFile::default(), started: false }
+    }
+
+    /// The journal last written to `dir`, if there is one.
+    pub fn load(dir: PathBuf) -> Result<Option<Self>> {
+        let path = dir.join("journal.toml");
+        if !path.exists() {
+            return Ok(None);
+        }
+        let file = toml::from_str(&fs::read_to_string(&path)?)?;
+        Ok(Some(Self { dir, file, started: true }))
+    }
+
+    pub fn taken(&self) -> Option<DateTime<Utc>> {
+        self.file.taken
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.file.entries
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.file.entries.is_empty()
+    }
+
+    /// Run `change` on `path`, recording what was there before and after.
+    /// The journal is saved after every change, so a pull that fails midway
+    /// can still be undone up to that point.
+    pub fn record(&mut self, path: &Path, change: impl FnOnce() -> Result<()>) -> Result<()> {
+        let before = FileState::of(path)?;
+        if !self.started {
+            if self.dir.exists() {
+                fs::remove_dir_all(&self.dir)?;
+            }
+            fs::create_dir_all(&self.dir)?;
+            self.file.taken = Some(Utc::now());
+            self.started = true;
+        }
+        if let FileState::File(hash) = &before {
+            let saved = self.dir.join(hash);
+            if !saved.exists() {
+                fs::copy(path, &saved)?;
+            }
+        }
+
+        let result = change();
+        let after = FileState::of(path)?;
+        match self.file.entries.iter_mut().find(|e| e.path == path) {
+            Some(entry) => entry.after = after,
+            None => {
+                self.file.entries.push(JournalEntry { path: path.to_path_buf(), before, after })
+            }
+        }
+        self.save()?;
+        result
+    }
+
+    /// The content a file had before the pull, by its [`FileState::File`] hash.
+    pub fn saved_content(&self, hash: &str) -> Result<Vec<u8>> {
+        Ok(fs::read(self.dir.join(hash))?)
+    }
+
+    /// Delete the journal and the content saved with it.
+    pub fn clear(self) -> Result<()> {
+        if self.dir.exists() {
+            fs::remove_dir_all(&self.dir)?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<()> {
+        let contents = toml::to_string_pretty(&self.file)?;
+        crate::sync::write_atomic(&self.dir.join("journal.toml"), contents)?;
+        Ok(())
+    }
+}
+
+fn content_hash(content: &[u8]) -> String {
+    hex::encode(Sha256::digest(content))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_records_changes_and_replaces_previous_run() {
+        let home = tempfile::tempdir().unwrap();
+        let dir = home.path().join("undo");
+        let edited = home.path().join("settings.json");
+        let created = home.path().join("new.conf");
+        fs::write(&edited, "{\"a\": 1}").unwrap();
+
+        let mut journal = UndoJournal::start(dir.clone());
+        journal.record(&edited, || Ok(fs::write(&edited, "{\"a\": 2}")?)).unwrap();
+        journal.record(&created, || Ok(fs::write(&created, "x")?)).unwrap();
+
+        let loaded = UndoJournal::load(dir.clone()).unwrap().unwrap();
+        assert!(loaded.taken().is_some());
+        let [edit, create] = loaded.entries() else { panic!("expected two entries") };
+        let FileState::File(hash) = &edit.before else { panic!("expected a file") };
+        assert_eq!(loaded.saved_content(hash).unwrap(), b"{\"a\": 1}");
+        assert_eq!(edit.after, FileState::of(&edited).unwrap());
+        assert_eq!(create.before, FileState::Missing);
+
+        // A run that changes nothing leaves the journal alone; one that
+        // does replaces it
+        UndoJournal::start(dir.clone());
+        assert_eq!(UndoJournal::load(dir.clone()).unwrap().unwrap().entries().len(), 2);
+        let mut next = UndoJournal::start(dir.clone());
+        next.record(&created, || Ok(fs::remove_file(&created)?)).unwrap();
+        let loaded = UndoJournal::load(dir.clone()).unwrap().unwrap();
+        assert_eq!(loaded.entries().len(), 1);
+        assert_eq!(loaded.entries()[0].after, FileState::Missing);
+        assert!(!dir.join(hash).exists());
+
+        loaded.clear().unwrap();
+        assert!(UndoJournal::load(dir).unwrap().is_none());
+    }
+}
@@ -1,6 +1,8 @@
 use crate::error::{DriftersError, Result};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LocalConfig {
@@ -12,9 +14,10 @@ pub struct LocalConfig {
     pub repo_path: PathBuf,
 
     // Update settings
-    /// When to check for updates: "never", "always", "daily". Default "always".
-    #[serde(default = "default_self_update_frequency")]
-    pub self_update_frequency: String,
+    /// When to check for updates: "never", "always", "daily", "weekly", or
+    /// an interval such as "12h" or "3d". Default "always".
+    #[serde(default)]
+    pub self_update_frequency: UpdateFrequency,
 
     /// Last update check timestamp (Unix epoch seconds).
     ///
@@ -25,15 +28,231 @@ pub struct LocalConfig {
     #[serde(deserialize_with = "deserialize_timestamp", default)]
     pub last_update_check: Option<u64>,
 
+    /// Where `self-update` reads expected SHA-256 digests from instead of the
+    /// release's own checksum files, e.g. a `sha256.sum` you host elsewhere.
+    /// `{version}` is replaced by the release version.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub self_update_checksum_url: Option<String>,
+
     /// Preferred editor command for opening files (e.g. "code", "zed", "vim").
     /// Falls back to $EDITOR env var, then the OS default app.
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(alias = "preferred_editor")]
     pub editor: Option<String>,
+
+    /// Use ASCII instead of emoji/arrows in output (same as `--plain`).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub plain_output: bool,
+
+    /// Newly matched files the user declined at the first-time push prompt.
+    /// They are not offered again unless `push-app --include-untracked`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub declined_files: Vec<String>,
+
+    /// Local paths this machine has pushed or pulled, per app. Only these
+    /// are treated as deleted when they go missing, and only these are
+    /// offered for deletion when another machine deletes them.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub synced_files: BTreeMap<String, BTreeSet<String>>,
+
+    /// How the working clone is brought up to date: "rebase" (default),
+    /// "merge", or "ff-only".
+    #[serde(default, skip_serializing_if = "PullStrategy::is_default")]
+    pub pull_strategy: PullStrategy,
+
+    /// Commit of main each app was last pulled from (app name → hash), so
+    /// `diff-app --since-last-sync` can show what changed since.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub last_pulled: BTreeMap<String, String>,
+
+    /// Back up local files before pull overwrites them (same as
+    /// `pull-app --backup`).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub backup_on_pull: bool,
+
+    /// Whether pull and merge ask before applying each change: "prompt"
+    /// (default), "diff-then-apply", or "silent". `pull-app --yolo` is
+    /// always silent.
+    #[serde(default, skip_serializing_if = "AutoApply::is_default")]
+    pub auto_apply: AutoApply,
+
+    /// Append `Drifters-Version`/`Drifters-Machine` trailers to the commits
+    /// drifters makes. Default true.
+    #[serde(default = "default_commit_trailers")]
+    pub commit_trailers: bool,
+
+    /// Log each push (app, file count, time, version) to
+    /// `.drifters/activity.toml` on main, for `drifters report`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub record_activity: bool,
+
+    /// Shared branch that machine branches are merged into and pulled from.
+    /// Default "main".
+    #[serde(default = "default_branch", skip_serializing_if = "is_default_branch")]
+    pub branch: String,
+
+    /// API base URL of a self-hosted GitHub or GitLab instance serving
+    /// presets and releases (e.g. "https://git.example.com/api/v4").
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forge_api_url: Option<String>,
+
+    /// Clone the repo with its full history instead of just the branch tips.
+    /// History is fetched on demand anyway; this is an escape hatch.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub full_clone: bool,
+
+    /// Keep the working clone between commands, fetching only when the last
+    /// fetch is older than this many seconds. 0 (the default) clones afresh
+    /// for every command.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub repo_cache_ttl_secs: u64,
+
+    /// Sign every commit drifters makes with git's configured signing key
+    /// (`user.signingkey`), for repos that require signed commits.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub sign_commits: bool,
+
+    /// Name on the commits drifters makes. Takes precedence over git's
+    /// `user.name`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_author_name: Option<String>,
+
+    /// Email on the commits drifters makes. Takes precedence over git's
+    /// `user.email`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git_author_email: Option<String>,
+}
+
+/// Flags passed to `git pull` when refreshing an existing clone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PullStrategy {
+    /// `git pull --rebase`
+    #[default]
+    Rebase,
+    /// `git pull --no-rebase` (creates a merge commit on divergence)
+    Merge,
+    /// `git pull --ff-only` (divergence is an error to resolve by hand)
+    FfOnly,
+}
+
+impl PullStrategy {
+    /// Full argument list for `git pull` under this strategy.
+    pub fn git_args(self) -> &'static [&'static str] {
+        match self {
+            PullStrategy::Rebase => &["pull", "--rebase"],
+            PullStrategy::Merge => &["pull", "--no-rebase"],
+            PullStrategy::FfOnly => &["pull", "--ff-only"],
+        }
+    }
+
+    fn is_default(&self) -> bool {
+        *self == PullStrategy::default()
+    }
+}
+
+/// How `pull-app` and `merge-app` go about changes they are ready to apply.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AutoApply {
+    /// Show each diff and ask before applying it
+    #[default]
+    Prompt,
+    /// Show each diff, then apply it without asking
+    DiffThenApply,
+    /// Apply without showing diffs or asking
+    Silent,
+}
+
+impl AutoApply {
+    /// Whether changes are shown before they are applied.
+    pub fn shows_diff(self) -> bool {
+        self != AutoApply::Silent
+    }
+
+    /// Whether each change waits for a yes.
+    pub fn asks(self) -> bool {
+        self == AutoApply::Prompt
+    }
+
+    fn is_default(&self) -> bool {
+        *self == AutoApply::default()
+    }
+}
+
+/// How often `maybe_check_for_updates` looks for a new release. Written to
+/// drifters.toml as the string it was parsed from ("weekly", "12h", ...).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum UpdateFrequency {
+    Never,
+    /// On every command
+    #[default]
+    Always,
+    /// At most once per interval, tracked with `last_update_check`
+    Every(Duration),
 }
 
-fn default_self_update_frequency() -> String {
-    "always".to_string()
+const DAY: u64 = 24 * 60 * 60;
+const WEEK: u64 = 7 * DAY;
+
+impl TryFrom<String> for UpdateFrequency {
+    type Error = String;
+
+    fn try_from(value: String) -> std::result::Result<Self, String> {
+        let every = |secs: u64| Ok(UpdateFrequency::Every(Duration::from_secs(secs)));
+        let text = value.trim();
+        match text {
+            "never" => return Ok(UpdateFrequency::Never),
+            "always" => return Ok(UpdateFrequency::Always),
+            "daily" => return every(DAY),
+            "weekly" => return every(WEEK),
+            _ => {}
+        }
+        let interval = text
+            .strip_suffix('h')
+            .map(|count| (count, 60 * 60))
+            .or_else(|| text.strip_suffix('d').map(|count| (count, DAY)));
+        match interval.map(|(count, unit)| (count.parse::<u64>(), unit)) {
+            Some((Ok(count), unit)) if count > 0 => every(count.saturating_mul(unit)),
+            _ => Err(format!(
+                "unknown self_update_frequency '{}': use \"never\", \"always\", \"daily\", \
+                 \"weekly\", or an interval in hours or days such as \"12h\" or \"3d\"",
+                value
+            )),
+        }
+    }
+}
+
+impl From<UpdateFrequency> for String {
+    fn from(frequency: UpdateFrequency) -> String {
+        match frequency {
+            UpdateFrequency::Never => "never".to_string(),
+            UpdateFrequency::Always => "always".to_string(),
+            UpdateFrequency::Every(interval) => match interval.as_secs() {
+                DAY => "daily".to_string(),
+                WEEK => "weekly".to_string(),
+                secs if secs % DAY == 0 => format!("{}d", secs / DAY),
+                secs => format!("{}h", secs / (60 * 60)),
+            },
+        }
+    }
+}
+
+fn default_commit_trailers() -> bool {
+    true
+}
+
+fn default_branch() -> String {
+    "main".to_string()
+}
+
+fn is_default_branch(branch: &String) -> bool {
+    *branch == default_branch()
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
 }
 
 /// Deserialize `last_update_check` from either an integer (`1708000000`) or a
@@ -65,9 +284,26 @@ impl LocalConfig {
             machine_id,
             repo_url,
             repo_path: Self::get_temp_repo_path().unwrap_or_default(),
-            self_update_frequency: default_self_update_frequency(),
+            self_update_frequency: UpdateFrequency::default(),
             last_update_check: None,
+            self_update_checksum_url: None,
             editor: None,
+            plain_output: false,
+            declined_files: Vec::new(),
+            synced_files: BTreeMap::new(),
+            pull_strategy: PullStrategy::default(),
+            last_pulled: BTreeMap::new(),
+            backup_on_pull: false,
+            auto_apply: AutoApply::default(),
+            commit_trailers: default_commit_trailers(),
+            record_activity: false,
+            branch: default_branch(),
+            forge_api_url: None,
+            full_clone: false,
+            repo_cache_ttl_secs: 0,
+            sign_commits: false,
+            git_author_name: None,
+            git_author_email: None,
         }
     }
 
@@ -95,7 +331,7 @@ impl LocalConfig {
         }
 
         let contents = toml::to_string_pretty(self)?;
-        std::fs::write(&config_path, contents)?;
+        crate::sync::write_atomic(&config_path, contents)?;
         Ok(())
     }
 
@@ -118,3 +354,82 @@ impl LocalConfig {
             .unwrap_or_else(|| "unknown".to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pull_strategy_git_args() {
+        assert_eq!(PullStrategy::default(), PullStrategy::Rebase);
+        assert_eq!(PullStrategy::Rebase.git_args(), ["pull", "--rebase"]);
+        assert_eq!(PullStrategy::Merge.git_args(), ["pull", "--no-rebase"]);
+        assert_eq!(PullStrategy::FfOnly.git_args(), ["pull", "--ff-only"]);
+
+        let config: LocalConfig = toml::from_str(
+            "machine_id = \"m1\"\nrepo_url = \"u\"\npull_strategy = \"ff-only\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.pull_strategy, PullStrategy::FfOnly);
+
+        // The default is not written back out
+        let config = LocalConfig::new("m1".to_string(), "u".to_string());
+        assert!(!toml::to_string(&config).unwrap().contains("pull_strategy"));
+    }
+
+    #[test]
+    fn test_auto_apply_parses_and_defaults_to_prompt() {
+        let config: LocalConfig = toml::from_str(
+            "machine_id = \"m1\"\nrepo_url = \"u\"\nauto_apply = \"diff-then-apply\"\n",
+        )
+        .unwrap();
+        assert_eq!(config.auto_apply, AutoApply::DiffThenApply);
+        assert!(config.auto_apply.shows_diff() && !config.auto_apply.asks());
+        assert!(!AutoApply::Silent.shows_diff());
+
+        let config = LocalConfig::new("m1".to_string(), "u".to_string());
+        assert_eq!(config.auto_apply, AutoApply::Prompt);
+        assert!(!toml::to_string(&config).unwrap().contains("auto_apply"));
+    }
+
+    #[test]
+    fn test_update_frequency_parses_intervals_and_round_trips() {
+        let parse = |s: &str| UpdateFrequency::try_from(s.to_string());
+        assert_eq!(parse("never"), Ok(UpdateFrequency::Never));
+        assert_eq!(parse("always"), Ok(UpdateFrequency::Always));
+        for (text, secs) in [("daily", DAY), ("weekly", WEEK), ("12h", 43200), ("3d", 3 * DAY)] {
+            let frequency = parse(text).unwrap();
+            assert_eq!(frequency, UpdateFrequency::Every(Duration::from_secs(secs)));
+            assert_eq!(String::from(frequency), text);
+        }
+        assert_eq!(String::from(parse("24h").unwrap()), "daily");
+        for bad in ["hourly", "0d", "d", "-1h", "5m", ""] {
+            assert!(parse(bad).is_err(), "{}", bad);
+        }
+
+        let config: LocalConfig =
+            toml::from_str("machine_id = \"m1\"\nrepo_url = \"u\"\n").unwrap();
+        assert_eq!(config.self_update_frequency, UpdateFrequency::Always);
+        let err = toml::from_str::<LocalConfig>(
+            "machine_id = \"m1\"\nrepo_url = \"u\"\nself_update_frequency = \"fortnightly\"\n",
+        )
+        .unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("unknown self_update_frequency 'fortnightly'"), "{}", message);
+    }
+
+    #[test]
+    fn test_branch_defaults_to_main_and_round_trips() {
+        let config: LocalConfig =
+            toml::from_str("machine_id = \"m1\"\nrepo_url = \"u\"\n").unwrap();
+        assert_eq!(config.branch, "main");
+        assert!(!toml::to_string(&config).unwrap().contains("branch"));
+
+        let mut config = LocalConfig::new("m1".to_string(), "u".to_string());
+        config.branch = "drifters".to_string();
+        let saved = toml::to_string(&config).unwrap();
+        assert!(saved.contains("branch = \"drifters\""), "{}", saved);
+        let loaded: LocalConfig = toml::from_str(&saved).unwrap();
+        assert_eq!(loaded.branch, "drifters");
+    }
+}
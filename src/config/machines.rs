@@ -1,12 +1,20 @@
-use crate::error::Result;
+use crate::error::{DriftersError, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// `last_sync` is refreshed at most this often by [`MachineRegistry::record_heartbeat`].
+pub const HEARTBEAT_INTERVAL_MINUTES: i64 = 60;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MachineRegistry {
     pub machines: HashMap<String, MachineInfo>,
+
+    /// Former machine IDs and what `rename-machine` renamed them to, so a
+    /// renamed machine can pick up its new ID the next time it runs.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub renamed: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,10 +29,11 @@ impl MachineRegistry {
     pub fn new() -> Self {
         Self {
             machines: HashMap::new(),
+            renamed: BTreeMap::new(),
         }
     }
 
-    pub fn load(repo_path: &PathBuf) -> Result<Self> {
+    pub fn load(repo_path: &Path) -> Result<Self> {
         let machines_path = repo_path.join(".drifters").join("machines.toml");
 
         if !machines_path.exists() {
@@ -36,13 +45,13 @@ impl MachineRegistry {
         Ok(registry)
     }
 
-    pub fn save(&self, repo_path: &PathBuf) -> Result<()> {
+    pub fn save(&self, repo_path: &Path) -> Result<()> {
         let drifters_dir = repo_path.join(".drifters");
         std::fs::create_dir_all(&drifters_dir)?;
 
         let machines_path = drifters_dir.join("machines.toml");
         let contents = toml::to_string_pretty(self)?;
-        std::fs::write(&machines_path, contents)?;
+        crate::sync::write_atomic(&machines_path, contents)?;
         Ok(())
     }
 
@@ -58,13 +67,223 @@ impl MachineRegistry {
         );
     }
 
+    /// Refresh a registered machine's `last_sync` and OS.
+    ///
+    /// Returns `true` if the registry changed and is worth committing: the OS
+    /// differs, or `last_sync` is older than [`HEARTBEAT_INTERVAL_MINUTES`].
+    /// More frequent activity is not recorded, so repeated pushes don't
+    /// churn `machines.toml`.
+    pub fn record_heartbeat(&mut self, machine_id: &str, os: &str, now: DateTime<Utc>) -> bool {
+        let Some(info) = self.machines.get_mut(machine_id) else {
+            return false;
+        };
+        let fresh = info.last_sync.is_some_and(|last| {
+            now.signed_duration_since(last) < chrono::Duration::minutes(HEARTBEAT_INTERVAL_MINUTES)
+        });
+        if fresh && info.os == os {
+            return false;
+        }
+        info.os = os.to_string();
+        info.last_sync = Some(now);
+        true
+    }
+
+    /// Move `old_id`'s entry to `new_id`, recording the rename.
+    pub fn rename_machine(&mut self, old_id: &str, new_id: &str) -> Option<&mut MachineInfo> {
+        let info = self.machines.remove(old_id)?;
+        self.renamed.remove(new_id);
+        self.renamed.insert(old_id.to_string(), new_id.to_string());
+        Some(self.machines.entry(new_id.to_string()).or_insert(info))
+    }
+
+    /// Drop `machine_id`'s entry, and the renames that led to it.
+    pub fn remove_machine(&mut self, machine_id: &str) -> Option<MachineInfo> {
+        let info = self.machines.remove(machine_id)?;
+        self.forget_renames_to(machine_id);
+        Some(info)
+    }
+
+    fn forget_renames_to(&mut self, id: &str) {
+        let former: Vec<String> = self
+            .renamed
+            .iter()
+            .filter(|(_, new)| *new == id)
+            .map(|(old, _)| old.clone())
+            .collect();
+        for old in former {
+            self.renamed.remove(&old);
+            self.forget_renames_to(&old);
+        }
+    }
+
+    /// The registered ID that `machine_id` was renamed to, following renames
+    /// of renames. `None` if it was never renamed or the machine has since
+    /// been removed.
+    pub fn renamed_to(&self, machine_id: &str) -> Option<&str> {
+        let mut id = self.renamed.get(machine_id)?.as_str();
+        // Each step follows one rename, so a cycle can't loop forever
+        for _ in 0..self.renamed.len() {
+            if self.machines.contains_key(id) {
+                return Some(id);
+            }
+            id = self.renamed.get(id)?;
+        }
+        None
+    }
+
+    /// The machine branches in `branches` (`machines/<id>`) that no
+    /// registered machine owns, e.g. left behind by an older drifters that
+    /// removed machines without deleting their branches. A registered
+    /// machine that hasn't pushed yet simply has no branch, so it is never
+    /// reported.
+    pub fn orphaned_branches<'a>(&self, branches: &'a [String]) -> Vec<&'a str> {
+        let owned: Vec<String> = self
+            .machines
+            .iter()
+            .map(|(id, info)| info.branch.clone().unwrap_or_else(|| format!("machines/{}", id)))
+            .collect();
+        branches
+            .iter()
+            .filter(|b| b.starts_with("machines/") && !owned.contains(b))
+            .map(String::as_str)
+            .collect()
+    }
+
     pub fn detect_os() -> String {
         std::env::consts::OS.to_string()
     }
 }
 
+/// Trim and validate a machine ID, returning the normalized ID.
+///
+/// IDs end up in branch names (`machines/<id>`) and TOML keys, so only ASCII
+/// letters, digits, `-`, `_` and `.` are allowed, and `.` may not start or
+/// end the ID or appear twice in a row.
+pub fn validate_machine_id(raw: &str) -> Result<String> {
+    let id = raw.trim();
+    let invalid = |why: &str| {
+        Err(DriftersError::Config(format!(
+            "Invalid machine ID '{}': {}",
+            id.escape_debug(),
+            why
+        )))
+    };
+
+    if id.is_empty() {
+        return invalid("it cannot be empty");
+    }
+    if let Some(c) = id
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+    {
+        return invalid(&format!(
+            "'{}' is not allowed (use letters, digits, '-', '_' or '.')",
+            c.escape_debug()
+        ));
+    }
+    if id.starts_with('.') || id.ends_with('.') {
+        return invalid("it cannot start or end with '.'");
+    }
+    if id.contains("..") {
+        return invalid("it cannot contain '..'");
+    }
+
+    Ok(id.to_string())
+}
+
 impl Default for MachineRegistry {
     fn default() -> Self {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_machine_id_accepts_safe_ids() {
+        assert_eq!(validate_machine_id("laptop").unwrap(), "laptop");
+        assert_eq!(validate_machine_id("  work-mbp_2 \n").unwrap(), "work-mbp_2");
+        assert_eq!(validate_machine_id("host.local").unwrap(), "host.local");
+    }
+
+    #[test]
+    fn test_validate_machine_id_rejects_unsafe_ids() {
+        let rejected = [
+            "", "   ", "my laptop", "a/b", "a\\b", ".hidden", "host.", "a..b", "tab\there",
+            "bell\u{7}", "caf\u{e9}",
+        ];
+        for bad in rejected {
+            assert!(validate_machine_id(bad).is_err(), "{:?} should be rejected", bad);
+        }
+    }
+
+    #[test]
+    fn test_heartbeat_only_changes_registry_when_stale() {
+        let mut registry = MachineRegistry::new();
+        registry.register_machine("laptop".to_string(), "linux".to_string());
+        let registered = registry.machines["laptop"].last_sync.unwrap();
+
+        // A push right after the last recorded sync changes nothing, so there
+        // is nothing to commit
+        let soon = registered + chrono::Duration::minutes(5);
+        assert!(!registry.record_heartbeat("laptop", "linux", soon));
+        assert_eq!(registry.machines["laptop"].last_sync, Some(registered));
+
+        let later = registered + chrono::Duration::minutes(HEARTBEAT_INTERVAL_MINUTES + 1);
+        assert!(registry.record_heartbeat("laptop", "linux", later));
+        assert_eq!(registry.machines["laptop"].last_sync, Some(later));
+
+        // An OS change is recorded immediately
+        assert!(registry.record_heartbeat("laptop", "macos", later));
+        assert_eq!(registry.machines["laptop"].os, "macos");
+
+        assert!(!registry.record_heartbeat("unknown", "linux", later));
+    }
+
+    #[test]
+    fn test_renames_are_followed_until_the_machine_is_removed() {
+        let mut registry = MachineRegistry::new();
+        registry.register_machine("laptop".to_string(), "linux".to_string());
+        registry.register_machine("desktop".to_string(), "linux".to_string());
+        assert_eq!(registry.renamed_to("laptop"), None);
+
+        registry.rename_machine("laptop", "work-laptop").unwrap();
+        registry.rename_machine("work-laptop", "old-laptop").unwrap();
+        assert_eq!(registry.renamed_to("laptop"), Some("old-laptop"));
+        assert_eq!(registry.renamed_to("work-laptop"), Some("old-laptop"));
+        assert_eq!(registry.renamed_to("old-laptop"), None);
+        assert!(registry.rename_machine("nope", "x").is_none());
+
+        // Renaming back to a former ID makes it current again
+        registry.rename_machine("old-laptop", "laptop").unwrap();
+        assert_eq!(registry.renamed_to("work-laptop"), Some("laptop"));
+        assert!(!registry.renamed.contains_key("laptop"));
+
+        let saved = toml::to_string_pretty(&registry).unwrap();
+        let loaded: MachineRegistry = toml::from_str(&saved).unwrap();
+        assert_eq!(loaded.renamed, registry.renamed);
+
+        registry.remove_machine("laptop").unwrap();
+        assert_eq!(registry.renamed_to("work-laptop"), None);
+        assert!(registry.renamed.is_empty(), "{:?}", registry.renamed);
+    }
+
+    #[test]
+    fn test_orphaned_branches_are_those_no_machine_owns() {
+        let mut registry = MachineRegistry::new();
+        registry.register_machine("laptop".to_string(), "macos".to_string());
+        registry.register_machine("fresh".to_string(), "linux".to_string());
+        registry.register_machine("old".to_string(), "linux".to_string());
+        registry.rename_machine("old", "new").unwrap().branch = Some("machines/new".to_string());
+
+        let branches: Vec<String> = ["machines/laptop", "machines/old", "machines/gone"]
+            .iter()
+            .map(|b| b.to_string())
+            .collect();
+        // 'fresh' never pushed and 'new' owns its renamed branch; neither
+        // is an orphan, while the leftover 'old' branch is
+        assert_eq!(registry.orphaned_branches(&branches), ["machines/old", "machines/gone"]);
+    }
+}
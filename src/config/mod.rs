@@ -1,9 +1,19 @@
+pub mod activity;
 pub mod fileset;
+pub mod journal;
 pub mod local;
 pub mod machines;
 pub mod sync_rules;
 
-pub use fileset::resolve_fileset;
-pub use local::LocalConfig;
-pub use machines::MachineRegistry;
-pub use sync_rules::{AppConfig, MachineOverride, SyncRules};
+pub use activity::{ActivityEntry, ActivityLog, MAX_ACTIVITY_ENTRIES};
+pub use fileset::{
+    check_fileset, home_dir, resolve_fileset, resolve_fileset_with_ignored, set_home_override,
+    DEFAULT_IGNORE,
+};
+pub use journal::{FileState, JournalEntry, UndoJournal};
+pub use local::{AutoApply, LocalConfig, PullStrategy, UpdateFrequency};
+pub use machines::{validate_machine_id, MachineRegistry};
+pub use sync_rules::{
+    check_globs, AppConfig, LineEndings, MachineOverride, MergeStrategy, SyncMode, SyncRules,
+    Warning,
+};
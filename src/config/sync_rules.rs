@@ -1,10 +1,25 @@
-use crate::error::Result;
+use crate::config::MachineRegistry;
+use crate::error::{DriftersError, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::Path;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SyncRules {
+    /// Patterns no app ever syncs, applied after every app's own patterns
+    /// (on top of the built-in `DEFAULT_IGNORE`). Without a `/` a pattern
+    /// matches file names in any directory.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore: Vec<String>,
+
+    /// Named sets of machine IDs. An app's `machines` table may be keyed by
+    /// a group name as well as a machine ID; a group's override applies to
+    /// every machine listed here.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, Vec<String>>,
+
     pub apps: HashMap<String, AppConfig>,
 }
 
@@ -46,7 +61,62 @@ pub struct AppConfig {
     #[serde(default)]
     pub no_merge: bool,
 
-    /// Machine-specific overrides
+    /// Regexes for single lines to keep local (e.g. a token in `.gitconfig`).
+    /// Matching lines are pushed as a placeholder and keep their local value
+    /// on pull.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub redact: Vec<String>,
+
+    /// Comment syntax for section tags in this app's files, overriding
+    /// detection by extension: a line prefix (`"--"`) or an opening and
+    /// closing delimiter separated by a space (`"/* */"`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment_syntax: Option<String>,
+
+    /// Whether wildcards (`*`, `?`, `[..]`) match names starting with a dot,
+    /// so `~/.config/app/*` picks up `.hidden` files too. Default true;
+    /// set false to only match dotfiles named explicitly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub match_hidden: Option<bool>,
+
+    /// Sync symlinks as links: push records where each one points and pull
+    /// recreates the link. Without this, symlinks are skipped with a
+    /// warning rather than synced as the content they point to.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub follow_symlinks: bool,
+
+    /// How `merge-app` combines a machine's files with main's.
+    #[serde(default, skip_serializing_if = "MergeStrategy::is_default")]
+    pub merge_strategy: MergeStrategy,
+
+    /// Line endings of the app's text files; by default they are left as
+    /// they are.
+    #[serde(default, skip_serializing_if = "LineEndings::is_default")]
+    pub line_endings: LineEndings,
+
+    /// Whether `drifters::exclude` sections stay on each machine
+    /// ("markers", the default) or files are synced whole ("full").
+    #[serde(default, skip_serializing_if = "SyncMode::is_default")]
+    pub sync_mode: SyncMode,
+
+    /// `sync_mode` per file, keyed by file name or by a glob over file
+    /// names such as `"*.local"`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub file_modes: BTreeMap<String, SyncMode>,
+
+    /// Machine IDs, highest priority first. When the JSON merge strategy
+    /// finds both sides committed at the same second, the side from the
+    /// higher-ranked machine wins instead of the machine being merged.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub priority: Vec<String>,
+
+    /// If true, an empty fileset on a machine is an error (non-zero exit in
+    /// status/push/pull) instead of a warning.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub required: bool,
+
+    /// Overrides keyed by machine ID or by the name of a group in
+    /// `SyncRules::groups`
     #[serde(default)]
     pub machines: HashMap<String, MachineOverride>,
 }
@@ -64,14 +134,133 @@ pub struct MachineOverride {
     pub singular: bool,
 }
 
+/// Something in the rules that is valid but probably not what was meant,
+/// such as an app that includes nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    /// Where in the rules, e.g. `zed.include-linux` or `groups.laptops`
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// How `merge-app` brings an app's files from a machine branch into main.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MergeStrategy {
+    /// Take the machine's version of each file (git merges whole branches)
+    #[default]
+    Text,
+    /// Deep-merge `.json` files key by key; on conflicting values the side
+    /// with the newer commit wins
+    Json,
+    /// Line-level three-way merge against the last version both sides
+    /// shared; overlapping edits are written with conflict markers
+    ThreeWay,
+}
+
+impl MergeStrategy {
+    fn is_default(&self) -> bool {
+        *self == MergeStrategy::default()
+    }
+}
+
+/// How an app's text files have their line endings converted. Anything
+/// but `preserve` stores them with LF in the repo, so a file edited on
+/// Windows doesn't differ in every line from the same file on macOS.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LineEndings {
+    /// Push and pull files with the line endings they have
+    #[default]
+    Preserve,
+    /// LF in the repo and on every machine
+    Lf,
+    /// LF in the repo, CRLF on every machine
+    Crlf,
+    /// LF in the repo; CRLF on Windows and LF elsewhere
+    Native,
+}
+
+impl LineEndings {
+    fn is_default(&self) -> bool {
+        *self == LineEndings::default()
+    }
+
+    /// `content` as it is stored in the repo.
+    pub fn for_repo(self, content: &str) -> Cow<'_, str> {
+        match self {
+            LineEndings::Preserve => Cow::Borrowed(content),
+            _ => to_lf(content),
+        }
+    }
+
+    /// `content` from the repo as it is written on this machine.
+    pub fn for_local(self, content: &str) -> Cow<'_, str> {
+        let crlf = match self {
+            LineEndings::Preserve => return Cow::Borrowed(content),
+            LineEndings::Lf => false,
+            LineEndings::Crlf => true,
+            LineEndings::Native => cfg!(windows),
+        };
+        let lf = to_lf(content);
+        if crlf {
+            Cow::Owned(lf.replace('\n', "\r\n"))
+        } else {
+            lf
+        }
+    }
+}
+
+/// What of a text file is synced.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncMode {
+    /// Everything but `drifters::exclude` sections, which stay local
+    #[default]
+    Markers,
+    /// The whole file, section tags and all
+    Full,
+}
+
+impl SyncMode {
+    fn is_default(&self) -> bool {
+        *self == SyncMode::default()
+    }
+}
+
+impl fmt::Display for SyncMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SyncMode::Markers => "markers",
+            SyncMode::Full => "full",
+        })
+    }
+}
+
+fn to_lf(content: &str) -> Cow<'_, str> {
+    if content.contains("\r\n") {
+        Cow::Owned(content.replace("\r\n", "\n"))
+    } else {
+        Cow::Borrowed(content)
+    }
+}
+
 impl SyncRules {
     pub fn new() -> Self {
         Self {
+            ignore: Vec::new(),
+            groups: BTreeMap::new(),
             apps: HashMap::new(),
         }
     }
 
-    pub fn load(repo_path: &PathBuf) -> Result<Self> {
+    pub fn load(repo_path: &Path) -> Result<Self> {
         let rules_path = repo_path.join(".drifters").join("sync-rules.toml");
 
         if !rules_path.exists() {
@@ -83,19 +272,293 @@ impl SyncRules {
         Ok(rules)
     }
 
-    pub fn save(&self, repo_path: &PathBuf) -> Result<()> {
+    pub fn save(&self, repo_path: &Path) -> Result<()> {
         let drifters_dir = repo_path.join(".drifters");
         std::fs::create_dir_all(&drifters_dir)?;
 
         let rules_path = drifters_dir.join("sync-rules.toml");
         let contents = toml::to_string_pretty(self)?;
-        std::fs::write(&rules_path, contents)?;
+        crate::sync::write_atomic(&rules_path, contents)?;
         Ok(())
     }
 
     pub fn add_app(&mut self, app_name: String, config: AppConfig) {
         self.apps.insert(app_name, config);
     }
+
+    /// Check every app's patterns after loading, reporting all problems at
+    /// once: patterns that don't compile as globs, and OS-specific includes
+    /// that a base (or same-OS) exclude would always drop.
+    ///
+    /// Rules without problems may still have warnings, which are returned:
+    /// apps with no include patterns at all, and patterns repeated or
+    /// already matched by another in the same or the base list.
+    pub fn validate(&self) -> Result<Vec<Warning>> {
+        let mut problems = Vec::new();
+
+        // A key of an app's `machines` table is a group if it names one, so a
+        // group named like a machine would hide that machine's own overrides
+        for group in self.groups.keys() {
+            if self.groups.values().flatten().any(|member| member == group) {
+                problems.push(format!(
+                    "groups.{}: '{}' is also a machine ID in a group; rename the group",
+                    group, group
+                ));
+            }
+        }
+
+        problems.extend(self.ignore.iter().filter_map(|p| glob_problem("ignore", p)));
+
+        let mut warnings = Vec::new();
+        let mut app_names: Vec<_> = self.apps.keys().collect();
+        app_names.sort();
+        for app_name in app_names {
+            self.apps[app_name].collect_problems(app_name, &mut problems);
+            self.apps[app_name].collect_warnings(app_name, &mut warnings);
+        }
+
+        if problems.is_empty() {
+            return Ok(warnings);
+        }
+        Err(DriftersError::Config(format!(
+            "Invalid sync rules ({} problem(s)):\n  - {}",
+            problems.len(),
+            problems.join("\n  - ")
+        )))
+    }
+
+    /// Machine IDs the rules name that aren't in `registry`: group members,
+    /// `machines` keys that aren't groups, and `priority` entries. Such
+    /// rules never apply; usually the ID is misspelled or the machine was
+    /// renamed or removed.
+    pub fn machine_warnings(&self, registry: &MachineRegistry) -> Vec<Warning> {
+        let unknown = |id: &str| -> Option<String> {
+            if registry.machines.contains_key(id) {
+                return None;
+            }
+            Some(match registry.renamed_to(id) {
+                Some(new_id) => format!("'{}' was renamed to '{}'", id, new_id),
+                None => format!("'{}' is not a registered machine", id),
+            })
+        };
+
+        let mut warnings = Vec::new();
+        for (group, members) in &self.groups {
+            for message in members.iter().filter_map(|member| unknown(member)) {
+                warnings.push(Warning { field: format!("groups.{}", group), message });
+            }
+        }
+        let mut app_names: Vec<_> = self.apps.keys().collect();
+        app_names.sort();
+        for app_name in app_names {
+            let app = &self.apps[app_name];
+            let mut keys: Vec<_> = app.machines.keys().collect();
+            keys.sort();
+            for key in keys.into_iter().filter(|key| !self.groups.contains_key(*key)) {
+                if let Some(message) = unknown(key) {
+                    let field = format!("{}.machines.{}", app_name, key);
+                    warnings.push(Warning { field, message });
+                }
+            }
+            for message in app.priority.iter().filter_map(|id| unknown(id)) {
+                warnings.push(Warning { field: format!("{}.priority", app_name), message });
+            }
+        }
+        warnings
+    }
+}
+
+impl AppConfig {
+    /// Whether wildcards match dotfiles (`match_hidden`, default true).
+    pub fn matches_hidden(&self) -> bool {
+        self.match_hidden.unwrap_or(true)
+    }
+
+    /// The sync mode of `filename`: its `file_modes` entry by exact name,
+    /// else the first glob there that matches, else the app's `sync_mode`.
+    pub fn sync_mode_for(&self, filename: &str) -> SyncMode {
+        if let Some(mode) = self.file_modes.get(filename) {
+            return *mode;
+        }
+        self.file_modes
+            .iter()
+            .find(|(pattern, _)| {
+                glob::Pattern::new(pattern).is_ok_and(|pattern| pattern.matches(filename))
+            })
+            .map_or(self.sync_mode, |(_, mode)| *mode)
+    }
+
+    /// The `machines` overrides that apply to `machine_id`, with their keys,
+    /// in the order they are applied: those of the groups it belongs to (by
+    /// group name), then its own.
+    pub fn overrides_for<'a>(
+        &'a self,
+        groups: &'a BTreeMap<String, Vec<String>>,
+        machine_id: &str,
+    ) -> Vec<(&'a str, &'a MachineOverride)> {
+        let mut overrides: Vec<_> = groups
+            .iter()
+            .filter(|(_, members)| members.iter().any(|member| member == machine_id))
+            .filter_map(|(group, _)| self.machines.get_key_value(group))
+            .map(|(key, machine_override)| (key.as_str(), machine_override))
+            .collect();
+        if !groups.contains_key(machine_id) {
+            if let Some((key, machine_override)) = self.machines.get_key_value(machine_id) {
+                overrides.push((key.as_str(), machine_override));
+            }
+        }
+        overrides
+    }
+
+    fn collect_problems(&self, app_name: &str, problems: &mut Vec<String>) {
+        if let Some(syntax) = &self.comment_syntax {
+            if syntax.trim().is_empty() {
+                problems.push(format!("{}.comment_syntax: must not be empty", app_name));
+            }
+        }
+
+        let mut lists: Vec<(String, &[String])> = vec![
+            ("include".to_string(), &self.include),
+            ("exclude".to_string(), &self.exclude),
+            ("include-macos".to_string(), &self.include_macos),
+            ("include-linux".to_string(), &self.include_linux),
+            ("include-windows".to_string(), &self.include_windows),
+            ("exclude-macos".to_string(), &self.exclude_macos),
+            ("exclude-linux".to_string(), &self.exclude_linux),
+            ("exclude-windows".to_string(), &self.exclude_windows),
+        ];
+        let mut machine_ids: Vec<_> = self.machines.keys().collect();
+        machine_ids.sort();
+        for id in machine_ids {
+            let machine = &self.machines[id];
+            lists.push((format!("machines.{}.include", id), &machine.include));
+            lists.push((format!("machines.{}.exclude", id), &machine.exclude));
+        }
+
+        for (field, patterns) in &lists {
+            let field = format!("{}.{}", app_name, field);
+            problems.extend(patterns.iter().filter_map(|p| glob_problem(&field, p)));
+        }
+
+        for pattern in self.file_modes.keys() {
+            if pattern.contains('/') {
+                problems.push(format!(
+                    "{}.file_modes: '{}' must be a file name, not a path",
+                    app_name, pattern
+                ));
+            } else if let Some(problem) =
+                glob_problem(&format!("{}.file_modes", app_name), pattern)
+            {
+                problems.push(problem);
+            }
+        }
+
+        for (os, includes, os_excludes) in [
+            ("macos", &self.include_macos, &self.exclude_macos),
+            ("linux", &self.include_linux, &self.exclude_linux),
+            ("windows", &self.include_windows, &self.exclude_windows),
+        ] {
+            for pattern in includes {
+                if self.exclude.contains(pattern) || os_excludes.contains(pattern) {
+                    problems.push(format!(
+                        "{}.include-{}: '{}' is also excluded, so it can never sync",
+                        app_name, os, pattern
+                    ));
+                }
+            }
+        }
+    }
+
+    fn collect_warnings(&self, app_name: &str, warnings: &mut Vec<Warning>) {
+        let has_includes = !self.include.is_empty()
+            || !self.include_macos.is_empty()
+            || !self.include_linux.is_empty()
+            || !self.include_windows.is_empty()
+            || self.machines.values().any(|machine| !machine.include.is_empty());
+        if !has_includes {
+            warnings.push(Warning {
+                field: app_name.to_string(),
+                message: "no include patterns, so it syncs nothing on any machine".to_string(),
+            });
+        }
+
+        // Each list is checked against itself and the base list it adds to
+        let base: [PatternList; 2] = [("include", &self.include), ("exclude", &self.exclude)];
+        let mut lists: Vec<(String, &[String], Option<PatternList>)> = vec![
+            ("include".to_string(), &self.include, None),
+            ("exclude".to_string(), &self.exclude, None),
+            ("include-macos".to_string(), &self.include_macos, Some(base[0])),
+            ("include-linux".to_string(), &self.include_linux, Some(base[0])),
+            ("include-windows".to_string(), &self.include_windows, Some(base[0])),
+            ("exclude-macos".to_string(), &self.exclude_macos, Some(base[1])),
+            ("exclude-linux".to_string(), &self.exclude_linux, Some(base[1])),
+            ("exclude-windows".to_string(), &self.exclude_windows, Some(base[1])),
+        ];
+        let mut machine_ids: Vec<_> = self.machines.keys().collect();
+        machine_ids.sort();
+        for id in machine_ids {
+            let machine = &self.machines[id];
+            lists.push((format!("machines.{}.include", id), &machine.include, Some(base[0])));
+            lists.push((format!("machines.{}.exclude", id), &machine.exclude, Some(base[1])));
+        }
+
+        let options = glob::MatchOptions {
+            require_literal_separator: true,
+            require_literal_leading_dot: !self.matches_hidden(),
+            ..glob::MatchOptions::new()
+        };
+        for (field, patterns, inherited) in lists {
+            for (i, pattern) in patterns.iter().enumerate() {
+                let message = if patterns[..i].contains(pattern) {
+                    "is listed twice".to_string()
+                } else if let Some((base_field, _)) =
+                    inherited.filter(|(_, base)| base.contains(pattern))
+                {
+                    format!("is already in {}", base_field)
+                } else if let Some(wildcard) = patterns
+                    .iter()
+                    .chain(inherited.map_or(&[][..], |(_, base)| base))
+                    .find(|other| *other != pattern && covers(other, pattern, options))
+                {
+                    format!("is already matched by '{}'", wildcard)
+                } else {
+                    continue;
+                };
+                warnings.push(Warning {
+                    field: format!("{}.{}", app_name, field),
+                    message: format!("'{}' {}", pattern, message),
+                });
+            }
+        }
+    }
+}
+
+/// A list of patterns and the name of its field, e.g. `("include", …)`.
+type PatternList<'a> = (&'a str, &'a [String]);
+
+/// Why `pattern` doesn't compile as a glob, if it doesn't, as `validate`
+/// reports it for `field` (e.g. `zsh.include`).
+fn glob_problem(field: &str, pattern: &str) -> Option<String> {
+    glob::Pattern::new(pattern)
+        .err()
+        .map(|e| format!("{}: invalid glob '{}': {}", field, pattern, e.msg))
+}
+
+/// Fail on the first of `patterns` that doesn't compile as a glob, naming
+/// it and `field`, so a bad pattern is refused when it is entered rather
+/// than skipped every time files are resolved.
+pub fn check_globs(field: &str, patterns: &[String]) -> Result<()> {
+    match patterns.iter().find_map(|p| glob_problem(field, p)) {
+        Some(problem) => Err(DriftersError::Config(problem)),
+        None => Ok(()),
+    }
+}
+
+/// Whether glob `pattern` matches every path `other` can: `other` is a
+/// literal path (no wildcards) that `pattern` matches.
+fn covers(pattern: &str, other: &str, options: glob::MatchOptions) -> bool {
+    !other.contains(['*', '?', '['])
+        && glob::Pattern::new(pattern).is_ok_and(|glob| glob.matches_with(other, options))
 }
 
 impl Default for SyncRules {
@@ -103,3 +566,224 @@ impl Default for SyncRules {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_reports_all_problems_at_once() {
+        let rules: SyncRules = toml::from_str(
+            r#"
+ignore = ["*.swp", "[x"]
+
+[apps.zed]
+include = ["~/.config/zed/[settings.json"]
+exclude = ["**/keymap.json"]
+include-linux = ["**/keymap.json"]
+
+[apps.zed.machines.laptop]
+exclude = ["***/x"]
+
+[apps.zsh]
+include = ["~/.zshrc"]
+
+[apps.nvim]
+include = ["~/.config/nvim/customrc"]
+comment_syntax = " "
+"#,
+        )
+        .unwrap();
+
+        let err = rules.validate().unwrap_err().to_string();
+        assert!(err.contains("5 problem(s)"), "{}", err);
+        assert!(err.contains("ignore: invalid glob '[x'"));
+        assert!(err.contains("nvim.comment_syntax: must not be empty"));
+        assert!(err.contains("zed.include: invalid glob '~/.config/zed/[settings.json'"));
+        assert!(err.contains("zed.machines.laptop.exclude: invalid glob '***/x'"));
+        assert!(err.contains("zed.include-linux: '**/keymap.json' is also excluded"));
+        assert!(!err.contains("zsh"));
+
+        let mut ok = rules.clone();
+        ok.ignore.pop();
+        ok.apps.remove("zed");
+        ok.apps.remove("nvim");
+        assert!(ok.validate().is_ok());
+
+        ok.groups.insert("laptops".to_string(), vec!["air".to_string(), "servers".to_string()]);
+        ok.groups.insert("servers".to_string(), vec!["db".to_string()]);
+        let err = ok.validate().unwrap_err().to_string();
+        assert!(err.contains("groups.servers: 'servers' is also a machine ID"), "{}", err);
+    }
+
+    #[test]
+    fn test_check_globs_names_the_first_bad_pattern() {
+        let patterns = ["~/.zshrc", "~/.config/**.lua", "[x"].map(String::from);
+        assert!(check_globs("zsh.include", &patterns[..1]).is_ok());
+        let err = check_globs("zsh.include", &patterns).unwrap_err().to_string();
+        assert!(err.contains("zsh.include: invalid glob '~/.config/**.lua'"), "{}", err);
+        assert!(!err.contains("[x"));
+    }
+
+    #[test]
+    fn test_validate_warns_about_footguns_and_unknown_machines() {
+        let rules: SyncRules = toml::from_str(
+            r#"
+[groups]
+laptops = ["air", "old-pro"]
+
+[apps.zed]
+include = ["~/.config/zed/*", "~/.config/zed/settings.json", "~/.config/zed/*"]
+include-linux = ["~/.config/zed/*"]
+exclude = ["~/.config/zed/db/**"]
+
+[apps.zed.machines.laptops]
+include = ["~/.config/zed/themes/*"]
+
+[apps.zed.machines.desktp]
+exclude = ["~/.config/zed/keymap.json"]
+
+[apps.empty]
+exclude = ["~/.cache"]
+
+[apps.vim]
+include = ["~/.vimrc", "~/.vim/*.vim"]
+"#,
+        )
+        .unwrap();
+
+        let warnings: Vec<String> =
+            rules.validate().unwrap().iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            warnings,
+            [
+                "empty: no include patterns, so it syncs nothing on any machine",
+                "zed.include: '~/.config/zed/settings.json' is already matched by \
+                 '~/.config/zed/*'",
+                "zed.include: '~/.config/zed/*' is listed twice",
+                "zed.include-linux: '~/.config/zed/*' is already in include",
+            ]
+        );
+
+        let mut registry = MachineRegistry::new();
+        registry.register_machine("air".to_string(), "macos".to_string());
+        registry.register_machine("pro".to_string(), "macos".to_string());
+        registry.rename_machine("pro", "m3");
+        let warnings: Vec<String> =
+            rules.machine_warnings(&registry).iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            warnings,
+            [
+                "groups.laptops: 'old-pro' is not a registered machine",
+                "zed.machines.desktp: 'desktp' is not a registered machine",
+            ]
+        );
+        let mut renamed = rules.clone();
+        renamed.groups.insert("laptops".to_string(), vec!["pro".to_string()]);
+        renamed.apps.get_mut("zed").unwrap().machines.remove("desktp");
+        let warnings = renamed.machine_warnings(&registry);
+        assert_eq!(warnings[0].message, "'pro' was renamed to 'm3'");
+    }
+
+    #[test]
+    fn test_line_endings_store_lf_and_write_the_configured_form() {
+        let rules: SyncRules = toml::from_str(
+            r#"
+[apps.vscode]
+include = ["~/settings.json"]
+line_endings = "crlf"
+
+[apps.zsh]
+include = ["~/.zshrc"]
+"#,
+        )
+        .unwrap();
+        let endings = rules.apps["vscode"].line_endings;
+        assert_eq!(endings, LineEndings::Crlf);
+        assert_eq!(rules.apps["zsh"].line_endings, LineEndings::Preserve);
+        assert!(!toml::to_string(&rules.apps["zsh"]).unwrap().contains("line_endings"));
+
+        let mixed = "a\r\nb\nc";
+        assert_eq!(endings.for_repo(mixed), "a\nb\nc");
+        assert_eq!(endings.for_local(mixed), "a\r\nb\r\nc");
+        assert_eq!(LineEndings::Lf.for_local(mixed), "a\nb\nc");
+        assert_eq!(LineEndings::Preserve.for_repo(mixed), mixed);
+        assert_eq!(LineEndings::Preserve.for_local(mixed), mixed);
+        let native = if cfg!(windows) { "a\r\nb\r\nc" } else { "a\nb\nc" };
+        assert_eq!(LineEndings::Native.for_local(mixed), native);
+    }
+
+    #[test]
+    fn test_file_modes_override_the_app_sync_mode() {
+        let rules: SyncRules = toml::from_str(
+            r#"
+[apps.zsh]
+include = ["~/.zshrc", "~/.config/zsh/*"]
+
+[apps.zsh.file_modes]
+"aliases.zsh" = "full"
+"*.local" = "full"
+"#,
+        )
+        .unwrap();
+        let zsh = &rules.apps["zsh"];
+        assert_eq!(zsh.sync_mode, SyncMode::Markers);
+        assert_eq!(zsh.sync_mode_for(".zshrc"), SyncMode::Markers);
+        assert_eq!(zsh.sync_mode_for("aliases.zsh"), SyncMode::Full);
+        assert_eq!(zsh.sync_mode_for("paths.local"), SyncMode::Full);
+        assert!(rules.validate().is_ok());
+        let toml = toml::to_string(zsh).unwrap();
+        assert!(toml.contains("aliases.zsh") && !toml.contains("sync_mode"));
+
+        // An exact name wins over the app's mode and any glob
+        let mut full = zsh.clone();
+        full.sync_mode = SyncMode::Full;
+        full.file_modes.insert("prompt.local".to_string(), SyncMode::Markers);
+        assert_eq!(full.sync_mode_for(".zshrc"), SyncMode::Full);
+        assert_eq!(full.sync_mode_for("prompt.local"), SyncMode::Markers);
+
+        let mut bad = rules.clone();
+        let file_modes = &mut bad.apps.get_mut("zsh").unwrap().file_modes;
+        file_modes.insert("~/.zshrc".to_string(), SyncMode::Full);
+        file_modes.insert("[x".to_string(), SyncMode::Full);
+        let err = bad.validate().unwrap_err().to_string();
+        assert!(err.contains("zsh.file_modes: '~/.zshrc' must be a file name"), "{}", err);
+        assert!(err.contains("zsh.file_modes: invalid glob '[x'"), "{}", err);
+    }
+
+    #[test]
+    fn test_overrides_apply_groups_by_name_then_the_machine() {
+        let rules: SyncRules = toml::from_str(
+            r#"
+[groups]
+servers = ["db", "web"]
+laptops = ["air", "pro"]
+all = ["air", "pro", "db", "web"]
+
+[apps.zsh]
+include = ["~/.zshrc"]
+
+[apps.zsh.machines.laptops]
+include = ["~/.zshrc.laptop"]
+
+[apps.zsh.machines.all]
+include = ["~/.zshrc.common"]
+
+[apps.zsh.machines.air]
+exclude = ["~/.zshrc.laptop"]
+singular = true
+"#,
+        )
+        .unwrap();
+        let zsh = &rules.apps["zsh"];
+        let keys = |machine_id| -> Vec<&str> {
+            zsh.overrides_for(&rules.groups, machine_id).into_iter().map(|(k, _)| k).collect()
+        };
+        assert_eq!(keys("air"), ["all", "laptops", "air"]);
+        assert_eq!(keys("pro"), ["all", "laptops"]);
+        assert_eq!(keys("db"), ["all"]);
+        assert_eq!(keys("laptop-3"), Vec::<&str>::new());
+        // A group name is not a machine ID
+        assert_eq!(keys("laptops"), Vec::<&str>::new());
+    }
+}
@@ -0,0 +1,272 @@
+use crate::error::{DriftersError, Result};
+use crate::parser::sections::{
+    decrypt_sections, encrypt_sections, has_encrypted_sections, CommentSyntax, SEALED_PREFIX,
+};
+use aes_gcm_siv::aead::{Aead, KeyInit};
+use aes_gcm_siv::{Aes256GcmSiv, Nonce};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Random salt for deriving the key from a passphrase, generated by the
+/// first `set-passphrase` and kept in the repo. It isn't secret: storing it
+/// next to the ciphertext keeps the same passphrase giving the same key on
+/// every machine, while a dictionary precomputed for one repo is useless
+/// against any other.
+const SALT_FILE: &str = "encrypt-salt";
+
+/// Length in bytes of a newly generated salt.
+const SALT_LEN: usize = 16;
+
+/// The repo's salt from `.drifters/encrypt-salt`, if one was created.
+pub fn load_salt(repo_path: &Path) -> Result<Option<Vec<u8>>> {
+    let path = salt_path(repo_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    match hex::decode(fs::read_to_string(&path)?.trim()) {
+        Ok(salt) if salt.len() >= SALT_LEN => Ok(Some(salt)),
+        _ => Err(DriftersError::Config(format!(
+            ".drifters/{} in the repo is not a valid salt",
+            SALT_FILE
+        ))),
+    }
+}
+
+/// Generate a new random salt.
+pub fn generate_salt() -> Result<Vec<u8>> {
+    let mut salt = vec![0u8; SALT_LEN];
+    getrandom::getrandom(&mut salt)
+        .map_err(|e| DriftersError::Config(format!("Could not generate a salt: {}", e)))?;
+    Ok(salt)
+}
+
+/// Write `salt` to `.drifters/encrypt-salt` in the repo.
+pub fn save_salt(repo_path: &Path, salt: &[u8]) -> Result<()> {
+    let path = salt_path(repo_path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, format!("{}\n", hex::encode(salt)))?;
+    Ok(())
+}
+
+fn salt_path(repo_path: &Path) -> PathBuf {
+    repo_path.join(".drifters").join(SALT_FILE)
+}
+
+/// The symmetric key for `drifters::encrypt` sections, derived from a
+/// passphrase with scrypt.
+///
+/// It is kept in `~/.config/drifters/secret.key` (mode 0600), next to but
+/// not in `drifters.toml`; the passphrase itself is never stored. Sections
+/// are sealed with AES-256-GCM-SIV under a nonce derived from their
+/// content, so an unchanged section encrypts to the same text on every
+/// push and doesn't show up as a change.
+pub struct SecretKey([u8; 32]);
+
+impl SecretKey {
+    /// Derive the key from `passphrase` and the repo's `salt`.
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> Result<Self> {
+        if passphrase.is_empty() {
+            return Err(DriftersError::Config("The passphrase can't be empty".to_string()));
+        }
+        // scrypt's recommended interactive parameters (N = 2^17, r = 8, p = 1)
+        let params = scrypt::Params::new(17, 8, 1, 32)
+            .map_err(|e| DriftersError::Config(format!("Invalid scrypt parameters: {}", e)))?;
+        let mut key = [0u8; 32];
+        scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut key)
+            .map_err(|e| DriftersError::Config(format!("Could not derive the key: {}", e)))?;
+        Ok(Self(key))
+    }
+
+    /// `~/.config/drifters/secret.key`
+    pub fn path() -> Result<PathBuf> {
+        let home = dirs::home_dir()
+            .ok_or_else(|| DriftersError::Config("Could not find home directory".to_string()))?;
+        Ok(home.join(".config").join("drifters").join("secret.key"))
+    }
+
+    /// This machine's key, if one was set with `drifters set-passphrase`.
+    pub fn load() -> Result<Option<Self>> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let bytes = BASE64.decode(fs::read_to_string(&path)?.trim()).ok();
+        match bytes.and_then(|b| <[u8; 32]>::try_from(b).ok()) {
+            Some(key) => Ok(Some(Self(key))),
+            None => Err(DriftersError::Config(format!(
+                "{} is not a valid key. Run 'drifters set-passphrase' to replace it.",
+                path.display()
+            ))),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut options = fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path)?;
+        // `mode` only applies when the file is created; tighten an existing one
+        #[cfg(unix)]
+        file.set_permissions(std::os::unix::fs::PermissionsExt::from_mode(0o600))?;
+        writeln!(file, "{}", BASE64.encode(self.0))?;
+        Ok(())
+    }
+
+    /// Delete this machine's key. Returns whether there was one.
+    pub fn remove() -> Result<bool> {
+        let path = Self::path()?;
+        if !path.exists() {
+            return Ok(false);
+        }
+        fs::remove_file(path)?;
+        Ok(true)
+    }
+
+    /// A short, non-secret ID of the key, to check that two machines have
+    /// the same one.
+    pub fn fingerprint(&self) -> String {
+        hex::encode(&self.subkey(b"fingerprint")[..4])
+    }
+
+    /// Encrypt `plaintext` into `v1:<base64 of nonce and ciphertext>`.
+    pub fn seal(&self, plaintext: &str) -> Result<String> {
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&self.subkey(b"nonce"))
+            .expect("HMAC accepts any key length");
+        mac.update(plaintext.as_bytes());
+        let tag = mac.finalize().into_bytes();
+        let nonce = Nonce::from_slice(&tag[..12]);
+        let ciphertext = self
+            .cipher()
+            .encrypt(nonce, plaintext.as_bytes())
+            .map_err(|_| DriftersError::Config("Encryption failed".to_string()))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(format!("{}{}", SEALED_PREFIX, BASE64.encode(sealed)))
+    }
+
+    /// Decrypt what [`seal`](Self::seal) produced.
+    pub fn open(&self, sealed: &str) -> Result<String> {
+        let malformed = || DriftersError::Config("malformed encrypted section".to_string());
+        let encoded = sealed.strip_prefix(SEALED_PREFIX).ok_or_else(malformed)?;
+        let bytes = BASE64.decode(encoded).map_err(|_| malformed())?;
+        if bytes.len() < 12 {
+            return Err(malformed());
+        }
+        let (nonce, ciphertext) = bytes.split_at(12);
+        let plaintext = self
+            .cipher()
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| {
+                DriftersError::Config(format!(
+                    "can't decrypt it with this machine's key ({}); it was encrypted with a \
+                     different passphrase",
+                    self.fingerprint()
+                ))
+            })?;
+        String::from_utf8(plaintext).map_err(|_| malformed())
+    }
+
+    fn cipher(&self) -> Aes256GcmSiv {
+        Aes256GcmSiv::new_from_slice(&self.subkey(b"encrypt")).expect("key is 32 bytes")
+    }
+
+    /// An independent key for one purpose, so the same bytes are never used
+    /// both to encrypt and to derive nonces.
+    fn subkey(&self, purpose: &[u8]) -> [u8; 32] {
+        let mut mac =
+            <Hmac<Sha256> as Mac>::new_from_slice(&self.0).expect("HMAC accepts any key length");
+        mac.update(purpose);
+        mac.finalize().into_bytes().into()
+    }
+}
+
+/// Encrypt the `drifters::encrypt` sections of `filename`'s content for the
+/// repo. Content without such sections is returned as is.
+pub fn seal_sections(
+    content: &str,
+    comment: &CommentSyntax,
+    key: Option<&SecretKey>,
+    filename: &str,
+) -> Result<String> {
+    if !has_encrypted_sections(content, comment) {
+        return Ok(content.to_string());
+    }
+    let key = key.ok_or_else(|| missing_key(filename))?;
+    encrypt_sections(content, comment, |plaintext| key.seal(plaintext))
+}
+
+/// Decrypt the `drifters::encrypt` sections of `filename`'s content from
+/// the repo. Content without such sections is returned as is.
+pub fn open_sections(
+    content: &str,
+    comment: &CommentSyntax,
+    key: Option<&SecretKey>,
+    filename: &str,
+) -> Result<String> {
+    if !has_encrypted_sections(content, comment) {
+        return Ok(content.to_string());
+    }
+    let key = key.ok_or_else(|| missing_key(filename))?;
+    decrypt_sections(content, comment, |sealed| key.open(sealed)).map_err(|e| match e {
+        DriftersError::Config(msg) => DriftersError::Config(format!("{}: {}", filename, msg)),
+        e => e,
+    })
+}
+
+fn missing_key(filename: &str) -> DriftersError {
+    DriftersError::Config(format!(
+        "{} has drifters::encrypt sections, but no encryption key is set on this machine. \
+         Run 'drifters set-passphrase' with the passphrase the other machines use.",
+        filename
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_is_deterministic_and_needs_the_same_key() {
+        let key = SecretKey([7; 32]);
+        let sealed = key.seal("token = abc\n").unwrap();
+        assert!(sealed.starts_with(SEALED_PREFIX));
+        assert!(!sealed.contains("abc"));
+        // Unchanged content seals the same, so pushes don't churn
+        assert_eq!(key.seal("token = abc\n").unwrap(), sealed);
+        assert_ne!(key.seal("token = abd\n").unwrap(), sealed);
+        assert_eq!(key.open(&sealed).unwrap(), "token = abc\n");
+
+        let other = SecretKey([8; 32]);
+        assert_ne!(other.fingerprint(), key.fingerprint());
+        let err = other.open(&sealed).unwrap_err().to_string();
+        assert!(err.contains("different passphrase"), "{}", err);
+        assert!(key.open("v1:!!").is_err());
+    }
+
+    #[test]
+    fn test_salt_round_trips_through_the_repo() {
+        let repo = tempfile::tempdir().unwrap();
+        assert!(load_salt(repo.path()).unwrap().is_none());
+
+        let salt = generate_salt().unwrap();
+        assert_eq!(salt.len(), SALT_LEN);
+        assert_ne!(generate_salt().unwrap(), salt);
+        save_salt(repo.path(), &salt).unwrap();
+        assert_eq!(load_salt(repo.path()).unwrap(), Some(salt));
+
+        fs::write(repo.path().join(".drifters").join(SALT_FILE), "not hex\n").unwrap();
+        assert!(load_salt(repo.path()).is_err());
+    }
+}
@@ -12,6 +12,9 @@ pub enum DriftersError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    #[error("Formatting error: {0}")]
+    Fmt(#[from] std::fmt::Error),
+
     #[error("Git error: {0}")]
     Git(String),
 
@@ -41,6 +44,9 @@ pub enum DriftersError {
 
     #[error("Merge conflict: {0}")]
     MergeConflict(String),
+
+    #[error("Local clone has diverged from origin: {0}")]
+    Diverged(String),
 }
 
 pub type Result<T> = std::result::Result<T, DriftersError>;
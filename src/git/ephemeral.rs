@@ -1,7 +1,12 @@
 use crate::config::LocalConfig;
 use crate::error::{DriftersError, Result};
-use crate::git::{checkout_or_create_branch, clone_repo, pull_latest};
-use std::path::PathBuf;
+use crate::git::{
+    checkout_or_create_branch, clone_repo, fetch_origin, main_branch, pull_latest, remote_url,
+    reset_to_origin,
+};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 // ─── Lock constants ──────────────────────────────────────────────────────────
 /// Maximum time (seconds) to wait for another process to release the lock.
@@ -10,6 +15,31 @@ const LOCK_TIMEOUT_SECS: u64 = 30;
 /// (i.e. the owning process died without cleaning up).
 const LOCK_STALE_SECS: u64 = 300; // 5 minutes
 
+/// Set from the global `--force-unlock` flag: reclaim a contended lock when
+/// its holder process is no longer running.
+static FORCE_UNLOCK: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable reclaiming locks held by dead processes.
+pub fn set_force_unlock(enabled: bool) {
+    FORCE_UNLOCK.store(enabled, Ordering::Relaxed);
+}
+
+/// Set from the global `--no-cache` flag: start from a fresh clone and
+/// delete it afterwards, whatever `repo_cache_ttl_secs` says.
+static NO_CACHE: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable ignoring the cached clone.
+pub fn set_no_cache(enabled: bool) {
+    NO_CACHE.store(enabled, Ordering::Relaxed);
+}
+
+/// How long a cached clone counts as fresh, or `None` if clones are not
+/// kept between commands.
+fn cache_ttl(config: &LocalConfig) -> Option<Duration> {
+    (config.repo_cache_ttl_secs > 0 && !NO_CACHE.load(Ordering::Relaxed))
+        .then(|| Duration::from_secs(config.repo_cache_ttl_secs))
+}
+
 // ─── Ephemeral repo helpers ──────────────────────────────────────────────────
 
 /// Set up ephemeral repo for this command.
@@ -17,17 +47,68 @@ const LOCK_STALE_SECS: u64 = 300; // 5 minutes
 pub fn setup_ephemeral_repo(config: &LocalConfig) -> Result<PathBuf> {
     let temp_repo = LocalConfig::get_temp_repo_path()?;
 
-    if temp_repo.exists() {
+    if temp_repo.exists() && NO_CACHE.load(Ordering::Relaxed) {
+        log::debug!("--no-cache: removing the existing clone");
+        std::fs::remove_dir_all(&temp_repo)?;
+    }
+
+    if let (true, Some(ttl)) = (temp_repo.exists(), cache_ttl(config)) {
+        match reuse_cached_repo(&temp_repo, config, ttl) {
+            Ok(()) => return Ok(temp_repo),
+            Err(e) => {
+                log::warn!("Cached clone is unusable ({}); cloning afresh", e);
+                std::fs::remove_dir_all(&temp_repo)?;
+                clone_repo(&config.repo_url, &temp_repo, !config.full_clone)?;
+            }
+        }
+    } else if temp_repo.exists() {
         log::debug!("Temp repo exists, pulling latest");
-        pull_latest(&temp_repo)?;
+        match pull_latest(&temp_repo, config.pull_strategy) {
+            Ok(()) => {}
+            // The clone holds nothing that isn't on the remote (or shouldn't
+            // be), so a fresh one is the safe way out of a conflicting pull
+            Err(DriftersError::Diverged(e)) => {
+                log::warn!("Local clone has diverged from origin ({}); cloning afresh", e);
+                std::fs::remove_dir_all(&temp_repo)?;
+                clone_repo(&config.repo_url, &temp_repo, !config.full_clone)?;
+            }
+            Err(e) => return Err(e),
+        }
     } else {
         log::debug!("Cloning repo to temp location");
-        clone_repo(&config.repo_url, &temp_repo)?;
+        clone_repo(&config.repo_url, &temp_repo, !config.full_clone)?;
     }
 
     Ok(temp_repo)
 }
 
+/// Bring a clone kept by an earlier command up to date: fetch unless the
+/// last fetch (`.git/FETCH_HEAD`) is younger than `ttl`, then discard
+/// whatever that command left behind.
+fn reuse_cached_repo(repo: &PathBuf, config: &LocalConfig, ttl: Duration) -> Result<()> {
+    let url = remote_url(repo)?;
+    if url != config.repo_url {
+        return Err(DriftersError::Config(format!("it was cloned from {}", url)));
+    }
+    if fetched_within(repo, ttl) {
+        log::debug!("Reusing cached clone, fetched less than {}s ago", ttl.as_secs());
+    } else {
+        log::debug!("Cached clone is older than {}s, fetching", ttl.as_secs());
+        fetch_origin(repo)?;
+    }
+    reset_to_origin(repo)
+}
+
+/// Whether the clone at `repo` was fetched less than `ttl` ago. A clone
+/// never fetched since it was cloned has no `FETCH_HEAD` and counts as old.
+fn fetched_within(repo: &Path, ttl: Duration) -> bool {
+    std::fs::metadata(repo.join(".git").join("FETCH_HEAD"))
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .is_some_and(|age| age < ttl)
+}
+
 /// Clean up ephemeral repo after command completes.
 pub fn cleanup_ephemeral_repo() -> Result<()> {
     let temp_repo = LocalConfig::get_temp_repo_path()?;
@@ -95,8 +176,119 @@ fn is_stale_lock(path: &PathBuf) -> bool {
     false
 }
 
+/// The PID recorded in a lock file, if any.
+fn lock_pid(path: &Path) -> Option<u32> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok())
+}
+
+/// The lock's PID, only if that process is known not to be running.
+/// Unknown liveness counts as alive.
+fn dead_holder(path: &Path) -> Option<u32> {
+    lock_pid(path).filter(|&pid| pid != std::process::id() && pid_is_alive(pid) == Some(false))
+}
+
+/// Returns true only if the lock records a PID and that process is known
+/// not to be running.
+fn holder_is_dead(path: &Path) -> bool {
+    dead_holder(path).is_some()
+}
+
+/// Remove the lock if its holder has died. Returns whether it was removed.
+fn reclaim_dead_lock(path: &Path) -> bool {
+    dead_holder(path).is_some_and(|pid| reclaim_lock_of(path, pid))
+}
+
+/// Remove the lock if it still belongs to `dead_pid`.
+///
+/// Several `--force-unlock` runs can see the same dead holder, and by the
+/// time one of them acts another may already have reclaimed the lock and
+/// taken it. So the lock is first renamed to a name only this process uses,
+/// which at most one of them can do, and the PID is checked again on the
+/// renamed file; a lock that turns out to be someone else's is put back.
+fn reclaim_lock_of(path: &Path, dead_pid: u32) -> bool {
+    let claimed = path.with_extension(format!("lock.reclaim-{}", std::process::id()));
+    if std::fs::rename(path, &claimed).is_err() {
+        return false;
+    }
+    if lock_pid(&claimed) == Some(dead_pid) {
+        let _ = std::fs::remove_file(&claimed);
+        return true;
+    }
+    // A live lock: restore it, without replacing one taken in the meantime
+    if let Err(e) = std::fs::hard_link(&claimed, path) {
+        log::warn!("Could not restore lock file {:?}: {}", path, e);
+    }
+    let _ = std::fs::remove_file(&claimed);
+    false
+}
+
+/// Whether a process with `pid` exists, or `None` if that can't be told.
+#[cfg(unix)]
+fn pid_is_alive(pid: u32) -> Option<bool> {
+    if std::path::Path::new("/proc/self").exists() {
+        return Some(std::path::Path::new(&format!("/proc/{}", pid)).exists());
+    }
+    // No procfs (macOS): `kill -0` probes without sending a signal
+    let output = std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .output()
+        .ok()?;
+    if output.status.success() {
+        return Some(true);
+    }
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // EPERM means the process exists but belongs to someone else
+    stderr.contains("No such process").then_some(false)
+}
+
+/// Whether a process with `pid` exists, or `None` if that can't be told.
+#[cfg(windows)]
+fn pid_is_alive(pid: u32) -> Option<bool> {
+    let output = std::process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+        .output()
+        .ok()?;
+    Some(String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+}
+
+/// Who, if anyone, holds the repo lock.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LockState {
+    Free,
+    /// Held by a running process (PID, if recorded)
+    Held(Option<u32>),
+    /// Left behind by a process that died or was interrupted
+    Abandoned(Option<u32>),
+}
+
+/// Inspect the repo lock without taking it.
+pub fn lock_state() -> Result<LockState> {
+    let path = lock_path()?;
+    if !path.exists() {
+        return Ok(LockState::Free);
+    }
+    let pid = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok());
+    if holder_is_dead(&path) || is_stale_lock(&path) {
+        Ok(LockState::Abandoned(pid))
+    } else {
+        Ok(LockState::Held(pid))
+    }
+}
+
 /// Acquire the lock, spinning up to `LOCK_TIMEOUT_SECS`.
 fn acquire_lock(path: &PathBuf) -> Result<()> {
+    acquire_lock_with(
+        path,
+        Duration::from_secs(LOCK_TIMEOUT_SECS),
+        FORCE_UNLOCK.load(Ordering::Relaxed),
+    )
+}
+
+fn acquire_lock_with(path: &PathBuf, timeout: Duration, force_unlock: bool) -> Result<()> {
     let start = std::time::Instant::now();
     let mut printed_waiting = false;
 
@@ -105,17 +297,24 @@ fn acquire_lock(path: &PathBuf) -> Result<()> {
             return Ok(());
         }
 
-        if start.elapsed().as_secs() >= LOCK_TIMEOUT_SECS {
+        // With --force-unlock, a lock whose holder has died is reclaimed
+        // straight away instead of waiting out the timeout
+        if force_unlock && reclaim_dead_lock(path) {
+            log::warn!("Reclaimed lock {:?} left behind by a dead process", path);
+            continue;
+        }
+
+        if start.elapsed() >= timeout {
             return Err(DriftersError::Config(format!(
                 "Timed out waiting for another drifters process to finish \
-                 (lock file: {:?}). If no other process is running, delete \
-                 the lock file manually.",
+                 (lock file: {:?}). If no other process is running, re-run \
+                 with --force-unlock or use 'drifters unlock'.",
                 path
             )));
         }
 
         if !printed_waiting {
-            println!("⏳ Another drifters process is running; waiting...");
+            eprintln!("⏳ Another drifters process is running; waiting...");
             printed_waiting = true;
         }
 
@@ -136,10 +335,12 @@ fn release_lock(path: &PathBuf) {
 /// RAII guard that:
 /// 1. Acquires a lock file before touching the shared temp repo.
 /// 2. Sets up (clones or pulls) the ephemeral repo.
-/// 3. Releases the lock and cleans up the repo on `Drop`.
+/// 3. Releases the lock and cleans up the repo on `Drop`, unless
+///    `repo_cache_ttl_secs` keeps it for the next command.
 pub struct EphemeralRepoGuard {
     repo_path: PathBuf,
     lock_path: PathBuf,
+    keep: bool,
 }
 
 impl EphemeralRepoGuard {
@@ -155,6 +356,7 @@ impl EphemeralRepoGuard {
             Ok(repo_path) => Ok(Self {
                 repo_path,
                 lock_path,
+                keep: cache_ttl(config).is_some(),
             }),
             Err(e) => {
                 release_lock(&lock_path);
@@ -167,7 +369,7 @@ impl EphemeralRepoGuard {
     /// Creates the branch from `main` if it doesn't exist.
     pub fn new_on_branch(config: &LocalConfig, branch: &str) -> Result<Self> {
         let guard = Self::new(config)?;
-        checkout_or_create_branch(&guard.repo_path, branch, "main")?;
+        checkout_or_create_branch(&guard.repo_path, branch, main_branch())?;
         Ok(guard)
     }
 
@@ -178,9 +380,120 @@ impl EphemeralRepoGuard {
 
 impl Drop for EphemeralRepoGuard {
     fn drop(&mut self) {
-        if let Err(e) = cleanup_ephemeral_repo() {
+        if self.keep {
+            log::debug!("Keeping the clone at {:?} for the next command", self.repo_path);
+        } else if let Err(e) = cleanup_ephemeral_repo() {
             log::warn!("Failed to cleanup ephemeral repo: {}", e);
         }
         release_lock(&self.lock_path);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git(repo: &Path, args: &[&str]) -> String {
+        let out = std::process::Command::new("git")
+            .arg("-C")
+            .arg(repo)
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        String::from_utf8_lossy(&out.stdout).trim().to_string()
+    }
+
+    #[test]
+    fn test_cached_clone_is_reset_and_fetched_only_when_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        let other = dir.path().join("other");
+        git(dir.path(), &["init", "-q", "--bare", "-b", "main", &remote.to_string_lossy()]);
+        git(dir.path(), &["clone", "-q", &remote.to_string_lossy(), &other.to_string_lossy()]);
+        git(&other, &["config", "user.name", "t"]);
+        git(&other, &["config", "user.email", "t@t"]);
+        let publish = |content: &str| {
+            std::fs::write(other.join("rc"), content).unwrap();
+            git(&other, &["add", "-A"]);
+            git(&other, &["commit", "-qm", content]);
+            git(&other, &["push", "-q", "origin", "HEAD:main"]);
+        };
+        publish("1");
+
+        let url = remote.to_string_lossy().to_string();
+        let config = LocalConfig::new("laptop".to_string(), url.clone());
+        let clone = dir.path().join("clone");
+        clone_repo(&url, &clone, false).unwrap();
+
+        // What an earlier command might leave behind
+        std::fs::write(clone.join("rc"), "edited").unwrap();
+        std::fs::write(clone.join("stray"), "").unwrap();
+        git(&clone, &["checkout", "-qb", "machines/laptop"]);
+
+        // Never fetched since cloning, so it is fetched now
+        publish("2");
+        let ttl = Duration::from_secs(60);
+        reuse_cached_repo(&clone, &config, ttl).unwrap();
+        assert_eq!(git(&clone, &["branch", "--show-current"]), "main");
+        assert_eq!(git(&clone, &["for-each-ref", "refs/heads/"]).lines().count(), 1);
+        assert_eq!(std::fs::read_to_string(clone.join("rc")).unwrap(), "2");
+        assert!(!clone.join("stray").exists());
+        assert!(fetched_within(&clone, ttl));
+
+        // Fresh: the new commit isn't seen until the TTL is up
+        publish("3");
+        reuse_cached_repo(&clone, &config, ttl).unwrap();
+        assert_eq!(std::fs::read_to_string(clone.join("rc")).unwrap(), "2");
+        reuse_cached_repo(&clone, &config, Duration::ZERO).unwrap();
+        assert_eq!(std::fs::read_to_string(clone.join("rc")).unwrap(), "3");
+
+        // A clone of another repo is not reused
+        let elsewhere = LocalConfig::new("laptop".to_string(), "/elsewhere.git".to_string());
+        assert!(reuse_cached_repo(&clone, &elsewhere, ttl).is_err());
+    }
+
+    #[test]
+    fn test_force_unlock_reclaims_dead_pid_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = dir.path().join("tmp-repo.lock");
+
+        // A PID that has certainly exited: a child we already reaped
+        let mut child = std::process::Command::new(std::env::current_exe().unwrap())
+            .arg("--help")
+            .stdout(std::process::Stdio::null())
+            .spawn()
+            .unwrap();
+        let dead_pid = child.id();
+        child.wait().unwrap();
+        std::fs::write(&lock, dead_pid.to_string()).unwrap();
+
+        // Without the flag a fresh lock is respected
+        assert!(acquire_lock_with(&lock, Duration::ZERO, false).is_err());
+
+        acquire_lock_with(&lock, Duration::ZERO, true).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&lock).unwrap(),
+            std::process::id().to_string()
+        );
+    }
+
+    #[test]
+    fn test_force_unlock_keeps_a_lock_retaken_in_between() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock = dir.path().join("tmp-repo.lock");
+
+        // Another process reclaimed the dead holder's lock and took it
+        // before this one got round to removing it
+        std::fs::write(&lock, std::process::id().to_string()).unwrap();
+        assert!(!reclaim_lock_of(&lock, u32::MAX));
+        assert_eq!(std::fs::read_to_string(&lock).unwrap(), std::process::id().to_string());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        // Still the dead holder's: removed
+        std::fs::write(&lock, "4242").unwrap();
+        assert!(reclaim_lock_of(&lock, 4242));
+        assert!(!lock.exists());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
@@ -3,11 +3,23 @@ pub mod operations;
 pub mod repo_layout;
 pub mod safety;
 
-pub use ephemeral::EphemeralRepoGuard;
+pub use ephemeral::{
+    cleanup_ephemeral_repo, lock_state, set_force_unlock, set_no_cache, EphemeralRepoGuard,
+    LockState,
+};
 pub use operations::{
-    checkout_branch, checkout_or_create_branch, checkout_paths, clone_repo, commit_and_push,
-    commit_merge, create_branch, fetch_branch, init_repo, list_branches, merge_branch,
-    merge_dry_run, pull_latest, run_mergetool, set_remote_origin,
+    amend_and_push, check_remote_access, checkout_branch, checkout_or_create_branch, checkout_paths,
+    clone_repo, commit_and_push, commit_info, commit_merge, create_branch, current_branch,
+    delete_remote_branches, ensure_full_history, fetch_branch, fetch_origin, file_blob_history,
+    file_last_commits, git_identity, has_staged_changes, init_repo, is_ancestor, latest_commit,
+    list_branches, list_tree_with_sizes, main_branch, merge_branch, merge_dry_run, merge_file,
+    messages_since, pull_latest, read_blob, remote_has_branch, remote_url, reset_to_origin,
+    resolve_commit, run_mergetool, set_commit_author, set_commit_trailers, set_main_branch,
+    set_remote_origin, set_sign_commits, show_file_at, stage_path, CommitInfo,
+};
+pub use repo_layout::{
+    is_metadata_file, list_all_branch_files, list_machine_branches, read_app_file_bytes,
+    read_app_files, read_app_links, read_app_modes, read_app_tombstones, write_app_links,
+    write_app_modes, write_app_tombstones, BranchFiles,
 };
-pub use repo_layout::read_app_files;
-pub use safety::{check_file_safety, confirm_operation};
+pub use safety::{check_file_safety, confirm_operation, prompt_attempts, set_unattended};
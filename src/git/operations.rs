@@ -1,6 +1,77 @@
+use crate::config::PullStrategy;
 use crate::error::{DriftersError, Result};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// Machine named in the `Drifters-Machine` commit trailer. Trailers are only
+/// added once this is set.
+static TRAILER_MACHINE: OnceLock<String> = OnceLock::new();
+
+/// Append `Drifters-Version` and `Drifters-Machine` trailers to every commit
+/// message this process writes.
+pub fn set_commit_trailers(machine_id: &str) {
+    let _ = TRAILER_MACHINE.set(machine_id.to_string());
+}
+
+/// The shared branch, when the config names one other than `main`.
+static MAIN_BRANCH: OnceLock<String> = OnceLock::new();
+
+/// Use `branch` as the shared branch for the rest of the process.
+pub fn set_main_branch(branch: &str) {
+    let _ = MAIN_BRANCH.set(branch.to_string());
+}
+
+/// The shared branch every machine branch is merged into: `main` unless the
+/// config says otherwise.
+pub fn main_branch() -> &'static str {
+    MAIN_BRANCH.get().map(String::as_str).unwrap_or("main")
+}
+
+/// Author from `git_author_name`/`git_author_email` in the local config,
+/// used in place of git's `user.name`/`user.email`.
+static COMMIT_AUTHOR: OnceLock<(Option<String>, Option<String>)> = OnceLock::new();
+
+/// Commit as `name <email>` instead of git's configured identity. Either may
+/// be left unset to use git's value for that part.
+pub fn set_commit_author(name: Option<&str>, email: Option<&str>) {
+    let _ = COMMIT_AUTHOR.set((name.map(str::to_string), email.map(str::to_string)));
+}
+
+/// Set from `sign_commits` in the local config: sign every commit, merge and
+/// rebase drifters makes.
+static SIGN_COMMITS: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable signing the commits this process makes.
+pub fn set_sign_commits(enabled: bool) {
+    SIGN_COMMITS.store(enabled, Ordering::Relaxed);
+}
+
+/// A single commit, as read by [`commit_info`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommitInfo {
+    pub hash: String,
+    /// Committer time, Unix seconds
+    pub time: i64,
+    pub subject: String,
+    /// From the `Drifters-Machine` trailer, if the commit has one
+    pub machine: Option<String>,
+}
+
+/// `message` with the drifters trailers appended when `machine_id` is given.
+fn with_trailers(message: &str, machine_id: Option<&str>) -> String {
+    match machine_id {
+        Some(id) => format!(
+            "{}\n\nDrifters-Version: {}\nDrifters-Machine: {}",
+            message,
+            env!("CARGO_PKG_VERSION"),
+            id
+        ),
+        None => message.to_string(),
+    }
+}
 
 /// Run a git command inside `cwd`.  Returns trimmed stdout on success or a
 /// `DriftersError::Git` carrying the trimmed stderr on failure.
@@ -19,15 +90,39 @@ fn git_run(cwd: &PathBuf, args: &[&str]) -> Result<String> {
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
-pub fn clone_repo(url: &str, path: &PathBuf) -> Result<()> {
-    log::info!("Cloning repo {} to {:?}", url, path);
+/// Like `git_run`, but returns stdout untouched (for file contents).
+fn git_run_untrimmed(cwd: &PathBuf, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(cwd)
+        .args(args)
+        .output()?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(DriftersError::Git(stderr));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Clone `url` into `path`, checking out the shared branch. A `shallow`
+/// clone has only the tip of each branch; see [`ensure_full_history`].
+pub fn clone_repo(url: &str, path: &PathBuf, shallow: bool) -> Result<()> {
+    log::info!("Cloning repo {} ({}) to {:?}", url, main_branch(), path);
 
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent)?;
     }
 
-    let output = Command::new("git")
-        .arg("clone")
+    let mut clone = Command::new("git");
+    clone.args(["clone", "--branch", main_branch()]);
+    if shallow {
+        // Every branch's tip, not just the shared one: machine branches are
+        // read and merged from the same clone
+        clone.args(["--depth", "1", "--no-single-branch"]);
+    }
+    let output = clone
         .arg(url)
         .arg(path)
         .output()?;
@@ -44,10 +139,22 @@ pub fn clone_repo(url: &str, path: &PathBuf) -> Result<()> {
     Ok(())
 }
 
+/// Fetch the rest of the history if the clone is shallow. Commands that read
+/// past commits (logs, merge bases, old file versions) call this first.
+pub fn ensure_full_history(repo_path: &PathBuf) -> Result<()> {
+    if git_run(repo_path, &["rev-parse", "--is-shallow-repository"])? == "true" {
+        log::debug!("Shallow clone, fetching full history");
+        git_run(repo_path, &["fetch", "--unshallow", "origin"])?;
+    }
+    Ok(())
+}
+
 pub fn init_repo(path: &PathBuf) -> Result<()> {
     log::info!("Initializing new repository at {:?}", path);
     std::fs::create_dir_all(path)?;
-    git_run(path, &["init"])?;
+    // drifters always works against its shared branch, whatever
+    // init.defaultBranch says
+    git_run(path, &["init", &format!("--initial-branch={}", main_branch())])?;
     log::info!("Successfully initialized repository");
     Ok(())
 }
@@ -57,48 +164,195 @@ pub fn set_remote_origin(repo_path: &PathBuf, url: &str) -> Result<()> {
     Ok(())
 }
 
-pub fn commit_and_push(repo_path: &PathBuf, message: &str) -> Result<()> {
-    log::info!("Committing and pushing: {}", message);
+/// Check that `url` can be read (`git ls-remote`), without prompting for
+/// HTTPS credentials. The error carries git's explanation.
+pub fn check_remote_access(url: &str) -> Result<()> {
+    let output = Command::new("git")
+        .args(["ls-remote", "--heads", url])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(DriftersError::Git(stderr));
+    }
+    Ok(())
+}
 
-    // Stage all changes (tracked + new files)
-    git_run(repo_path, &["add", "."])?;
+/// Whether `origin` already has `branch` (`git ls-remote --heads`).
+pub fn remote_has_branch(repo_path: &PathBuf, branch: &str) -> Result<bool> {
+    let output = git_run(repo_path, &["ls-remote", "--heads", "origin", branch])?;
+    Ok(!output.is_empty())
+}
 
-    // Guard: nothing-to-commit check.
+/// Delete `branches` from `origin` in one atomic push: all of them go, or
+/// none do.
+pub fn delete_remote_branches(repo_path: &PathBuf, branches: &[&str]) -> Result<()> {
+    let mut args = vec!["push", "--atomic", "origin", "--delete"];
+    args.extend(branches);
+    git_run(repo_path, &args)?;
+    Ok(())
+}
+
+/// Whether the index has changes to commit.
+pub fn has_staged_changes(repo_path: &PathBuf) -> Result<bool> {
     // `git diff --cached --quiet` exits 0 when the index is clean (nothing staged).
     let staged = Command::new("git")
         .arg("-C")
         .arg(repo_path)
         .args(["diff", "--cached", "--quiet"])
         .status()?;
+    Ok(!staged.success())
+}
+
+pub fn commit_and_push(repo_path: &PathBuf, message: &str) -> Result<()> {
+    log::info!("Committing and pushing: {}", message);
 
-    if staged.success() {
+    // Stage all changes (tracked + new files)
+    git_run(repo_path, &["add", "."])?;
+
+    // Guard: nothing-to-commit check.
+    if !has_staged_changes(repo_path)? {
         log::debug!("Nothing to commit (index clean), skipping push");
         return Ok(());
     }
 
-    // Read author from git config; fall back to sensible defaults so drifters
-    // works even on machines with no global git user config.
-    let name = git_run(repo_path, &["config", "user.name"])
-        .unwrap_or_else(|_| "Drifters User".to_string());
-    let email = git_run(repo_path, &["config", "user.email"])
-        .unwrap_or_else(|_| "drifters@localhost".to_string());
+    commit_as_user(repo_path, message, &[])?;
+    log::debug!("Created commit: {}", message);
+
+    push_to_remote(repo_path)
+}
 
+/// Replace the branch's last commit — already on the remote as `previous` —
+/// with one that also includes the current changes, and force-push it. The
+/// push is refused if the remote branch no longer points at `previous`.
+pub fn amend_and_push(repo_path: &PathBuf, message: &str, previous: &str) -> Result<()> {
+    log::info!("Amending {} and pushing: {}", previous, message);
+
+    git_run(repo_path, &["add", "."])?;
+    if !has_staged_changes(repo_path)? {
+        log::debug!("Nothing to amend (index clean), skipping push");
+        return Ok(());
+    }
+
+    commit_as_user(repo_path, message, &["--amend"])?;
+    log::debug!("Amended commit: {}", message);
+
+    let branch = git_run(repo_path, &["rev-parse", "--abbrev-ref", "HEAD"])?;
     git_run(
         repo_path,
         &[
-            "-c", &format!("user.name={}", name),
-            "-c", &format!("user.email={}", email),
-            "commit", "-m", message,
+            "push",
+            &format!("--force-with-lease={}:{}", branch, previous),
+            "origin",
+            &branch,
         ],
     )?;
+    log::info!("Successfully force-pushed amended commit");
+    Ok(())
+}
 
-    log::debug!("Created commit: {}", message);
+/// `-c` options for git commands that create commits: the committer, and
+/// commit signing when `sign` is set.
+fn commit_config(repo_path: &PathBuf, sign: bool) -> Result<Vec<String>> {
+    let (name, email) = COMMIT_AUTHOR.get().cloned().unwrap_or_default();
+    let (name, email) = commit_identity(repo_path, name, email);
+    let mut config = vec![
+        "-c".to_string(),
+        format!("user.name={}", name),
+        "-c".to_string(),
+        format!("user.email={}", email),
+    ];
 
-    push_to_remote(repo_path)
+    if sign {
+        // Signing itself is left to git, which knows the key and whether to
+        // use gpg, ssh or x509; it just has to have one
+        if git_run(repo_path, &["config", "user.signingkey"]).is_err() {
+            return Err(DriftersError::Config(
+                "sign_commits is enabled, but git has no signing key configured.\n\
+                 Set one with `git config --global user.signingkey <key>`, or turn \
+                 sign_commits off in drifters.toml."
+                    .to_string(),
+            ));
+        }
+        config.extend(["-c".to_string(), "commit.gpgsign=true".to_string()]);
+    }
+    Ok(config)
 }
 
-pub fn pull_latest(repo_path: &PathBuf) -> Result<()> {
-    log::info!("Pulling latest from {:?}", repo_path);
+/// The identity to commit as: drifters' own setting, then git config, then a
+/// placeholder so drifters works even where git has no identity at all.
+fn commit_identity(
+    repo_path: &PathBuf,
+    name: Option<String>,
+    email: Option<String>,
+) -> (String, String) {
+    let (git_name, git_email) = git_identity(repo_path);
+    (
+        name.or(git_name).unwrap_or_else(|| "Drifters User".to_string()),
+        email.or(git_email).unwrap_or_else(|| "drifters@localhost".to_string()),
+    )
+}
+
+/// `user.name` and `user.email` as git sees them in `repo_path`.
+pub fn git_identity(repo_path: &PathBuf) -> (Option<String>, Option<String>) {
+    let get = |key| git_run(repo_path, &["config", key]).ok().filter(|v| !v.is_empty());
+    (get("user.name"), get("user.email"))
+}
+
+/// Commit the index with `message` plus trailers and any extra `git commit`
+/// arguments.
+fn commit_as_user(repo_path: &PathBuf, message: &str, extra: &[&str]) -> Result<()> {
+    let config = commit_config(repo_path, SIGN_COMMITS.load(Ordering::Relaxed))?;
+    let full_message = with_trailers(message, TRAILER_MACHINE.get().map(String::as_str));
+
+    let mut args: Vec<&str> = config.iter().map(String::as_str).collect();
+    args.extend(["commit", "-m", &full_message]);
+    args.extend_from_slice(extra);
+    git_run(repo_path, &args)?;
+    Ok(())
+}
+
+/// Fetch every branch from origin, dropping remote-tracking refs of
+/// branches deleted there. An empty remote is not an error.
+pub fn fetch_origin(repo_path: &PathBuf) -> Result<()> {
+    match git_run(repo_path, &["fetch", "--prune", "origin"]) {
+        Err(DriftersError::Git(e)) if e.contains("no matching remote head") => {
+            log::debug!("Fetch found no remote head (empty repo?)");
+            Ok(())
+        }
+        result => result.map(|_| ()),
+    }
+}
+
+/// URL of the `origin` remote.
+pub fn remote_url(repo_path: &PathBuf) -> Result<String> {
+    git_run(repo_path, &["remote", "get-url", "origin"])
+}
+
+/// Discard everything a previous command left in the clone: check out the
+/// shared branch at origin's tip, drop uncommitted and untracked changes,
+/// unfinished merges and every other local branch. Afterwards the clone
+/// looks as it would freshly cloned, as of the last fetch.
+pub fn reset_to_origin(repo_path: &PathBuf) -> Result<()> {
+    let main = main_branch();
+    let origin_main = format!("origin/{}", main);
+    git_run(repo_path, &["reset", "-q", "--hard"])?;
+    git_run(repo_path, &["clean", "-q", "-fd"])?;
+    if git_run(repo_path, &["rev-parse", "--verify", "-q", &origin_main]).is_err() {
+        // Nothing pushed yet: keep whatever the clone has
+        return Ok(());
+    }
+    git_run(repo_path, &["checkout", "-q", "-B", main, &origin_main])?;
+    let branches =
+        git_run(repo_path, &["for-each-ref", "--format=%(refname:short)", "refs/heads/"])?;
+    for branch in branches.lines().filter(|branch| *branch != main) {
+        git_run(repo_path, &["branch", "-q", "-D", branch])?;
+    }
+    Ok(())
+}
+
+pub fn pull_latest(repo_path: &PathBuf, strategy: PullStrategy) -> Result<()> {
+    log::info!("Pulling latest from {:?} ({:?})", repo_path, strategy);
 
     // Fetch first (always works even on empty repos)
     let fetch = Command::new("git")
@@ -137,7 +391,7 @@ pub fn pull_latest(repo_path: &PathBuf) -> Result<()> {
     let output = Command::new("git")
         .arg("-C")
         .arg(repo_path)
-        .args(["pull", "--rebase"])
+        .args(strategy.git_args())
         .output()?;
 
     if !output.status.success() {
@@ -147,6 +401,26 @@ pub fn pull_latest(repo_path: &PathBuf) -> Result<()> {
             log::debug!("No tracking branch, skipping pull");
             return Ok(());
         }
+        if strategy == PullStrategy::FfOnly && stderr.contains("Not possible to fast-forward") {
+            return Err(DriftersError::Git(format!(
+                "Local clone has diverged from origin and pull_strategy is \"ff-only\"\n\
+                 Resolve it by hand, or set pull_strategy = \"rebase\" in drifters.toml\nError: {}",
+                stderr
+            )));
+        }
+        // A conflicting rebase or merge stops half-way; undo it so the
+        // checkout is never left in that state, whatever the caller does next
+        let abort: &[&str] = match strategy {
+            PullStrategy::Rebase => &["rebase", "--abort"],
+            PullStrategy::Merge => &["merge", "--abort"],
+            PullStrategy::FfOnly => &[],
+        };
+        if !abort.is_empty() && git_run(repo_path, abort).is_ok() {
+            return Err(DriftersError::Diverged(format!(
+                "its history and the remote's conflict, so pulling was aborted\nError: {}",
+                stderr
+            )));
+        }
         return Err(DriftersError::Git(format!(
             "Failed to pull latest changes\nError: {}",
             stderr
@@ -157,33 +431,74 @@ pub fn pull_latest(repo_path: &PathBuf) -> Result<()> {
     Ok(())
 }
 
+/// How many times a push rejected because the remote moved is retried.
+const PUSH_RETRIES: usize = 3;
+
+/// Push the current branch. If another machine pushed to it since this clone
+/// was pulled, rebase onto its work and try again, up to `PUSH_RETRIES` times.
 fn push_to_remote(repo_path: &PathBuf) -> Result<()> {
     let branch = git_run(repo_path, &["rev-parse", "--abbrev-ref", "HEAD"])
-        .unwrap_or_else(|_| "main".to_string());
+        .unwrap_or_else(|_| main_branch().to_string());
 
     let remote_url = git_run(repo_path, &["remote", "get-url", "origin"])
         .unwrap_or_else(|_| "unknown".to_string());
 
-    log::debug!("Pushing {} to origin", branch);
+    let mut retries = 0;
+    loop {
+        log::debug!("Pushing {} to origin", branch);
 
-    let output = Command::new("git")
-        .arg("-C")
-        .arg(repo_path)
-        .args(["push", "-u", "origin", &branch])
-        .output()?;
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(repo_path)
+            .args(["push", "-u", "origin", &branch])
+            .output()?;
 
-    if !output.status.success() {
+        if output.status.success() {
+            break;
+        }
         let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
-        return Err(DriftersError::Git(format!(
-            "Failed to push to remote\nRepository URL: {}\nError: {}",
-            remote_url, stderr
-        )));
+        if retries == PUSH_RETRIES || !remote_moved(&stderr) {
+            return Err(DriftersError::Git(format!(
+                "Failed to push to remote\nRepository URL: {}\nError: {}",
+                remote_url, stderr
+            )));
+        }
+        retries += 1;
+        log::warn!(
+            "origin/{} moved since it was pulled; rebasing and retrying the push ({}/{})",
+            branch,
+            retries,
+            PUSH_RETRIES
+        );
+        rebase_onto_remote(repo_path, &branch)?;
     }
 
     log::info!("Successfully pushed to remote");
     Ok(())
 }
 
+/// Whether a push was rejected only because the remote branch has commits
+/// this clone doesn't (as opposed to hooks, protection rules, auth...).
+fn remote_moved(stderr: &str) -> bool {
+    stderr.contains("(fetch first)") || stderr.contains("(non-fast-forward)")
+}
+
+/// Replay this branch's new commits on top of `origin/<branch>`. Merge
+/// commits are kept. A conflicting rebase is aborted and reported.
+fn rebase_onto_remote(repo_path: &PathBuf, branch: &str) -> Result<()> {
+    let config = commit_config(repo_path, SIGN_COMMITS.load(Ordering::Relaxed))?;
+    let mut pull: Vec<&str> = config.iter().map(String::as_str).collect();
+    pull.extend(["pull", "--rebase=merges", "origin", branch]);
+    if let Err(e) = git_run(repo_path, &pull) {
+        let _ = git_run(repo_path, &["rebase", "--abort"]);
+        return Err(DriftersError::Diverged(format!(
+            "changes pushed to {} meanwhile conflict with this one, so it was not pushed\n{}",
+            branch, e
+        )));
+    }
+    Ok(())
+}
+
 // ─── Branch operations ──────────────────────────────────────────────────────
 
 /// Create and checkout a new branch from the current HEAD.
@@ -192,6 +507,11 @@ pub fn create_branch(repo_path: &PathBuf, branch_name: &str) -> Result<()> {
     Ok(())
 }
 
+/// Name of the branch checked out in `repo_path`.
+pub fn current_branch(repo_path: &PathBuf) -> Result<String> {
+    git_run(repo_path, &["rev-parse", "--abbrev-ref", "HEAD"])
+}
+
 /// Checkout an existing branch.
 pub fn checkout_branch(repo_path: &PathBuf, branch_name: &str) -> Result<()> {
     git_run(repo_path, &["checkout", branch_name])?;
@@ -205,29 +525,224 @@ pub fn checkout_paths(repo_path: &PathBuf, source_ref: &str, pathspec: &str) ->
     Ok(())
 }
 
+/// List every file in `git_ref` with its size in bytes (`git ls-tree -r -l`).
+/// Paths are relative to the repo root.
+pub fn list_tree_with_sizes(repo_path: &PathBuf, git_ref: &str) -> Result<Vec<(String, u64)>> {
+    let output = git_run(repo_path, &["ls-tree", "-r", "-l", "-z", git_ref])?;
+    Ok(output
+        .split('\0')
+        .filter_map(|entry| {
+            // "<mode> <type> <object> <size>\t<path>"
+            let (meta, path) = entry.split_once('\t')?;
+            let size = meta.split_whitespace().nth(3)?.parse().ok()?;
+            Some((path.to_string(), size))
+        })
+        .collect())
+}
+
+/// The newest commit on any branch (local or remote-tracking), as
+/// (committer time in Unix seconds, subject). `None` in an empty repo.
+pub fn latest_commit(repo_path: &PathBuf) -> Result<Option<(i64, String)>> {
+    let output = git_run(repo_path, &["log", "-1", "--all", "--date-order", "--format=%ct %s"])?;
+    let (time, subject) = output.split_once(' ').unwrap_or((&output, ""));
+    Ok(time.parse().ok().map(|time| (time, subject.to_string())))
+}
+
+/// The last commit on `git_ref` that touched each file under `dir`, found in
+/// one `git log` pass: repo-relative path → committer time (Unix seconds)
+/// and the machine from the commit's `Drifters-Machine` trailer, if any.
+/// Files no commit touched are left out.
+///
+/// History is simplified for `dir` as a whole rather than per file, so a
+/// file's change on a merged branch counts even where the merge kept the
+/// other side's version of that file.
+pub fn file_last_commits(
+    repo_path: &PathBuf,
+    git_ref: &str,
+    dir: &str,
+) -> Result<HashMap<String, (i64, Option<String>)>> {
+    // Each commit is \x01<time> <machine>\0, then its files, each ending in
+    // \0. Merges list only the files they changed relative to every
+    // parent, i.e. those resolved by hand rather than taken from one side.
+    let output = git_run_untrimmed(
+        repo_path,
+        &[
+            "log",
+            "-z",
+            "--name-only",
+            "--no-renames",
+            "--cc",
+            "--format=%x01%ct %(trailers:key=Drifters-Machine,valueonly,separator=%x20)",
+            git_ref,
+            "--",
+            dir,
+        ],
+    )?;
+
+    let mut commits = HashMap::new();
+    for record in output.split('\x01').filter(|record| !record.is_empty()) {
+        let mut fields = record.split('\0');
+        let header = fields.next().unwrap_or_default();
+        let (time, machine) = header.split_once(' ').unwrap_or((header, ""));
+        let Ok(time) = time.parse::<i64>() else {
+            continue;
+        };
+        let machine = Some(machine.trim()).filter(|m| !m.is_empty()).map(str::to_string);
+        for path in fields.map(|field| field.trim_start_matches('\n')) {
+            if !path.is_empty() {
+                // Newest first, so the first commit seen for a file is its last
+                commits.entry(path.to_string()).or_insert_with(|| (time, machine.clone()));
+            }
+        }
+    }
+    Ok(commits)
+}
+
+/// The commit `git_ref` points at, or `None` if it doesn't exist (e.g. a
+/// branch with no commits yet).
+pub fn commit_info(repo_path: &PathBuf, git_ref: &str) -> Result<Option<CommitInfo>> {
+    let format = "--format=%H%x00%ct%x00%s%x00\
+                  %(trailers:key=Drifters-Machine,valueonly,separator=%x20)";
+    let Ok(output) = git_run(repo_path, &["log", "-1", format, git_ref, "--"]) else {
+        return Ok(None);
+    };
+    let mut fields = output.split('\0');
+    let (Some(hash), Some(time), Some(subject)) = (fields.next(), fields.next(), fields.next())
+    else {
+        return Ok(None);
+    };
+    let machine = fields.next().map(str::trim).filter(|m| !m.is_empty());
+    Ok(time.parse().ok().map(|time| CommitInfo {
+        hash: hash.to_string(),
+        time,
+        subject: subject.to_string(),
+        machine: machine.map(str::to_string),
+    }))
+}
+
+/// Full messages of the commits on `git_ref` made at or after `since` (Unix
+/// seconds), newest first.
+pub fn messages_since(repo_path: &PathBuf, git_ref: &str, since: i64) -> Result<Vec<String>> {
+    let output = git_run(
+        repo_path,
+        &["log", &format!("--since=@{}", since), "--format=%B%x00", git_ref, "--"],
+    )?;
+    Ok(output
+        .split('\0')
+        .map(str::trim)
+        .filter(|message| !message.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Whether `commit` is part of `git_ref`'s history.
+pub fn is_ancestor(repo_path: &PathBuf, commit: &str, git_ref: &str) -> Result<bool> {
+    let status = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(["merge-base", "--is-ancestor", commit, git_ref])
+        .status()?;
+    Ok(status.success())
+}
+
+/// Stage a path (`git add -- <path>`).
+pub fn stage_path(repo_path: &PathBuf, path: &str) -> Result<()> {
+    git_run(repo_path, &["add", "--", path])?;
+    Ok(())
+}
+
+/// Resolve `rev` to a full commit hash; fails if it names no commit here.
+pub fn resolve_commit(repo_path: &PathBuf, rev: &str) -> Result<String> {
+    git_run(repo_path, &["rev-parse", "--verify", "--quiet", &format!("{}^{{commit}}", rev)])
+}
+
+/// Read a file's content as of `git_ref`, untrimmed (`git show <ref>:<path>`).
+pub fn show_file_at(repo_path: &PathBuf, git_ref: &str, path: &str) -> Result<String> {
+    git_run_untrimmed(repo_path, &["show", &format!("{}:{}", git_ref, path)])
+}
+
+/// Blob ids `path` has had on `git_ref`, newest first (deletions skipped).
+pub fn file_blob_history(repo_path: &PathBuf, git_ref: &str, path: &str) -> Result<Vec<String>> {
+    let output = git_run(
+        repo_path,
+        &["log", "--format=", "--raw", "--no-abbrev", git_ref, "--", path],
+    )?;
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            // ":<old mode> <new mode> <old blob> <new blob> <status>\t<path>"
+            let blob = line.split_whitespace().nth(3)?;
+            (!blob.bytes().all(|b| b == b'0')).then(|| blob.to_string())
+        })
+        .collect())
+}
+
+/// Read a blob's content, untrimmed.
+pub fn read_blob(repo_path: &PathBuf, blob: &str) -> Result<String> {
+    git_run_untrimmed(repo_path, &["cat-file", "blob", blob])
+}
+
+/// Line-level three-way merge of `ours` and `theirs` against `base`
+/// (`git merge-file`). Overlapping changes become standard
+/// `<<<<<<<`/`=======`/`>>>>>>>` conflict blocks labelled with `labels`
+/// (ours, base, theirs).
+///
+/// Returns the merged text and whether it contains conflicts.
+pub fn merge_file(
+    repo_path: &PathBuf,
+    ours: &str,
+    base: &str,
+    theirs: &str,
+    labels: [&str; 3],
+) -> Result<(String, bool)> {
+    // git merge-file only works on files; stage the inputs inside .git
+    let scratch = repo_path.join(".git").join("drifters-merge");
+    std::fs::create_dir_all(&scratch)?;
+    let inputs = [("ours", ours), ("base", base), ("theirs", theirs)];
+    for (name, content) in inputs {
+        std::fs::write(scratch.join(name), content)?;
+    }
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo_path)
+        .args(["merge-file", "-p"])
+        .args(["-L", labels[0], "-L", labels[1], "-L", labels[2]])
+        .args(inputs.map(|(name, _)| scratch.join(name)))
+        .output();
+    let _ = std::fs::remove_dir_all(&scratch);
+    let output = output?;
+
+    // Exit code: number of conflicts (capped at 127); anything else is an error
+    match output.status.code() {
+        Some(conflicts @ 0..=127) => Ok((
+            String::from_utf8_lossy(&output.stdout).to_string(),
+            conflicts > 0,
+        )),
+        _ => Err(DriftersError::Git(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        )),
+    }
+}
+
 /// Fetch a specific branch from origin.
 pub fn fetch_branch(repo_path: &PathBuf, branch_name: &str) -> Result<()> {
     git_run(repo_path, &["fetch", "origin", branch_name])?;
     Ok(())
 }
 
-/// Merge a source branch into the current branch.
+/// Merge a source branch into the current branch, with `message` instead of
+/// git's default merge message if given.
 /// Returns Ok(()) on clean merge, Err(MergeConflict) if conflicts arise.
-pub fn merge_branch(repo_path: &PathBuf, source_branch: &str) -> Result<()> {
-    let name = git_run(repo_path, &["config", "user.name"])
-        .unwrap_or_else(|_| "Drifters User".to_string());
-    let email = git_run(repo_path, &["config", "user.email"])
-        .unwrap_or_else(|_| "drifters@localhost".to_string());
+pub fn merge_branch(repo_path: &PathBuf, source_branch: &str, message: Option<&str>) -> Result<()> {
+    let config = commit_config(repo_path, SIGN_COMMITS.load(Ordering::Relaxed))?;
 
-    let result = Command::new("git")
-        .arg("-C")
-        .arg(repo_path)
-        .arg("-c")
-        .arg(format!("user.name={}", name))
-        .arg("-c")
-        .arg(format!("user.email={}", email))
-        .args(["merge", source_branch])
-        .output()?;
+    let mut command = Command::new("git");
+    command.arg("-C").arg(repo_path).args(&config).arg("merge");
+    if let Some(message) = message {
+        command.args(["-m", message]);
+    }
+    let result = command.arg(source_branch).output()?;
 
     if !result.status.success() {
         let stderr = String::from_utf8_lossy(&result.stderr).trim().to_string();
@@ -294,18 +809,13 @@ pub fn checkout_or_create_branch(
 /// Merge a branch without committing (for dry-run).
 /// Returns Ok(true) if clean, Ok(false) if conflicts, and aborts the merge.
 pub fn merge_dry_run(repo_path: &PathBuf, source_branch: &str) -> Result<(bool, String)> {
-    let name = git_run(repo_path, &["config", "user.name"])
-        .unwrap_or_else(|_| "Drifters User".to_string());
-    let email = git_run(repo_path, &["config", "user.email"])
-        .unwrap_or_else(|_| "drifters@localhost".to_string());
+    // Nothing is committed, so there is nothing to sign
+    let config = commit_config(repo_path, false)?;
 
     let result = Command::new("git")
         .arg("-C")
         .arg(repo_path)
-        .arg("-c")
-        .arg(format!("user.name={}", name))
-        .arg("-c")
-        .arg(format!("user.email={}", email))
+        .args(&config)
         .args(["merge", "--no-commit", source_branch])
         .output()?;
 
@@ -327,18 +837,252 @@ pub fn merge_dry_run(repo_path: &PathBuf, source_branch: &str) -> Result<(bool,
 
 /// Commit merge result (after mergetool resolution).
 pub fn commit_merge(repo_path: &PathBuf, message: &str) -> Result<()> {
-    let name = git_run(repo_path, &["config", "user.name"])
-        .unwrap_or_else(|_| "Drifters User".to_string());
-    let email = git_run(repo_path, &["config", "user.email"])
-        .unwrap_or_else(|_| "drifters@localhost".to_string());
+    commit_as_user(repo_path, message, &[])
+}
 
-    git_run(
-        repo_path,
-        &[
-            "-c", &format!("user.name={}", name),
-            "-c", &format!("user.email={}", email),
-            "commit", "-m", message,
-        ],
-    )?;
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_trailers_are_appended_and_parse_as_trailers() {
+        assert_eq!(with_trailers("Update zsh", None), "Update zsh");
+
+        let message = with_trailers("Update zsh configs from laptop", Some("laptop"));
+        assert!(message.starts_with("Update zsh configs from laptop\n\n"));
+
+        // git itself must recognise them as trailers
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("msg");
+        std::fs::write(&path, &message).unwrap();
+        let parsed = git_run(
+            &dir.path().to_path_buf(),
+            &["interpret-trailers", "--parse", &path.to_string_lossy()],
+        )
+        .unwrap();
+        assert_eq!(
+            parsed,
+            format!(
+                "Drifters-Version: {}\nDrifters-Machine: laptop",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+
+    fn git(repo: &std::path::Path, args: &[&str]) {
+        let out = Command::new("git").arg("-C").arg(repo).args(args).output().unwrap();
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    }
+
+    #[test]
+    fn test_conflicting_pull_is_aborted_and_reported_as_diverged() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        let ours = dir.path().join("ours");
+        let theirs = dir.path().join("theirs");
+        git(dir.path(), &["init", "-q", "--bare", "-b", "main", &remote.to_string_lossy()]);
+        for clone in [&ours, &theirs] {
+            git(dir.path(), &["clone", "-q", &remote.to_string_lossy(), &clone.to_string_lossy()]);
+            git(clone, &["config", "user.name", "t"]);
+            git(clone, &["config", "user.email", "t@t"]);
+        }
+        std::fs::write(theirs.join("rc"), "a=1\n").unwrap();
+        git(&theirs, &["add", "-A"]);
+        git(&theirs, &["commit", "-qm", "first"]);
+        git(&theirs, &["push", "-q", "origin", "HEAD:main"]);
+        git(&ours, &["pull", "-q", "origin", "main"]);
+
+        // Both sides change the same line
+        std::fs::write(theirs.join("rc"), "a=2\n").unwrap();
+        git(&theirs, &["commit", "-qam", "theirs"]);
+        git(&theirs, &["push", "-q", "origin", "HEAD:main"]);
+        std::fs::write(ours.join("rc"), "a=3\n").unwrap();
+        git(&ours, &["commit", "-qam", "ours"]);
+
+        for strategy in [PullStrategy::Rebase, PullStrategy::Merge] {
+            let result = pull_latest(&ours, strategy);
+            assert!(matches!(result, Err(DriftersError::Diverged(_))), "{:?}", result);
+            // Nothing is left half-done
+            assert!(!ours.join(".git/rebase-merge").exists());
+            assert!(!ours.join(".git/MERGE_HEAD").exists());
+            assert_eq!(std::fs::read_to_string(ours.join("rc")).unwrap(), "a=3\n");
+        }
+    }
+
+    #[test]
+    fn test_messages_since_includes_bodies() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().to_path_buf();
+        git(&repo, &["init", "-q", "-b", "main"]);
+        git(&repo, &["config", "user.name", "t"]);
+        git(&repo, &["config", "user.email", "t@t"]);
+        std::fs::write(repo.join("rc"), "a=1\n").unwrap();
+        git(&repo, &["add", "-A"]);
+        git(&repo, &["commit", "-qm", "Bump the prompt", "-m", "Merge zsh from machines/laptop"]);
+        git(&repo, &["commit", "-q", "--allow-empty", "-m", "Update configs from desk"]);
+
+        let messages = messages_since(&repo, "HEAD", 0).unwrap();
+        assert_eq!(
+            messages,
+            vec![
+                "Update configs from desk".to_string(),
+                "Bump the prompt\n\nMerge zsh from machines/laptop".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_file_last_commits_finds_each_files_last_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().to_path_buf();
+        git(&repo, &["init", "-q", "-b", "main"]);
+        git(&repo, &["config", "user.name", "t"]);
+        git(&repo, &["config", "user.email", "t@t"]);
+        let commit = |machine: &str| {
+            git(&repo, &["add", "-A"]);
+            let trailer = format!("Drifters-Machine: {}", machine);
+            git(&repo, &["commit", "-qm", machine, "--trailer", &trailer]);
+        };
+        let write = |path: &str, content: &str| {
+            std::fs::create_dir_all(repo.join(path).parent().unwrap()).unwrap();
+            std::fs::write(repo.join(path), content).unwrap();
+        };
+
+        write("apps/a/x", "1");
+        write("apps/a/y z", "1");
+        write("apps/b/x", "1");
+        commit("first");
+        git(&repo, &["checkout", "-qb", "side"]);
+        write("apps/a/x", "2");
+        commit("side");
+        git(&repo, &["checkout", "-q", "main"]);
+        write("apps/a/y z", "2");
+        commit("main");
+        git(&repo, &["merge", "-q", "--no-ff", "-m", "merge", "side"]);
+
+        // A merge that changes a file beyond both parents counts as its edit
+        git(&repo, &["checkout", "-q", "side"]);
+        write("apps/a/x", "3");
+        commit("side again");
+        git(&repo, &["checkout", "-q", "main"]);
+        git(&repo, &["merge", "-q", "--no-ff", "--no-commit", "side"]);
+        write("apps/a/x", "resolved");
+        commit("resolved");
+
+        let commits = file_last_commits(&repo, "HEAD", "apps/a/").unwrap();
+        assert_eq!(commits.len(), 2, "{:?}", commits);
+        assert_eq!(commits["apps/a/x"].1.as_deref(), Some("resolved"));
+        assert_eq!(commits["apps/a/y z"].1.as_deref(), Some("main"));
+        let side = file_last_commits(&repo, "side", "apps/a/").unwrap();
+        assert_eq!(side["apps/a/x"].1.as_deref(), Some("side again"));
+        assert_eq!(side["apps/a/y z"].1.as_deref(), Some("first"));
+        assert!(file_last_commits(&repo, "HEAD", "apps/none/").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_shallow_clone_fetches_history_on_demand() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("source");
+        git(dir.path(), &["init", "-q", "-b", "main", &source.to_string_lossy()]);
+        git(&source, &["config", "user.name", "t"]);
+        git(&source, &["config", "user.email", "t@t"]);
+        for n in 1..=3 {
+            std::fs::write(source.join("rc"), format!("a={}\n", n)).unwrap();
+            git(&source, &["add", "-A"]);
+            git(&source, &["commit", "-qm", &format!("commit {}", n)]);
+        }
+        git(&source, &["branch", "machines/laptop"]);
+
+        // --depth is ignored for plain local paths
+        let url = format!("file://{}", source.display());
+        let clone = dir.path().join("clone");
+        clone_repo(&url, &clone, true).unwrap();
+        let count = |r: &str| git_run(&clone, &["rev-list", "--count", r]).unwrap();
+        assert_eq!(count("HEAD"), "1");
+        assert!(resolve_commit(&clone, "origin/machines/laptop").is_ok());
+
+        ensure_full_history(&clone).unwrap();
+        assert_eq!(count("HEAD"), "3");
+        // Already complete: nothing to do
+        ensure_full_history(&clone).unwrap();
+    }
+
+    #[test]
+    fn test_push_rebases_onto_a_remote_that_moved() {
+        let dir = tempfile::tempdir().unwrap();
+        let remote = dir.path().join("remote.git");
+        let ours = dir.path().join("ours");
+        let theirs = dir.path().join("theirs");
+        git(dir.path(), &["init", "-q", "--bare", "-b", "main", &remote.to_string_lossy()]);
+        for clone in [&ours, &theirs] {
+            git(dir.path(), &["clone", "-q", &remote.to_string_lossy(), &clone.to_string_lossy()]);
+            git(clone, &["config", "user.name", "t"]);
+            git(clone, &["config", "user.email", "t@t"]);
+        }
+        std::fs::write(theirs.join("a"), "1\n").unwrap();
+        commit_and_push(&theirs, "first").unwrap();
+        git(&ours, &["pull", "-q", "origin", "main"]);
+
+        // Another machine pushes between our pull and our push
+        std::fs::write(theirs.join("b"), "1\n").unwrap();
+        commit_and_push(&theirs, "theirs").unwrap();
+        std::fs::write(ours.join("c"), "1\n").unwrap();
+        commit_and_push(&ours, "ours").unwrap();
+
+        let log = git_run(&remote, &["log", "--format=%s", "main"]).unwrap();
+        assert_eq!(log, "ours\ntheirs\nfirst");
+
+        // Conflicting work is not pushed, and the clone is left clean
+        std::fs::write(theirs.join("a"), "2\n").unwrap();
+        commit_and_push(&theirs, "theirs again").unwrap();
+        std::fs::write(ours.join("a"), "3\n").unwrap();
+        let result = commit_and_push(&ours, "ours again");
+        assert!(matches!(result, Err(DriftersError::Diverged(_))), "{:?}", result);
+        assert!(!ours.join(".git/rebase-merge").exists());
+        assert!(!remote_moved("! [remote rejected] main -> main (protected branch hook declined)"));
+    }
+
+    #[test]
+    fn test_signing_needs_a_key_and_sets_gpgsign() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().to_path_buf();
+        git(&repo, &["init", "-q"]);
+        git(&repo, &["config", "user.name", "t"]);
+        git(&repo, &["config", "user.email", "t@t"]);
+
+        let unsigned = commit_config(&repo, false).unwrap();
+        assert_eq!(unsigned, ["-c", "user.name=t", "-c", "user.email=t@t"]);
+
+        // Unless the machine running the tests has a global key
+        if git_run(&repo, &["config", "user.signingkey"]).is_err() {
+            let err = commit_config(&repo, true).unwrap_err().to_string();
+            assert!(err.contains("no signing key"), "{}", err);
+        }
+
+        git(&repo, &["config", "user.signingkey", "ABCD1234"]);
+        let signed = commit_config(&repo, true).unwrap();
+        assert_eq!(signed[4..], ["-c", "commit.gpgsign=true"]);
+    }
+
+    #[test]
+    fn test_configured_author_takes_precedence_over_git_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().to_path_buf();
+        git(&repo, &["init", "-q"]);
+        git(&repo, &["config", "user.name", "Git Name"]);
+        git(&repo, &["config", "user.email", "git@example.com"]);
+
+        assert_eq!(
+            commit_identity(&repo, None, None),
+            ("Git Name".to_string(), "git@example.com".to_string())
+        );
+        assert_eq!(
+            commit_identity(&repo, Some("Jo".to_string()), None),
+            ("Jo".to_string(), "git@example.com".to_string())
+        );
+        assert_eq!(
+            commit_identity(&repo, Some("Jo".to_string()), Some("jo@example.com".to_string())),
+            ("Jo".to_string(), "jo@example.com".to_string())
+        );
+    }
 }
@@ -1,11 +1,35 @@
 use crate::error::Result;
-use std::collections::HashMap;
+use crate::git::{list_branches, list_tree_with_sizes, main_branch};
+use crate::sync::map_bounded;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::Path;
 
-/// Read all files for an app on the current branch.
-/// Returns a map of filename → content.
-pub fn read_app_files(repo_path: &Path, app_name: &str) -> Result<HashMap<String, String>> {
+/// Files on one branch: (repo-relative path, size in bytes).
+pub type BranchFiles = Vec<(String, u64)>;
+
+/// Per-app metadata file in `apps/<app>/` recording each synced file's Unix
+/// permission bits (filename → octal mode). Not itself a synced file.
+pub const MODES_FILE: &str = ".drifters-modes.toml";
+
+/// Per-app metadata file in `apps/<app>/` recording files synced as
+/// symlinks (filename → link target), for apps with `follow_symlinks`.
+pub const LINKS_FILE: &str = ".drifters-links.toml";
+
+/// Per-app metadata file in `apps/<app>/` recording deleted files
+/// (filename → deletion time, Unix seconds), so merges and pulls remove
+/// them elsewhere too.
+pub const TOMBSTONES_FILE: &str = ".drifters-deleted.toml";
+
+/// Whether `filename` in `apps/<app>/` is drifters metadata rather than a
+/// synced file.
+pub fn is_metadata_file(filename: &str) -> bool {
+    [MODES_FILE, LINKS_FILE, TOMBSTONES_FILE].contains(&filename)
+}
+
+/// Read all files for an app on the current branch, text or binary.
+/// Returns a map of filename → raw content.
+pub fn read_app_file_bytes(repo_path: &Path, app_name: &str) -> Result<HashMap<String, Vec<u8>>> {
     let app_dir = repo_path.join("apps").join(app_name);
     let mut files = HashMap::new();
 
@@ -22,11 +46,161 @@ pub fn read_app_files(repo_path: &Path, app_name: &str) -> Result<HashMap<String
         }
 
         if let Some(filename) = path.file_name().and_then(|s| s.to_str()) {
-            let content = fs::read_to_string(&path)?;
-            files.insert(filename.to_string(), content);
+            if is_metadata_file(filename) {
+                continue;
+            }
+            files.insert(filename.to_string(), fs::read(&path)?);
         }
     }
 
     Ok(files)
 }
 
+/// Read the text files for an app on the current branch.
+/// Returns a map of filename → content; binary (non-UTF-8) files are left
+/// out, see [`read_app_file_bytes`].
+pub fn read_app_files(repo_path: &Path, app_name: &str) -> Result<HashMap<String, String>> {
+    Ok(read_app_file_bytes(repo_path, app_name)?
+        .into_iter()
+        .filter_map(|(filename, bytes)| match String::from_utf8(bytes) {
+            Ok(content) => Some((filename, content)),
+            Err(_) => {
+                log::debug!("Skipping binary file {}/{}", app_name, filename);
+                None
+            }
+        })
+        .collect())
+}
+
+/// Read the recorded permission bits of an app's files on the current
+/// branch. Empty if none were recorded (e.g. pushed from Windows).
+pub fn read_app_modes(repo_path: &Path, app_name: &str) -> Result<BTreeMap<String, u32>> {
+    let path = repo_path.join("apps").join(app_name).join(MODES_FILE);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    let recorded: BTreeMap<String, String> = toml::from_str(&fs::read_to_string(&path)?)?;
+    Ok(recorded
+        .into_iter()
+        .filter_map(|(file, mode)| Some((file, u32::from_str_radix(&mode, 8).ok()?)))
+        .collect())
+}
+
+/// Record permission bits for an app's files, keeping entries for files not
+/// in `modes`.
+pub fn write_app_modes(
+    repo_path: &Path,
+    app_name: &str,
+    modes: &BTreeMap<String, u32>,
+) -> Result<()> {
+    let mut all = read_app_modes(repo_path, app_name)?;
+    all.extend(modes.iter().map(|(file, mode)| (file.clone(), *mode)));
+    if all.is_empty() {
+        return Ok(());
+    }
+    let recorded: BTreeMap<&String, String> =
+        all.iter().map(|(file, mode)| (file, format!("{:o}", mode))).collect();
+    let dir = repo_path.join("apps").join(app_name);
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(MODES_FILE), toml::to_string(&recorded)?)?;
+    Ok(())
+}
+
+/// Read the files of an app recorded as symlinks on the current branch:
+/// filename → link target.
+pub fn read_app_links(repo_path: &Path, app_name: &str) -> Result<BTreeMap<String, String>> {
+    let path = repo_path.join("apps").join(app_name).join(LINKS_FILE);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    Ok(toml::from_str(&fs::read_to_string(&path)?)?)
+}
+
+/// Update an app's recorded symlinks: `Some(target)` records a file as a
+/// link, `None` records it as a regular file again. Files not in `updates`
+/// keep their entries.
+pub fn write_app_links(
+    repo_path: &Path,
+    app_name: &str,
+    updates: &BTreeMap<String, Option<String>>,
+) -> Result<()> {
+    let mut links = read_app_links(repo_path, app_name)?;
+    let before = links.clone();
+    for (file, target) in updates {
+        match target {
+            Some(target) => links.insert(file.clone(), target.clone()),
+            None => links.remove(file),
+        };
+    }
+    if links == before {
+        return Ok(());
+    }
+    let path = repo_path.join("apps").join(app_name).join(LINKS_FILE);
+    if links.is_empty() {
+        fs::remove_file(&path)?;
+    } else {
+        fs::create_dir_all(path.parent().unwrap_or(repo_path))?;
+        fs::write(&path, toml::to_string(&links)?)?;
+    }
+    Ok(())
+}
+
+/// Read an app's deleted files on the current branch: filename → deletion
+/// time (Unix seconds).
+pub fn read_app_tombstones(repo_path: &Path, app_name: &str) -> Result<BTreeMap<String, i64>> {
+    let path = repo_path.join("apps").join(app_name).join(TOMBSTONES_FILE);
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+    Ok(toml::from_str(&fs::read_to_string(&path)?)?)
+}
+
+/// Replace an app's recorded deletions; an empty map removes the file.
+pub fn write_app_tombstones(
+    repo_path: &Path,
+    app_name: &str,
+    tombstones: &BTreeMap<String, i64>,
+) -> Result<()> {
+    let path = repo_path.join("apps").join(app_name).join(TOMBSTONES_FILE);
+    if tombstones.is_empty() {
+        if path.exists() {
+            fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+    fs::create_dir_all(path.parent().unwrap_or(repo_path))?;
+    fs::write(&path, toml::to_string(tombstones)?)?;
+    Ok(())
+}
+
+/// The remote's machine branches (`machines/<id>`), in name order.
+pub fn list_machine_branches(repo_path: &Path) -> Result<Vec<String>> {
+    let mut machine_branches: Vec<String> = list_branches(&repo_path.to_path_buf())?
+        .into_iter()
+        .filter_map(|b| b.strip_prefix("origin/").map(str::to_string))
+        .filter(|b| b.starts_with("machines/"))
+        .collect();
+    machine_branches.sort();
+    machine_branches.dedup();
+    Ok(machine_branches)
+}
+
+/// List the files of `main` and of every machine branch, without checking
+/// anything out. Machine branches are read from their remote-tracking refs
+/// and returned in name order after `main`.
+pub fn list_all_branch_files(repo_path: &Path) -> Result<Vec<(String, BranchFiles)>> {
+    let repo_path = repo_path.to_path_buf();
+
+    let machine_branches = list_machine_branches(&repo_path)?;
+
+    // One `git ls-tree` per branch, a few at a time
+    let main = main_branch();
+    let mut branches = vec![(main.to_string(), list_tree_with_sizes(&repo_path, main)?)];
+    let listed = map_bounded(&machine_branches, |branch| {
+        list_tree_with_sizes(&repo_path, &format!("origin/{}", branch))
+    });
+    for (branch, files) in machine_branches.into_iter().zip(listed) {
+        branches.push((branch, files?));
+    }
+    Ok(branches)
+}
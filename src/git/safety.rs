@@ -1,5 +1,6 @@
 use crate::error::{DriftersError, Result};
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 const EMPTY_FILE_THRESHOLD: u64 = 10; // bytes
 const SIZE_RATIO_WARNING: f64 = 10.0; // Warn if file is 10x smaller
@@ -55,24 +56,82 @@ pub fn check_file_safety(local_path: &PathBuf, repo_path: &PathBuf) -> Result<bo
     Ok(true)
 }
 
+/// Default number of times an interactive prompt re-asks after unrecognised
+/// input before giving up.
+pub const DEFAULT_PROMPT_ATTEMPTS: usize = 3;
+
+/// Environment variable overriding `DEFAULT_PROMPT_ATTEMPTS` (useful for slow
+/// typists, assistive tooling, and tests).
+pub const PROMPT_ATTEMPTS_ENV: &str = "DRIFTERS_PROMPT_ATTEMPTS";
+
+/// Number of attempts interactive prompts allow, honouring
+/// `DRIFTERS_PROMPT_ATTEMPTS` when it holds a positive integer.
+pub fn prompt_attempts() -> usize {
+    parse_prompt_attempts(std::env::var(PROMPT_ATTEMPTS_ENV).ok().as_deref())
+}
+
+fn parse_prompt_attempts(value: Option<&str>) -> usize {
+    match value.map(|v| v.trim().parse::<usize>()) {
+        Some(Ok(n)) if n > 0 => n,
+        Some(_) => {
+            log::warn!(
+                "Ignoring invalid {} value; using {}",
+                PROMPT_ATTEMPTS_ENV,
+                DEFAULT_PROMPT_ATTEMPTS
+            );
+            DEFAULT_PROMPT_ATTEMPTS
+        }
+        None => DEFAULT_PROMPT_ATTEMPTS,
+    }
+}
+
+/// Set while `drifters watch` runs commands with nobody at the terminal:
+/// prompts take their default answer instead of reading stdin.
+static UNATTENDED: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable answering every prompt with its default.
+pub fn set_unattended(enabled: bool) {
+    UNATTENDED.store(enabled, Ordering::Relaxed);
+}
+
 /// Confirm with user before proceeding with potentially dangerous operation
 pub fn confirm_operation(message: &str, default_yes: bool) -> Result<bool> {
-    use std::io::{self, Write};
+    if UNATTENDED.load(Ordering::Relaxed) {
+        println!("{} {}", message, if default_yes { "yes" } else { "no" });
+        return Ok(default_yes);
+    }
+    let stdin = std::io::stdin();
+    confirm_with(
+        message,
+        default_yes,
+        prompt_attempts(),
+        &mut stdin.lock(),
+        &mut std::io::stdout(),
+    )
+}
 
+/// `confirm_operation` with explicit attempt count and I/O handles.
+fn confirm_with(
+    message: &str,
+    default_yes: bool,
+    attempts: usize,
+    input: &mut impl std::io::BufRead,
+    output: &mut impl std::io::Write,
+) -> Result<bool> {
     let prompt = if default_yes {
         format!("{} [Y/n]: ", message)
     } else {
         format!("{} [y/N]: ", message)
     };
 
-    for _ in 0..3usize {
-        print!("{}", prompt);
-        io::stdout().flush()?;
+    for _ in 0..attempts {
+        write!(output, "{}", prompt)?;
+        output.flush()?;
 
-        let mut input = String::new();
-        io::stdin().read_line(&mut input)?;
+        let mut line = String::new();
+        input.read_line(&mut line)?;
 
-        match input.trim().to_lowercase().as_str() {
+        match line.trim().to_lowercase().as_str() {
             "" => return Ok(default_yes),
             "y" | "yes" => return Ok(true),
             "n" | "no" => return Ok(false),
@@ -80,8 +139,39 @@ pub fn confirm_operation(message: &str, default_yes: bool) -> Result<bool> {
         }
     }
 
-    // Three unrecognised inputs in a row — fall back to the safe default of "no"
-    // rather than silently applying default_yes (which is often true).
-    eprintln!("  Could not read a valid answer after 3 attempts. Treating as 'no'.");
+    // Too many unrecognised inputs in a row — fall back to the safe default of
+    // "no" rather than silently applying default_yes (which is often true).
+    eprintln!(
+        "  Could not read a valid answer after {} attempts. Treating as 'no'.",
+        attempts
+    );
     Ok(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_parse_prompt_attempts() {
+        assert_eq!(parse_prompt_attempts(None), DEFAULT_PROMPT_ATTEMPTS);
+        assert_eq!(parse_prompt_attempts(Some("5")), 5);
+        assert_eq!(parse_prompt_attempts(Some("0")), DEFAULT_PROMPT_ATTEMPTS);
+        assert_eq!(parse_prompt_attempts(Some("lots")), DEFAULT_PROMPT_ATTEMPTS);
+    }
+
+    #[test]
+    fn test_confirm_with_configured_attempts() {
+        // Four bad answers, then "y": five attempts is enough, three is not
+        let answers = "a\nb\nc\nd\ny\n";
+        let mut out = Vec::new();
+        let yes = confirm_with("Go?", true, 5, &mut Cursor::new(answers), &mut out).unwrap();
+        assert!(yes);
+        assert_eq!(String::from_utf8(out).unwrap().matches("Go? [Y/n]: ").count(), 5);
+
+        let mut out = Vec::new();
+        let exhausted = confirm_with("Go?", true, 3, &mut Cursor::new(answers), &mut out).unwrap();
+        assert!(!exhausted, "exhausting attempts must fall back to 'no'");
+    }
+}
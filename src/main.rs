@@ -1,5 +1,6 @@
 mod cli;
 mod config;
+mod crypto;
 mod error;
 mod git;
 mod parser;
@@ -19,6 +20,23 @@ pub struct Cli {
 
     #[arg(short, long, global = true)]
     verbose: bool,
+
+    /// If the repo lock is held by a process that is no longer running, reclaim it
+    #[arg(long, global = true)]
+    force_unlock: bool,
+
+    /// Plain ASCII output: no emoji, arrows or box drawing
+    #[arg(long, global = true)]
+    plain: bool,
+
+    /// Never colour diffs (they are only coloured on a terminal, and not when
+    /// NO_COLOR is set)
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Start from a fresh clone and don't keep it, even with repo_cache_ttl_secs set
+    #[arg(long, global = true)]
+    no_cache: bool,
 }
 
 #[derive(Subcommand)]
@@ -28,17 +46,52 @@ enum Commands {
     Init {
         /// GitHub repository URL
         repo_url: String,
+        /// Keep the shared state on this branch instead of main
+        #[arg(long)]
+        branch: Option<String>,
     },
     /// Add an app to sync
     #[command(arg_required_else_help = true)]
     AddApp {
         /// App name to add
         app_name: String,
+        /// File pattern to include (repeatable); skips the interactive prompts
+        #[arg(long, value_name = "PATTERN")]
+        include: Vec<String>,
+        /// File pattern to exclude (repeatable); needs --include
+        #[arg(long, value_name = "PATTERN")]
+        exclude: Vec<String>,
+        /// Propose include patterns for the files in this directory
+        #[arg(long, value_name = "DIR", conflicts_with = "include")]
+        from_dir: Option<std::path::PathBuf>,
+        /// How many directory levels --from-dir looks into
+        #[arg(
+            long,
+            value_name = "N",
+            requires = "from_dir",
+            default_value_t = cli::add::DEFAULT_SCAN_DEPTH
+        )]
+        max_depth: usize,
     },
     /// Push local configs to this machine's branch
     PushApp {
         /// Optional app name to push (all if not specified)
         app_name: Option<String>,
+        /// Include newly matched, never-pushed files without asking
+        #[arg(long)]
+        include_untracked: bool,
+        /// Show which files would be pushed (after exclude markers and
+        /// redaction) without writing or pushing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Fold the changes into this machine's previous push if it is
+        /// recent and not yet merged, instead of adding a commit
+        #[arg(long)]
+        amend: bool,
+        /// Commit message to use instead of the generated one, which is kept
+        /// below it
+        #[arg(short, long, conflicts_with = "amend", value_parser = non_empty_message)]
+        message: Option<String>,
     },
     /// Pull configs from a branch (default: main)
     PullApp {
@@ -47,9 +100,35 @@ enum Commands {
         /// Show what would change without applying
         #[arg(long)]
         dry_run: bool,
-        /// Pull from a specific machine's branch instead of main
-        #[arg(long)]
+        /// Pull one machine's configs as it last pushed them, from its
+        /// branch instead of main (bypasses merging and last-write-wins)
+        #[arg(long, visible_alias = "machine", value_name = "MACHINE")]
         from: Option<String>,
+        /// Never page long diffs; print them directly
+        #[arg(long)]
+        no_pager: bool,
+        /// Write results under this directory instead of the real config paths
+        #[arg(long, value_name = "DIR")]
+        output_dir: Option<std::path::PathBuf>,
+        /// Treat this directory as $HOME when resolving `~` (e.g. a mounted
+        /// home being provisioned)
+        #[arg(long, value_name = "DIR")]
+        home: Option<std::path::PathBuf>,
+        /// Save `<file>.drifters-bak-<timestamp>` before overwriting a file
+        /// (backups older than 30 days are pruned)
+        #[arg(long)]
+        backup: bool,
+        /// Apply everything without showing diffs or asking, including
+        /// deleting local files that were deleted on another machine and
+        /// dropping local exclude sections the pulled file has no block for
+        #[arg(long)]
+        yolo: bool,
+    },
+    /// Put the local files the last pull changed back as they were
+    Undo {
+        /// Revert without asking
+        #[arg(short, long)]
+        yes: bool,
     },
     /// List all apps configured for sync (detailed)
     ListApp {
@@ -57,7 +136,11 @@ enum Commands {
         app_name: Option<String>,
     },
     /// Print current sync-rules.toml
-    ListRules,
+    ListRules {
+        /// Print only this app's rules, as TOML to copy into a preset
+        #[arg(long, value_name = "NAME")]
+        app: Option<String>,
+    },
     /// Remove an app's configs from this machine, a specific machine, or all machines
     #[command(arg_required_else_help = true)]
     RemoveApp {
@@ -69,6 +152,9 @@ enum Commands {
         /// Remove from ALL machines and delete the app from sync-rules entirely
         #[arg(long)]
         all: bool,
+        /// Don't ask for confirmation
+        #[arg(short = 'y', long, visible_alias = "yes")]
+        yolo: bool,
     },
     /// Rename an app in the registry and repo
     #[command(arg_required_else_help = true)]
@@ -77,17 +163,64 @@ enum Commands {
         old_name: String,
         /// New app name
         new_name: String,
+        /// Show what would be renamed without changing anything
+        #[arg(long)]
+        dry_run: bool,
     },
-    /// Exclude a file from syncing on this machine
+    /// Exclude a file from syncing on this machine, another machine, or all machines
     #[command(arg_required_else_help = true)]
     ExcludeApp {
         /// App name
         app_name: String,
         /// Filename to exclude (e.g., "settings.json")
         filename: String,
+        /// Exclude on this specific machine ID instead of the local machine
+        #[arg(long, conflicts_with = "all_machines")]
+        machine: Option<String>,
+        /// Exclude on every machine (edits the app's base exclude list)
+        #[arg(long)]
+        all_machines: bool,
     },
     /// Show sync status
-    Status,
+    Status {
+        /// Keep refreshing every N seconds (default 5) until Ctrl-C
+        #[arg(long, value_name = "SECONDS", num_args = 0..=1, default_missing_value = "5")]
+        watch: Option<u64>,
+        /// Print the status of every file as JSON, for scripts and prompts
+        #[arg(long, conflicts_with = "watch")]
+        json: bool,
+    },
+    /// Push automatically whenever synced files change, until Ctrl-C
+    Watch {
+        /// Seconds without further changes before pushing them
+        #[arg(long, value_name = "SECONDS", default_value_t = 2)]
+        interval: u64,
+    },
+    /// Show the repo layout: metadata, apps, and each branch's files
+    Tree,
+    /// Summarize the sync footprint: apps, files, sizes per branch
+    Stats,
+    /// Diagnose problems with this machine's setup (e.g. clock skew)
+    Doctor,
+    /// Summarize recorded push activity per machine and app
+    Report {
+        /// How many days back to include
+        #[arg(long, default_value_t = 30)]
+        days: u32,
+    },
+    /// Back up the whole repo (rules and every branch's app data) as a .tar.gz
+    #[command(arg_required_else_help = true)]
+    Archive {
+        /// Archive file to write (e.g. drifters-backup.tar.gz)
+        #[arg(long, value_name = "FILE")]
+        output: std::path::PathBuf,
+    },
+    /// Recreate the repo from an archive and push it to an empty remote
+    #[command(arg_required_else_help = true)]
+    RestoreArchive {
+        /// Archive written by `drifters archive`
+        file: std::path::PathBuf,
+    },
     /// Show diff of this machine's branch against main
     DiffApp {
         /// Optional app name to diff
@@ -98,6 +231,33 @@ enum Commands {
         /// Open diffs in external difftool (uses git difftool config)
         #[arg(long)]
         tool: bool,
+        /// Never page long diffs; print them directly
+        #[arg(long)]
+        no_pager: bool,
+        /// Show what changed on main since this machine last pulled each app
+        #[arg(long, conflicts_with_all = ["against", "tool"])]
+        since_last_sync: bool,
+        /// Compare against the files as they were at this commit of the
+        /// branch; files added since show as entirely new
+        #[arg(long, value_name = "HASH", conflicts_with = "since_last_sync")]
+        commit: Option<String>,
+    },
+    /// Compare what two machines last pushed for an app
+    DiffMachines {
+        /// App to compare
+        app_name: String,
+        /// First machine ID
+        machine_a: String,
+        /// Second machine ID
+        machine_b: String,
+        /// Never page long diffs; print them directly
+        #[arg(long)]
+        no_pager: bool,
+    },
+    /// Check that local files match what this machine last pushed (read-only)
+    Verify {
+        /// Optional app name to verify (all if not specified)
+        app_name: Option<String>,
     },
     /// Merge a machine branch into main (or another branch)
     MergeApp {
@@ -111,15 +271,29 @@ enum Commands {
         /// Show what would change without applying
         #[arg(long)]
         dry_run: bool,
+
+        /// Commit message to use instead of the generated one, which is kept
+        /// below it
+        #[arg(short, long, value_parser = non_empty_message)]
+        message: Option<String>,
     },
-    /// Import app definition from file (defaults to ./<app>.toml)
+    /// Import app definition from file (defaults to ./<app>.toml) or URL
     #[command(arg_required_else_help = true)]
     ImportApp {
         /// App name
         app_name: String,
-        /// File to import from (optional, defaults to ./<app>.toml)
+        /// File to import from, or - for stdin (same as --file)
+        #[arg(value_name = "FILE", conflicts_with_all = ["file", "from_url"])]
+        path: Option<std::path::PathBuf>,
+        /// File to import from (optional, defaults to ./<app>.toml; - reads stdin)
         #[arg(long)]
         file: Option<std::path::PathBuf>,
+        /// Fetch the app definition from an https:// URL (e.g. a raw gist)
+        #[arg(long, conflicts_with = "file")]
+        from_url: Option<String>,
+        /// Import even if the definition has warnings, without asking
+        #[arg(short, long)]
+        yes: bool,
     },
     /// Export app definition to file (defaults to ./<app>.toml)
     #[command(arg_required_else_help = true)]
@@ -129,18 +303,33 @@ enum Commands {
         /// File to export to (optional, defaults to ./<app>.toml)
         #[arg(long)]
         file: Option<std::path::PathBuf>,
+        /// Write to .drifters/apps/<app>.toml in the repo and commit it instead
+        #[arg(long, conflicts_with = "file")]
+        to_repo: bool,
+        /// Print the definition to stdout instead of writing a file
+        #[arg(long, conflicts_with_all = ["file", "to_repo"])]
+        stdout: bool,
     },
     /// Import entire sync-rules.toml from file (defaults to ./sync-rules.toml)
     ImportRules {
-        /// File to import from (optional, defaults to ./sync-rules.toml)
+        /// File to import from, or - for stdin (same as --file)
+        #[arg(value_name = "FILE", conflicts_with = "file")]
+        path: Option<std::path::PathBuf>,
+        /// File to import from (optional, defaults to ./sync-rules.toml; - reads stdin)
         #[arg(long)]
         file: Option<std::path::PathBuf>,
+        /// Import even if the rules have warnings, without asking
+        #[arg(short, long)]
+        yes: bool,
     },
     /// Export entire sync-rules.toml to file (defaults to ./sync-rules.toml)
     ExportRules {
         /// File to export to (optional, defaults to ./sync-rules.toml)
         #[arg(long)]
         file: Option<std::path::PathBuf>,
+        /// Print the rules to stdout instead of writing a file
+        #[arg(long, conflicts_with = "file")]
+        stdout: bool,
     },
     /// List available presets from GitHub repository
     ListPresets,
@@ -152,6 +341,12 @@ enum Commands {
     },
     /// Auto-detect installed apps on this machine and offer to add them from presets
     DiscoverPresets,
+    /// Manage the global ignore list: files no app ever syncs
+    #[command(arg_required_else_help = true)]
+    Ignore {
+        #[command(subcommand)]
+        action: IgnoreAction,
+    },
     /// Show history of rules or app
     #[command(arg_required_else_help = true)]
     History {
@@ -164,6 +359,11 @@ enum Commands {
         #[command(subcommand)]
         target: RestoreTarget,
     },
+    /// List registered machines with their OS and when each last synced
+    Machines {
+        #[command(subcommand)]
+        action: Option<MachinesAction>,
+    },
     /// Rename a machine in the registry and repo
     #[command(arg_required_else_help = true)]
     RenameMachine {
@@ -171,6 +371,9 @@ enum Commands {
         old_id: String,
         /// New machine ID
         new_id: String,
+        /// Show what would be renamed without changing anything
+        #[arg(long)]
+        dry_run: bool,
     },
     /// Remove a machine from the registry and delete its configs
     #[command(arg_required_else_help = true)]
@@ -216,6 +419,23 @@ enum Commands {
         #[arg(long)]
         clear: bool,
     },
+    /// Set (or clear) the name and email on commits drifters makes
+    SetAuthor {
+        /// Author name. Omit both arguments to show the current values.
+        #[arg(requires = "email")]
+        name: Option<String>,
+        /// Author email
+        email: Option<String>,
+        /// Remove the setting (fall back to git's user.name / user.email)
+        #[arg(long, conflicts_with = "name")]
+        clear: bool,
+    },
+    /// Set (or clear) the passphrase that encrypts drifters::encrypt sections
+    SetPassphrase {
+        /// Remove this machine's encryption key
+        #[arg(long)]
+        clear: bool,
+    },
     /// Open the local drifters config file in your editor
     EditConfig,
     /// Open one of an app's config files in your editor
@@ -230,6 +450,28 @@ enum Commands {
     Unlock,
 }
 
+#[derive(Subcommand)]
+enum IgnoreAction {
+    /// Ignore files matching a pattern in every app (e.g. "credentials.json",
+    /// "~/.aws/**"); patterns without a '/' match file names anywhere
+    Add {
+        /// Glob pattern to ignore
+        pattern: String,
+    },
+    /// Show the built-in and configured ignore patterns
+    List,
+}
+
+#[derive(Subcommand)]
+enum MachinesAction {
+    /// Delete machine branches that no registered machine owns
+    Prune {
+        /// Delete without asking
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
 #[derive(Subcommand)]
 enum HistoryTarget {
     /// Show history of all rules
@@ -252,6 +494,19 @@ enum HistoryTarget {
         #[arg(long)]
         commit: Option<String>,
     },
+    /// Show history of one of an app's files, across all machines
+    File {
+        /// App name
+        app_name: String,
+        /// File name, as stored in the repo (e.g. ".zshrc")
+        filename: String,
+        /// Number of commits to show
+        #[arg(long, default_value = "10")]
+        limit: usize,
+        /// Show diff for specific commit
+        #[arg(long)]
+        commit: Option<String>,
+    },
 }
 
 #[derive(Subcommand)]
@@ -270,6 +525,30 @@ enum RestoreTarget {
         #[arg(long)]
         commit: String,
     },
+    /// Restore one synced file's content from a previous commit
+    File {
+        /// App name
+        app_name: String,
+        /// File name as stored in the repo (e.g. .zshrc)
+        filename: String,
+        /// Commit hash to restore from
+        #[arg(long)]
+        commit: String,
+        /// Also write the restored content to the local file
+        #[arg(long)]
+        apply: bool,
+        /// Restore onto this machine's branch instead of the local machine's
+        #[arg(long, conflicts_with = "apply")]
+        machine: Option<String>,
+    },
+}
+
+/// `--message` values: anything but blank.
+fn non_empty_message(message: &str) -> std::result::Result<String, String> {
+    if message.trim().is_empty() {
+        return Err("the commit message can't be empty".to_string());
+    }
+    Ok(message.to_string())
 }
 
 fn main() {
@@ -283,14 +562,31 @@ fn run() -> Result<()> {
     let cli = Cli::parse();
 
     // Initialize logger
+    let mut logger = env_logger::Builder::from_default_env();
     if cli.verbose {
-        env_logger::Builder::from_default_env()
-            .filter_level(log::LevelFilter::Debug)
-            .init();
+        logger.filter_level(log::LevelFilter::Debug);
     } else {
-        env_logger::Builder::from_default_env()
-            .filter_level(log::LevelFilter::Info)
-            .init();
+        logger.filter_level(log::LevelFilter::Info);
+    }
+    if cli.no_color {
+        logger.write_style(env_logger::WriteStyle::Never);
+    }
+    logger.init();
+
+    git::set_force_unlock(cli.force_unlock);
+    git::set_no_cache(cli.no_cache);
+    cli::diffview::set_no_color(cli.no_color);
+    let local_config = config::LocalConfig::load().ok();
+    cli::style::set_plain(
+        cli.plain || local_config.as_ref().is_some_and(|c| c.plain_output),
+    );
+    if let Some(c) = local_config.as_ref() {
+        git::set_main_branch(&c.branch);
+        git::set_sign_commits(c.sign_commits);
+        git::set_commit_author(c.git_author_name.as_deref(), c.git_author_email.as_deref());
+    }
+    if let Some(c) = local_config.as_ref().filter(|c| c.commit_trailers) {
+        git::set_commit_trailers(&c.machine_id);
     }
 
     // Check for updates (unless running self-update, init, or machine management commands)
@@ -303,6 +599,8 @@ fn run() -> Result<()> {
             | Commands::OpenReadme
             | Commands::Completion { .. }
             | Commands::SetEditor { .. }
+            | Commands::SetAuthor { .. }
+            | Commands::SetPassphrase { .. }
             | Commands::Unlock
     ) {
         if let Ok(mut config) = config::LocalConfig::load() {
@@ -311,53 +609,140 @@ fn run() -> Result<()> {
     }
 
     match cli.command {
-        Commands::Init { repo_url } => {
-            cli::init::initialize(repo_url)
-        }
-        Commands::AddApp { app_name } => {
-            cli::add::add_app(app_name)
-        }
-        Commands::PushApp { app_name } => {
-            cli::push::push_command(app_name)
-        }
-        Commands::PullApp { app_name, dry_run, from } => {
-            cli::pull::pull_command(app_name, dry_run, from)
+        Commands::Init { repo_url, branch } => {
+            cli::init::initialize(repo_url, branch)
+        }
+        Commands::AddApp {
+            app_name,
+            include,
+            exclude,
+            from_dir,
+            max_depth,
+        } => {
+            cli::add::add_app(app_name, include, exclude, from_dir, max_depth)
+        }
+        Commands::PushApp {
+            app_name,
+            include_untracked,
+            dry_run,
+            amend,
+            message,
+        } => {
+            cli::push::push_command(app_name, include_untracked, dry_run, amend, message)
+        }
+        Commands::PullApp {
+            app_name,
+            dry_run,
+            from,
+            no_pager,
+            output_dir,
+            home,
+            backup,
+            yolo,
+        } => {
+            let options = cli::pull::PullOptions {
+                dry_run,
+                from,
+                no_pager,
+                output_dir,
+                home,
+                backup,
+                yolo,
+            };
+            cli::pull::pull_command(app_name, options)
+        }
+        Commands::Undo { yes } => {
+            cli::undo::undo_command(yes)
         }
         Commands::ListApp { app_name } => {
             cli::list::list_apps(app_name)
         }
-        Commands::ListRules => {
-            cli::list::list_rules()
-        }
-        Commands::RemoveApp { app_name, machine, all } => {
-            cli::remove::remove_app(app_name, machine, all)
-        }
-        Commands::RenameApp { old_name, new_name } => {
-            cli::rename_app::rename_app(old_name, new_name)
-        }
-        Commands::ExcludeApp { app_name, filename } => {
-            cli::exclude::exclude_file(app_name, filename)
-        }
-        Commands::Status => {
-            cli::status::show_status()
-        }
-        Commands::DiffApp { app_name, against, tool } => {
-            cli::diff::show_diff(app_name, against, tool)
-        }
-        Commands::MergeApp { app_name, from, dry_run } => {
-            cli::merge::merge_command(app_name, from, dry_run)
-        }
-        Commands::ImportApp { app_name, file } => {
-            cli::import::import_app(app_name, file)
-        }
-        Commands::ExportApp { app_name, file } => {
-            cli::export::export_app(app_name, file)
-        }
-        Commands::ImportRules { file } => {
-            cli::import::import_rules(file)
-        }
-        Commands::ExportRules { file } => {
-            cli::export::export_rules(file)
+        Commands::ListRules { app } => {
+            cli::list::list_rules(app)
+        }
+        Commands::RemoveApp {
+            app_name,
+            machine,
+            all,
+            yolo,
+        } => {
+            cli::remove::remove_app(app_name, machine, all, yolo)
+        }
+        Commands::RenameApp { old_name, new_name, dry_run } => {
+            cli::rename_app::rename_app(old_name, new_name, dry_run)
+        }
+        Commands::ExcludeApp {
+            app_name,
+            filename,
+            machine,
+            all_machines,
+        } => {
+            cli::exclude::exclude_file(app_name, filename, machine, all_machines)
+        }
+        Commands::Status { watch, json } => {
+            cli::status::show_status(watch, json)
+        }
+        Commands::Watch { interval } => {
+            cli::watch::watch_command(interval)
+        }
+        Commands::Tree => {
+            cli::tree::show_tree()
+        }
+        Commands::Stats => {
+            cli::stats::show_stats()
+        }
+        Commands::Doctor => {
+            cli::doctor::run_doctor()
+        }
+        Commands::Report { days } => {
+            cli::report::show_report(days)
+        }
+        Commands::Archive { output } => {
+            cli::archive::archive(output)
+        }
+        Commands::RestoreArchive { file } => {
+            cli::archive::restore_archive(file)
+        }
+        Commands::DiffApp {
+            app_name,
+            against,
+            tool,
+            no_pager,
+            since_last_sync,
+            commit,
+        } => {
+            cli::diff::show_diff(app_name, against, tool, no_pager, since_last_sync, commit)
+        }
+        Commands::DiffMachines {
+            app_name,
+            machine_a,
+            machine_b,
+            no_pager,
+        } => {
+            cli::diff::show_machine_diff(app_name, machine_a, machine_b, no_pager)
+        }
+        Commands::Verify { app_name } => {
+            cli::verify::verify_command(app_name)
+        }
+        Commands::MergeApp { app_name, from, dry_run, message } => {
+            cli::merge::merge_command(app_name, from, dry_run, message)
+        }
+        Commands::ImportApp { app_name, path, file, from_url, yes } => {
+            cli::import::import_app(app_name, path.or(file), from_url, yes)
+        }
+        Commands::ExportApp {
+            app_name,
+            file,
+            to_repo,
+            stdout,
+        } => {
+            cli::export::export_app(app_name, file, to_repo, stdout)
+        }
+        Commands::ImportRules { path, file, yes } => {
+            cli::import::import_rules(path.or(file), yes)
+        }
+        Commands::ExportRules { file, stdout } => {
+            cli::export::export_rules(file, stdout)
         }
         Commands::ListPresets => {
             cli::presets::list_presets()
@@ -368,6 +753,10 @@ fn run() -> Result<()> {
         Commands::DiscoverPresets => {
             cli::presets::discover_presets()
         }
+        Commands::Ignore { action } => match action {
+            IgnoreAction::Add { pattern } => cli::ignore::add_ignore(pattern),
+            IgnoreAction::List => cli::ignore::list_ignore(),
+        },
         Commands::History { target } => match target {
             HistoryTarget::Rules { limit, commit } => {
                 if let Some(hash) = commit {
@@ -383,6 +772,13 @@ fn run() -> Result<()> {
                     cli::history::show_history_app(app_name, limit)
                 }
             }
+            HistoryTarget::File { app_name, filename, limit, commit } => {
+                if let Some(hash) = commit {
+                    cli::history::show_file_commit_diff(hash, app_name, filename)
+                } else {
+                    cli::history::show_history_file(app_name, filename, limit)
+                }
+            }
         }
         Commands::Restore { target } => match target {
             RestoreTarget::App { app_name, commit } => {
@@ -391,9 +787,16 @@ fn run() -> Result<()> {
             RestoreTarget::Rules { commit } => {
                 cli::restore::restore_rules(commit)
             }
+            RestoreTarget::File { app_name, filename, commit, apply, machine } => {
+                cli::restore::restore_file(app_name, filename, commit, apply, machine)
+            }
         }
-        Commands::RenameMachine { old_id, new_id } => {
-            cli::rename_machine::rename_machine(old_id, new_id)
+        Commands::Machines { action } => match action {
+            None => cli::machines::list_machines(),
+            Some(MachinesAction::Prune { yes }) => cli::machines::prune_machines(yes),
+        },
+        Commands::RenameMachine { old_id, new_id, dry_run } => {
+            cli::rename_machine::rename_machine(old_id, new_id, dry_run)
         }
         Commands::RemoveMachine { machine_id } => {
             cli::remove_machine::remove_machine(machine_id)
@@ -440,6 +843,38 @@ fn run() -> Result<()> {
             }
             Ok(())
         }
+        Commands::SetAuthor { name, email, clear } => {
+            let mut config = config::LocalConfig::load()?;
+            if clear {
+                config.git_author_name = None;
+                config.git_author_email = None;
+                config.save()?;
+                println!(
+                    "{} author cleared (will use git's user.name / user.email).",
+                    cli::style::ok()
+                );
+            } else if let (Some(name), Some(email)) = (name, email) {
+                config.git_author_name = Some(name.clone());
+                config.git_author_email = Some(email.clone());
+                config.save()?;
+                println!("{} author set to \"{} <{}>\".", cli::style::ok(), name, email);
+            } else {
+                match (&config.git_author_name, &config.git_author_email) {
+                    (None, None) => {
+                        println!("author is not set (using git's user.name / user.email).")
+                    }
+                    (name, email) => {
+                        let unset = "(git config)".to_string();
+                        println!("git_author_name = \"{}\"", name.as_ref().unwrap_or(&unset));
+                        println!("git_author_email = \"{}\"", email.as_ref().unwrap_or(&unset));
+                    }
+                }
+            }
+            Ok(())
+        }
+        Commands::SetPassphrase { clear } => {
+            cli::passphrase::set_passphrase(clear)
+        }
         Commands::EditConfig => {
             cli::edit_config::edit_config()
         }
@@ -0,0 +1,37 @@
+/// Whether two versions of a file differ only trivially: line endings
+/// (`\r\n` vs `\n`), trailing whitespace on a line, or blank lines at the end.
+///
+/// Indentation still counts, since it is meaningful in YAML, Python and
+/// friends.
+pub fn content_equivalent_ignoring_whitespace(a: &str, b: &str) -> bool {
+    normalized_lines(a).eq(normalized_lines(b))
+}
+
+fn normalized_lines(content: &str) -> impl Iterator<Item = &str> {
+    let lines: Vec<&str> = content.lines().map(str::trim_end).collect();
+    let len = lines.iter().rposition(|l| !l.is_empty()).map_or(0, |i| i + 1);
+    lines.into_iter().take(len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_whitespace_only_differences_are_equivalent() {
+        let a = "[editor]\ntheme = \"dark\"\n";
+        assert!(content_equivalent_ignoring_whitespace(a, "[editor]\r\ntheme = \"dark\"\r\n"));
+        assert!(content_equivalent_ignoring_whitespace(a, "[editor]  \ntheme = \"dark\"\t\n"));
+        assert!(content_equivalent_ignoring_whitespace(a, "[editor]\ntheme = \"dark\"\n\n\n"));
+        assert!(content_equivalent_ignoring_whitespace(a, "[editor]\ntheme = \"dark\""));
+    }
+
+    #[test]
+    fn test_substantive_differences_are_not_equivalent() {
+        let a = "[editor]\ntheme = \"dark\"\n";
+        assert!(!content_equivalent_ignoring_whitespace(a, "[editor]\ntheme = \"light\"\n"));
+        assert!(!content_equivalent_ignoring_whitespace(a, "[editor]\n  theme = \"dark\"\n"));
+        assert!(!content_equivalent_ignoring_whitespace(a, "[editor]\n\ntheme = \"dark\"\n"));
+        assert!(!content_equivalent_ignoring_whitespace("", "x\n"));
+    }
+}
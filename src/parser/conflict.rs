@@ -0,0 +1,33 @@
+/// Whether `content` contains an unresolved conflict block left by a
+/// three-way merge: `<<<<<<<`, then `=======`, then `>>>>>>>`, each at the
+/// start of a line.
+pub fn has_conflict_markers(content: &str) -> bool {
+    let mut expect = ["<<<<<<<", "=======", ">>>>>>>"].into_iter().peekable();
+    for line in content.lines() {
+        match expect.peek() {
+            Some(marker) if line.starts_with(marker) => {
+                expect.next();
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+    expect.peek().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_complete_conflict_blocks_only() {
+        let conflicted = "a\n<<<<<<< main\nb\n=======\nc\n>>>>>>> machines/m1\nd\n";
+        assert!(has_conflict_markers(conflicted));
+
+        assert!(!has_conflict_markers("a\nb\n"));
+        // A markdown rule or a lone marker is not a conflict
+        assert!(!has_conflict_markers("title\n=======\n"));
+        assert!(!has_conflict_markers("<<<<<<< main\nb\n"));
+        assert!(!has_conflict_markers("  <<<<<<< x\n=======\n>>>>>>> y\n"));
+    }
+}
@@ -0,0 +1,78 @@
+use serde_json::Value;
+
+/// Deep-merge two versions of a JSON document.
+///
+/// Objects are merged key by key, recursively; a key present on only one
+/// side is kept. Any other clash — differing scalars, arrays (replaced
+/// wholesale, never concatenated) or mismatched types — goes to `theirs` if
+/// `theirs_newer`, else to `ours`. Key order follows `ours`, with keys new
+/// in `theirs` appended.
+///
+/// Fails if either side is not valid JSON; callers fall back to a
+/// whole-file merge.
+pub fn merge_json(ours: &str, theirs: &str, theirs_newer: bool) -> serde_json::Result<String> {
+    let ours: Value = serde_json::from_str(ours)?;
+    let theirs: Value = serde_json::from_str(theirs)?;
+    let merged = merge_values(ours, theirs, theirs_newer);
+    Ok(serde_json::to_string_pretty(&merged)? + "\n")
+}
+
+fn merge_values(ours: Value, theirs: Value, theirs_newer: bool) -> Value {
+    match (ours, theirs) {
+        (Value::Object(mut ours), Value::Object(theirs)) => {
+            for (key, their_value) in theirs {
+                match ours.get_mut(&key) {
+                    Some(slot) => {
+                        let our_value = std::mem::take(slot);
+                        *slot = merge_values(our_value, their_value, theirs_newer);
+                    }
+                    None => {
+                        ours.insert(key, their_value);
+                    }
+                }
+            }
+            Value::Object(ours)
+        }
+        (ours, theirs) if ours == theirs => ours,
+        (_, theirs) if theirs_newer => theirs,
+        (ours, _) => ours,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_json_combines_keys_and_resolves_clashes_by_age() {
+        let main = r#"{"theme": "dark", "font": {"size": 14, "family": "Mono"}, "ext": ["a", "b"]}"#;
+        let machine = r#"{"theme": "light", "font": {"size": 16}, "ext": ["c"], "vim": true}"#;
+
+        let merged: Value = serde_json::from_str(&merge_json(main, machine, true).unwrap()).unwrap();
+        assert_eq!(
+            merged,
+            serde_json::json!({
+                "theme": "light",
+                "font": {"size": 16, "family": "Mono"},
+                "ext": ["c"],
+                "vim": true
+            })
+        );
+
+        // Older machine edit: main keeps its values, still gains new keys
+        let merged: Value = serde_json::from_str(&merge_json(main, machine, false).unwrap()).unwrap();
+        assert_eq!(merged["theme"], "dark");
+        assert_eq!(merged["font"]["size"], 14);
+        assert_eq!(merged["ext"], serde_json::json!(["a", "b"]));
+        assert_eq!(merged["vim"], true);
+    }
+
+    #[test]
+    fn test_merge_json_keeps_key_order_and_rejects_invalid_json() {
+        let merged = merge_json("{\"b\": 1, \"a\": 2}", "{\"c\": 3, \"a\": 2}", true).unwrap();
+        assert_eq!(merged, "{\n  \"b\": 1,\n  \"a\": 2,\n  \"c\": 3\n}\n");
+
+        assert!(merge_json("{\"a\": 1}", "{\"a\": 1,}", true).is_err());
+        assert!(merge_json("// comment\n{}", "{}", true).is_err());
+    }
+}
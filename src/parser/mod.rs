@@ -1 +1,5 @@
+pub mod compare;
+pub mod conflict;
+pub mod json_merge;
+pub mod redact;
 pub mod sections;
@@ -0,0 +1,118 @@
+use crate::error::{DriftersError, Result};
+use regex::Regex;
+
+/// Text that replaces a redacted line in the pushed copy of a file.
+pub const REDACTED_PLACEHOLDER: &str = "<redacted by drifters>";
+
+/// Compile an app's `redact` patterns, reporting the first invalid one.
+pub fn compile_redactions(patterns: &[String]) -> Result<Vec<Regex>> {
+    patterns
+        .iter()
+        .map(|p| {
+            Regex::new(p).map_err(|e| {
+                DriftersError::Config(format!("Invalid redact pattern '{}': {}", p, e))
+            })
+        })
+        .collect()
+}
+
+/// Replace every line matching any of `patterns` with the placeholder.
+///
+/// Line endings are kept, so the redacted file has the same line count as
+/// the original.
+pub fn redact_content(content: &str, patterns: &[Regex]) -> String {
+    if patterns.is_empty() {
+        return content.to_string();
+    }
+
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            let (body, ending) = split_line_ending(line);
+            if patterns.iter().any(|re| re.is_match(body)) {
+                format!("{}{}", REDACTED_PLACEHOLDER, ending)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect()
+}
+
+/// Put this machine's own values back where `incoming` has placeholders.
+///
+/// Placeholders are matched to the local lines that match `patterns` in
+/// order of appearance. A placeholder with no local counterpart (e.g. the
+/// file is new on this machine) is left as is, so the gap stays visible.
+pub fn restore_redacted(incoming: &str, local: &str, patterns: &[Regex]) -> String {
+    if patterns.is_empty() || !incoming.contains(REDACTED_PLACEHOLDER) {
+        return incoming.to_string();
+    }
+
+    let mut local_values = local
+        .split_inclusive('\n')
+        .map(|line| split_line_ending(line).0)
+        .filter(|body| patterns.iter().any(|re| re.is_match(body)));
+
+    incoming
+        .split_inclusive('\n')
+        .map(|line| {
+            let (body, ending) = split_line_ending(line);
+            if body == REDACTED_PLACEHOLDER {
+                if let Some(value) = local_values.next() {
+                    return format!("{}{}", value, ending);
+                }
+            }
+            line.to_string()
+        })
+        .collect()
+}
+
+/// Split a line into its content and its `\n` / `\r\n` terminator.
+fn split_line_ending(line: &str) -> (&str, &str) {
+    if let Some(body) = line.strip_suffix("\r\n") {
+        (body, "\r\n")
+    } else if let Some(body) = line.strip_suffix('\n') {
+        (body, "\n")
+    } else {
+        (line, "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token_patterns() -> Vec<Regex> {
+        compile_redactions(&[r"^\s*token\s*=".to_string()]).unwrap()
+    }
+
+    #[test]
+    fn test_redact_on_push() {
+        let content = "[user]\n  name = me\n  token = abc123\n";
+        let redacted = redact_content(content, &token_patterns());
+        assert_eq!(redacted, "[user]\n  name = me\n<redacted by drifters>\n");
+        assert!(!redacted.contains("abc123"));
+    }
+
+    #[test]
+    fn test_restore_preserves_local_value_on_pull() {
+        let local = "[user]\n  name = old\n  token = mine\n";
+        let incoming = "[user]\n  name = new\n<redacted by drifters>\n";
+        assert_eq!(
+            restore_redacted(incoming, local, &token_patterns()),
+            "[user]\n  name = new\n  token = mine\n"
+        );
+
+        // Nothing local to restore from: the placeholder stays visible
+        assert_eq!(
+            restore_redacted(incoming, "[user]\n", &token_patterns()),
+            incoming
+        );
+    }
+
+    #[test]
+    fn test_invalid_redact_pattern_is_reported() {
+        let err = compile_redactions(&["(unclosed".to_string()]).unwrap_err();
+        assert!(err.to_string().contains("(unclosed"));
+    }
+}
@@ -1,19 +1,163 @@
 use crate::error::{DriftersError, Result};
+use std::collections::HashMap;
 
-/// Extract syncable content (everything EXCEPT exclude sections)
+/// How a file type writes comments, and so how drifters markers look in it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommentSyntax {
+    /// A line prefix: `# drifters::exclude::start`
+    Line(String),
+    /// Paired delimiters: `/* drifters::exclude::start */`
+    Block(String, String),
+    /// The format has no comments at all (strict JSON), so it can't carry
+    /// markers; such files are always synced whole.
+    None,
+}
+
+impl CommentSyntax {
+    fn line(prefix: &str) -> Self {
+        CommentSyntax::Line(prefix.to_string())
+    }
+
+    fn block(open: &str, close: &str) -> Self {
+        CommentSyntax::Block(open.to_string(), close.to_string())
+    }
+
+    /// Parse an app's `comment_syntax` setting: a line prefix such as `--`,
+    /// an opening and closing delimiter separated by a space (`/* */`), or
+    /// `none` for formats without comments (e.g. strict JSON).
+    pub fn parse(spec: &str) -> Self {
+        if spec.trim() == "none" {
+            return CommentSyntax::None;
+        }
+        let mut parts = spec.split_whitespace();
+        match (parts.next(), parts.next()) {
+            (Some(open), Some(close)) => CommentSyntax::block(open, close),
+            _ => CommentSyntax::line(spec.trim()),
+        }
+    }
+
+    /// If `line` is the marker `drifters::<name>` (e.g. `exclude::start`),
+    /// return whatever follows the tag on that line, such as ` id=secrets`.
+    /// Leading indentation is allowed; text before the comment is not.
+    fn marker_args<'a>(&self, line: &'a str, name: &str) -> Option<&'a str> {
+        let (open, close) = match self {
+            CommentSyntax::Line(open) => (open.as_str(), ""),
+            CommentSyntax::Block(open, close) => (open.as_str(), close.as_str()),
+            CommentSyntax::None => return None,
+        };
+        let tag = format!("{} drifters::{}", open, name);
+        line.trim_start_matches(BOM).trim().strip_prefix(&tag)?.strip_suffix(close)
+    }
+
+    fn is_start(&self, line: &str, kind: &str) -> bool {
+        self.marker_args(line, &format!("{}::start", kind)).is_some()
+    }
+
+    fn is_stop(&self, line: &str, kind: &str) -> bool {
+        self.marker_args(line, &format!("{}::stop", kind)).is_some()
+    }
+
+    /// Whether any line of `content` is a `kind` start marker.
+    fn has_start(&self, content: &str, kind: &str) -> bool {
+        content.lines().any(|line| self.is_start(line, kind))
+    }
+
+    /// `text` as a comment line, e.g. `# text` or `/* text */`.
+    fn comment_line(&self, text: &str) -> String {
+        match self {
+            CommentSyntax::Line(open) => format!("{} {}", open, text),
+            CommentSyntax::Block(open, close) => format!("{} {} {}", open, text, close),
+            CommentSyntax::None => text.to_string(),
+        }
+    }
+
+    /// The text of a comment line written by [`comment_line`](Self::comment_line).
+    fn uncomment<'a>(&self, line: &'a str) -> Option<&'a str> {
+        let (open, close) = match self {
+            CommentSyntax::Line(open) => (open.as_str(), ""),
+            CommentSyntax::Block(open, close) => (open.as_str(), close.as_str()),
+            CommentSyntax::None => return None,
+        };
+        Some(line.trim().strip_prefix(open)?.strip_suffix(close)?.trim())
+    }
+}
+
+/// Start of every sealed section body, naming the format so it can change
+/// later.
+pub const SEALED_PREFIX: &str = "v1:";
+
+/// Width of the comment lines an encrypted section is wrapped into.
+const SEALED_LINE_WIDTH: usize = 76;
+
+/// The byte order mark some Windows editors start UTF-8 files with.
+const BOM: char = '\u{feff}';
+
+/// `content` without a leading byte order mark, and whether it had one.
+fn strip_bom(content: &str) -> (&str, bool) {
+    match content.strip_prefix(BOM) {
+        Some(rest) => (rest, true),
+        None => (content, false),
+    }
+}
+
+/// `content` with a byte order mark in front if `bom`.
+fn with_bom(content: String, bom: bool) -> String {
+    if bom {
+        format!("{}{}", BOM, content)
+    } else {
+        content
+    }
+}
+
+/// Marker mode needs comments; refuse it clearly for formats without them
+/// instead of silently syncing the markers as content.
+fn refuse_markers_without_comments(content: &str) -> Result<()> {
+    if ["exclude", "include", "encrypt"]
+        .iter()
+        .any(|kind| content.contains(&format!("drifters::{}::", kind)))
+    {
+        return Err(DriftersError::Config(
+            "drifters markers found in a file type without comments (e.g. strict JSON); \
+             markers can't be used there, so remove them to sync the whole file"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Extract syncable content (everything EXCEPT exclude sections, or ONLY the
+/// include sections when the file uses include markers)
 /// Returns the content that should be synced to other machines
-pub fn extract_syncable_content(content: &str, comment_syntax: &str) -> Result<Option<String>> {
-    let exclude_start = format!("{} drifters::exclude::start", comment_syntax);
-    let exclude_stop = format!("{} drifters::exclude::stop", comment_syntax);
+pub fn extract_syncable_content(
+    content: &str,
+    comment_syntax: &CommentSyntax,
+) -> Result<Option<String>> {
+    if *comment_syntax == CommentSyntax::None {
+        refuse_markers_without_comments(content)?;
+        return Ok(None);
+    }
+
+    // The result keeps the file's byte order mark, without it getting in
+    // the way of a marker on the first line
+    let (content, bom) = strip_bom(content);
+    if comment_syntax.has_start(content, "include") {
+        if comment_syntax.has_start(content, "exclude") {
+            return Err(DriftersError::Config(
+                "file uses both drifters::include and drifters::exclude markers; \
+                 use one or the other"
+                    .to_string(),
+            ));
+        }
+        return extract_included_content(content, comment_syntax)
+            .map(|included| Some(with_bom(included, bom)));
+    }
 
     let mut result = String::new();
     let mut in_exclude_block = false;
     let mut found_any_tags = false;
 
     for line in content.lines() {
-        let trimmed = line.trim();
-
-        if trimmed.starts_with(&exclude_start) {
+        if comment_syntax.is_start(line, "exclude") {
             in_exclude_block = true;
             found_any_tags = true;
             // Include the tag itself for reconstruction
@@ -22,7 +166,7 @@ pub fn extract_syncable_content(content: &str, comment_syntax: &str) -> Result<O
             continue;
         }
 
-        if trimmed.starts_with(&exclude_stop) {
+        if comment_syntax.is_stop(line, "exclude") {
             in_exclude_block = false;
             result.push_str(line);
             result.push('\n');
@@ -46,51 +190,87 @@ pub fn extract_syncable_content(content: &str, comment_syntax: &str) -> Result<O
     }
 
     if found_any_tags {
-        Ok(Some(result))
+        Ok(Some(with_bom(result, bom)))
     } else {
         // No tags found, sync entire file
         Ok(None)
     }
 }
 
+/// Extract only the include sections (markers included), one after another.
+/// Everything outside them stays local.
+pub fn extract_included_content(content: &str, comment_syntax: &CommentSyntax) -> Result<String> {
+    let sections = extract_marked_sections(content, comment_syntax, "include")?;
+    Ok(sections.concat())
+}
+
+/// Synced content merged into a local file by [`merge_synced_content`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Merged {
+    pub content: String,
+    /// Local exclude sections the synced content has no block for (more of
+    /// them than synced blocks, or an ID no synced block has), so they are
+    /// not in `content`
+    pub dropped_sections: usize,
+}
+
 /// Merge synced content back into local file
-/// Preserves local exclude sections, replaces everything else
+/// Preserves local exclude sections, replaces everything else.
+/// If the synced content uses include markers, only the local include
+/// sections are replaced instead and everything else is kept.
 pub fn merge_synced_content(
     local_content: &str,
     synced_content: &str,
-    comment_syntax: &str,
-) -> Result<String> {
-    let exclude_start = format!("{} drifters::exclude::start", comment_syntax);
-    let exclude_stop = format!("{} drifters::exclude::stop", comment_syntax);
+    comment_syntax: &CommentSyntax,
+) -> Result<Merged> {
+    if *comment_syntax == CommentSyntax::None {
+        refuse_markers_without_comments(local_content)?;
+        return Ok(Merged { content: synced_content.to_string(), dropped_sections: 0 });
+    }
+
+    // Markers are matched without byte order marks; the result has one if
+    // the local file has, whatever the machine that pushed it saved
+    let (local_content, bom) = strip_bom(local_content);
+    let (synced_content, _) = strip_bom(synced_content);
+    if comment_syntax.has_start(synced_content, "include") {
+        // Local include sections beyond the synced ones are kept
+        let content = merge_included_content(local_content, synced_content, comment_syntax)?;
+        return Ok(Merged { content: with_bom(content, bom), dropped_sections: 0 });
+    }
 
-    // Extract local exclude sections with their positions
-    let local_excludes = extract_exclude_sections(local_content, &exclude_start, &exclude_stop)?;
+    // Local exclude sections: named ones by ID, the rest by position
+    let mut local_excludes = LocalSections::new(
+        extract_marked_sections(local_content, comment_syntax, "exclude")?,
+        comment_syntax,
+    );
 
     let mut result = String::new();
     let mut in_exclude_block = false;
-    let mut exclude_index = 0;
+    let mut used_local = false;
 
     for line in synced_content.lines() {
-        let trimmed = line.trim();
-
-        if trimmed.starts_with(&exclude_start) {
+        if comment_syntax.is_start(line, "exclude") {
             // Use local exclude section if it exists
-            if let Some(local_exclude) = local_excludes.get(exclude_index) {
-                result.push_str(local_exclude);
-                exclude_index += 1;
-            } else {
-                // No local version, include the synced exclude section
-                result.push_str(line);
-                result.push('\n');
+            match local_excludes.take(section_id(line, comment_syntax)) {
+                Some(local_exclude) => {
+                    result.push_str(&local_exclude);
+                    used_local = true;
+                }
+                None => {
+                    // No local version, include the synced exclude section
+                    result.push_str(line);
+                    result.push('\n');
+                    used_local = false;
+                }
             }
             in_exclude_block = true;
             continue;
         }
 
-        if trimmed.starts_with(&exclude_stop) {
+        if comment_syntax.is_stop(line, "exclude") {
             in_exclude_block = false;
             // Skip stop tag if we already included it with local content
-            if exclude_index > 0 && local_excludes.get(exclude_index - 1).is_some() {
+            if used_local {
                 continue;
             }
             result.push_str(line);
@@ -105,23 +285,243 @@ pub fn merge_synced_content(
         // Skip lines inside exclude blocks (they come from local_excludes)
     }
 
+    Ok(Merged { content: with_bom(result, bom), dropped_sections: local_excludes.len() })
+}
+
+/// A file's local exclude sections, ready to be matched against synced ones.
+struct LocalSections {
+    /// Sections whose start tag carries `id=<name>`
+    named: HashMap<String, String>,
+    /// Unnamed sections, matched in order of appearance
+    unnamed: std::vec::IntoIter<String>,
+}
+
+impl LocalSections {
+    fn new(sections: Vec<String>, comment_syntax: &CommentSyntax) -> Self {
+        let mut named = HashMap::new();
+        let mut unnamed = Vec::new();
+        for section in sections {
+            let first_line = section.lines().next().unwrap_or("");
+            match section_id(first_line, comment_syntax) {
+                Some(id) => {
+                    named.insert(id.to_string(), section);
+                }
+                None => unnamed.push(section),
+            }
+        }
+        Self {
+            named,
+            unnamed: unnamed.into_iter(),
+        }
+    }
+
+    /// How many sections were not taken.
+    fn len(&self) -> usize {
+        self.named.len() + self.unnamed.len()
+    }
+
+    /// The local section for a synced block: by ID when it has one,
+    /// otherwise the next unnamed section.
+    fn take(&mut self, id: Option<&str>) -> Option<String> {
+        match id {
+            Some(id) => self.named.remove(id),
+            None => self.unnamed.next(),
+        }
+    }
+}
+
+/// The `id=<name>` of an exclude start tag, e.g. `# drifters::exclude::start id=secrets`.
+fn section_id<'a>(tag_line: &'a str, comment_syntax: &CommentSyntax) -> Option<&'a str> {
+    comment_syntax
+        .marker_args(tag_line, "exclude::start")?
+        .split_whitespace()
+        .find_map(|word| word.strip_prefix("id="))
+        .filter(|id| !id.is_empty())
+}
+
+/// Replace the local include sections, in order, with the synced ones.
+/// Synced sections beyond what the local file has are appended at the end.
+fn merge_included_content(
+    local_content: &str,
+    synced_content: &str,
+    comment_syntax: &CommentSyntax,
+) -> Result<String> {
+    let synced = extract_marked_sections(synced_content, comment_syntax, "include")?;
+    let local = extract_marked_sections(local_content, comment_syntax, "include")?;
+
+    let mut result = String::new();
+    let mut in_include_block = false;
+    let mut include_index = 0;
+
+    for line in local_content.lines() {
+        if comment_syntax.is_start(line, "include") {
+            in_include_block = true;
+            continue;
+        }
+
+        if comment_syntax.is_stop(line, "include") {
+            in_include_block = false;
+            // Keep a local section the synced file doesn't have (yet)
+            match synced.get(include_index) {
+                Some(section) => result.push_str(section),
+                None => result.push_str(&local[include_index]),
+            }
+            include_index += 1;
+            continue;
+        }
+
+        if !in_include_block {
+            result.push_str(line);
+            result.push('\n');
+        }
+    }
+
+    for section in synced.iter().skip(local.len()) {
+        result.push_str(section);
+    }
+
+    Ok(result)
+}
+
+/// How a file's section tags scope what it syncs, for `list-app`, e.g.
+/// `exclude sections: secrets, 1 unnamed`. `None` when it has no tags and
+/// syncs whole.
+pub fn describe_sections(content: &str, comment_syntax: &CommentSyntax) -> Option<String> {
+    let (content, _) = strip_bom(content);
+    let starts = |kind: &'static str| {
+        content.lines().filter(move |line| comment_syntax.is_start(line, kind))
+    };
+    let mut parts = Vec::new();
+    let included = starts("include").count();
+    if included > 0 {
+        parts.push(format!("only {} include section(s) synced", included));
+    }
+    let excludes: Vec<Option<&str>> =
+        starts("exclude").map(|line| section_id(line, comment_syntax)).collect();
+    if !excludes.is_empty() {
+        let mut names: Vec<String> = excludes.iter().flatten().map(|id| id.to_string()).collect();
+        let unnamed = excludes.iter().filter(|id| id.is_none()).count();
+        if unnamed > 0 {
+            names.push(format!("{} unnamed", unnamed));
+        }
+        parts.push(format!("exclude sections: {}", names.join(", ")));
+    }
+    let encrypted = starts("encrypt").count();
+    if encrypted > 0 {
+        parts.push(format!("{} encrypted section(s)", encrypted));
+    }
+    (!parts.is_empty()).then(|| parts.join("; "))
+}
+
+/// Whether `content` has `drifters::encrypt` sections.
+pub fn has_encrypted_sections(content: &str, comment_syntax: &CommentSyntax) -> bool {
+    comment_syntax.has_start(content, "encrypt")
+}
+
+/// Replace the body of every `drifters::encrypt` section with `seal` of it,
+/// wrapped into comment lines so the file keeps its syntax. The markers
+/// stay as they are.
+pub fn encrypt_sections(
+    content: &str,
+    comment_syntax: &CommentSyntax,
+    mut seal: impl FnMut(&str) -> Result<String>,
+) -> Result<String> {
+    if *comment_syntax == CommentSyntax::None {
+        refuse_markers_without_comments(content)?;
+        return Ok(content.to_string());
+    }
+
+    let mut result = String::new();
+    let mut section: Option<String> = None;
+    for line in content.lines() {
+        if let Some(body) = section.as_mut() {
+            if !comment_syntax.is_stop(line, "encrypt") {
+                body.push_str(line);
+                body.push('\n');
+                continue;
+            }
+            let sealed = seal(body)?;
+            for chunk in sealed.as_bytes().chunks(SEALED_LINE_WIDTH) {
+                // Sealed text is base64, so every chunk is valid UTF-8
+                let chunk = std::str::from_utf8(chunk).unwrap_or_default();
+                result.push_str(&comment_syntax.comment_line(chunk));
+                result.push('\n');
+            }
+            section = None;
+        } else if comment_syntax.is_start(line, "encrypt") {
+            section = Some(String::new());
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+
+    if section.is_some() {
+        return Err(DriftersError::Config(
+            "unclosed drifters::encrypt::start block \
+             (missing drifters::encrypt::stop)"
+                .to_string(),
+        ));
+    }
     Ok(result)
 }
 
-/// Extract exclude sections from content
-fn extract_exclude_sections(
+/// Undo [`encrypt_sections`], passing each section's sealed text to `open`.
+/// A section whose body isn't sealed text (pushed before it was marked for
+/// encryption) is kept as is.
+pub fn decrypt_sections(
     content: &str,
-    start_tag: &str,
-    stop_tag: &str,
+    comment_syntax: &CommentSyntax,
+    mut open: impl FnMut(&str) -> Result<String>,
+) -> Result<String> {
+    let mut result = String::new();
+    let mut section: Option<Vec<&str>> = None;
+    for line in content.lines() {
+        if let Some(lines) = section.as_mut() {
+            if !comment_syntax.is_stop(line, "encrypt") {
+                lines.push(line);
+                continue;
+            }
+            let sealed: Option<String> =
+                lines.iter().map(|l| comment_syntax.uncomment(l)).collect();
+            match sealed.filter(|s| s.starts_with(SEALED_PREFIX)) {
+                Some(sealed) => result.push_str(&open(&sealed)?),
+                None => {
+                    for line in lines.iter() {
+                        result.push_str(line);
+                        result.push('\n');
+                    }
+                }
+            }
+            section = None;
+        } else if comment_syntax.is_start(line, "encrypt") {
+            section = Some(Vec::new());
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+
+    if section.is_some() {
+        return Err(DriftersError::Config(
+            "unclosed drifters::encrypt::start block \
+             (missing drifters::encrypt::stop)"
+                .to_string(),
+        ));
+    }
+    Ok(result)
+}
+
+/// Extract `kind` (exclude/include) sections from content, markers included
+fn extract_marked_sections(
+    content: &str,
+    comment_syntax: &CommentSyntax,
+    kind: &str,
 ) -> Result<Vec<String>> {
     let mut sections = Vec::new();
     let mut current_section = String::new();
     let mut in_section = false;
 
     for line in content.lines() {
-        let trimmed = line.trim();
-
-        if trimmed.starts_with(start_tag) {
+        if comment_syntax.is_start(line, kind) {
             in_section = true;
             current_section.clear();
             current_section.push_str(line);
@@ -129,7 +529,7 @@ fn extract_exclude_sections(
             continue;
         }
 
-        if trimmed.starts_with(stop_tag) {
+        if comment_syntax.is_stop(line, kind) {
             current_section.push_str(line);
             current_section.push('\n');
             sections.push(current_section.clone());
@@ -146,21 +546,32 @@ fn extract_exclude_sections(
     // A dangling start tag means the partial section would be silently dropped,
     // causing the local exclude content to disappear on the next pull.
     if in_section {
-        return Err(DriftersError::Config(
-            "unclosed drifters::exclude::start block \
-             (missing drifters::exclude::stop)"
-                .to_string(),
-        ));
+        return Err(DriftersError::Config(format!(
+            "unclosed drifters::{kind}::start block \
+             (missing drifters::{kind}::stop)"
+        )));
     }
 
     Ok(sections)
 }
 
-/// Detect comment syntax from file extension
-pub fn detect_comment_syntax(filename: &str) -> &str {
+/// The comment syntax for a file: the app's `comment_syntax` override if
+/// set, otherwise detected from the file name.
+pub fn comment_syntax_for(app_override: Option<&str>, filename: &str) -> CommentSyntax {
+    match app_override {
+        Some(spec) => CommentSyntax::parse(spec),
+        None => detect_comment_syntax(filename),
+    }
+}
+
+/// Detect comment syntax from file name and extension
+pub fn detect_comment_syntax(filename: &str) -> CommentSyntax {
     // Check for special filenames first
     if filename.contains("vimrc") || filename.ends_with(".vim") {
-        return "\"";
+        return CommentSyntax::line("\"");
+    }
+    if filename.eq_ignore_ascii_case("dockerfile") || filename.starts_with("Dockerfile.") {
+        return CommentSyntax::line("#");
     }
 
     // Use Path::extension() rather than split('.').last() so that compound
@@ -170,19 +581,31 @@ pub fn detect_comment_syntax(filename: &str) -> &str {
         .and_then(|e| e.to_str())
         .unwrap_or("");
 
-    match ext {
-        // Shell scripts, Python, Ruby, YAML, TOML
-        "sh" | "bash" | "zsh" | "py" | "rb" | "yaml" | "yml" | "toml" | "conf" => "#",
+    match ext.to_ascii_lowercase().as_str() {
+        // Shell scripts, Python, Ruby, YAML, TOML, PowerShell, Dockerfiles
+        "sh" | "bash" | "zsh" | "fish" | "py" | "rb" | "yaml" | "yml" | "toml" | "conf"
+        | "ps1" | "dockerfile" => CommentSyntax::line("#"),
+        // INI files
+        "ini" => CommentSyntax::line(";"),
         // JavaScript, TypeScript, C, C++, Rust, Go, Java
-        "js" | "ts" | "jsx" | "tsx" | "c" | "cpp" | "h" | "hpp" | "rs" | "go" | "java" => "//",
+        "js" | "ts" | "jsx" | "tsx" | "c" | "cpp" | "h" | "hpp" | "rs" | "go" | "java" => {
+            CommentSyntax::line("//")
+        }
+        // Stylesheets only have block comments
+        "css" | "scss" => CommentSyntax::block("/*", "*/"),
+        // HTML and XML, including macOS property lists
+        "html" | "htm" | "xml" | "plist" | "svg" => CommentSyntax::block("<!--", "-->"),
+        // JSON with comments, as most editors' settings.json accept; strict
+        // JSON files need `comment_syntax = "none"` instead
+        "json" | "jsonc" => CommentSyntax::line("//"),
         // Lua
-        "lua" => "--",
+        "lua" => CommentSyntax::line("--"),
         // Vim
-        "vim" => "\"",
+        "vim" => CommentSyntax::line("\""),
         // SQL
-        "sql" => "--",
+        "sql" => CommentSyntax::line("--"),
         // Default to # for unknown files
-        _ => "#",
+        _ => CommentSyntax::line("#"),
     }
 }
 
@@ -190,6 +613,10 @@ pub fn detect_comment_syntax(filename: &str) -> &str {
 mod tests {
     use super::*;
 
+    fn hash() -> CommentSyntax {
+        CommentSyntax::line("#")
+    }
+
     #[test]
     fn test_extract_syncable_content_exclude() {
         let content = r#"
@@ -203,7 +630,7 @@ alias local_alias="foo"
 export ANOTHER_SHARED="also shared"
 "#;
 
-        let result = extract_syncable_content(content, "#").unwrap();
+        let result = extract_syncable_content(content, &hash()).unwrap();
         assert!(result.is_some());
         let synced = result.unwrap();
         assert!(synced.contains("export SHARED"));
@@ -217,7 +644,7 @@ export ANOTHER_SHARED="also shared"
     #[test]
     fn test_no_tags() {
         let content = "export EDITOR=\"nvim\"\nalias g=\"git\"";
-        let result = extract_syncable_content(content, "#").unwrap();
+        let result = extract_syncable_content(content, &hash()).unwrap();
         assert!(result.is_none());
     }
 
@@ -242,7 +669,7 @@ export SHARED="new_value"
 export OTHER="new_other"
 "#;
 
-        let result = merge_synced_content(local, synced, "#").unwrap();
+        let result = merge_synced_content(local, synced, &hash()).unwrap().content;
         assert!(result.contains("export SHARED=\"new_value\""));
         assert!(result.contains("export OTHER=\"new_other\""));
         assert!(result.contains("export LOCAL=\"my_local_value\""));
@@ -264,7 +691,7 @@ export LOCAL2="local"
 # drifters::exclude::stop
 "#;
 
-        let result = extract_syncable_content(content, "#").unwrap();
+        let result = extract_syncable_content(content, &hash()).unwrap();
         assert!(result.is_some());
         let synced = result.unwrap();
         assert!(synced.contains("SHARED1"));
@@ -277,7 +704,7 @@ export LOCAL2="local"
     fn test_leading_whitespace_tags_are_recognized() {
         // Tags with leading whitespace (e.g. inside indented blocks) must be detected
         let content = "export SHARED=\"shared\"\n  # drifters::exclude::start\nexport LOCAL=\"local\"\n  # drifters::exclude::stop\nexport OTHER=\"other\"\n";
-        let result = extract_syncable_content(content, "#").unwrap();
+        let result = extract_syncable_content(content, &hash()).unwrap();
         assert!(result.is_some());
         let synced = result.unwrap();
         assert!(synced.contains("export SHARED"));
@@ -285,23 +712,89 @@ export LOCAL2="local"
         assert!(!synced.contains("export LOCAL"));
     }
 
+    #[test]
+    fn test_describe_sections_names_what_stays_local() {
+        let comment = CommentSyntax::Line("#".to_string());
+        assert_eq!(describe_sections("a=1\n", &comment), None);
+
+        let content = "\u{feff}# drifters::exclude::start id=secrets\ntoken=1\n\
+                       # drifters::exclude::stop\na=1\n# drifters::exclude::start\n\
+                       # drifters::exclude::stop\n# drifters::encrypt::start\nb=2\n\
+                       # drifters::encrypt::stop\n";
+        assert_eq!(
+            describe_sections(content, &comment).unwrap(),
+            "exclude sections: secrets, 1 unnamed; 1 encrypted section(s)"
+        );
+        let included = "# drifters::include::start\na=1\n# drifters::include::stop\n";
+        assert_eq!(
+            describe_sections(included, &comment).unwrap(),
+            "only 1 include section(s) synced"
+        );
+    }
+
+    #[test]
+    fn test_byte_order_mark_before_first_marker() {
+        let local = "\u{feff}# drifters::exclude::start\nexport LOCAL=1\n\
+                     # drifters::exclude::stop\nexport A=1\n";
+        let synced = extract_syncable_content(local, &hash()).unwrap().unwrap();
+        assert_eq!(
+            synced,
+            "\u{feff}# drifters::exclude::start\n# drifters::exclude::stop\nexport A=1\n"
+        );
+
+        // The local file keeps its own choice, whatever the pushing machine saved
+        let incoming = synced.replace("A=1", "A=2");
+        let merged = merge_synced_content(local, &incoming, &hash()).unwrap().content;
+        assert_eq!(merged, local.replace("A=1", "A=2"));
+        let without_bom = local.trim_start_matches('\u{feff}');
+        let merged = merge_synced_content(without_bom, &incoming, &hash()).unwrap().content;
+        assert_eq!(merged, without_bom.replace("A=1", "A=2"));
+
+        let secret = "\u{feff}# drifters::encrypt::start\ntoken=1\n# drifters::encrypt::stop\n";
+        assert!(has_encrypted_sections(secret, &hash()));
+    }
+
     #[test]
     fn test_inline_tags_are_ignored() {
         // Tags that appear after other content on the same line must NOT be treated as section delimiters
         let content = "export VAR=\"val\"  # drifters::exclude::start\nexport OTHER=\"other\"\n";
-        let result = extract_syncable_content(content, "#").unwrap();
+        let result = extract_syncable_content(content, &hash()).unwrap();
         // No tags should have been detected, so the whole file is synced
         assert!(result.is_none());
     }
 
     #[test]
     fn test_detect_comment_syntax() {
-        assert_eq!(detect_comment_syntax("test.sh"), "#");
-        assert_eq!(detect_comment_syntax("config.py"), "#");
-        assert_eq!(detect_comment_syntax("app.js"), "//");
-        assert_eq!(detect_comment_syntax("main.rs"), "//");
-        assert_eq!(detect_comment_syntax("init.lua"), "--");
-        assert_eq!(detect_comment_syntax(".vimrc"), "\"");
+        let line = CommentSyntax::line;
+        assert_eq!(detect_comment_syntax("test.sh"), line("#"));
+        assert_eq!(detect_comment_syntax("config.py"), line("#"));
+        assert_eq!(detect_comment_syntax("app.js"), line("//"));
+        assert_eq!(detect_comment_syntax("main.rs"), line("//"));
+        assert_eq!(detect_comment_syntax("init.lua"), line("--"));
+        assert_eq!(detect_comment_syntax(".vimrc"), line("\""));
+        assert_eq!(detect_comment_syntax("config.fish"), line("#"));
+        assert_eq!(detect_comment_syntax("profile.ps1"), line("#"));
+        assert_eq!(detect_comment_syntax("settings.ini"), line(";"));
+        assert_eq!(detect_comment_syntax("Dockerfile"), line("#"));
+        assert_eq!(detect_comment_syntax("dev.dockerfile"), line("#"));
+        assert_eq!(detect_comment_syntax("userChrome.css"), CommentSyntax::block("/*", "*/"));
+        assert_eq!(detect_comment_syntax("theme.scss"), CommentSyntax::block("/*", "*/"));
+        assert_eq!(detect_comment_syntax("index.html"), CommentSyntax::block("<!--", "-->"));
+        assert_eq!(detect_comment_syntax("com.app.plist"), CommentSyntax::block("<!--", "-->"));
+        assert_eq!(detect_comment_syntax("settings.jsonc"), line("//"));
+        assert_eq!(detect_comment_syntax("settings.json"), line("//"));
+    }
+
+    #[test]
+    fn test_json_settings_use_line_comment_markers() {
+        let json = detect_comment_syntax("settings.json");
+        let content = "{\n  \"theme\": \"dark\",\n  // drifters::exclude::start\n  \"token\": \"abc\",\n  // drifters::exclude::stop\n  \"vim_mode\": true\n}\n";
+        let synced = extract_syncable_content(content, &json).unwrap().unwrap();
+        assert!(!synced.contains("token"));
+        assert!(synced.contains("vim_mode"));
+
+        // Strict JSON opts out of markers entirely
+        assert_eq!(comment_syntax_for(Some("none"), "package.json"), CommentSyntax::None);
     }
 
     #[test]
@@ -311,14 +804,14 @@ export LOCAL2="local"
         let content = "export SHARED=\"shared\"\n\
                        # drifters::exclude::start\n\
                        export LOCAL=\"local\"\n";
-        let result = extract_syncable_content(content, "#");
+        let result = extract_syncable_content(content, &hash());
         assert!(result.is_err(), "expected Err for unclosed exclude block");
         assert!(result.unwrap_err().to_string().contains("unclosed"));
     }
 
     #[test]
     fn test_unclosed_exclude_block_merge() {
-        // extract_exclude_sections is called inside merge_synced_content; an
+        // extract_marked_sections is called inside merge_synced_content; an
         // unclosed block in the local content must surface as an error so the
         // caller knows the local file is malformed rather than silently dropping
         // the partial exclude section.
@@ -326,8 +819,261 @@ export LOCAL2="local"
         let synced = "export SHARED=\"shared\"\n\
                       # drifters::exclude::start\n\
                       # drifters::exclude::stop\n";
-        let result = merge_synced_content(local, synced, "#");
+        let result = merge_synced_content(local, synced, &hash());
         assert!(result.is_err(), "expected Err for unclosed exclude block in local");
         assert!(result.unwrap_err().to_string().contains("unclosed"));
     }
+
+    #[test]
+    fn test_include_markers_sync_only_the_block() {
+        let content = "export LOCAL=1\n\
+                       # drifters::include::start\n\
+                       alias g=git\n\
+                       # drifters::include::stop\n\
+                       export ALSO_LOCAL=2\n";
+        let synced = extract_syncable_content(content, &hash()).unwrap().unwrap();
+        assert_eq!(
+            synced,
+            "# drifters::include::start\nalias g=git\n# drifters::include::stop\n"
+        );
+
+        // On pull only the include block is replaced
+        let incoming = "# drifters::include::start\nalias g=git\nalias s=status\n# drifters::include::stop\n";
+        let merged = merge_synced_content(content, incoming, &hash()).unwrap().content;
+        assert_eq!(
+            merged,
+            "export LOCAL=1\n\
+             # drifters::include::start\n\
+             alias g=git\n\
+             alias s=status\n\
+             # drifters::include::stop\n\
+             export ALSO_LOCAL=2\n"
+        );
+
+        // A local file without the block gets it appended
+        let merged = merge_synced_content("export MINE=1\n", incoming, &hash()).unwrap().content;
+        assert_eq!(merged, format!("export MINE=1\n{}", incoming));
+    }
+
+    #[test]
+    fn test_include_and_exclude_markers_conflict() {
+        let content = "# drifters::include::start\na\n# drifters::include::stop\n\
+                       # drifters::exclude::start\nb\n# drifters::exclude::stop\n";
+        let err = extract_syncable_content(content, &hash()).unwrap_err().to_string();
+        assert!(err.contains("both drifters::include and drifters::exclude"));
+    }
+
+    #[test]
+    fn test_unclosed_include_block() {
+        let content = "# drifters::include::start\nalias g=git\n";
+        let err = extract_syncable_content(content, &hash()).unwrap_err().to_string();
+        assert!(err.contains("unclosed drifters::include::start"), "{}", err);
+
+        let incoming = "# drifters::include::start\nx\n# drifters::include::stop\n";
+        assert!(merge_synced_content(content, incoming, &hash()).is_err());
+    }
+
+    #[test]
+    fn test_named_exclude_sections_survive_reordering() {
+        let local = "\
+# drifters::exclude::start id=secrets
+export TOKEN=abc
+# drifters::exclude::stop
+shared=1
+# drifters::exclude::start id=paths
+export WORK=/home/me/work
+# drifters::exclude::stop
+";
+        // Another machine moved the paths block to the top
+        let synced = "\
+# drifters::exclude::start id=paths
+# drifters::exclude::stop
+shared=2
+# drifters::exclude::start id=secrets
+# drifters::exclude::stop
+";
+        let merged = merge_synced_content(local, synced, &hash()).unwrap().content;
+        assert_eq!(
+            merged,
+            "\
+# drifters::exclude::start id=paths
+export WORK=/home/me/work
+# drifters::exclude::stop
+shared=2
+# drifters::exclude::start id=secrets
+export TOKEN=abc
+# drifters::exclude::stop
+"
+        );
+    }
+
+    #[test]
+    fn test_mixed_named_and_unnamed_exclude_sections() {
+        let local = "\
+# drifters::exclude::start
+first local
+# drifters::exclude::stop
+# drifters::exclude::start id=secrets
+export TOKEN=abc
+# drifters::exclude::stop
+# drifters::exclude::start
+second local
+# drifters::exclude::stop
+";
+        let synced = "\
+# drifters::exclude::start id=secrets
+# drifters::exclude::stop
+# drifters::exclude::start
+# drifters::exclude::stop
+shared
+# drifters::exclude::start
+# drifters::exclude::stop
+# drifters::exclude::start id=new
+# drifters::exclude::stop
+";
+        let merged = merge_synced_content(local, synced, &hash()).unwrap().content;
+        assert_eq!(
+            merged,
+            "\
+# drifters::exclude::start id=secrets
+export TOKEN=abc
+# drifters::exclude::stop
+# drifters::exclude::start
+first local
+# drifters::exclude::stop
+shared
+# drifters::exclude::start
+second local
+# drifters::exclude::stop
+# drifters::exclude::start id=new
+# drifters::exclude::stop
+"
+        );
+    }
+
+    #[test]
+    fn test_exclude_section_count_mismatch() {
+        let tags = |id: &str| {
+            format!("# drifters::exclude::start{}\nlocal\n# drifters::exclude::stop\n", id)
+        };
+        let empty = |id: &str| {
+            format!("# drifters::exclude::start{}\n# drifters::exclude::stop\n", id)
+        };
+
+        // More local sections than synced blocks: the extra one is dropped
+        let local = format!("a\n{}b\n{}", tags(""), tags(""));
+        let synced = format!("a\n{}b\n", empty(""));
+        let merged = merge_synced_content(&local, &synced, &hash()).unwrap();
+        assert_eq!(merged.dropped_sections, 1);
+        assert_eq!(merged.content, format!("a\n{}b\n", tags("")));
+        // ... as is a named one whose ID no synced block has
+        let local = format!("a\n{}", tags(" id=mine"));
+        let merged = merge_synced_content(&local, "a\n", &hash()).unwrap();
+        assert_eq!((merged.content.as_str(), merged.dropped_sections), ("a\n", 1));
+
+        // More synced blocks than local sections: nothing is lost, the new
+        // block arrives empty
+        let local = format!("a\n{}", tags(""));
+        let synced = format!("a\n{}b\n{}", empty(""), empty(""));
+        let merged = merge_synced_content(&local, &synced, &hash()).unwrap();
+        assert_eq!(merged.dropped_sections, 0);
+        assert_eq!(merged.content, format!("a\n{}b\n{}", tags(""), empty("")));
+    }
+
+    #[test]
+    fn test_block_comment_markers() {
+        let css = CommentSyntax::block("/*", "*/");
+        let local = "\
+body { color: black; }
+/* drifters::exclude::start id=fonts */
+body { font-size: 14px; }
+/* drifters::exclude::stop */
+";
+        let synced = extract_syncable_content(local, &css).unwrap().unwrap();
+        assert_eq!(
+            synced,
+            "body { color: black; }\n/* drifters::exclude::start id=fonts */\n/* drifters::exclude::stop */\n"
+        );
+
+        let incoming = synced.replace("black", "navy");
+        let merged = merge_synced_content(local, &incoming, &css).unwrap().content;
+        assert!(merged.contains("color: navy"));
+        assert!(merged.contains("font-size: 14px"));
+
+        // The closing delimiter is part of the tag, so this is not a start marker
+        let unterminated = "/* drifters::exclude::start\nx\n/* drifters::exclude::stop */\n";
+        assert!(extract_syncable_content(unterminated, &css).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_html_comment_markers_round_trip() {
+        let html = detect_comment_syntax("bookmarks.html");
+        let local = "\
+<ul>
+  <li>Shared</li>
+  <!-- drifters::exclude::start id=work -->
+  <li>Intranet</li>
+  <!-- drifters::exclude::stop -->
+</ul>
+";
+        let synced = extract_syncable_content(local, &html).unwrap().unwrap();
+        assert!(!synced.contains("Intranet"));
+        assert!(synced.contains("<!-- drifters::exclude::start id=work -->"));
+
+        // Another machine's push changes the shared part; the excluded
+        // block survives the pull unchanged
+        let incoming = synced.replace("Shared", "Shared, renamed");
+        let merged = merge_synced_content(local, &incoming, &html).unwrap().content;
+        assert_eq!(merged, local.replace("Shared", "Shared, renamed"));
+        assert_eq!(extract_syncable_content(&merged, &html).unwrap().unwrap(), incoming);
+    }
+
+    #[test]
+    fn test_no_comment_syntax_refuses_markers() {
+        let json = "{\"a\": 1}\n";
+        assert!(extract_syncable_content(json, &CommentSyntax::None).unwrap().is_none());
+        assert_eq!(merge_synced_content("{}", json, &CommentSyntax::None).unwrap().content, json);
+
+        let with_markers = "{\n// drifters::exclude::start\n\"a\": 1\n// drifters::exclude::stop\n}\n";
+        let err = extract_syncable_content(with_markers, &CommentSyntax::None).unwrap_err();
+        assert!(err.to_string().contains("without comments"));
+    }
+
+    #[test]
+    fn test_app_comment_syntax_override() {
+        // An extensionless rc file that is really Lua
+        assert_eq!(comment_syntax_for(None, "customrc"), CommentSyntax::line("#"));
+        assert_eq!(comment_syntax_for(Some("--"), "customrc"), CommentSyntax::line("--"));
+        assert_eq!(
+            comment_syntax_for(Some("<!-- -->"), "page.json"),
+            CommentSyntax::block("<!--", "-->")
+        );
+
+        let lua = comment_syntax_for(Some("--"), "customrc");
+        let content = "a = 1\n-- drifters::exclude::start\nb = 2\n-- drifters::exclude::stop\n";
+        let synced = extract_syncable_content(content, &lua).unwrap().unwrap();
+        assert!(!synced.contains("b = 2"));
+    }
+
+    #[test]
+    fn test_encrypted_sections_round_trip_in_comments() {
+        let content = "a = 1\n# drifters::encrypt::start\ntoken = abc\n# drifters::encrypt::stop\n";
+        let seal = |body: &str| Ok(format!("v1:{}", "x".repeat(100) + &body.replace('\n', "~")));
+        let sealed = encrypt_sections(content, &hash(), seal).unwrap();
+        assert!(!sealed.contains("token = abc\n"));
+        // Wrapped into comment lines, markers kept
+        let lines: Vec<_> = sealed.lines().collect();
+        assert_eq!(lines[1], "# drifters::encrypt::start");
+        assert!(lines[2].starts_with("# v1:") && lines[3].starts_with("# "));
+        assert_eq!(lines.last(), Some(&"# drifters::encrypt::stop"));
+
+        let open =
+            |sealed: &str| Ok(sealed.trim_start_matches(['v', '1', ':', 'x']).replace('~', "\n"));
+        assert_eq!(decrypt_sections(&sealed, &hash(), open).unwrap(), content);
+        // A section pushed before it was marked is left as is
+        assert_eq!(decrypt_sections(content, &hash(), open).unwrap(), content);
+
+        let unclosed = "# drifters::encrypt::start\ntoken = abc\n";
+        assert!(encrypt_sections(unclosed, &hash(), seal).is_err());
+    }
 }
@@ -0,0 +1,90 @@
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Replace `path`'s contents so that, even if the process is killed or the
+/// disk fills up midway, the file holds either the old or the new contents —
+/// never a truncated mix.
+///
+/// The new contents go to a temp file next to `path`, which is synced and
+/// then renamed over it. The replaced file's permissions are kept, and a
+/// symlink is written through rather than replaced. Should the rename fail,
+/// the temp file is copied into place and synced instead.
+pub fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let path = resolve_symlink(path)?;
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("not a file path: {:?}", path))
+    })?;
+    let temp = path.with_file_name(format!(
+        ".{}.drifters-tmp-{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = write_temp(&temp, contents.as_ref(), &path).and_then(|()| {
+        fs::rename(&temp, &path).or_else(|e| {
+            log::debug!("Renaming {:?} into place failed ({}), copying instead", temp, e);
+            copy_into_place(&temp, &path)
+        })
+    });
+    if temp.exists() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Dotfiles are often symlinks into a dotfiles repo (GNU Stow and the like);
+/// the link should keep pointing there.
+fn resolve_symlink(path: &Path) -> io::Result<PathBuf> {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_symlink() => fs::canonicalize(path),
+        _ => Ok(path.to_path_buf()),
+    }
+}
+
+fn write_temp(temp: &Path, contents: &[u8], target: &Path) -> io::Result<()> {
+    let mut file = fs::File::create(temp)?;
+    file.write_all(contents)?;
+    if let Ok(meta) = fs::metadata(target) {
+        file.set_permissions(meta.permissions())?;
+    }
+    file.sync_all()
+}
+
+fn copy_into_place(temp: &Path, target: &Path) -> io::Result<()> {
+    fs::copy(temp, target)?;
+    fs::OpenOptions::new().write(true).open(target)?.sync_all()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_atomic_replaces_in_place_and_cleans_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join(".zshrc");
+
+        write_atomic(&target, "export A=1\n").unwrap();
+        write_atomic(&target, "export A=2\n").unwrap();
+        assert_eq!(fs::read_to_string(&target).unwrap(), "export A=2\n");
+        // No temp file left behind
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+            write_atomic(&target, "export A=3\n").unwrap();
+            let mode = fs::metadata(&target).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+
+            // A symlinked dotfile stays a symlink; its target gets the content
+            let link = dir.path().join("link");
+            std::os::unix::fs::symlink(&target, &link).unwrap();
+            write_atomic(&link, "export A=4\n").unwrap();
+            assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+            assert_eq!(fs::read_to_string(&target).unwrap(), "export A=4\n");
+        }
+    }
+}
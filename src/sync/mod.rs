@@ -1,2 +1,7 @@
-// This module is kept for potential future sync utilities
-// Current sync functionality is implemented in cli/ modules
+// Sync utilities shared by the cli/ modules; the commands themselves are
+// implemented there
+pub mod atomic;
+pub mod parallel;
+
+pub use atomic::write_atomic;
+pub use parallel::map_bounded;
@@ -0,0 +1,68 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+/// Most worker threads [`map_bounded`] starts, however many cores there are.
+/// Each item usually runs a `git` subprocess or walks a directory tree, so
+/// more would only contend for the disk.
+const MAX_WORKERS: usize = 8;
+
+/// Apply `f` to every item on a few worker threads, returning the results
+/// in the order of `items`, as a sequential `map` would.
+///
+/// Workers take the next unclaimed item until none are left, so a slow item
+/// doesn't hold up the others. A single item runs on the calling thread.
+pub fn map_bounded<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let workers = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(MAX_WORKERS)
+        .min(items.len());
+    if workers <= 1 {
+        return items.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let mut indexed: Vec<(usize, R)> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(i) else { break };
+                        done.push((i, f(item)));
+                    }
+                    done
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_else(|e| std::panic::resume_unwind(e)))
+            .collect()
+    });
+    indexed.sort_by_key(|(i, _)| *i);
+    indexed.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_map_bounded_keeps_input_order() {
+        let items: Vec<u64> = (0..50).collect();
+        // Early items finish last, so completion order differs from input order
+        let results = map_bounded(&items, |&n| {
+            thread::sleep(Duration::from_millis(50 - n));
+            n * 2
+        });
+        assert_eq!(results, items.iter().map(|n| n * 2).collect::<Vec<_>>());
+        assert!(map_bounded(&[] as &[u64], |&n| n).is_empty());
+    }
+}
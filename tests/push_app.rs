@@ -0,0 +1,84 @@
+//! Runs the built binary against a local bare repo, so `push-app` is
+//! exercised end to end: config loading, fileset resolution, section
+//! extraction, and the commit on the machine branch.
+
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+/// Run drifters with `home` as the home directory, feeding it `input`.
+fn drifters(home: &Path, args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_drifters"))
+        .args(args)
+        .env("HOME", home)
+        .env("NO_COLOR", "1")
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "drifters {:?} failed:\n{}\n{}",
+        args,
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+fn git(home: &Path, args: &[&str]) -> String {
+    let output = Command::new("git")
+        .args(args)
+        .env("HOME", home)
+        .env("GIT_CONFIG_NOSYSTEM", "1")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_push_app_commits_synced_content_to_the_machine_branch() {
+    let dir = tempfile::tempdir().unwrap();
+    let home = dir.path().join("home");
+    let remote = dir.path().join("remote.git");
+    fs::create_dir_all(&home).unwrap();
+    let remote_url = remote.to_string_lossy().to_string();
+    git(&home, &["init", "-q", "--bare", "-b", "main", &remote_url]);
+    git(&home, &["config", "--global", "user.name", "tester"]);
+    git(&home, &["config", "--global", "user.email", "tester@example.com"]);
+
+    // No hook, machine ID "m1", no custom branch
+    drifters(&home, &["init", &remote_url], "n\nm1\nn\n");
+    let config = home.join(".config/drifters/drifters.toml");
+    let toml = fs::read_to_string(&config).unwrap();
+    // self_update_frequency: no update checks over the network
+    let toml = toml.replace("= \"always\"", "= \"never\"");
+    fs::write(&config, toml).unwrap();
+
+    fs::write(
+        home.join(".zshrc"),
+        "export A=1\n# drifters::exclude::start\nexport TOKEN=secret\n# drifters::exclude::stop\n",
+    )
+    .unwrap();
+    drifters(&home, &["add-app", "zsh", "--include", "~/.zshrc"], "");
+    drifters(&home, &["push-app", "zsh", "--include-untracked", "-m", "First push"], "y\n");
+
+    let pushed = git(&home, &["-C", &remote_url, "show", "machines/m1:apps/zsh/.zshrc"]);
+    assert_eq!(
+        pushed,
+        "export A=1\n# drifters::exclude::start\n# drifters::exclude::stop\n"
+    );
+    let subject = git(&home, &["-C", &remote_url, "log", "-1", "--format=%s", "machines/m1"]);
+    assert_eq!(subject.trim(), "First push");
+
+    // Nothing changed: a second push commits nothing
+    let head = git(&home, &["-C", &remote_url, "rev-parse", "machines/m1"]);
+    drifters(&home, &["push-app", "zsh"], "");
+    assert_eq!(git(&home, &["-C", &remote_url, "rev-parse", "machines/m1"]), head);
+}